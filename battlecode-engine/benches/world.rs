@@ -0,0 +1,145 @@
+//! Benchmarks of the game world, each against the slower implementation it
+//! replaced. Run them with `cargo bench --features bench`.
+
+#[macro_use]
+extern crate criterion;
+extern crate battlecode_engine;
+
+use criterion::{Criterion, Fun};
+use std::cmp;
+use std::collections::HashSet;
+
+use battlecode_engine::bench;
+use battlecode_engine::location::*;
+use battlecode_engine::map::PlanetMap;
+use battlecode_engine::unit::UnitType;
+use battlecode_engine::world::*;
+
+/// The world each benchmark starts from.
+fn setup() -> GameWorld {
+    bench::populated_world()
+}
+
+/// Every location on the planet's map, and the squares just off its edges.
+fn all_locations(map: &PlanetMap) -> Vec<MapLocation> {
+    let mut locations = vec![];
+    for y in -1..map.height as i32 + 1 {
+        for x in -1..map.width as i32 + 1 {
+            locations.push(MapLocation::new(map.planet, x, y));
+        }
+    }
+    locations
+}
+
+fn filter(c: &mut Criterion) {
+    let naive = Fun::new("naive", |b, world: &GameWorld| b.iter(|| {
+        Player::all().into_iter().map(|player| bench::naive_filter(world, player)).count()
+    }));
+    let single_pass = Fun::new("single pass", |b, world: &GameWorld| b.iter(|| {
+        Player::all().into_iter().map(|player| bench::filter(world, player)).count()
+    }));
+    c.bench_functions("filter for all four players", vec![naive, single_pass], setup());
+}
+
+fn can_sense_location(c: &mut Criterion) {
+    let world = bench::filter(&setup(), Player::new(Team::Red, Planet::Earth));
+    let locations = all_locations(world.starting_map(Planet::Earth));
+
+    // Visible squares used to be kept in a hash set.
+    let visible: HashSet<MapLocation> = locations.iter().cloned()
+        .filter(|&loc| world.can_sense_location(loc))
+        .collect();
+    let hashed = Fun::new("hash set", move |b, locations: &Vec<MapLocation>| b.iter(|| {
+        locations.iter().filter(|loc| visible.contains(loc)).count()
+    }));
+    let dense = Fun::new("grid", move |b, locations: &Vec<MapLocation>| b.iter(|| {
+        locations.iter().filter(|&&loc| world.can_sense_location(loc)).count()
+    }));
+    c.bench_functions("can_sense_location on every square", vec![hashed, dense], locations);
+}
+
+/// The locations within the radius of the center, found the way
+/// `all_locations_within_clipped()` used to, by scanning the square around
+/// the center, clipped to the map.
+fn scan_locations_within(center: MapLocation, radius_squared: u32, map: &PlanetMap)
+                         -> Vec<MapLocation> {
+    let mut locations = vec![];
+    let radius = (radius_squared as f32).sqrt() as i32;
+    let min_x = cmp::max(center.x - radius, 0);
+    let max_x = cmp::min(center.x + radius, map.width as i32 - 1);
+    let min_y = cmp::max(center.y - radius, 0);
+    let max_y = cmp::min(center.y + radius, map.height as i32 - 1);
+    for x in min_x..max_x + 1 {
+        for y in min_y..max_y + 1 {
+            let loc = MapLocation::new(center.planet, x, y);
+            if center.distance_squared_to(loc) <= radius_squared {
+                locations.push(loc);
+            }
+        }
+    }
+    locations
+}
+
+fn all_locations_within_clipped(c: &mut Criterion) {
+    let map = setup().starting_map(Planet::Earth).clone();
+    let centers: Vec<MapLocation> = all_locations(&map).into_iter()
+        .filter(|&loc| map.on_map(loc))
+        .collect();
+    let radius_squared = UnitType::Ranger.vision_range(0).unwrap();
+    let scan_map = map.clone();
+    let scanned = Fun::new("scan", move |b, centers: &Vec<MapLocation>| b.iter(|| {
+        centers.iter()
+            .map(|&center| scan_locations_within(center, radius_squared, &scan_map).len())
+            .sum::<usize>()
+    }));
+    let clipped = Fun::new("cached disc", move |b, centers: &Vec<MapLocation>| b.iter(|| {
+        centers.iter()
+            .map(|center| center.all_locations_within_clipped(radius_squared, &map).count())
+            .sum::<usize>()
+    }));
+    c.bench_functions("all locations within ranger vision of every square",
+                      vec![scanned, clipped], centers);
+}
+
+fn clone(c: &mut Criterion) {
+    let world = setup();
+    let player_world = bench::filter(&world, Player::new(Team::Red, Planet::Earth));
+    for (name, world) in vec![("dev engine", world), ("player", player_world)] {
+        let unshared = Fun::new("unshared", |b, world: &GameWorld| b.iter(|| {
+            bench::unshared_clone(world)
+        }));
+        let shared = Fun::new("shared", |b, world: &GameWorld| b.iter(|| world.clone()));
+        c.bench_functions(&format!("clone the {} world", name), vec![unshared, shared], world);
+    }
+}
+
+fn move_robot(c: &mut Criterion) {
+    let mut world = setup();
+    let west = (0..world.starting_map(Planet::Earth).height as i32)
+        .map(|y| MapLocation::new(Planet::Earth, 0, y))
+        .find(|&loc| world.is_occupiable(loc).unwrap()
+                  && world.is_occupiable(loc.add(Direction::East)).unwrap())
+        .expect("no room to move a knight");
+    let east = west.add(Direction::East);
+    let knight = bench::create_unit(&mut world, Team::Red, west, UnitType::Knight).unwrap();
+
+    // The first move after cloning stops sharing the occupancy grid.
+    let cloned = world.clone();
+    c.bench_function("move_robot after a clone", move |b| b.iter(|| {
+        let mut copy = cloned.clone();
+        bench::move_to(&mut copy, knight, east).unwrap();
+        copy
+    }));
+
+    // The knight goes back and forth between two squares.
+    let mut at_west = true;
+    c.bench_function("move_robot", move |b| b.iter(|| {
+        let destination = if at_west { east } else { west };
+        bench::move_to(&mut world, knight, destination).unwrap();
+        at_west = !at_west;
+    }));
+}
+
+criterion_group!(benches, filter, can_sense_location, all_locations_within_clipped,
+                 clone, move_robot);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+extern crate battlecode_engine as bc;
+
+use std::env;
+use std::process;
+
+use bc::controller::*;
+use bc::map::*;
+use bc::strategies;
+use bc::world::Team;
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() != 5 {
+        println!("usage: headless MAP RED BLUE REPLAY");
+        println!("strategies: {}", strategies::STRATEGY_NAMES.join(", "));
+        process::exit(2);
+    }
+    let strategy = |name: &str| strategies::by_name(name).unwrap_or_else(|| {
+        println!("unknown strategy: {}", name);
+        process::exit(2);
+    });
+    let (red, blue) = (strategy(&args[2]), strategy(&args[3]));
+    let map = GameMap::from_file(&args[1]).unwrap_or_else(|e| {
+        println!("could not load {}: {}", args[1], e);
+        process::exit(1);
+    });
+
+    let result = run_game(map, red, blue).unwrap_or_else(|e| {
+        println!("a player errored: {}", e);
+        process::exit(1);
+    });
+    if let Err(e) = result.replay.write_to_file(&args[4]) {
+        println!("could not write {}: {}", args[4], e);
+        process::exit(1);
+    }
+    let (winner, loser) = match result.winner {
+        Team::Red => (&args[2], &args[3]),
+        Team::Blue => (&args[3], &args[2]),
+    };
+    println!("{:?} ({}) beat {:?} ({}) in {} rounds on {}",
+             result.winner, winner, result.winner.other(), loser, result.rounds, args[1]);
+}
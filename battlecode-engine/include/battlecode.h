@@ -0,0 +1,156 @@
+/*
+ * The C ABI over a player's controller, from src/ffi.rs. Build the library
+ * with the `ffi` feature, for example:
+ *
+ *     cargo rustc --release --features ffi -- --crate-type staticlib
+ *
+ * Every function returns BC_OK or a BC_ERROR_* code, and writes its results
+ * through out-pointers. After an error, bc_last_error() describes it.
+ * Everything the library returns is freed with the matching *_free function.
+ *
+ * Pointers passed in must be null, or valid: controllers from
+ * bc_controller_new() that have not been freed, NUL-terminated strings, and
+ * writable out-pointers. A controller must not be used from two threads at
+ * once.
+ */
+
+#ifndef BATTLECODE_H
+#define BATTLECODE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Error codes. */
+#define BC_OK 0
+#define BC_ERROR_NULL 1
+#define BC_ERROR_INVALID_MESSAGE 2
+#define BC_ERROR_INVALID_ARGUMENT 3
+#define BC_ERROR_GAME 4
+/* The controller may be inconsistent, and should only be freed. */
+#define BC_ERROR_PANIC 5
+
+/* Planets. */
+#define BC_PLANET_EARTH 0
+#define BC_PLANET_MARS 1
+
+/* Teams. */
+#define BC_TEAM_RED 0
+#define BC_TEAM_BLUE 1
+
+/* Directions. */
+#define BC_DIRECTION_NORTH 0
+#define BC_DIRECTION_NORTHEAST 1
+#define BC_DIRECTION_EAST 2
+#define BC_DIRECTION_SOUTHEAST 3
+#define BC_DIRECTION_SOUTH 4
+#define BC_DIRECTION_SOUTHWEST 5
+#define BC_DIRECTION_WEST 6
+#define BC_DIRECTION_NORTHWEST 7
+#define BC_DIRECTION_CENTER 8
+
+/* Unit types, which are also the research branches. */
+#define BC_UNIT_TYPE_WORKER 0
+#define BC_UNIT_TYPE_KNIGHT 1
+#define BC_UNIT_TYPE_RANGER 2
+#define BC_UNIT_TYPE_MAGE 3
+#define BC_UNIT_TYPE_HEALER 4
+#define BC_UNIT_TYPE_FACTORY 5
+#define BC_UNIT_TYPE_ROCKET 6
+
+/* Location tags. */
+#define BC_LOCATION_ON_MAP 0
+#define BC_LOCATION_IN_GARRISON 1
+#define BC_LOCATION_IN_SPACE 2
+#define BC_LOCATION_UNKNOWN 3
+
+/* A unit's location. Fields the tag does not use are zero. */
+typedef struct {
+    uint32_t tag;
+    uint32_t planet;
+    int32_t x;
+    int32_t y;
+    uint16_t structure_id;
+} BcLocation;
+
+/* The public state of a unit. */
+typedef struct {
+    uint16_t id;
+    uint32_t team;
+    uint32_t unit_type;
+    uint32_t health;
+    BcLocation location;
+} BcUnit;
+
+typedef struct GameController GameController;
+
+/* Memory and errors. */
+void bc_string_free(char *string);
+void bc_units_free(BcUnit *units, size_t len);
+char *bc_last_error(void);
+
+/* Turn loop. Messages are the JSON the manager sends and expects. */
+int32_t bc_controller_new(const char *start_game, GameController **out);
+void bc_controller_free(GameController *controller);
+int32_t bc_controller_start_turn(GameController *c, const char *start_turn);
+int32_t bc_controller_end_turn(GameController *c, char **out);
+
+/* Queries. */
+int32_t bc_controller_round(GameController *c, uint32_t *out);
+int32_t bc_controller_planet(GameController *c, uint32_t *out);
+int32_t bc_controller_team(GameController *c, uint32_t *out);
+int32_t bc_controller_karbonite(GameController *c, uint32_t *out);
+int32_t bc_controller_karbonite_at(GameController *c, uint32_t planet,
+                                   int32_t x, int32_t y, uint32_t *out);
+int32_t bc_controller_unit(GameController *c, uint16_t id, BcUnit *out);
+int32_t bc_controller_units(GameController *c, BcUnit **out_units,
+                            size_t *out_len);
+int32_t bc_controller_my_units(GameController *c, BcUnit **out_units,
+                               size_t *out_len);
+
+/* Actions. */
+int32_t bc_controller_move_robot(GameController *c, uint16_t robot_id,
+                                 uint32_t direction);
+int32_t bc_controller_attack(GameController *c, uint16_t robot_id,
+                             uint16_t target_unit_id);
+int32_t bc_controller_harvest(GameController *c, uint16_t worker_id,
+                              uint32_t direction);
+int32_t bc_controller_blueprint(GameController *c, uint16_t worker_id,
+                                uint32_t structure_type, uint32_t direction);
+int32_t bc_controller_build(GameController *c, uint16_t worker_id,
+                            uint16_t blueprint_id);
+int32_t bc_controller_repair(GameController *c, uint16_t worker_id,
+                             uint16_t structure_id);
+int32_t bc_controller_replicate(GameController *c, uint16_t worker_id,
+                                uint32_t direction);
+int32_t bc_controller_javelin(GameController *c, uint16_t knight_id,
+                              uint16_t target_unit_id);
+int32_t bc_controller_begin_snipe(GameController *c, uint16_t ranger_id,
+                                  uint32_t planet, int32_t x, int32_t y);
+int32_t bc_controller_blink(GameController *c, uint16_t mage_id,
+                            uint32_t planet, int32_t x, int32_t y);
+int32_t bc_controller_heal(GameController *c, uint16_t healer_id,
+                           uint16_t target_robot_id);
+int32_t bc_controller_overcharge(GameController *c, uint16_t healer_id,
+                                 uint16_t target_robot_id);
+int32_t bc_controller_load(GameController *c, uint16_t structure_id,
+                           uint16_t robot_id);
+int32_t bc_controller_unload(GameController *c, uint16_t structure_id,
+                             uint32_t direction);
+int32_t bc_controller_produce_robot(GameController *c, uint16_t factory_id,
+                                    uint32_t robot_type);
+int32_t bc_controller_launch_rocket(GameController *c, uint16_t rocket_id,
+                                    uint32_t planet, int32_t x, int32_t y);
+int32_t bc_controller_disintegrate_unit(GameController *c, uint16_t unit_id);
+int32_t bc_controller_queue_research(GameController *c, uint32_t branch,
+                                     bool *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BATTLECODE_H */
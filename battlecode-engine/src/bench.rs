@@ -0,0 +1,58 @@
+//! Hooks into the engine for the benchmarks in `benches/`. The benchmarks
+//! are built as a separate crate, so they can only reach the engine's
+//! internals through here. Only built with the `bench` feature.
+
+use failure::Error;
+
+use super::location::*;
+use super::map::TestMapConfig;
+use super::unit::*;
+use super::world::*;
+
+/// A world with units of both teams on both planets of a 50x50 map,
+/// including units garrisoned in structures, and a rocket in space. Red
+/// Earth is to move.
+pub fn populated_world() -> GameWorld {
+    GameWorld::populated_world_with(TestMapConfig {
+        width: 50,
+        height: 50,
+        ..Default::default()
+    })
+}
+
+/// Creates a unit on the map, at its team's research level.
+///
+/// * LocationOffMap - the location is off the map.
+/// * LocationNotEmpty - the location is occupied by a unit.
+pub fn create_unit(world: &mut GameWorld, team: Team, location: MapLocation,
+                   unit_type: UnitType) -> Result<UnitID, Error> {
+    world.create_unit(team, location, unit_type)
+}
+
+/// The world as the player sees it.
+pub fn filter(world: &GameWorld, player: Player) -> GameWorld {
+    world.filter(player)
+}
+
+/// The world as the player sees it, filtered the way `filter()` used to,
+/// with a pass over the units for each collection it builds.
+pub fn naive_filter(world: &GameWorld, player: Player) -> GameWorld {
+    world.naive_filter(player)
+}
+
+/// A clone of the world that shares no state with it, as cloning worked
+/// before the bulky parts of the world were shared between clones.
+pub fn unshared_clone(world: &GameWorld) -> GameWorld {
+    world.unshared_clone()
+}
+
+/// Moves the robot without checking that it may move there or heating it
+/// up, so that it can be moved as often as a benchmark needs.
+///
+/// * NoSuchUnit - the robot does not exist.
+/// * TeamNotAllowed - the robot is not on the team to move.
+/// * InternalEngineError - the robot is not on the map.
+pub fn move_to(world: &mut GameWorld, robot_id: UnitID, location: MapLocation)
+               -> Result<(), Error> {
+    world.move_to(robot_id, location)
+}
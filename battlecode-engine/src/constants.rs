@@ -9,6 +9,20 @@ use super::world::Rounds;
 /// The round at which the game is forced to end
 pub const ROUND_LIMIT: Rounds = 1000;
 
+/// The maximum number of actions a player can take in a single turn
+pub const MAX_DELTAS_PER_TURN: usize = 10000;
+
+/// The maximum number of failed actions logged for a player in a single turn
+pub const MAX_LOGGED_FAILURES_PER_TURN: usize = 100;
+
+/// The default time in each player's pool at the start of the game, in
+/// milliseconds
+pub const TIME_POOL_MS: i32 = 10000;
+
+/// The default time added to a player's pool after each of its turns, in
+/// milliseconds
+pub const TIME_PER_TURN_MS: i32 = 50;
+
 // *********************************
 // *** COMMUNICATION CONSTANTS *****
 // *********************************
@@ -41,6 +55,9 @@ pub const MAP_KARBONITE_MIN: u32 = 0;
 /// The maximum starting Karbonite deposit on Earth.
 pub const MAP_KARBONITE_MAX: u32 = 50;
 
+/// The most workers each team can start with on Earth.
+pub const MAP_INITIAL_WORKERS_MAX: usize = 3;
+
 // *********************************
 // ****** WEATHER CONSTANTS ********
 // *********************************
@@ -57,6 +74,10 @@ pub const ASTEROID_KARB_MIN: u32 = 20;
 /// The maximum karbonite in an asteroid strike.
 pub const ASTEROID_KARB_MAX: u32 = 100;
 
+/// The most karbonite a single square can hold. Karbonite added to a square,
+/// such as by asteroid strikes, is clamped to this amount.
+pub const MAX_KARBONITE_PER_SQUARE: u32 = 200;
+
 /// The minimum flight time due to the orbit.
 pub const ORBIT_FLIGHT_MIN: u32 = 50;
 
@@ -70,7 +91,7 @@ pub const APOCALYPSE_ROUND: Rounds = 750;
 // ***** KARBONITE CONSTANTS *******
 // *********************************
 
-/// The starting amount of karbonite per team.
+/// The starting amount of karbonite per team, if the map does not set one.
 pub const KARBONITE_STARTING: u32 = 100;
 /// The base amount of karbonite gained per turn.
 pub const KARBONITE_PER_ROUND: u32 = 10;
@@ -134,3 +155,5 @@ pub const BLUEPRINT_FACTORY_COST: u32 = 100;
 pub const BLUEPRINT_ROCKET_COST: u32 = 75;
 /// The cost to replicate a worker.
 pub const REPLICATE_WORKER_COST: u32 = 15;
+/// The number of rounds it takes a factory to produce a robot.
+pub const FACTORY_PRODUCTION_ROUNDS: Rounds = 5;
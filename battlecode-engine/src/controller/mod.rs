@@ -2,11 +2,13 @@
 //! the API that the player will use, and for generating messages to
 //! send to other parts of the Battlecode infrastructure.
 
+use constants::*;
 use error::*;
 use location::*;
 use map::*;
 use research::*;
 use rockets::*;
+use replay::GameReplay;
 use schema::*;
 use team_array::*;
 use unit::*;
@@ -149,7 +151,7 @@ impl GameController {
 
         // block, and eventually receive the start game message
         let msg = stream.read::<ReceivedMessage<StartGameMessage>>()?;
-        let StartGameMessage { mut world } = check_message(msg, &player_key[..])?;
+        let StartGameMessage { mut world, .. } = check_message(msg, &player_key[..])?;
 
 
         // then the start turn message
@@ -164,7 +166,7 @@ impl GameController {
             old_world: world.clone(),
             world,
             config: Config::player_config(),
-            turn: TurnMessage { changes: vec![] },
+            turn: TurnMessage { changes: vec![], checksum: None, indicators: vec![] },
             stream: Some(stream),
             player_key: Some(player_key),
             time_left_ms: Some(turn.time_left_ms)
@@ -182,8 +184,9 @@ impl GameController {
         }
 
         // extract our previous turn, replacing it with an empty one
-        let mut turn_message = TurnMessage { changes: vec![] };
+        let mut turn_message = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
         mem::swap(&mut self.turn, &mut turn_message);
+        turn_message.checksum = Some(self.world.player_checksum());
 
         // send off our previous turn
         self.stream.as_mut().unwrap().write(&SentMessage {
@@ -211,15 +214,41 @@ impl GameController {
         self.time_left_ms.expect("only the player controller should call get_time_left_ms()")
     }
 
-    /// Initializes the game world and creates a new controller
-    /// for a player to interact with it.
-    /// Mainly for testing purposes.
-    fn new_player(game: StartGameMessage) -> GameController {
+    /// Runs `f` on a copy of this controller, and returns its result. The
+    /// copy can be changed freely to try out a plan, such as moving and then
+    /// attacking, without affecting the real game: nothing done to it is sent
+    /// to the manager or kept afterwards.
+    ///
+    /// Copying is cheap. The maps and karbonite deposits are shared with the
+    /// real game until the copy changes them, so most of the cost is copying
+    /// the units.
+    pub fn speculate<T, F>(&self, f: F) -> T where F: FnOnce(&mut GameController) -> T {
+        let mut sandbox = GameController {
+            world: self.world.clone(),
+            old_world: self.old_world.clone(),
+            config: Config {
+                generate_viewer_messages: false,
+                generate_turn_messages: false,
+            },
+            turn: TurnMessage { changes: vec![], checksum: None, indicators: vec![] },
+            stream: None,
+            player_key: None,
+            time_left_ms: self.time_left_ms,
+        };
+        f(&mut sandbox)
+    }
+
+    /// Creates a controller for a player from the first message the manager
+    /// sends them, for hosts that exchange messages with the manager
+    /// themselves instead of through `new_player_env()`. Call `start_turn()`
+    /// with each start turn message, then `end_turn()` for the turn message
+    /// to send back.
+    pub fn new_player(game: StartGameMessage) -> GameController {
         GameController {
             world: game.world.clone(),
             old_world: game.world,
             config: Config::player_config(),
-            turn: TurnMessage { changes: vec![] },
+            turn: TurnMessage { changes: vec![], checksum: None, indicators: vec![] },
             stream: None,
             player_key: None,
             time_left_ms: None,
@@ -228,18 +257,26 @@ impl GameController {
 
     /// Starts the current turn, by updating the player's GameWorld with changes
     /// made since the last time the player had a turn.
-    fn start_turn(&mut self, turn: &StartTurnMessage) {
+    ///
+    /// Only for controllers created with `new_player()`. Controllers created
+    /// with `new_player_env()` start their turns in `next_turn()`.
+    pub fn start_turn(&mut self, turn: &StartTurnMessage) {
         self.old_world.start_turn(turn);
         self.world = self.old_world.clone();
-        self.turn = TurnMessage { changes: vec![] };
+        self.turn = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
         self.time_left_ms = Some(turn.time_left_ms);
     }
 
-    /// Ends the current turn. Returns the list of changes made in this turn.
-    /// Mainly for testing purposes; use next_turn().
-    fn end_turn(&mut self) -> TurnMessage {
+    /// Ends the current turn. Returns the list of changes made in this turn,
+    /// to send to the manager.
+    ///
+    /// Only for controllers created with `new_player()`. Controllers created
+    /// with `new_player_env()` end their turns in `next_turn()`.
+    pub fn end_turn(&mut self) -> TurnMessage {
         self.world.flush_viewer_changes();
-        self.turn.clone()
+        let mut turn = self.turn.clone();
+        turn.checksum = Some(self.world.player_checksum());
+        turn
     }
 
     // ************************************************************************
@@ -268,50 +305,79 @@ impl GameController {
         self.world.starting_map(planet)
     }
 
+    /// The units the given team started the game with, on both planets,
+    /// sorted by ID. The starting maps are public, so you can see the other
+    /// team's starting positions too.
+    pub fn initial_units(&self, team: Team) -> Vec<Unit> {
+        self.world.initial_units(team)
+    }
+
     /// The karbonite in the team's resource pool.
     pub fn karbonite(&self) -> u32 {
         self.world.karbonite()
     }
 
+    /// The running totals of the team's units and karbonite. You can only
+    /// see the stats of your own team.
+    ///
+    /// * TeamNotAllowed - the team is not your team.
+    pub fn team_stats(&self, team: Team) -> Result<TeamStats, Error> {
+        self.world.team_stats(team)
+    }
+
     // ************************************************************************
     // ************************** SENSING METHODS *****************************
     // ************************************************************************
 
     /// The single unit with this ID. Use this method to get detailed
-    /// statistics on a unit: heat, cooldowns, and properties of special
-    /// abilities like units garrisoned in a rocket.
+    /// statistics on a unit on your team: heat, cooldowns, and properties of
+    /// special abilities like units garrisoned in a rocket.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team.
     pub fn unit_ref(&self, id: UnitID) -> Result<&Unit, Error> {
         self.world.unit_ref(id)
     }
 
     /// The single unit with this ID. Use this method to get detailed
-    /// statistics on a unit: heat, cooldowns, and properties of special
-    /// abilities like units garrisoned in a rocket.
+    /// statistics on a unit on your team: heat, cooldowns, and properties of
+    /// special abilities like units garrisoned in a rocket.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team.
     pub fn unit(&self, id: UnitID) -> Result<Unit, Error> {
         self.world.unit(id)
     }
 
-    /// All the units within the vision range, in no particular order.
+    /// All the units within the vision range, sorted by ID.
     /// Does not include units in space.
     pub fn units_ref(&self) -> Vec<&Unit> {
         self.world.units_ref()
     }
 
-    /// All the units within the vision range, in no particular order.
+    /// All the units within the vision range, sorted by ID.
     /// Does not include units in space.
     pub fn units(&self) -> Vec<Unit> {
         self.world.units()
     }
 
-    /// All the units on your team.
-    /// Does not include units in space.
+    /// All the units on your team on your planet, sorted by ID. Includes
+    /// units in garrisons, but not units in space.
     pub fn my_units(&self) -> Vec<Unit> {
-        let my_team = self.world.team();
-        self.world.units().iter().filter(|u| u.team() == my_team).map(|u| u.clone()).collect()
+        self.world.my_units()
+    }
+
+    /// All the units on your team on your planet with the given type, sorted
+    /// by ID. Includes units in garrisons, but not units in space.
+    pub fn my_units_by_type(&self, unit_type: UnitType) -> Vec<Unit> {
+        self.world.my_units_by_type(unit_type)
+    }
+
+    /// All the units on the given planet within the vision range, sorted by
+    /// ID. Does not include units in space. You cannot see the other planet,
+    /// so it has no units.
+    pub fn units_on_planet(&self, planet: Planet) -> Vec<Unit> {
+        self.world.units_on_planet(planet)
     }
 
     /// All the units within the vision range, by ID.
@@ -326,8 +392,9 @@ impl GameController {
         self.world.units_by_loc()
     }
 
-    /// All the units of this team that are in space. You cannot see units
-    /// on the other team that are in space.
+    /// All the units of this team that are in space, sorted by ID. Includes
+    /// both the rockets and the units garrisoned inside them. You cannot see
+    /// units on the other team that are in space.
     pub fn units_in_space(&self) -> Vec<Unit> {
         self.world.units_in_space()
     }
@@ -435,6 +502,15 @@ impl GameController {
         self.world.current_duration_of_flight()
     }
 
+    /// The round within the next `window` rounds, starting with the current
+    /// round, on which a rocket launched would have the shortest flight. The
+    /// earliest such round is chosen in case of a tie. Research on rockets
+    /// shortens every flight by the same amount, so it does not change the
+    /// best round.
+    pub fn best_launch_round_within(&self, window: Rounds) -> Rounds {
+        self.world.best_launch_round_within(window)
+    }
+
     // ************************************************************************
     // *********************** COMMUNICATION METHODS **************************
     // ************************************************************************
@@ -459,6 +535,43 @@ impl GameController {
         Ok(())
     }
 
+    // ************************************************************************
+    // ************************** DEBUG METHODS *******************************
+    // ************************************************************************
+
+    /// Draws a dot at the location in the viewer, in the given color.
+    /// Indicators only appear in the viewer, and have no effect on the game.
+    ///
+    /// At most `MAX_INDICATORS_PER_TURN` indicators are kept each turn, and
+    /// any more are ignored.
+    pub fn debug_dot(&mut self, location: MapLocation, r: u8, g: u8, b: u8) {
+        self.add_indicator(Indicator::Dot { location, r, g, b });
+    }
+
+    /// Draws a line between the locations in the viewer, in the given color.
+    /// Indicators only appear in the viewer, and have no effect on the game.
+    ///
+    /// At most `MAX_INDICATORS_PER_TURN` indicators are kept each turn, and
+    /// any more are ignored.
+    pub fn debug_line(&mut self, from: MapLocation, to: MapLocation, r: u8, g: u8, b: u8) {
+        self.add_indicator(Indicator::Line { from, to, r, g, b });
+    }
+
+    /// Attaches a string to the unit in the viewer. Indicators only appear
+    /// in the viewer, and have no effect on the game.
+    ///
+    /// Strings longer than `MAX_INDICATOR_STRING_BYTES` are truncated, and
+    /// at most `MAX_INDICATORS_PER_TURN` indicators are kept each turn.
+    pub fn debug_string(&mut self, unit_id: UnitID, text: &str) {
+        self.add_indicator(Indicator::String { unit_id, text: text.to_string() });
+    }
+
+    fn add_indicator(&mut self, indicator: Indicator) {
+        if self.config.generate_turn_messages && self.turn.indicators.len() < MAX_INDICATORS_PER_TURN {
+            self.turn.indicators.extend(Indicator::cap(&[indicator]));
+        }
+    }
+
     // ************************************************************************
     // ********************** UNIT DESTRUCTION METHODS ************************
     // ************************************************************************
@@ -468,6 +581,7 @@ impl GameController {
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - the unit is not on the current player's team.
+    /// * UnitNotOnMap - the unit is in a garrison.
     pub fn disintegrate_unit(&mut self, unit_id: UnitID) -> Result<(), Error> {
         let delta = Delta::Disintegrate { unit_id };
         self.world.apply(&delta)?;
@@ -482,9 +596,9 @@ impl GameController {
     // ************************************************************************
 
     /// Whether the location is clear for a unit to occupy, either by movement
-    /// or by construction.
+    /// or by construction. Locations off the map, or on the other planet, are
+    /// never clear.
     ///
-    /// * LocationOffMap - the location is off the map.
     /// * LocationNotVisible - the location is outside the vision range.
     pub fn is_occupiable(&self, location: MapLocation) -> Result<bool, Error> {
         self.world.is_occupiable(location)
@@ -497,7 +611,21 @@ impl GameController {
         self.world.can_move(robot_id, direction)
     }
 
-    /// Whether the robot is ready to move. Tests whether the robot's attack
+    /// Whether the robot can move in the given direction now, or after
+    /// cooling down for some rounds, or is blocked by the edge of the map,
+    /// terrain, or another unit. The robot can move now if and only if
+    /// `can_move()` and `is_move_ready()` are both true.
+    ///
+    /// * NoSuchUnit - the robot does not exist (within the vision range).
+    /// * TeamNotAllowed - the robot is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a robot.
+    /// * UnitNotOnMap - the robot is not on the map.
+    /// * LocationNotVisible - the location is outside the vision range.
+    pub fn move_status(&self, robot_id: UnitID, direction: Direction) -> Result<MoveStatus, Error> {
+        self.world.move_status(robot_id, direction)
+    }
+
+    /// Whether the robot is ready to move. Tests whether the robot's movement
     /// heat is sufficiently low.
     pub fn is_move_ready(&self, robot_id: UnitID) -> bool {
         self.world.is_move_ready(robot_id)
@@ -507,6 +635,7 @@ impl GameController {
     ///
     /// * NoSuchUnit - the robot does not exist (within the vision range).
     /// * TeamNotAllowed - the robot is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a robot.
     /// * UnitNotOnMap - the robot is not on the map.
     /// * LocationNotVisible - the location is outside the vision range.
     /// * LocationOffMap - the location is off the map.
@@ -529,15 +658,34 @@ impl GameController {
     /// account the robot's attack heat. Takes into account only the robot's
     /// attack range, and the location of the robot and target.
     ///
-    /// Healers cannot attack, and should use `can_heal()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `can_heal()` instead.
     pub fn can_attack(&self, robot_id: UnitID, target_unit_id: UnitID) -> bool {
         self.world.can_attack(robot_id, target_unit_id)
     }
 
+    /// Ok if the robot can attack the given unit, and otherwise the reason
+    /// it cannot. Agrees with `can_attack()`, so it does not take into
+    /// account the robot's attack heat either.
+    ///
+    /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team, or
+    ///   the target is.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
+    /// * UnitNotOnMap - the unit or target is not on the map.
+    /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
+    pub fn attack_status(&self, robot_id: UnitID, target_unit_id: UnitID) -> Result<(), Error> {
+        self.world.attack_status(robot_id, target_unit_id)
+    }
+
     /// Whether the robot is ready to attack. Tests whether the robot's attack
     /// heat is sufficiently low.
     ///
-    /// Healers cannot attack, and should use `is_heal_ready()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `is_heal_ready()` instead.
     pub fn is_attack_ready(&self, robot_id: UnitID) -> bool {
         self.world.is_attack_ready(robot_id)
     }
@@ -545,13 +693,18 @@ impl GameController {
     /// Commands a robot to attack a unit, dealing the
     /// robot's standard amount of damage.
     ///
-    /// Healers cannot attack, and should use `heal()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `heal()` instead.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the unit is not on the current player's team.
-    /// * InappropriateUnitType - the unit is not a robot, or is a healer.
+    /// * TeamNotAllowed - the unit is not on the current player's team, or
+    ///   the target is.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
     /// * UnitNotOnMap - the unit or target is not on the map.
     /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
     /// * Overheated - the unit is not ready to attack.
     pub fn attack(&mut self, robot_id: UnitID, target_unit_id: UnitID) -> Result<(), Error> {
         let delta = Delta::Attack { robot_id, target_unit_id };
@@ -572,6 +725,24 @@ impl GameController {
         Ok(self.world.research_info())
     }
 
+    /// The branch currently being researched by the current team, or None if
+    /// the research queue is empty.
+    pub fn current_research(&self) -> Option<UnitType> {
+        self.world.current_research()
+    }
+
+    /// The number of rounds left until the current team's current research
+    /// is completed, or None if the research queue is empty.
+    pub fn research_rounds_left(&self) -> Option<Rounds> {
+        self.world.research_rounds_left()
+    }
+
+    /// The research queue of the current team, where the branch currently
+    /// being researched is at the beginning of the list.
+    pub fn research_queue(&self) -> Vec<UnitType> {
+        self.world.research_queue()
+    }
+
     /// Resets the research queue to be empty. Returns true if the queue was
     /// not empty before, and false otherwise.
     pub fn reset_research(&mut self) -> Result<bool, Error> {
@@ -598,6 +769,16 @@ impl GameController {
     // *************************** WORKER METHODS *****************************
     // ************************************************************************
 
+    /// Whether your worker has already acted (harvested, blueprinted, built, or
+    /// repaired) this round. Moving and replicating are not worker actions.
+    ///
+    /// * NoSuchUnit - the worker does not exist (inside the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker.
+    pub fn worker_has_acted(&self, worker_id: UnitID) -> Result<bool, Error> {
+        self.world.worker_has_acted(worker_id)
+    }
+
     /// Whether the worker is ready to harvest, and the given direction contains
     /// karbonite to harvest. The worker cannot already have performed an action
     /// this round.
@@ -605,6 +786,21 @@ impl GameController {
         self.world.can_harvest(worker_id, direction)
     }
 
+    /// Ok if the worker can harvest in the given direction, and otherwise
+    /// the reason it cannot. Agrees with `can_harvest()`.
+    ///
+    /// * NoSuchUnit - the worker does not exist (within the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker.
+    /// * Overheated - the worker has already performed an action this turn.
+    /// * UnitNotOnMap - the worker is not on the map.
+    /// * LocationOffMap - the location in the target direction is off the map.
+    /// * LocationNotVisible - the location is not in the vision range.
+    /// * KarboniteDepositEmpty - the location described contains no Karbonite.
+    pub fn harvest_status(&self, worker_id: UnitID, direction: Direction) -> Result<(), Error> {
+        self.world.harvest_status(worker_id, direction)
+    }
+
     /// Harvests up to the worker's harvest amount of karbonite from the given
     /// location, adding it to the team's resource pool.
     ///
@@ -635,6 +831,29 @@ impl GameController {
         self.world.can_blueprint(worker_id, unit_type, direction)
     }
 
+    /// Ok if the worker can blueprint a structure of the given type in the
+    /// given direction, and otherwise the reason it cannot. Agrees with
+    /// `can_blueprint()`.
+    ///
+    /// * NoSuchUnit - the worker does not exist (within the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker, or the unit type
+    ///   is not a structure.
+    /// * Overheated - the worker has already performed an action this turn.
+    /// * UnitNotOnMap - the unit is not on the map.
+    /// * LocationOffMap - the location in the target direction is off the map.
+    /// * LocationNotVisible - the location is outside the vision range.
+    /// * LocationNotEmpty - the location in the target direction is already
+    ///   occupied.
+    /// * CannotBuildOnMars - you cannot blueprint a structure on Mars.
+    /// * ResearchNotUnlocked - you do not have the needed research to blueprint rockets.
+    /// * InsufficientKarbonite - your team does not have enough Karbonite to
+    ///   build the requested structure.
+    pub fn blueprint_status(&self, worker_id: UnitID, unit_type: UnitType,
+                            direction: Direction) -> Result<(), Error> {
+        self.world.blueprint_status(worker_id, unit_type, direction)
+    }
+
     /// Blueprints a unit of the given type in the given direction. Subtract
     /// cost of that unit from the team's resource pool.
     ///
@@ -709,6 +928,7 @@ impl GameController {
     /// * Overheated - the worker has already performed an action this turn.
     /// * OutOfRange - the worker is not adjacent to the structure.
     /// * StructureNotYetBuilt - the structure has not been completed.
+    /// * StructureAtMaxHealth - the structure is not damaged.
     pub fn repair(&mut self, worker_id: UnitID, structure_id: UnitID) -> Result<(), Error> {
         let delta = Delta::Repair { worker_id, structure_id };
         self.world.apply(&delta)?;
@@ -727,7 +947,8 @@ impl GameController {
     }
 
     /// Replicates a worker in the given direction. Subtracts the cost of the
-    /// worker from the team's resource pool.
+    /// worker from the team's resource pool. The new worker cannot act until
+    /// the next round.
     ///
     /// * NoSuchUnit - the worker does not exist (within the vision range).
     /// * TeamNotAllowed - the worker is not on the current player's team.
@@ -770,8 +991,9 @@ impl GameController {
     /// Javelins the robot, dealing the knight's standard damage.
     ///
     /// * NoSuchUnit - either unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the knight is not on the current player's team.
-    /// * UnitNotOnMap - the knight is not on the map.
+    /// * TeamNotAllowed - the knight is not on the current player's team, or
+    ///   the target is.
+    /// * UnitNotOnMap - the knight or target is not on the map.
     /// * InappropriateUnitType - the unit is not a knight.
     /// * ResearchNotUnlocked - you do not have the needed research to use javelin.
     /// * OutOfRange - the target does not lie within ability range of the knight.
@@ -885,7 +1107,7 @@ impl GameController {
     /// * InappropriateUnitType - the unit is not a healer, or the target is not
     ///   a robot.
     /// * TeamNotAllowed - either robot is not on the current player's team.
-    /// * UnitNotOnMap - the healer is not on the map.
+    /// * UnitNotOnMap - either unit is not on the map.
     /// * OutOfRange - the target does not lie within "attack" range of the healer.
     /// * Overheated - the healer is not ready to heal again.
     pub fn heal(&mut self, healer_id: UnitID, target_robot_id: UnitID) -> Result<(), Error> {
@@ -911,11 +1133,12 @@ impl GameController {
     }
 
     /// Overcharges the robot, resetting the robot's cooldowns. The robot must
-    /// be on the same team as you.
+    /// be on the same team as you. Overcharging a sniping ranger cancels its
+    /// snipe.
     ///
     /// * NoSuchUnit - either unit does not exist (inside the vision range).
     /// * TeamNotAllowed - either robot is not on the current player's team.
-    /// * UnitNotOnMap - the healer is not on the map.
+    /// * UnitNotOnMap - either unit is not on the map.
     /// * InappropriateUnitType - the unit is not a healer, or the target is not
     ///   a robot.
     /// * ResearchNotUnlocked - you do not have the needed research to use overcharge.
@@ -935,6 +1158,16 @@ impl GameController {
     // ************************* STRUCTURE METHODS ****************************
     // ************************************************************************
 
+    /// The robots in the garrison of your structure, in the order they will be
+    /// unloaded.
+    ///
+    /// * NoSuchUnit - the structure does not exist (inside the vision range).
+    /// * TeamNotAllowed - the structure is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a structure.
+    pub fn structure_garrison(&self, structure_id: UnitID) -> Result<Vec<UnitID>, Error> {
+        self.world.structure_garrison(structure_id)
+    }
+
     /// Whether the robot can be loaded into the given structure's garrison. The robot
     /// must be ready to move and must be adjacent to the structure. The structure
     /// and the robot must be on the same team, and the structure must have space.
@@ -971,7 +1204,8 @@ impl GameController {
     }
 
     /// Unloads a robot from the garrison of the specified structure into an
-    /// adjacent space. Robots are unloaded in the order they were loaded.
+    /// adjacent space. Robots are unloaded in the order they were loaded, and
+    /// cannot act again until the next round.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - either unit is not on the current player's team.
@@ -997,6 +1231,26 @@ impl GameController {
     // ************************** FACTORY METHODS *****************************
     // ************************************************************************
 
+    /// The type of robot your factory is producing.
+    ///
+    /// * NoSuchUnit - the factory does not exist (inside the vision range).
+    /// * TeamNotAllowed - the factory is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a factory.
+    /// * NullValue - the factory is not producing.
+    pub fn factory_unit_type(&self, factory_id: UnitID) -> Result<UnitType, Error> {
+        self.world.factory_unit_type(factory_id)
+    }
+
+    /// The number of rounds left until your factory produces its robot.
+    ///
+    /// * NoSuchUnit - the factory does not exist (inside the vision range).
+    /// * TeamNotAllowed - the factory is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a factory.
+    /// * NullValue - the factory is not producing.
+    pub fn factory_rounds_left(&self, factory_id: UnitID) -> Result<Rounds, Error> {
+        self.world.factory_rounds_left(factory_id)
+    }
+
     /// Whether the factory can produce a robot of the given type. The factory
     /// must not currently be producing a robot, and the team must have
     /// sufficient resources in its resource pool.
@@ -1034,23 +1288,55 @@ impl GameController {
         self.world.rocket_landings()
     }
 
+    /// The landings of the current team's rockets in the rounds [start, end),
+    /// ordered by round and then by rocket ID.
+    pub fn landings_between(&self, start: Rounds, end: Rounds) -> Vec<(Rounds, RocketLanding)> {
+        self.world.landings_between(start, end)
+    }
+
+    /// The rockets in space that belong to the current team, grouped by the
+    /// round they are scheduled to land. Includes the units garrisoned in each
+    /// rocket. Landings of rockets that no longer exist are skipped.
+    pub fn rockets_in_space(&self) -> FnvHashMap<Rounds, Vec<Unit>> {
+        self.world.rockets_in_space()
+    }
+
     /// Whether the rocket can launch into space to the given destination. The
     /// rocket can launch if the it has never been used before. The destination
-    /// is valid if it contains passable terrain on the other planet.
+    /// is valid if it contains passable terrain on the other planet, or if it
+    /// is off the other planet's map.
     pub fn can_launch_rocket(&self, rocket_id: UnitID, destination: MapLocation) -> bool {
         self.world.can_launch_rocket(rocket_id, destination)
     }
 
+    /// Ok if the rocket can launch to the given destination, and otherwise
+    /// the reason it cannot. Agrees with `can_launch_rocket()`.
+    ///
+    /// * NoSuchUnit - the rocket does not exist (inside the vision range).
+    /// * TeamNotAllowed - the rocket is not on the current player's team.
+    /// * UnitNotOnMap - the rocket is not on the map.
+    /// * SamePlanet - the rocket cannot fly to a location on the same planet.
+    /// * InappropriateUnitType - the unit is not a rocket.
+    /// * StructureNotYetBuilt - the rocket has not yet been completed.
+    /// * RocketUsed - the rocket has already been used.
+    /// * LocationNotEmpty - the given location contains impassable terrain.
+    pub fn launch_rocket_status(&self, rocket_id: UnitID, destination: MapLocation)
+                                -> Result<(), Error> {
+        self.world.launch_rocket_status(rocket_id, destination)
+    }
+
     /// Launches the rocket into space, damaging the units adjacent to the
-    /// takeoff location.
+    /// takeoff location. If the destination is not on the map of the other
+    /// planet, the rocket flies off, never to be seen again: the rocket and
+    /// its garrison are destroyed.
     ///
     /// * NoSuchUnit - the rocket does not exist (inside the vision range).
     /// * TeamNotAllowed - the rocket is not on the current player's team.
+    /// * UnitNotOnMap - the rocket is not on the map.
     /// * SamePlanet - the rocket cannot fly to a location on the same planet.
     /// * InappropriateUnitType - the unit is not a rocket.
     /// * StructureNotYetBuilt - the rocket has not yet been completed.
     /// * RocketUsed - the rocket has already been used.
-    /// * LocationOffMap - the given location is off the map.
     /// * LocationNotEmpty - the given location contains impassable terrain.
     pub fn launch_rocket(&mut self, rocket_id: UnitID, location: MapLocation)
                          -> Result<(), Error> {
@@ -1071,16 +1357,27 @@ impl GameController {
     // ************************************************************************
 
     /// Initializes the game world and creates a new controller
-    /// for the manager to interact with it.
+    /// for the manager to interact with it. Each player's time pool starts
+    /// at TIME_POOL_MS, and gains TIME_PER_TURN_MS after each of its turns.
     ///
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
     pub fn new_manager(map: GameMap) -> GameController {
-        let world = GameWorld::new(map);
+        GameController::new_manager_with_time(map, TIME_POOL_MS, TIME_PER_TURN_MS)
+    }
+
+    /// Initializes the game world and creates a new controller for the
+    /// manager to interact with it. Each player's time pool starts at
+    /// `time_pool_ms`, and gains `time_per_turn_ms` after each of its turns.
+    ///
+    /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
+    pub fn new_manager_with_time(map: GameMap, time_pool_ms: i32,
+                                 time_per_turn_ms: i32) -> GameController {
+        let world = GameWorld::with_time(map, time_pool_ms, time_per_turn_ms);
         GameController {
             world: world.clone(),
             old_world: world,
             config: Config::runner_config(),
-            turn: TurnMessage { changes: vec![] },
+            turn: TurnMessage { changes: vec![], checksum: None, indicators: vec![] },
             stream: None,
             player_key: None,
             time_left_ms: None,
@@ -1101,7 +1398,7 @@ impl GameController {
         world.cached_world.clear();
         InitialTurnApplication {
             start_turn: self.world.initial_start_turn_message(time_left_ms),
-            viewer: ViewerKeyframe { world },
+            viewer: ViewerKeyframe { schema_version: SCHEMA_VERSION, world },
         }
     }
 
@@ -1110,47 +1407,97 @@ impl GameController {
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
     pub fn start_game(&self, player: Player) -> StartGameMessage {
         StartGameMessage {
+            schema_version: SCHEMA_VERSION,
             world: self.world.cached_world(player).clone(),
+            player,
         }
     }
 
     /// Given a TurnMessage from a player, apply those changes.
     /// Receives the StartTurnMessage for the next player.
     ///
+    /// Changes that fail to apply are skipped, and reported to the viewer
+    /// alongside the error they raised, up to MAX_LOGGED_FAILURES_PER_TURN
+    /// of them. If the turn carries a checksum that
+    /// does not match the manager's state after applying it, the player's
+    /// world has diverged from the manager's, and the application is marked
+    /// as desynced.
+    ///
+    /// The turn's debug indicators are passed on to the viewer, capped by
+    /// `Indicator::cap()`.
+    ///
     /// The time left is the amount of time left for the next player to go,
     /// and not the player whose turn you are applying.
     ///
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
     pub fn apply_turn(&mut self, turn: &TurnMessage, time_left_ms: i32) -> TurnApplication {
+        let team = self.world.team();
         // Serialize the filtered game state to send to the player
-        let start_turn = self.world.apply_turn(turn, time_left_ms);
+        self.world.apply_many(&turn.changes);
+        let errors = self.world.action_log().to_vec();
+        let desynced = match turn.checksum {
+            Some(checksum) => checksum != self.world.player_checksum(),
+            None => false,
+        };
+        let start_turn = self.world.end_turn(time_left_ms);
         // Serialize the game state to send to the viewer
         let viewer = ViewerMessage {
+            schema_version: SCHEMA_VERSION,
             changes: turn.changes.clone(),
+            errors,
             units: self.world.get_viewer_units(),
             additional_changes: self.world.flush_viewer_changes(),
             karbonite: self.world.karbonite(),
+            indicators: TeamIndicators { team, indicators: Indicator::cap(&turn.indicators) },
         };
         TurnApplication {
-            start_turn, viewer
+            start_turn, viewer, desynced
         }
     }
 
-    /// Determines if the game has ended, returning the winning team if so.
+    /// Takes the time the player to move spent on its turn out of its time
+    /// pool. Call it after receiving each turn, before applying it. Once a
+    /// player's pool goes negative, its turns are skipped and its team loses.
+    ///
+    /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
+    pub fn apply_time_used(&mut self, time_used_ms: i32) {
+        let player = self.world.player_to_move();
+        self.world.apply_time_used(player, time_used_ms);
+    }
+
+    /// The time left in the player's time pool, in milliseconds.
     ///
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
-    pub fn is_game_over(&self) -> Option<Team> {
-        self.world.is_game_over()
+    pub fn time_left(&self, player: Player) -> i32 {
+        self.world.time_left(player)
     }
 
+    /// Whether the player has run out of time.
+    ///
+    /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
+    pub fn is_timed_out(&self, player: Player) -> bool {
+        self.world.is_timed_out(player)
+    }
+
+    /// The winning team, if the game has ended.
+    ///
+    /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
+    pub fn winner(&self) -> Option<Team> {
+        self.world.winner()
+    }
+
+    /// Whether the game has ended.
+    ///
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
     pub fn is_over(&self) -> bool {
-        self.is_game_over().is_some()
+        self.world.is_over()
     }
 
+    /// The winning team, or an error if the game has not ended.
+    ///
     /// DO NOT CALL THIS FUNCTION UNLESS YOU ARE THE MANAGER!
     pub fn winning_team(&self) -> Result<Team, Error> {
-        if let Some(team) = self.is_game_over() {
+        if let Some(team) = self.winner() {
             Ok(team)
         } else {
             bail!("Game is not finished");
@@ -1417,7 +1764,9 @@ impl GameController {
 #[derive(Debug, Clone)]
 pub struct TurnApplication {
     pub start_turn: StartTurnMessage,
-    pub viewer: ViewerMessage
+    pub viewer: ViewerMessage,
+    /// Whether the player's checksum disagreed with the manager's state.
+    pub desynced: bool,
 }
 
 /// Returned from initial_start_turn_message.
@@ -1478,13 +1827,58 @@ pub fn run_game_ansi<R, B>(mut r: R, mut b: B, turns: usize, delay: u32)
         pcs[p].print_game_ansi();
         use std::{thread, time};
         thread::sleep(time::Duration::from_millis(delay.into()));
-        if let Some(team) = master.is_game_over() {
+        if let Some(team) = master.winner() {
             println!("Winner: {:?}", team);
             return;
         }
     }
 }
 
+/// The outcome of a game played by `run_game()`.
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    /// The team that won the game.
+    pub winner: Team,
+    /// The round the game ended on.
+    pub rounds: Rounds,
+    /// A recording of every turn in the game.
+    pub replay: GameReplay,
+}
+
+/// Plays a game between two rust bots on the given map until it ends, and
+/// records it. Unlike `run_game_ansi()`, prints nothing.
+///
+/// Errors if either bot returns an error.
+pub fn run_game<R, B>(map: GameMap, mut r: R, mut b: B) -> Result<GameResult, Error>
+        where R: FnMut(&mut GameController) -> Result<(), Error>,
+              B: FnMut(&mut GameController) -> Result<(), Error> {
+
+    // A filler time that doesn't matter for games without a time limit.
+    let time = 10000;
+    let mut replay = GameReplay::new(map.clone());
+    let mut master = GameController::new_manager(map);
+    let players = Player::all();
+    let mut pcs: Vec<_> = players.iter().map(|p| GameController::new_player(master.start_game(*p))).collect();
+
+    let mut start_turn = master.initial_start_turn_message(time).start_turn;
+    for p in (0..players.len()).cycle() {
+        pcs[p].start_turn(&start_turn);
+        if players[p].team == Red {
+            r(&mut pcs[p])?;
+        } else {
+            b(&mut pcs[p])?;
+        }
+
+        let turn = pcs[p].end_turn();
+        start_turn = master.apply_turn(&turn, time).start_turn;
+        replay.record(&master.world, &turn);
+        if let Some(winner) = master.winner() {
+            return Ok(GameResult { winner, rounds: master.round(), replay });
+        }
+    }
+    unreachable!("the players take turns until the game is over");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1551,6 +1945,263 @@ mod tests {
         assert![player_controller_blue.move_robot(blue_robot, Direction::West).is_ok()];
     }
 
+    #[test]
+    fn test_turn_deltas() {
+        let red_player = Player::new(Team::Red, Planet::Earth);
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = vec![
+            Unit::new(1, Team::Red, UnitType::Worker, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 0, 0))).unwrap(),
+            Unit::new(2, Team::Red, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 5, 5))).unwrap(),
+            Unit::new(3, Team::Blue, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 7, 5))).unwrap(),
+        ];
+        let mut manager = GameController::new_manager(map.clone());
+        let start_game_msg = manager.start_game(red_player);
+        let start_turn_msg = manager.initial_start_turn_message(10000).start_turn;
+
+        // Red scripts a whole turn, and each action is visible immediately.
+        let mut player = GameController::new_player(start_game_msg.clone());
+        player.start_turn(&start_turn_msg);
+        let karbonite = player.karbonite();
+        assert![player.harvest(1, Direction::North).is_ok()];
+        assert_gt![player.karbonite(), karbonite];
+        assert![player.move_robot(2, Direction::East).is_ok()];
+        assert![player.can_attack(2, 3)];
+        assert![player.attack(2, 3).is_ok()];
+        assert_lt![player.units_by_id()[&3].health(), 250];
+        let turn = player.end_turn();
+        assert_eq![turn.changes, vec![
+            Delta::Harvest { worker_id: 1, direction: Direction::North },
+            Delta::Move { robot_id: 2, direction: Direction::East },
+            Delta::Attack { robot_id: 2, target_unit_id: 3 },
+        ]];
+
+        // Applying the deltas to a fresh copy of the world reproduces it.
+        let mut fresh = GameController::new_player(start_game_msg);
+        fresh.start_turn(&start_turn_msg);
+        assert![fresh.world.apply_many(&turn.changes).iter().all(|r| r.is_ok())];
+        assert_eq![fresh.world.planet_states, player.world.planet_states];
+        assert_eq![fresh.world.team_states, player.world.team_states];
+
+        // So does the manager, and it agrees with the player's checksum.
+        assert![turn.checksum.is_some()];
+        let application = manager.apply_turn(&turn, 10000);
+        assert![application.viewer.errors.is_empty()];
+        assert![!application.desynced];
+        for id in 1..4 {
+            assert_eq![manager.world.planet_states[&Planet::Earth].units[&id], player.units_by_id()[&id]];
+        }
+        assert_eq![manager.manager_karbonite(Team::Red), player.karbonite()];
+
+        // A player whose world has diverged sends a checksum the manager
+        // does not agree with.
+        let mut diverged = turn.clone();
+        diverged.changes.pop();
+        let mut other_manager = GameController::new_manager(map);
+        other_manager.initial_start_turn_message(10000);
+        assert![other_manager.apply_turn(&diverged, 10000).desynced];
+    }
+
+    #[test]
+    fn test_speculate() {
+        let red_player = Player::new(Team::Red, Planet::Earth);
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = vec![
+            Unit::new(1, Team::Red, UnitType::Worker, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 0, 0))).unwrap(),
+            Unit::new(2, Team::Red, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 5, 5))).unwrap(),
+            Unit::new(3, Team::Blue, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 7, 5))).unwrap(),
+        ];
+        let manager = GameController::new_manager(map);
+        let mut player = GameController::new_player(manager.start_game(red_player));
+        player.start_turn(&manager.initial_start_turn_message(10000).start_turn);
+
+        let deposit = MapLocation::new(Planet::Earth, 0, 1);
+        let karbonite_at = player.karbonite_at(deposit).unwrap();
+        let before = player.world.clone();
+
+        // The sandbox sees its own changes.
+        let health = player.speculate(|sandbox| {
+            sandbox.harvest(1, Direction::North).unwrap();
+            sandbox.move_robot(2, Direction::East).unwrap();
+            sandbox.attack(2, 3).unwrap();
+            assert_lt![sandbox.karbonite_at(deposit).unwrap(), karbonite_at];
+            sandbox.units_by_id()[&3].health()
+        });
+        assert_lt![health, 250];
+
+        // None of them leak into the real world or turn.
+        assert_eq![player.world, before];
+        assert_eq![player.karbonite_at(deposit).unwrap(), karbonite_at];
+        assert_eq![player.units_by_id()[&3].health(), 250];
+        assert![player.turn.changes.is_empty()];
+        assert![player.move_robot(2, Direction::East).is_ok()];
+    }
+
+    #[test]
+    fn test_run_game() {
+        use strategies;
+
+        // A red knight near a lone blue worker.
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = vec![
+            Unit::new(1, Team::Red, UnitType::Worker, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 1, 1))).unwrap(),
+            Unit::new(2, Team::Red, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 2, 1))).unwrap(),
+            Unit::new(3, Team::Blue, UnitType::Worker, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 12, 4))).unwrap(),
+        ];
+
+        // The rush hunts the worker down well before the round limit.
+        let result = run_game(map.clone(), strategies::rush, strategies::idle).unwrap();
+        assert_eq!(result.winner, Team::Red);
+        assert!(result.rounds < 50, "the game took {} rounds", result.rounds);
+        assert_eq!(result.replay.play().unwrap().checksum(),
+                   *result.replay.checksums.last().unwrap());
+
+        // The game plays out the same way every time.
+        let again = run_game(map.clone(), strategies::rush, strategies::idle).unwrap();
+        assert_eq!(again.rounds, result.rounds);
+        assert_eq!(again.replay, result.replay);
+
+        // A bot that errors ends the game early.
+        let failing = |_: &mut GameController| -> Result<(), Error> { bail!("oops") };
+        assert!(run_game(map, strategies::idle, failing).is_err());
+    }
+
+    #[test]
+    fn test_message_exchange() {
+        use serde_json::{from_str, to_string};
+        use std::sync::mpsc::channel;
+
+        let red_player = Player::new(Team::Red, Planet::Earth);
+        let blue_player = Player::new(Team::Blue, Planet::Earth);
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = vec![
+            Unit::new(1, Team::Red, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 0, 0))).unwrap(),
+            Unit::new(2, Team::Blue, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 5, 0))).unwrap(),
+        ];
+        let mut manager = GameController::new_manager(map);
+        let time = 10000;
+
+        // Messages travel as JSON, as they would over a socket.
+        let (to_red, red_inbox) = channel::<String>();
+        let (to_blue, blue_inbox) = channel::<String>();
+        let (to_manager, manager_inbox) = channel::<String>();
+
+        // Each player receives its identity and its view of the world.
+        to_red.send(to_string(&manager.start_game(red_player)).unwrap()).unwrap();
+        to_blue.send(to_string(&manager.start_game(blue_player)).unwrap()).unwrap();
+        let red_start: StartGameMessage = from_str(&red_inbox.recv().unwrap()).unwrap();
+        let blue_start: StartGameMessage = from_str(&blue_inbox.recv().unwrap()).unwrap();
+        assert_eq!(red_start.player, red_player);
+        assert_eq!(blue_start.player, blue_player);
+        let mut red = GameController::new_player(red_start);
+        let mut blue = GameController::new_player(blue_start);
+
+        // Red moves its knight east.
+        let initial = manager.initial_start_turn_message(time);
+        to_red.send(to_string(&initial.start_turn).unwrap()).unwrap();
+        red.start_turn(&from_str(&red_inbox.recv().unwrap()).unwrap());
+        red.move_robot(1, Direction::East).unwrap();
+        to_manager.send(to_string(&red.end_turn()).unwrap()).unwrap();
+
+        let turn: TurnMessage = from_str(&manager_inbox.recv().unwrap()).unwrap();
+        let application = manager.apply_turn(&turn, time);
+        assert_eq!(application.viewer.errors, vec![]);
+        to_blue.send(to_string(&application.start_turn).unwrap()).unwrap();
+
+        // Blue sees red's knight move, and sends a turn with a bogus delta
+        // ahead of its own move.
+        blue.start_turn(&from_str(&blue_inbox.recv().unwrap()).unwrap());
+        assert_eq!(blue.sense_unit_at_location(MapLocation::new(Planet::Earth, 1, 0)).unwrap().id(), 1);
+        blue.move_robot(2, Direction::West).unwrap();
+        let mut turn = blue.end_turn();
+        turn.changes.insert(0, Delta::Disintegrate { unit_id: 1 });
+        to_manager.send(to_string(&turn).unwrap()).unwrap();
+
+        // The manager skips the failed delta and reports it to the viewer.
+        let turn: TurnMessage = from_str(&manager_inbox.recv().unwrap()).unwrap();
+        let application = manager.apply_turn(&turn, time);
+        let viewer: ViewerMessage = from_str(&to_string(&application.viewer).unwrap()).unwrap();
+        assert_eq!(viewer.errors.len(), 1);
+        assert_eq!(viewer.errors[0].index, 0);
+        let knight = |id| viewer.units.iter().find(|unit| unit.id == id).map(|unit| unit.location);
+        assert_eq!(knight(1), Some(MapLocation::new(Planet::Earth, 1, 0)));
+        assert_eq!(knight(2), Some(MapLocation::new(Planet::Earth, 4, 0)));
+    }
+
+    #[test]
+    fn test_indicators() {
+        use serde_json::to_string;
+
+        let mut manager = GameController::new_manager(GameMap::test_map());
+        let mut red = GameController::new_player(manager.start_game(Player::new(Team::Red, Planet::Earth)));
+        let mut blue = GameController::new_player(manager.start_game(Player::new(Team::Blue, Planet::Earth)));
+        red.start_turn(&manager.initial_start_turn_message(10000).start_turn);
+
+        // Red draws more indicators than the cap allows.
+        let loc = MapLocation::new(Planet::Earth, 1, 1);
+        red.debug_string(1, &"secret".repeat(20));
+        red.debug_line(loc, MapLocation::new(Planet::Earth, 5, 5), 0, 255, 0);
+        for _ in 0..MAX_INDICATORS_PER_TURN {
+            red.debug_dot(loc, 255, 0, 0);
+        }
+        let turn = red.end_turn();
+        assert_eq!(turn.indicators.len(), MAX_INDICATORS_PER_TURN);
+        match turn.indicators[0] {
+            Indicator::String { ref text, .. } => assert_eq!(text.len(), MAX_INDICATOR_STRING_BYTES),
+            _ => panic!("expected a string"),
+        }
+        assert_eq!(turn.indicators[1], Indicator::Line {
+            from: loc, to: MapLocation::new(Planet::Earth, 5, 5), r: 0, g: 255, b: 0,
+        });
+
+        // The viewer gets red's indicators, capped even if the player
+        // bypassed the controller.
+        let mut bloated = turn.clone();
+        bloated.indicators.push(Indicator::Dot { location: loc, r: 0, g: 0, b: 255 });
+        let application = manager.apply_turn(&bloated, 10000);
+        assert_eq!(application.viewer.indicators, TeamIndicators {
+            team: Team::Red,
+            indicators: turn.indicators.clone(),
+        });
+
+        // Blue never sees them.
+        assert!(!to_string(&application.start_turn).unwrap().contains("secret"));
+        blue.start_turn(&application.start_turn);
+        assert!(!to_string(&blue.world).unwrap().contains("secret"));
+        assert_eq!(blue.end_turn().indicators, vec![]);
+        assert!(!to_string(&manager.world).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn test_manager_time_pool() {
+        let mut manager = GameController::new_manager_with_time(GameMap::test_map(), 500, 25);
+        let red = Player::new(Team::Red, Planet::Earth);
+        let blue = Player::new(Team::Blue, Planet::Earth);
+        assert_eq!(manager.time_left(red), 500);
+        assert_eq!(manager.time_left(blue), 500);
+
+        // Red's turn comes out of its own pool, which gains the increment.
+        manager.apply_time_used(120);
+        let turn = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
+        manager.apply_turn(&turn, 500);
+        assert_eq!(manager.time_left(red), 500 - 120 + 25);
+        assert!(!manager.is_timed_out(red));
+
+        // Blue spends more than its pool, though not the default pool.
+        manager.apply_time_used(501);
+        assert!(manager.is_timed_out(blue));
+    }
+
     #[test]
     fn test_serialization() {
         use serde_json::to_string;
@@ -1569,18 +2220,18 @@ mod tests {
         println!("----initial cached_world {}", initial.viewer.world.cached_world.len());
         println!("{}", to_string(&initial.viewer.world.cached_world).unwrap());
         println!("----apply");
-        let t = TurnMessage { changes: vec![] };
+        let t = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
         let a = c.apply_turn(&t, filler_time);
         println!("{}", to_string(&a.viewer).unwrap());
     }
 
     #[test]
     fn uneven_viewer_message() {
-        let manager = GameController::new_manager(GameMap::parse_text_map(include_str!("../map/fat.bc18t")).unwrap());
+        let manager = GameController::new_manager(GameMap::parse_text_map(include_str!("../../tests/maps/fat.bc18t")).unwrap());
         manager.manager_viewer_message();
         manager.print_game_ansi();
 
-        let manager = GameController::new_manager(GameMap::parse_text_map(include_str!("../map/tall.bc18t")).unwrap());
+        let manager = GameController::new_manager(GameMap::parse_text_map(include_str!("../../tests/maps/tall.bc18t")).unwrap());
         manager.manager_viewer_message();
         manager.print_game_ansi();
     }
@@ -1,5 +1,6 @@
 //! Detailed game errors.
-use super::unit::UnitType;
+use super::location::MapLocation;
+use super::unit::{UnitID, UnitType};
 
 /// Detailed game errors.
 #[derive(Debug, Fail, PartialEq, Eq)]
@@ -20,6 +21,10 @@ pub enum GameError {
     #[fail(display = "The factory is already producing a unit.")]
     FactoryBusy,
 
+    /// The game is over, and no more actions can be taken.
+    #[fail(display = "The game is over, and no more actions can be taken.")]
+    GameOver,
+
     /// The structure's garrison is empty.
     #[fail(display = "The structure's garrison is empty.")]
     GarrisonEmpty,
@@ -36,25 +41,39 @@ pub enum GameError {
     #[fail(display = "The map-related object is invalid.")]
     InvalidMapObject,
 
+    /// The saved game world is invalid, or was saved by a different version
+    /// of the engine.
+    #[fail(display = "The saved game world is invalid, or was saved by a different version of the engine.")]
+    InvalidSnapshot,
+
     /// Your team does not have enough Karbonite to perform the requested action.
     #[fail(display = "Your team does not have enough Karbonite to perform the requested action.")]
     InsufficientKarbonite,
 
+    /// The engine's internal state is inconsistent. This is a bug in the
+    /// engine, not in your code.
+    #[fail(display = "The engine's internal state is inconsistent.")]
+    InternalEngineError,
+
     /// The Karbonite deposit is empty and cannot be harvested further.
     #[fail(display = "The Karbonite deposit is empty and cannot be harvested further.")]
     KarboniteDepositEmpty,
 
     /// The location corresponding to the requested action is not empty.
-    #[fail(display = "The location corresponding to the requested action is not empty.")]
-    LocationNotEmpty,
+    #[fail(display = "The location {:?} corresponding to the requested action is not empty.", location)]
+    LocationNotEmpty { location: MapLocation },
 
     /// The location is outside your vision range.
     #[fail(display = "The location is outside your vision range.")]
     LocationNotVisible,
 
     /// The location is off the map of the current planet.
-    #[fail(display = "The location is off the map of the current planet.")]
-    LocationOffMap,
+    #[fail(display = "The location {:?} is off the map of the current planet.", location)]
+    LocationOffMap { location: MapLocation },
+
+    /// The game has run out of unit IDs, and no more units can be created.
+    #[fail(display = "The game has run out of unit IDs, and no more units can be created.")]
+    NoMoreUnitIDs,
 
     /// The specified unit does not exist, at least within your vision range.
     #[fail(display = "The specified unit does not exist, at least within your vision range.")]
@@ -69,8 +88,8 @@ pub enum GameError {
     OutOfRange,
 
     /// The unit's heat is not low enough to perform the requested action.
-    #[fail(display = "The unit's heat is not low enough to perform the requested action.")]
-    Overheated,
+    #[fail(display = "The heat of unit {} is not low enough to perform the requested action.", unit_id)]
+    Overheated { unit_id: UnitID },
 
     /// The level of research does not exist for this branch.
     #[fail(display = "The level of research does not exist for this branch.")]
@@ -92,6 +111,10 @@ pub enum GameError {
     #[fail(display = "The structure has already been completed, and cannot be built further.")]
     StructureAlreadyBuilt,
 
+    /// The structure is already at full health, and cannot be repaired.
+    #[fail(display = "The structure is already at full health, and cannot be repaired.")]
+    StructureAtMaxHealth,
+
     /// The structure has not yet been completed, and cannot perform actions yet.
     #[fail(display = "The structure has not yet been completed, and cannot perform actions yet.")]
     StructureNotYetBuilt,
@@ -100,6 +123,10 @@ pub enum GameError {
     #[fail(display = "You are not allowed to control units on the other team.")]
     TeamNotAllowed,
 
+    /// The unit is too close to perform an action.
+    #[fail(display = "The unit is too close to perform an action.")]
+    TooClose,
+
     /// The unit is in a structure's garrison or flying through space.
     #[fail(display = "The unit is in a structure's garrison or flying through space.")]
     UnitNotOnMap,
@@ -111,18 +138,19 @@ pub enum GameError {
 
 /// Asserts that $left is an Err whose unwrapped value is the game error
 /// $right. This macro is helpful since all of our Errors wrap GameErrors,
-/// and we can use this to ensure it's the correct type of GameError.
+/// and we can use this to ensure it's the correct type of GameError. Any
+/// context added to the error is looked through.
 #[cfg(test)]
 macro_rules! assert_err {
     ($left:expr, $right:expr) => ({
         assert_eq!(
-            $left.unwrap_err().downcast::<GameError>().expect("wrong error type"),
+            *$left.unwrap_err().find_root_cause().downcast_ref::<GameError>().expect("wrong error type"),
             $right
         )
     });
     ($left:expr, $right:expr, $($arg:tt)+) => ({
         assert_eq!(
-            $left.unwrap_err().downcast::<GameError>().expect("wrong error type"),
+            *$left.unwrap_err().find_root_cause().downcast_ref::<GameError>().expect("wrong error type"),
             $right,
             format_args!($($arg)+)
         )
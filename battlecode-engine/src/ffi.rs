@@ -0,0 +1,626 @@
+//! A C ABI over a player's `GameController`, for hosts that run players in
+//! other languages and exchange messages with the manager themselves. Only
+//! built with the `ffi` feature. The declarations are in
+//! `include/battlecode.h`.
+//!
+//! A host creates a controller from the manager's serialized
+//! `StartGameMessage`, then for every turn passes in the serialized
+//! `StartTurnMessage`, queries the world and issues actions by unit ID, and
+//! ends the turn to get the serialized `TurnMessage` to send back.
+//!
+//! Every function returns `BC_OK` or one of the `BC_ERROR_*` codes, and
+//! writes its results through out-pointers. Panics are caught and reported
+//! as `BC_ERROR_PANIC`, after which the controller should be freed. After
+//! any error, `bc_last_error()` describes it. Everything returned to the
+//! host is freed with the matching `*_free` function.
+//!
+//! # Safety
+//!
+//! Every pointer passed in must be null, or valid for the function's use of
+//! it: controllers from `bc_controller_new()` that have not been freed,
+//! NUL-terminated strings, and writable out-pointers. A controller must not
+//! be used from two threads at once. The functions check for null, but
+//! cannot check anything else.
+
+// The safety requirements are the same for every function, and are given
+// above.
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use failure::Error;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use super::controller::GameController;
+use super::location::*;
+use super::location::Location::*;
+use super::unit::*;
+
+/// The call succeeded.
+pub const BC_OK: i32 = 0;
+/// A pointer argument was null.
+pub const BC_ERROR_NULL: i32 = 1;
+/// A message was not valid UTF-8, or could not be deserialized.
+pub const BC_ERROR_INVALID_MESSAGE: i32 = 2;
+/// An enum argument was out of range.
+pub const BC_ERROR_INVALID_ARGUMENT: i32 = 3;
+/// The engine refused the query or action, for example because a unit is
+/// overheated.
+pub const BC_ERROR_GAME: i32 = 4;
+/// The engine panicked. The controller may be inconsistent, and should only
+/// be freed.
+pub const BC_ERROR_PANIC: i32 = 5;
+
+/// The unit is on the map, at `planet`, `x` and `y`.
+pub const BC_LOCATION_ON_MAP: u32 = 0;
+/// The unit is in the garrison of the structure `structure_id`.
+pub const BC_LOCATION_IN_GARRISON: u32 = 1;
+/// The unit is a rocket in space.
+pub const BC_LOCATION_IN_SPACE: u32 = 2;
+/// The unit is not in the game.
+pub const BC_LOCATION_UNKNOWN: u32 = 3;
+
+/// A unit's location, as a tag and the fields that tag uses. Unused fields
+/// are zero.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BcLocation {
+    /// One of the `BC_LOCATION_*` tags.
+    pub tag: u32,
+    pub planet: u32,
+    pub x: i32,
+    pub y: i32,
+    pub structure_id: u16,
+}
+
+/// The public state of a unit.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BcUnit {
+    pub id: u16,
+    /// A `Team`, as an integer.
+    pub team: u32,
+    /// A `UnitType`, as an integer.
+    pub unit_type: u32,
+    pub health: u32,
+    pub location: BcLocation,
+}
+
+impl BcUnit {
+    fn new(unit: &Unit) -> BcUnit {
+        let mut location = BcLocation { tag: 0, planet: 0, x: 0, y: 0, structure_id: 0 };
+        match unit.location() {
+            OnMap(map_location) => {
+                location.tag = BC_LOCATION_ON_MAP;
+                location.planet = map_location.planet as u32;
+                location.x = map_location.x;
+                location.y = map_location.y;
+            },
+            InGarrison(structure_id) => {
+                location.tag = BC_LOCATION_IN_GARRISON;
+                location.structure_id = structure_id;
+            },
+            InSpace => location.tag = BC_LOCATION_IN_SPACE,
+            Unknown => location.tag = BC_LOCATION_UNKNOWN,
+        }
+        BcUnit {
+            id: unit.id(),
+            team: unit.team() as u32,
+            unit_type: unit.unit_type() as u32,
+            health: unit.health(),
+            location,
+        }
+    }
+}
+
+thread_local! {
+    /// The description of the last error on this thread.
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// A failed call, with its status code and a description.
+struct Failure {
+    code: i32,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: i32, message: &str) -> Failure {
+        Failure { code, message: message.to_string() }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Failure {
+        Failure { code: BC_ERROR_GAME, message: error.to_string() }
+    }
+}
+
+/// Runs the body of an exported function, catching panics, recording the
+/// error if there is one, and returning the status code.
+fn call<F>(body: F) -> i32 where F: FnOnce() -> Result<(), Failure> {
+    let result = panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+        Err(Failure::new(BC_ERROR_PANIC, "The engine panicked."))
+    });
+    let (code, message) = match result {
+        Ok(()) => (BC_OK, None),
+        Err(failure) => (failure.code, Some(failure.message)),
+    };
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+    code
+}
+
+unsafe fn controller<'a>(controller: *mut GameController)
+                         -> Result<&'a mut GameController, Failure> {
+    controller.as_mut().ok_or_else(|| Failure::new(BC_ERROR_NULL, "The controller is null."))
+}
+
+unsafe fn message<T: DeserializeOwned>(json: *const c_char) -> Result<T, Failure> {
+    if json.is_null() {
+        return Err(Failure::new(BC_ERROR_NULL, "The message is null."));
+    }
+    let json = CStr::from_ptr(json).to_str()
+        .map_err(|_| Failure::new(BC_ERROR_INVALID_MESSAGE, "The message is not UTF-8."))?;
+    serde_json::from_str(json)
+        .map_err(|e| Failure { code: BC_ERROR_INVALID_MESSAGE, message: e.to_string() })
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), Failure> {
+    match out.as_mut() {
+        Some(out) => {
+            *out = value;
+            Ok(())
+        },
+        None => Err(Failure::new(BC_ERROR_NULL, "The output pointer is null.")),
+    }
+}
+
+/// Hands a string to the host, to be freed with `bc_string_free()`.
+fn string(value: String) -> *mut c_char {
+    // Serialized messages and error descriptions never contain a NUL.
+    CString::new(value).expect("no NUL in the string").into_raw()
+}
+
+unsafe fn write_units(units: Vec<Unit>, out_units: *mut *mut BcUnit,
+                      out_len: *mut usize) -> Result<(), Failure> {
+    if out_units.is_null() || out_len.is_null() {
+        return Err(Failure::new(BC_ERROR_NULL, "The output pointer is null."));
+    }
+    let units: Box<[BcUnit]> = units.iter().map(BcUnit::new).collect::<Vec<_>>().into_boxed_slice();
+    write(out_len, units.len())?;
+    write(out_units, Box::into_raw(units) as *mut BcUnit)
+}
+
+fn planet(planet: u32) -> Result<Planet, Failure> {
+    match planet {
+        0 => Ok(Planet::Earth),
+        1 => Ok(Planet::Mars),
+        _ => Err(Failure::new(BC_ERROR_INVALID_ARGUMENT, "There is no such planet.")),
+    }
+}
+
+fn map_location(planet_index: u32, x: i32, y: i32) -> Result<MapLocation, Failure> {
+    Ok(MapLocation::new(planet(planet_index)?, x, y))
+}
+
+fn direction(direction: u32) -> Result<Direction, Failure> {
+    Direction::all().into_iter().chain(Some(Direction::Center))
+        .find(|d| *d as u32 == direction)
+        .ok_or_else(|| Failure::new(BC_ERROR_INVALID_ARGUMENT, "There is no such direction."))
+}
+
+fn unit_type(unit_type: u32) -> Result<UnitType, Failure> {
+    UnitType::all().into_iter()
+        .find(|t| *t as u32 == unit_type)
+        .ok_or_else(|| Failure::new(BC_ERROR_INVALID_ARGUMENT, "There is no such unit type."))
+}
+
+// ****************************************************************************
+// ***************************** MEMORY AND ERRORS ****************************
+// ****************************************************************************
+
+/// Frees a string returned by the library.
+#[no_mangle]
+pub unsafe extern "C" fn bc_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Frees an array of units returned by the library.
+#[no_mangle]
+pub unsafe extern "C" fn bc_units_free(units: *mut BcUnit, len: usize) {
+    if !units.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(units, len)));
+    }
+}
+
+/// A description of the last error on this thread, or null if the last call
+/// succeeded. Free it with `bc_string_free()`.
+#[no_mangle]
+pub extern "C" fn bc_last_error() -> *mut c_char {
+    LAST_ERROR.with(|last_error| match *last_error.borrow() {
+        Some(ref message) => string(message.clone()),
+        None => ptr::null_mut(),
+    })
+}
+
+// ****************************************************************************
+// ******************************** TURN LOOP *********************************
+// ****************************************************************************
+
+/// Creates a player's controller from the serialized `StartGameMessage` the
+/// manager sent them. Free it with `bc_controller_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_new(start_game: *const c_char,
+                                           out: *mut *mut GameController) -> i32 {
+    call(|| {
+        if out.is_null() {
+            return Err(Failure::new(BC_ERROR_NULL, "The output pointer is null."));
+        }
+        let controller = GameController::new_player(message(start_game)?);
+        write(out, Box::into_raw(Box::new(controller)))
+    })
+}
+
+/// Frees a controller.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_free(controller: *mut GameController) {
+    if !controller.is_null() {
+        drop(Box::from_raw(controller));
+    }
+}
+
+/// Starts the turn with the serialized `StartTurnMessage` from the manager.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_start_turn(c: *mut GameController,
+                                                  start_turn: *const c_char) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        c.start_turn(&message(start_turn)?);
+        Ok(())
+    })
+}
+
+/// Ends the turn, returning the serialized `TurnMessage` to send to the
+/// manager. Free it with `bc_string_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_end_turn(c: *mut GameController,
+                                                out: *mut *mut c_char) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        if out.is_null() {
+            return Err(Failure::new(BC_ERROR_NULL, "The output pointer is null."));
+        }
+        let turn = serde_json::to_string(&c.end_turn()).expect("turns serialize");
+        write(out, string(turn))
+    })
+}
+
+// ****************************************************************************
+// ********************************* QUERIES **********************************
+// ****************************************************************************
+
+/// The current round.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_round(c: *mut GameController, out: *mut u32) -> i32 {
+    call(|| write(out, controller(c)?.round()))
+}
+
+/// The player's planet.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_planet(c: *mut GameController, out: *mut u32) -> i32 {
+    call(|| write(out, controller(c)?.planet() as u32))
+}
+
+/// The player's team.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_team(c: *mut GameController, out: *mut u32) -> i32 {
+    call(|| write(out, controller(c)?.team() as u32))
+}
+
+/// The karbonite in the team's resource pool.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_karbonite(c: *mut GameController, out: *mut u32) -> i32 {
+    call(|| write(out, controller(c)?.karbonite()))
+}
+
+/// The karbonite at a location the player can sense.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_karbonite_at(c: *mut GameController, planet: u32,
+                                                    x: i32, y: i32, out: *mut u32) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        write(out, c.karbonite_at(map_location(planet, x, y)?)?)
+    })
+}
+
+/// A unit the player can sense.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_unit(c: *mut GameController, id: u16,
+                                            out: *mut BcUnit) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        write(out, BcUnit::new(c.unit_ref(id)?))
+    })
+}
+
+/// All the units the player can sense. Free the array with
+/// `bc_units_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_units(c: *mut GameController, out_units: *mut *mut BcUnit,
+                                             out_len: *mut usize) -> i32 {
+    call(|| write_units(controller(c)?.units(), out_units, out_len))
+}
+
+/// The player's own units, including those in space. Free the array with
+/// `bc_units_free()`.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_my_units(c: *mut GameController,
+                                                out_units: *mut *mut BcUnit,
+                                                out_len: *mut usize) -> i32 {
+    call(|| write_units(controller(c)?.my_units(), out_units, out_len))
+}
+
+// ****************************************************************************
+// ********************************* ACTIONS **********************************
+// ****************************************************************************
+
+/// Moves a robot in the direction.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_move_robot(c: *mut GameController, robot_id: u16,
+                                                  direction_index: u32) -> i32 {
+    call(|| Ok(controller(c)?.move_robot(robot_id, direction(direction_index)?)?))
+}
+
+/// Attacks the target with the robot.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_attack(c: *mut GameController, robot_id: u16,
+                                              target_unit_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.attack(robot_id, target_unit_id)?))
+}
+
+/// Harvests karbonite in the direction with the worker.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_harvest(c: *mut GameController, worker_id: u16,
+                                               direction_index: u32) -> i32 {
+    call(|| Ok(controller(c)?.harvest(worker_id, direction(direction_index)?)?))
+}
+
+/// Blueprints a structure in the direction with the worker.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_blueprint(c: *mut GameController, worker_id: u16,
+                                                 structure_type: u32,
+                                                 direction_index: u32) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        Ok(c.blueprint(worker_id, unit_type(structure_type)?, direction(direction_index)?)?)
+    })
+}
+
+/// Builds the blueprint with the worker.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_build(c: *mut GameController, worker_id: u16,
+                                             blueprint_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.build(worker_id, blueprint_id)?))
+}
+
+/// Repairs the structure with the worker.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_repair(c: *mut GameController, worker_id: u16,
+                                              structure_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.repair(worker_id, structure_id)?))
+}
+
+/// Replicates the worker in the direction.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_replicate(c: *mut GameController, worker_id: u16,
+                                                 direction_index: u32) -> i32 {
+    call(|| Ok(controller(c)?.replicate(worker_id, direction(direction_index)?)?))
+}
+
+/// Throws the knight's javelin at the target.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_javelin(c: *mut GameController, knight_id: u16,
+                                               target_unit_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.javelin(knight_id, target_unit_id)?))
+}
+
+/// Begins the ranger's snipe at the location.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_begin_snipe(c: *mut GameController, ranger_id: u16,
+                                                   planet: u32, x: i32, y: i32) -> i32 {
+    call(|| Ok(controller(c)?.begin_snipe(ranger_id, map_location(planet, x, y)?)?))
+}
+
+/// Blinks the mage to the location.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_blink(c: *mut GameController, mage_id: u16,
+                                             planet: u32, x: i32, y: i32) -> i32 {
+    call(|| Ok(controller(c)?.blink(mage_id, map_location(planet, x, y)?)?))
+}
+
+/// Heals the target robot with the healer.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_heal(c: *mut GameController, healer_id: u16,
+                                            target_robot_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.heal(healer_id, target_robot_id)?))
+}
+
+/// Overcharges the target robot with the healer.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_overcharge(c: *mut GameController, healer_id: u16,
+                                                  target_robot_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.overcharge(healer_id, target_robot_id)?))
+}
+
+/// Loads the robot into the structure's garrison.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_load(c: *mut GameController, structure_id: u16,
+                                            robot_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.load(structure_id, robot_id)?))
+}
+
+/// Unloads the structure's next robot in the direction.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_unload(c: *mut GameController, structure_id: u16,
+                                              direction_index: u32) -> i32 {
+    call(|| Ok(controller(c)?.unload(structure_id, direction(direction_index)?)?))
+}
+
+/// Starts producing a robot in the factory.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_produce_robot(c: *mut GameController, factory_id: u16,
+                                                     robot_type: u32) -> i32 {
+    call(|| Ok(controller(c)?.produce_robot(factory_id, unit_type(robot_type)?)?))
+}
+
+/// Launches the rocket to the location.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_launch_rocket(c: *mut GameController, rocket_id: u16,
+                                                     planet: u32, x: i32, y: i32) -> i32 {
+    call(|| Ok(controller(c)?.launch_rocket(rocket_id, map_location(planet, x, y)?)?))
+}
+
+/// Disintegrates the unit.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_disintegrate_unit(c: *mut GameController,
+                                                         unit_id: u16) -> i32 {
+    call(|| Ok(controller(c)?.disintegrate_unit(unit_id)?))
+}
+
+/// Adds the branch to the end of the research queue. Writes whether it was
+/// added.
+#[no_mangle]
+pub unsafe extern "C" fn bc_controller_queue_research(c: *mut GameController, branch: u32,
+                                                      out: *mut bool) -> i32 {
+    call(|| {
+        let c = controller(c)?;
+        let added = c.queue_research(unit_type(branch)?)?;
+        write(out, added)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::GameMap;
+    use schema::TurnMessage;
+    use std::slice;
+    use world::{Player, Team};
+
+    /// The last error, as a Rust string.
+    fn last_error() -> Option<String> {
+        unsafe {
+            let error = bc_last_error();
+            if error.is_null() {
+                return None;
+            }
+            let message = CStr::from_ptr(error).to_str().unwrap().to_string();
+            bc_string_free(error);
+            Some(message)
+        }
+    }
+
+    /// The units the controller returns, copied out of the array.
+    unsafe fn my_units(c: *mut GameController) -> Vec<BcUnit> {
+        let mut units: *mut BcUnit = ptr::null_mut();
+        let mut len = 0;
+        assert_eq!(bc_controller_my_units(c, &mut units, &mut len), BC_OK);
+        let copied = slice::from_raw_parts(units, len).to_vec();
+        bc_units_free(units, len);
+        copied
+    }
+
+    #[test]
+    fn test_ffi_turn_loop() {
+        let mut manager = GameController::new_manager(GameMap::test_map());
+        let player = Player::new(Team::Red, Planet::Earth);
+        let start_game = serde_json::to_string(&manager.start_game(player)).unwrap();
+        let start_game = CString::new(start_game).unwrap();
+        let start_turn = serde_json::to_string(&manager.initial_start_turn_message(10000).start_turn).unwrap();
+        let start_turn = CString::new(start_turn).unwrap();
+
+        unsafe {
+            // The host creates a controller and starts its first turn.
+            let mut c: *mut GameController = ptr::null_mut();
+            assert_eq!(bc_controller_new(start_game.as_ptr(), &mut c), BC_OK);
+            assert!(!c.is_null());
+            assert_eq!(bc_controller_start_turn(c, start_turn.as_ptr()), BC_OK);
+            assert_eq!(last_error(), None);
+
+            let (mut round, mut planet, mut team, mut karbonite) = (0, 9, 9, 0);
+            assert_eq!(bc_controller_round(c, &mut round), BC_OK);
+            assert_eq!(bc_controller_planet(c, &mut planet), BC_OK);
+            assert_eq!(bc_controller_team(c, &mut team), BC_OK);
+            assert_eq!(bc_controller_karbonite(c, &mut karbonite), BC_OK);
+            assert_eq!((round, planet, team), (1, Planet::Earth as u32, Team::Red as u32));
+            assert_eq!(karbonite, manager.manager_karbonite(Team::Red));
+
+            // It finds its worker, and reads the karbonite underneath it.
+            let units = my_units(c);
+            assert_eq!(units.len(), 1);
+            let worker = units[0];
+            assert_eq!(worker.unit_type, UnitType::Worker as u32);
+            assert_eq!(worker.location.tag, BC_LOCATION_ON_MAP);
+            assert_eq!(worker.location.planet, Planet::Earth as u32);
+            let mut deposit = 0;
+            assert_eq!(bc_controller_karbonite_at(c, worker.location.planet, worker.location.x,
+                                                  worker.location.y, &mut deposit), BC_OK);
+            assert_eq!(deposit, 10);
+            let mut by_id = BcUnit { id: 0, team: 0, unit_type: 0, health: 0, location: worker.location };
+            assert_eq!(bc_controller_unit(c, worker.id, &mut by_id), BC_OK);
+            assert_eq!(by_id, worker);
+
+            // It moves the worker. Moving again is refused by the engine.
+            assert_eq!(bc_controller_move_robot(c, worker.id, Direction::North as u32), BC_OK);
+            assert_eq!(bc_controller_move_robot(c, worker.id, Direction::North as u32), BC_ERROR_GAME);
+            assert!(last_error().is_some());
+            let moved = my_units(c)[0];
+            assert_eq!(moved.location.y, worker.location.y + 1);
+
+            // Bad arguments are reported, not panicked on.
+            assert_eq!(bc_controller_move_robot(c, worker.id, 42), BC_ERROR_INVALID_ARGUMENT);
+            assert_eq!(bc_controller_harvest(ptr::null_mut(), worker.id, 0), BC_ERROR_NULL);
+            assert_eq!(bc_controller_round(c, ptr::null_mut()), BC_ERROR_NULL);
+            let garbage = CString::new("{\"bees\": true}").unwrap();
+            assert_eq!(bc_controller_start_turn(c, garbage.as_ptr()), BC_ERROR_INVALID_MESSAGE);
+            assert_eq!(bc_controller_unit(c, 999, &mut by_id), BC_ERROR_GAME);
+
+            // It ends the turn, and the manager accepts the turn message.
+            let mut turn: *mut c_char = ptr::null_mut();
+            assert_eq!(bc_controller_end_turn(c, &mut turn), BC_OK);
+            let turn_json = CStr::from_ptr(turn).to_str().unwrap().to_string();
+            bc_string_free(turn);
+            let turn: TurnMessage = serde_json::from_str(&turn_json).unwrap();
+            assert_eq!(turn.changes.len(), 1);
+            let application = manager.apply_turn(&turn, 10000);
+            assert!(!application.desynced);
+            let on_manager = manager.units().into_iter()
+                .find(|unit| unit.id() == worker.id).unwrap();
+            assert_eq!(BcUnit::new(&on_manager), moved);
+
+            bc_controller_free(c);
+        }
+    }
+
+    #[test]
+    fn test_ffi_catches_panics() {
+        assert_eq!(call(|| panic!("bees")), BC_ERROR_PANIC);
+        assert_eq!(last_error(), Some("The engine panicked.".to_string()));
+        assert_eq!(call(|| Ok(())), BC_OK);
+        assert_eq!(last_error(), None);
+
+        // Freeing null is allowed, as in C.
+        unsafe {
+            bc_controller_free(ptr::null_mut());
+            bc_string_free(ptr::null_mut());
+            bc_units_free(ptr::null_mut(), 0);
+        }
+    }
+}
@@ -2,7 +2,17 @@
 //!
 //! Algorithm from:
 //! http://preshing.com/20121224/how-to-generate-a-sequence-of-unique-random-integers/
+//!
+//! Each step of the algorithm is a permutation of the 16-bit integers, so
+//! distinct indices always produce distinct IDs. A game has a single
+//! generator, shared by both teams on both planets: each player receives the
+//! generator's state at the start of its turn, and the manager mirrors the
+//! IDs that player took. No two units in a game can share an ID.
+
+use failure::Error;
+use std::u16;
 
+use error::GameError;
 use unit::UnitID;
 
 /// IDs generated are less than p, which is a prime such that p = 3 mod 4. This
@@ -19,8 +29,9 @@ const MAX_RESERVED_ID: UnitID = 10;
 pub struct IDGenerator {
     /// Map seed.
     seed: u16,
-    /// An index into the random number generator. Must not be 0 or 1.
-    index: u16,
+    /// An index into the random number generator. Must not be 0 or 1. The
+    /// generator is exhausted once the index passes the largest `UnitID`.
+    index: u32,
 }
 
 fn permute_qpr(x: UnitID) -> UnitID {
@@ -45,19 +56,37 @@ impl IDGenerator {
         }
     }
 
+    /// The map seed this generator was created with.
+    pub(crate) fn seed(&self) -> u16 {
+        self.seed
+    }
+
     /// Return a new ID. Each unit ID is unique.
     /// Does not produce IDs in the range [0, MAX_RESERVED_ID].
-    pub fn next_id(&mut self) -> UnitID {
-        let mut id = self.next_id_unchecked();
-        while id <= MAX_RESERVED_ID {
-            id = self.next_id_unchecked();
+    ///
+    /// * NoMoreUnitIDs - every ID has already been generated.
+    pub fn next_id(&mut self) -> Result<UnitID, Error> {
+        loop {
+            if self.index > u16::MAX as u32 {
+                Err(GameError::NoMoreUnitIDs)?;
+            }
+            let id = self.id_at(self.index as u16);
+            self.index += 1;
+            if id > MAX_RESERVED_ID {
+                return Ok(id);
+            }
         }
-        id
     }
 
-    fn next_id_unchecked(&mut self) -> UnitID {
-        let x = self.index;
-        self.index += 1;
+    /// The number of IDs this generator can still produce. Takes time
+    /// proportional to that number, so avoid calling it every turn.
+    pub fn capacity(&self) -> usize {
+        (self.index..u16::MAX as u32 + 1)
+            .filter(|&x| self.id_at(x as u16) > MAX_RESERVED_ID)
+            .count()
+    }
+
+    fn id_at(&self, x: u16) -> UnitID {
         permute_qpr(permute_qpr(x).wrapping_add(self.seed) ^ XOR_VALUE)
     }
 }
@@ -79,7 +108,7 @@ mod tests {
             // Generate a bunch of IDs. All the IDs should be unique.
             let mut ids = HashSet::new();
             for i in 0..NUM_IDS {
-                let id = id_gen.next_id();
+                let id = id_gen.next_id().unwrap();
                 assert!(!ids.contains(&id), "failed at the {}th ID", i);
                 ids.insert(id);
             }
@@ -92,8 +121,8 @@ mod tests {
         let mut id_gen_a = IDGenerator::new(1337);
         let mut id_gen_b = IDGenerator::new(1337);
         for _ in 0..NUM_IDS {
-            let id_a = id_gen_a.next_id();
-            let id_b = id_gen_b.next_id();
+            let id_a = id_gen_a.next_id().unwrap();
+            let id_b = id_gen_b.next_id().unwrap();
             assert_eq!(id_a, id_b);
         }
 
@@ -102,8 +131,8 @@ mod tests {
         let mut id_gen_b = IDGenerator::new(6370);
         let mut different_results = false;
         for _ in 0..NUM_IDS {
-            let id_a = id_gen_a.next_id();
-            let id_b = id_gen_b.next_id();
+            let id_a = id_gen_a.next_id().unwrap();
+            let id_b = id_gen_b.next_id().unwrap();
             different_results = different_results || id_a != id_b;
         }
         assert!(different_results)
@@ -120,12 +149,12 @@ mod tests {
         // Red makes some units.
         let mut red_ids: Vec<UnitID> = vec![];
         for _ in 0..NUM_IDS {
-            red_ids.push(id_gen_red.next_id());
+            red_ids.push(id_gen_red.next_id().unwrap());
         }
 
         // The manager mirrors creating two Red units.
         for i in 0..NUM_IDS {
-            let manager_id = id_gen_manager.next_id();
+            let manager_id = id_gen_manager.next_id().unwrap();
             assert_eq!(manager_id, red_ids[i]);
         }
 
@@ -133,13 +162,35 @@ mod tests {
         let mut id_gen_blue = id_gen_manager.clone();
         let mut blue_ids: Vec<UnitID> = vec![];
         for _ in 0..NUM_IDS {
-            blue_ids.push(id_gen_blue.next_id());
+            blue_ids.push(id_gen_blue.next_id().unwrap());
         }
 
         // The manager mirrors creating two Blue units.
         for i in 0..NUM_IDS {
-            let manager_id = id_gen_manager.next_id();
+            let manager_id = id_gen_manager.next_id().unwrap();
             assert_eq!(manager_id, blue_ids[i]);
         }
     }
+
+    #[test]
+    fn test_id_generator_capacity() {
+        // Every ID the generator can produce is unique, and once they are
+        // all used up, the generator errors instead of repeating one.
+        let mut id_gen = IDGenerator::new(31415);
+        let capacity = id_gen.capacity();
+        // Indices 0 and 1 are skipped, as are the indices of reserved IDs.
+        assert_gte!(capacity, u16::MAX as usize - 1 - (MAX_RESERVED_ID as usize + 1));
+        let mut ids = HashSet::new();
+        for i in 0..capacity {
+            let id = id_gen.next_id().unwrap();
+            assert_gt!(id, MAX_RESERVED_ID);
+            assert!(ids.insert(id), "failed at the {}th ID", i);
+            if i == NUM_IDS {
+                assert_eq!(id_gen.capacity(), capacity - NUM_IDS - 1);
+            }
+        }
+        assert_eq!(id_gen.capacity(), 0);
+        assert_err!(id_gen.next_id(), GameError::NoMoreUnitIDs);
+        assert_err!(id_gen.next_id(), GameError::NoMoreUnitIDs);
+    }
 }
@@ -24,6 +24,9 @@ extern crate rand;
 // significantly faster for integer keys.
 extern crate fnv;
 
+// Gzip compression for replays.
+extern crate flate2;
+
 extern crate ansi_term;
 
 // see error.rs
@@ -36,6 +39,12 @@ pub mod controller;
 // see schema.rs
 pub mod schema;
 
+// see replay.rs
+pub mod replay;
+
+// see strategies.rs
+pub mod strategies;
+
 // see world.rs
 pub mod world;
 
@@ -63,6 +72,20 @@ pub mod constants;
 // see id_generator.rs
 mod id_generator;
 
+// see occupancy.rs
+mod occupancy;
+
+// see rng.rs
+mod rng;
+
+// see ffi.rs
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+// see bench.rs
+#[cfg(feature = "bench")]
+pub mod bench;
+
 #[cfg(test)]
 mod tests {
     #[test]
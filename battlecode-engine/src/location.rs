@@ -1,9 +1,14 @@
 //! Simple data structures to represent locations, directions, and planets.
 
 use failure::Error;
+use fnv::FnvHashMap;
+use std::cell::RefCell;
+use std::cmp;
+use std::rc::Rc;
 use std::u32;
 use location::Direction::*;
 use super::error::GameError;
+use super::map::PlanetMap;
 use super::unit::UnitID;
 
 /// A direction from one MapLocation to another.
@@ -58,6 +63,29 @@ impl Direction {
         ]
     }
 
+    /// Returns the four cardinal directions ordered clockwise, starting with
+    /// north.
+    pub fn cardinals() -> Vec<Direction> {
+        vec![North, East, South, West]
+    }
+
+    /// Returns the direction with the given x and y displacement, or None if
+    /// there is none. Center has no displacement.
+    pub fn from_delta(dx: i32, dy: i32) -> Option<Direction> {
+        match (dx, dy) {
+            (0, 1) => Some(North),
+            (1, 1) => Some(Northeast),
+            (1, 0) => Some(East),
+            (1, -1) => Some(Southeast),
+            (0, -1) => Some(South),
+            (-1, -1) => Some(Southwest),
+            (-1, 0) => Some(West),
+            (-1, 1) => Some(Northwest),
+            (0, 0) => Some(Center),
+            _ => None,
+        }
+    }
+
     /// Returns the x displacement of this direction.
     pub fn dx(&self) -> i32 {
         match *self {
@@ -198,13 +226,17 @@ impl MapLocation {
     }
 
     /// Computes the square of the distance from this location to the specified
-    /// location. If on different planets, returns the maximum integer.
+    /// location. If on different planets, or too far apart for the distance to
+    /// fit, returns the maximum integer.
     pub fn distance_squared_to(&self, o: MapLocation) -> u32 {
-        if self.planet == o.planet {
-            ((self.x - o.x) * (self.x - o.x) + (self.y - o.y) * (self.y - o.y)) as u32
-        } else {
-            u32::max_value()
+        if self.planet != o.planet {
+            return u32::max_value();
         }
+        // Each difference fits in 32 bits, so its square fits in a u64, but
+        // the sum of two squares may not.
+        let dx = (self.x as i64 - o.x as i64).unsigned_abs();
+        let dy = (self.y as i64 - o.y as i64).unsigned_abs();
+        cmp::min((dx * dx).saturating_add(dy * dy), u32::max_value() as u64) as u32
     }
 
     /// Returns the Direction from this location to the specified location.
@@ -220,8 +252,8 @@ impl MapLocation {
             return Ok(Center);
         }
 
-        let dx = (o.x - self.x) as f32;
-        let dy = (o.y - self.y) as f32;
+        let dx = (o.x as i64 - self.x as i64) as f32;
+        let dy = (o.y as i64 - self.y as i64) as f32;
 
         // 2.414 is an approximation of tan(67.5 degrees). It's a minor
         // optimization for an expensive trigonometric operation.
@@ -277,6 +309,103 @@ impl MapLocation {
         }
         range >= self.distance_squared_to(o)
     }
+
+    /// Iterates over all locations within a certain radius squared of this
+    /// location that are on the given map, without allocating for the radii
+    /// used by units.
+    ///
+    /// The locations are ordered first by the x-coordinate, then the
+    /// y-coordinate. The radius squared is inclusive. This location does not
+    /// need to be on the map.
+    pub fn all_locations_within_clipped<'a>(&self, radius_squared: u32,
+                                            map: &'a PlanetMap) -> LocationsWithin<'a> {
+        let offsets = if radius_squared <= MAX_CACHED_RADIUS_SQUARED {
+            DISC_OFFSETS.with(|cache| {
+                cache.borrow_mut().entry(radius_squared)
+                    .or_insert_with(|| Rc::new(disc_offsets(radius_squared, None)))
+                    .clone()
+            })
+        } else {
+            // Large discs can cover much more than the map, so only take the
+            // offsets that land on it.
+            let bounds = (-(self.x as i64), map.width as i64 - 1 - self.x as i64,
+                          -(self.y as i64), map.height as i64 - 1 - self.y as i64);
+            Rc::new(disc_offsets(radius_squared, Some(bounds)))
+        };
+        LocationsWithin {
+            center: *self,
+            map,
+            index: if map.planet == self.planet { 0 } else { offsets.len() },
+            offsets,
+        }
+    }
+}
+
+/// The largest radius squared whose disc of offsets is cached. Covers the
+/// vision and ability ranges of every unit.
+const MAX_CACHED_RADIUS_SQUARED: u32 = 100;
+
+thread_local! {
+    /// The offsets within each radius squared, by radius squared.
+    static DISC_OFFSETS: RefCell<FnvHashMap<u32, Rc<Vec<(i32, i32)>>>> =
+        RefCell::new(FnvHashMap::default());
+}
+
+/// The (dx, dy) offsets within the radius squared, ordered first by dx, then
+/// by dy. If given, only offsets within the inclusive bounds
+/// (min dx, max dx, min dy, max dy) are included.
+fn disc_offsets(radius_squared: u32, bounds: Option<(i64, i64, i64, i64)>) -> Vec<(i32, i32)> {
+    let radius_squared = radius_squared as i64;
+    let mut radius = (radius_squared as f64).sqrt() as i64;
+    while radius * radius > radius_squared {
+        radius -= 1;
+    }
+    while (radius + 1) * (radius + 1) <= radius_squared {
+        radius += 1;
+    }
+
+    let (min_dx, max_dx, min_dy, max_dy) = match bounds {
+        Some((min_dx, max_dx, min_dy, max_dy)) => (
+            cmp::max(min_dx, -radius), cmp::min(max_dx, radius),
+            cmp::max(min_dy, -radius), cmp::min(max_dy, radius),
+        ),
+        None => (-radius, radius, -radius, radius),
+    };
+
+    let mut offsets = vec![];
+    for dx in min_dx..max_dx + 1 {
+        for dy in min_dy..max_dy + 1 {
+            if dx * dx + dy * dy <= radius_squared {
+                offsets.push((dx as i32, dy as i32));
+            }
+        }
+    }
+    offsets
+}
+
+/// An iterator over the locations within a radius squared of a location that
+/// are on a map. See `MapLocation::all_locations_within_clipped()`.
+pub struct LocationsWithin<'a> {
+    center: MapLocation,
+    map: &'a PlanetMap,
+    offsets: Rc<Vec<(i32, i32)>>,
+    index: usize,
+}
+
+impl<'a> Iterator for LocationsWithin<'a> {
+    type Item = MapLocation;
+
+    fn next(&mut self) -> Option<MapLocation> {
+        while let Some(&(dx, dy)) = self.offsets.get(self.index) {
+            self.index += 1;
+            let x = self.center.x as i64 + dx as i64;
+            let y = self.center.y as i64 + dy as i64;
+            if x >= 0 && y >= 0 && x < self.map.width as i64 && y < self.map.height as i64 {
+                return Some(MapLocation::new(self.center.planet, x as i32, y as i32));
+            }
+        }
+        None
+    }
 }
 
 /// Any location in the Battlecode world.
@@ -442,6 +571,22 @@ mod tests {
         assert_eq!(West.rotate_right(), Northwest);
         assert_eq!(Northwest.rotate_right(), North);
         assert_eq!(Center.rotate_right(), Center);
+
+        assert_eq!(Direction::cardinals(), vec![North, East, South, West]);
+        assert!(Direction::cardinals().iter().all(|dir| !dir.is_diagonal()));
+    }
+
+    #[test]
+    fn test_direction_from_delta() {
+        for &dir in Direction::all().iter().chain(Some(Center).iter()) {
+            assert_eq!(Direction::from_delta(dir.dx(), dir.dy()), Some(dir));
+            assert_eq!(Direction::from_delta(-dir.dx(), -dir.dy()), Some(dir.opposite()));
+            assert_eq!(dir.rotate_left().rotate_right(), dir);
+            assert_eq!(dir.opposite().opposite(), dir);
+        }
+        assert_eq!(Direction::from_delta(2, 0), None);
+        assert_eq!(Direction::from_delta(-1, 2), None);
+        assert_eq!(Direction::from_delta(i32::min_value(), 0), None);
     }
 
     #[test]
@@ -487,11 +632,27 @@ mod tests {
         assert_eq!(a.distance_squared_to(c), 9);
         assert_eq!(b.distance_squared_to(c), 13);
         assert!(a.distance_squared_to(d) == u32::max_value());
+
+        // Diagonals, and locations too far apart to fit the distance.
+        assert_eq!(a.distance_squared_to(MapLocation::new(Earth, 3, 5)), 2);
+        assert_eq!(a.distance_squared_to(MapLocation::new(Earth, 1, 7)), 18);
+        let far = MapLocation::new(Earth, i32::max_value(), i32::min_value());
+        assert_eq!(a.distance_squared_to(far), u32::max_value());
+        assert_eq!(far.distance_squared_to(far), 0);
+        let bottom = MapLocation::new(Earth, 0, i32::min_value());
+        let top = MapLocation::new(Earth, 0, i32::max_value());
+        assert_eq!(bottom.distance_squared_to(top), u32::max_value());
+        assert_eq!(top.distance_squared_to(bottom), u32::max_value());
+        let corner = MapLocation::new(Earth, i32::min_value(), i32::min_value());
+        let opposite = MapLocation::new(Earth, i32::max_value(), i32::max_value());
+        assert_eq!(corner.distance_squared_to(opposite), u32::max_value());
     }
 
     #[test]
     fn test_map_location_direction_to() {
         let origin = MapLocation::new(Mars, 0, 0);
+        assert_eq!(origin.direction_to(origin).unwrap(), Center);
+
         let nn = MapLocation::new(Mars, 0, 2);
         let ne = MapLocation::new(Mars, 2, 2);
         let ee = MapLocation::new(Mars, 2, 0);
@@ -579,6 +740,40 @@ mod tests {
             "location is on the border");
     }
 
+    #[test]
+    fn test_all_locations_within_clipped() {
+        let map = ::map::GameMap::test_map().earth_map;
+        let naive = |center: MapLocation, radius_squared: u32| {
+            let mut locs = vec![];
+            for x in 0..map.width as i32 {
+                for y in 0..map.height as i32 {
+                    let loc = MapLocation::new(Earth, x, y);
+                    if center.distance_squared_to(loc) <= radius_squared {
+                        locs.push(loc);
+                    }
+                }
+            }
+            locs
+        };
+
+        let max = map.width as i32 - 1;
+        let centers = [(0, 0), (max, max), (0, max), (1, max - 1), (7, 9),
+                       (-3, 2), (max + 4, -4)];
+        for &(x, y) in centers.iter() {
+            let center = MapLocation::new(Earth, x, y);
+            for &radius_squared in [0, 1, 2, 10, 50, 70, 100, 101, 1000, u32::max_value()].iter() {
+                assert_eq!(center.all_locations_within_clipped(radius_squared, &map)
+                                 .collect::<Vec<_>>(),
+                           naive(center, radius_squared),
+                           "{:?} within {}", center, radius_squared);
+            }
+        }
+
+        // Nothing on another planet's map is within range.
+        let center = MapLocation::new(Mars, 5, 5);
+        assert_eq!(center.all_locations_within_clipped(50, &map).count(), 0);
+    }
+
     #[test]
     fn test_location_is_adjacent_to() {
         let loc_a = Location::OnMap(MapLocation::new(Planet::Earth, 0, 0));
@@ -0,0 +1,178 @@
+//! Random generation of valid game maps.
+
+use super::*;
+use std::collections::VecDeque;
+
+/// The fraction of squares that are seeded with impassable terrain.
+const IMPASSABLE_DENSITY: f32 = 0.15;
+
+/// The fraction of passable squares on Earth with a karbonite deposit.
+const KARBONITE_DENSITY: f32 = 0.3;
+
+/// The number of attempts at generating connected, symmetric terrain before
+/// falling back to a map with no impassable terrain.
+const TERRAIN_ATTEMPTS: usize = 20;
+
+pub(crate) fn random_map(seed: u16) -> GameMap {
+    let mut rng = GameRng::new(seed, "map");
+
+    let earth_map = random_earth(&mut rng);
+    let mars_map = random_mars(&mut rng);
+
+    // The flight times must be in [ORBIT_FLIGHT_MIN, ORBIT_FLIGHT_MAX].
+    let max_amplitude = (ORBIT_FLIGHT_MAX - ORBIT_FLIGHT_MIN) / 2;
+    let amplitude = Range::new(0, max_amplitude + 1).ind_sample(&mut rng);
+    let center = Range::new(ORBIT_FLIGHT_MIN + amplitude, ORBIT_FLIGHT_MAX - amplitude + 1)
+        .ind_sample(&mut rng);
+    let period = Range::new(50, 201).ind_sample(&mut rng);
+
+    GameMap {
+        seed,
+        asteroids: AsteroidPattern::random(seed, &mars_map),
+        orbit: OrbitPattern::new(amplitude, period, center),
+        earth_map,
+        mars_map,
+        starting_karbonite: KARBONITE_STARTING,
+    }
+}
+
+fn random_dimensions(rng: &mut GameRng) -> (usize, usize) {
+    let width = Range::new(MAP_WIDTH_MIN as u32, MAP_WIDTH_MAX as u32 + 1).ind_sample(rng);
+    let height = Range::new(MAP_HEIGHT_MIN as u32, MAP_HEIGHT_MAX as u32 + 1).ind_sample(rng);
+    (width as usize, height as usize)
+}
+
+/// Random terrain where each square is impassable with probability
+/// `IMPASSABLE_DENSITY`.
+fn random_terrain(rng: &mut GameRng, width: usize, height: usize) -> Vec<Vec<bool>> {
+    let chance = Range::new(0., 1.);
+    (0..height).map(|_| {
+        (0..width).map(|_| chance.ind_sample(rng) >= IMPASSABLE_DENSITY).collect()
+    }).collect()
+}
+
+/// The squares in the largest connected region of passable terrain.
+fn largest_region(terrain: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let height = terrain.len();
+    let width = terrain[0].len();
+    let mut region = vec![vec![0; width]; height];
+    let mut sizes = vec![0];
+    for y in 0..height {
+        for x in 0..width {
+            if !terrain[y][x] || region[y][x] != 0 {
+                continue;
+            }
+            let label = sizes.len();
+            let mut size = 0;
+            let mut queue = VecDeque::new();
+            region[y][x] = label;
+            queue.push_back((x, y));
+            while let Some((x, y)) = queue.pop_front() {
+                size += 1;
+                for &(dx, dy) in &[(-1, -1), (-1, 0), (-1, 1), (0, -1),
+                                   (0, 1), (1, -1), (1, 0), (1, 1)] {
+                    let nx = x as i32 + dx;
+                    let ny = y as i32 + dy;
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if terrain[ny][nx] && region[ny][nx] == 0 {
+                        region[ny][nx] = label;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+
+    let largest = (1..sizes.len()).max_by_key(|&label| sizes[label]).unwrap_or(0);
+    region.iter()
+          .map(|row| row.iter().map(|&label| largest != 0 && label == largest).collect())
+          .collect()
+}
+
+fn random_earth(rng: &mut GameRng) -> PlanetMap {
+    let (width, height) = random_dimensions(rng);
+    let symmetry = MapSymmetry::all()[Range::new(0, 3).ind_sample(rng) as usize];
+
+    // Keep the largest region of symmetric terrain. It is only symmetric
+    // itself if it contains its own opposite squares.
+    let mut terrain = vec![vec![true; width]; height];
+    for _ in 0..TERRAIN_ATTEMPTS {
+        let mut attempt = random_terrain(rng, width, height);
+        symmetrize(&mut attempt, symmetry);
+        let region = largest_region(&attempt);
+        let mirrored = {
+            let mut mirrored = region.clone();
+            symmetrize(&mut mirrored, symmetry);
+            mirrored
+        };
+        if region == mirrored {
+            terrain = region;
+            break;
+        }
+    }
+
+    let chance = Range::new(0., 1.);
+    let amount = Range::new(MAP_KARBONITE_MIN, MAP_KARBONITE_MAX + 1);
+    let mut karbonite = vec![vec![0; width]; height];
+    for y in 0..height {
+        for x in 0..width {
+            if terrain[y][x] && chance.ind_sample(rng) < KARBONITE_DENSITY {
+                karbonite[y][x] = amount.ind_sample(rng);
+            }
+        }
+    }
+
+    // Place each red worker on a passable square whose opposite square is
+    // distinct and free. Mirroring the map places a blue worker there.
+    let mut candidates = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if terrain[y][x] && symmetry.opposite(x, y, width, height) != (x, y) {
+                candidates.push((x, y));
+            }
+        }
+    }
+    let num_workers = Range::new(1, MAP_INITIAL_WORKERS_MAX as u32 + 1).ind_sample(rng) as usize;
+    let mut taken = vec![];
+    let mut initial_units = vec![];
+    while initial_units.len() < num_workers {
+        let (x, y) = candidates[Range::new(0, candidates.len() as u32).ind_sample(rng) as usize];
+        let (ox, oy) = symmetry.opposite(x, y, width, height);
+        if taken.contains(&(x, y)) || taken.contains(&(ox, oy)) {
+            continue;
+        }
+        taken.push((x, y));
+        taken.push((ox, oy));
+        let id = initial_units.len() as UnitID + 1;
+        let location = MapLocation::new(Planet::Earth, x as i32, y as i32);
+        initial_units.push(Unit::new(id, Team::Red, UnitType::Worker, 0,
+                                     Location::OnMap(location)).unwrap());
+    }
+
+    let half = PlanetMap {
+        planet: Planet::Earth,
+        height,
+        width,
+        is_passable_terrain: terrain,
+        initial_karbonite: karbonite,
+        initial_units,
+    };
+    half.mirrored(symmetry)
+}
+
+fn random_mars(rng: &mut GameRng) -> PlanetMap {
+    let (width, height) = random_dimensions(rng);
+    let terrain = random_terrain(rng, width, height);
+    PlanetMap {
+        planet: Planet::Mars,
+        height,
+        width,
+        is_passable_terrain: largest_region(&terrain),
+        initial_karbonite: vec![vec![0; width]; height],
+        initial_units: vec![],
+    }
+}
@@ -2,6 +2,12 @@ use super::*;
 use super::super::world::Rounds;
 use fnv::FnvHashMap;
 
+/// Returns an `InvalidMapObject` error describing why the text map can't be
+/// parsed. Takes the same arguments as `format!`.
+macro_rules! invalid {
+    ($($arg:tt)*) => { return Err(invalid_map(format!($($arg)*))) };
+}
+
 /// Tokenizer.
 struct Tok<'a> {
     line: usize,
@@ -125,7 +131,7 @@ struct PlanetData {
 pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
     let mut tok = Tok {
         cur,
-        line: 0,
+        line: 1,
         col: 0
     };
 
@@ -133,6 +139,7 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
     let mut orbit_period: Option<Rounds> = None;
     let mut orbit_center: Option<Rounds> = None;
     let mut seed: Option<u16> = None;
+    let mut starting_karbonite: Option<u32> = None;
 
     let mut names = FnvHashMap::<char, Thing>::default();
 
@@ -151,7 +158,7 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
         let start = start.unwrap();
         if start == "EARTH" || start == "MARS" {
             if tok.chew() != Some(":") {
-                bail!("{} must be followed by a colon!", start);
+                invalid!("{} must be followed by a colon at line {}", start, tok.line);
             }
             cur_planet = if start == "EARTH" {
                 Some(Planet::Earth)
@@ -162,38 +169,38 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
         }
         if start == ">" {
             if cur_planet.is_none() {
-                bail!("need to set planet to draw map at line {} col {}", tok.line, tok.col);
+                invalid!("need to set planet to draw map at line {} col {}", tok.line, tok.col);
             }
             let p = cur_planet.unwrap() as usize;
 
             let y = planets[p].cur_y;
             if y.is_none() {
-                bail!("need to set height before drawing map at line {} col {}", tok.line, tok.col);
+                invalid!("need to set height before drawing map at line {} col {}", tok.line, tok.col);
             }
             let y = y.unwrap();
             if y == 9999 {
-                bail!("too many lines in map at line {} col {}", tok.line, tok.col);
+                invalid!("too many lines in map at line {} col {}", tok.line, tok.col);
             }
             let sym = planets[p].symmetry;
             if sym.is_none() {
-                bail!("need to set symmetry before drawing map at line {} col {}", tok.line, tok.col);
+                invalid!("need to set symmetry before drawing map at line {} col {}", tok.line, tok.col);
             }
             let sym = sym.unwrap();
             let height = planets[p].height;
             if height.is_none() {
-                bail!("need to set height before drawing map at line {} col {}", tok.line, tok.col);
+                invalid!("need to set height before drawing map at line {} col {}", tok.line, tok.col);
             }
             let height = height.unwrap();
             let width = planets[p].width;
             if width.is_none() {
-                bail!("need to set width before drawing map at line {} col {}", tok.line, tok.col);
+                invalid!("need to set width before drawing map at line {} col {}", tok.line, tok.col);
             }
             let width = width.unwrap();
 
             let mut x = 0;
             while let Some(thing_) = tok.chew_map() {
                 if x >= width {
-                    bail!("map too wide at line {} col {}", tok.line, tok.col);
+                    invalid!("map too wide at line {} col {}", tok.line, tok.col);
                 }
                 let thing = names.get(&thing_);
                 if let Some(thing) = thing {
@@ -207,7 +214,7 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                         planets[p].things.insert(opposite, otherthing);
                     }
                 } else {
-                    bail!("unknown map symbol: {} at line {} col {}", thing_, tok.line, tok.col)
+                    invalid!("unknown map symbol: {} at line {} col {}", thing_, tok.line, tok.col)
                 }
                 x += 1;
             }
@@ -227,22 +234,24 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                 if let (Ok(round), Ok(x), Ok(y), Ok(karbonite)) = (round.parse(), x.parse(), y.parse(), karbonite.parse()) {
                     asteroids.push((round, x, y, karbonite));
                 } else {
-                    bail!("failed to parse asteroid at line {}", tok.line)
+                    invalid!("failed to parse asteroid at line {}", tok.line)
                 }
             } else {
-                bail!("failed to parse asteroid at line {}", tok.line)
+                invalid!("failed to parse asteroid at line {}", tok.line)
             }
             continue;
         }
 
         let sep = tok.chew();
         if sep.is_none() {
-            bail!("expected = or : at line {} col {}", tok.line, tok.col);
+            invalid!("expected = or : at line {} col {}", tok.line, tok.col);
         }
         let sep = sep.unwrap();
         if sep == "=" {
-            assert_eq!(start.chars().count(), 1, "map symbols can only be one code point wide, {} is too long at line {} col {}",
-                start, tok.line, tok.col);
+            if start.chars().count() != 1 {
+                invalid!("map symbols can only be one code point wide, {} is too long at line {} col {}",
+                    start, tok.line, tok.col);
+            }
             let mut thing = Thing {
                 passable: true,
                 team: None,
@@ -258,10 +267,10 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                         if let Ok(karb) = karb {
                             thing.karbonite = karb;
                         } else {
-                            bail!("failed to convert karbonite string \"{}\" to integer at line {}, col {}", a, tok.line, tok.col);
+                            invalid!("failed to convert karbonite string \"{}\" to integer at line {}, col {}", a, tok.line, tok.col);
                         }
                     } else {
-                        bail!("unknown attribute: {} (did you leave a 'k' off your integer?) at line {} col {}", a, tok.line, tok.col);
+                        invalid!("unknown attribute: {} (did you leave a 'k' off your integer?) at line {} col {}", a, tok.line, tok.col);
                     }
                 }
             }
@@ -271,12 +280,12 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                 if let Some(value) = tok.chew() {
                     value
                 } else {
-                    bail!("expected value for property {} at line {} col {}", start, tok.line, tok.col);
+                    invalid!("expected value for property {} at line {} col {}", start, tok.line, tok.col);
                 }
             };
             if start == "symmetry" {
                 if cur_planet.is_none() {
-                    bail!("need to set planet to set symmetry at line {} col {}", tok.line, tok.col);
+                    invalid!("need to set planet to set symmetry at line {} col {}", tok.line, tok.col);
                 }
                 let p = cur_planet.unwrap() as usize;
 
@@ -285,21 +294,29 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                     "hor" | "horizontal" | "h" => Symmetry::Horiz,
                     "spiral" => Symmetry::Spiral,
                     "none" => Symmetry::None,
-                    _ => bail!("unknown symmetry {} at line {} col {}", value, tok.line, tok.col)
+                    _ => invalid!("unknown symmetry {} at line {} col {}", value, tok.line, tok.col)
                 });
                 continue;
             }
             let value = if let Ok(value) = value.parse::<usize>() {
                 value
             } else {
-                bail!("failed to parse {} as int at line {}, col {}", value, tok.line, tok.col);
+                invalid!("failed to parse {} as int at line {}, col {}", value, tok.line, tok.col);
             };
 
             if start == "width" || start == "height" {
                 if cur_planet.is_none() {
-                    bail!("need to set planet to set {} at line {} col {}", start, tok.line, tok.col);
+                    invalid!("need to set planet to set {} at line {} col {}", start, tok.line, tok.col);
                 }
                 let p = cur_planet.unwrap() as usize;
+                let (min, max) = match start {
+                    "width" => (MAP_WIDTH_MIN, MAP_WIDTH_MAX),
+                    _ => (MAP_HEIGHT_MIN, MAP_HEIGHT_MAX),
+                };
+                if value < min || value > max {
+                    invalid!("{} {} is not between {} and {} at line {}",
+                             start, value, min, max, tok.line);
+                }
                 match start {
                     "width" => planets[p].width = Some(value),
                     "height" => {
@@ -315,17 +332,19 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
                 "orbit_period" => orbit_period = Some(value as Rounds),
                 "orbit_center" => orbit_center = Some(value as Rounds),
                 "seed" => seed = Some(value as u16),
-                _ => bail!("unknown map property: {} at line {} col {}", start, tok.line, tok.col)
+                "starting_karbonite" => starting_karbonite = Some(value as u32),
+                _ => invalid!("unknown map property: {} at line {} col {}", start, tok.line, tok.col)
             }
         } 
     }
 
-    let orbit_amplitude = if let Some(orbit_amplitude) = orbit_amplitude { orbit_amplitude } else { bail!("orbit_amplitude unset") };
-    let orbit_period = if let Some(orbit_period) = orbit_period { orbit_period } else { bail!("orbit_period unset") };
-    let orbit_center = if let Some(orbit_center) = orbit_center { orbit_center } else { bail!("orbit_center unset") };
+    let orbit_amplitude = if let Some(orbit_amplitude) = orbit_amplitude { orbit_amplitude } else { invalid!("orbit_amplitude is never set") };
+    let orbit_period = if let Some(orbit_period) = orbit_period { orbit_period } else { invalid!("orbit_period is never set") };
+    let orbit_center = if let Some(orbit_center) = orbit_center { orbit_center } else { invalid!("orbit_center is never set") };
     let orbit = OrbitPattern::new(orbit_amplitude, orbit_period, orbit_center);
 
-    let seed = if let Some(seed) = seed { seed } else { bail!("seed unset") };
+    let seed = if let Some(seed) = seed { seed } else { invalid!("seed is never set") };
+    let starting_karbonite = starting_karbonite.unwrap_or(KARBONITE_STARTING);
 
     let mut earth_map = None;
     let mut mars_map = None;
@@ -335,8 +354,8 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
         } else {
             Planet::Mars
         };
-        let width = if let Some(width) = planet.width { width } else { bail!("width unset") };
-        let height = if let Some(height) = planet.height { height } else { bail!("height unset") };
+        let width = if let Some(width) = planet.width { width } else { invalid!("the width of {:?} is never set", p) };
+        let height = if let Some(height) = planet.height { height } else { invalid!("the height of {:?} is never set", p) };
         let mut map = PlanetMap {
             planet: p,
             height: height,
@@ -349,6 +368,9 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
         let mut id = 1;
 
         for (&(x,y), thing) in planet.things.iter() {
+            if x >= width || y >= height {
+                invalid!("the map of {:?} was drawn larger than its final size", p);
+            }
             map.is_passable_terrain[y as usize][x as usize] = thing.passable;
             map.initial_karbonite[y as usize][x as usize] = thing.karbonite;
 
@@ -381,8 +403,9 @@ pub(crate) fn parse_text_map(cur: &str) -> Result<GameMap, Error> {
         seed,
         earth_map: earth_map.unwrap(),
         mars_map: mars_map.unwrap(),
-        asteroids: AsteroidPattern { pattern: ast },
-        orbit
+        asteroids: AsteroidPattern::new(&ast),
+        orbit,
+        starting_karbonite,
     })
 }
 
@@ -391,12 +414,13 @@ mod tests {
     use super::*;
     #[test]
     fn parse_bananas() {
-        let bananas = include_str!("bananas.bc18t");
+        let bananas = include_str!("../../tests/maps/bananas.bc18t");
         let map = parse_text_map(bananas).unwrap();
 
         assert_eq!(map.orbit.amplitude, 50);
         assert_eq!(map.orbit.period, 40);
         assert_eq!(map.orbit.center, 100);
+        assert_eq!(map.starting_karbonite, 200);
 
         assert_eq!(map.asteroids.pattern[&200].karbonite, 150);
         assert_eq!(map.mars_map.initial_karbonite[29][29], 1000);
@@ -421,9 +445,38 @@ mod tests {
         assert!(founda);
         assert!(foundb);
     }
+    #[test]
+    fn parse_malformed() {
+        let valid = include_str!("../../tests/maps/valid.bc18t");
+        let error = |text: &str| {
+            assert_err!(parse_text_map(text), GameError::InvalidMapObject);
+            parse_text_map(text).unwrap_err().to_string()
+        };
+
+        // Sizes out of range are rejected, rather than underflowing.
+        let zero = valid.replacen("height: 20", "height: 0", 1);
+        assert_eq!(error(&zero), "Invalid map: height 0 is not between 20 and 50 at line 12.");
+        let huge = valid.replacen("width: 20", "width: 1000000", 1);
+        assert!(error(&huge).contains("width 1000000 is not between"));
+
+        // Errors name the line they were found on.
+        let symbol = valid.replacen("> _ _", "> _ ?", 1);
+        assert!(error(&symbol).contains("unknown map symbol: ? at line"));
+        let colon = valid.replacen("EARTH:", "EARTH", 1);
+        assert!(error(&colon).contains("EARTH must be followed by a colon at line"));
+        assert!(error(&valid.replacen("seed:", "sead:", 1)).contains("unknown map property: sead at line"));
+
+        // Properties that are never set, and grids drawn before a planet
+        // shrinks, are rejected too.
+        assert_eq!(error(&valid.replacen("seed: 42", "", 1)), "Invalid map: seed is never set.");
+        let shrunk = format!("{}\nMARS:\nwidth: 20\n", valid);
+        assert!(error(&shrunk).contains("the map of Mars was drawn larger than its final size"));
+    }
+
     #[test]
     fn parse_fat() {
-        let fat = include_str!("fat.bc18t");
+        let fat = include_str!("../../tests/maps/fat.bc18t");
         let map = parse_text_map(fat).unwrap();
+        assert_eq!(map.starting_karbonite, KARBONITE_STARTING);
     }
 }
\ No newline at end of file
@@ -1,20 +1,34 @@
 //! The starting properties of the game world.
 
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::collections::Bound;
+use std::cmp;
 use std::f32;
-use failure::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use failure::{Error, Fail};
+use serde_json;
 use fnv::FnvHashMap;
-use rand::{SeedableRng, StdRng};
 use rand::distributions::IndependentSample;
 use rand::distributions::range::Range;
 
 use constants::*;
 use error::GameError;
 use location::*;
+use rng::GameRng;
 use unit::*;
 use world::*;
 
+mod mapgen;
 mod mapparser;
 
+/// An `InvalidMapObject` error, with a description of what is invalid.
+fn invalid_map(reason: String) -> Error {
+    GameError::InvalidMapObject.context(format!("Invalid map: {}.", reason)).into()
+}
+
 /// The map defining the starting state for an entire game.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GameMap {
@@ -28,6 +42,14 @@ pub struct GameMap {
     pub asteroids: AsteroidPattern,
     /// The orbit pattern that determines a rocket's flight duration.
     pub orbit: OrbitPattern,
+    /// The karbonite in each team's resource pool at the start of the game.
+    /// Maps saved before it was added start with KARBONITE_STARTING.
+    #[serde(default = "default_starting_karbonite")]
+    pub starting_karbonite: u32,
+}
+
+fn default_starting_karbonite() -> u32 {
+    KARBONITE_STARTING
 }
 
 impl GameMap {
@@ -37,26 +59,236 @@ impl GameMap {
     pub fn validate(&self) -> Result<(), Error> {
         self.earth_map.validate()?;
         self.mars_map.validate()?;
-        self.asteroids.validate()?;
+        self.asteroids.validate_against(&self.mars_map)?;
         self.orbit.validate()?;
         Ok(())
     }
 
+    /// A small, flat map for tests, with the default `TestMapConfig`.
     pub fn test_map() -> GameMap {
-        let seed = 1;
-        let mars_map = PlanetMap::test_map(Planet::Mars);
+        GameMap::test_map_with(TestMapConfig::default())
+    }
+
+    /// A map for tests, laid out by the config. Earth is symmetric by a
+    /// rotation, so walls and gaps are centered, and each red worker has a
+    /// blue worker opposite. The map is valid as long as Earth has some
+    /// karbonite and its wall leaves a gap.
+    pub fn test_map_with(config: TestMapConfig) -> GameMap {
+        let planet_map = |planet| {
+            let mut map = PlanetMap::new(planet, config.width, config.height)
+                .expect("invalid test map dimensions");
+            let wall = match planet {
+                Planet::Earth => config.earth_wall,
+                Planet::Mars => config.mars_wall,
+            };
+            if let Some(wall) = wall {
+                for y in 0..config.height {
+                    for x in 0..config.width {
+                        if wall.contains(x, y, config.width, config.height) {
+                            map.is_passable_terrain[y][x] = false;
+                        }
+                    }
+                }
+            }
+            map
+        };
+
+        let mut earth_map = planet_map(Planet::Earth);
+        for y in 0..config.height {
+            for x in 0..config.width {
+                earth_map.initial_karbonite[y][x] = match config.karbonite {
+                    TestKarbonite::Uniform(amount) => amount,
+                    TestKarbonite::Checkerboard(amount) if (x + y) % 2 == 0 => amount,
+                    TestKarbonite::Checkerboard(_) => 0,
+                };
+            }
+        }
+        for i in 0..config.workers_per_team {
+            let location = MapLocation::new(Planet::Earth, 1 + 2 * i as i32, 1);
+            earth_map.add_initial_unit(Team::Red, UnitType::Worker, location)
+                     .expect("invalid test worker");
+        }
+        let earth_map = earth_map.mirrored(MapSymmetry::Rotational);
+        let mars_map = planet_map(Planet::Mars);
+
         GameMap {
-            seed: seed,
-            earth_map: PlanetMap::test_map(Planet::Earth),
-            mars_map: mars_map.clone(),
-            asteroids: AsteroidPattern::random(seed, &mars_map),
-            orbit: OrbitPattern::new(100, 100, 300),
+            seed: config.seed,
+            asteroids: AsteroidPattern::random(config.seed, &mars_map),
+            orbit: config.orbit,
+            earth_map,
+            mars_map,
+            starting_karbonite: KARBONITE_STARTING,
         }
     }
 
+    /// Generate a random, valid game map from the seed. Earth is symmetric,
+    /// and the passable terrain on each planet is connected.
+    pub fn random(seed: u16) -> GameMap {
+        self::mapgen::random_map(seed)
+    }
+
+    /// Parse a game map in the text format, without validating it.
+    ///
+    /// * InvalidMapObject - the text can't be parsed. The error names the
+    ///   line the problem was found on.
     pub fn parse_text_map(map: &str) -> Result<GameMap, Error> {
         self::mapparser::parse_text_map(map)
     }
+
+    /// Load a game map from a file, and validate it. Files with the
+    /// extension `.bc18map` are parsed as JSON, and all other files as
+    /// text maps.
+    ///
+    /// Errors with a description of the problem and where it was found if
+    /// the file cannot be parsed.
+    ///
+    /// * InvalidMapObject - the file can't be read or parsed, or the game map
+    ///   is invalid.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<GameMap, Error> {
+        let path = path.as_ref();
+        let mut contents = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| invalid_map(format!("{} can't be read: {}", path.display(), e)))?;
+        let map = if path.extension().map_or(false, |ext| ext == "bc18map") {
+            serde_json::from_str(&contents)
+                .map_err(|e| invalid_map(format!("{} is not a JSON map: {}", path.display(), e)))?
+        } else {
+            GameMap::parse_text_map(&contents)?
+        };
+        map.validate()?;
+        Ok(map)
+    }
+}
+
+/// The layout of a map made by `GameMap::test_map_with()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestMapConfig {
+    /// The width of both planets.
+    pub width: usize,
+    /// The height of both planets.
+    pub height: usize,
+    /// The initial karbonite on Earth.
+    pub karbonite: TestKarbonite,
+    /// A wall across the middle of Earth.
+    pub earth_wall: Option<TestWall>,
+    /// A wall across the middle of Mars.
+    pub mars_wall: Option<TestWall>,
+    /// The number of workers each team starts with. Red's are placed along
+    /// the row y = 1, starting at x = 1, every other square.
+    pub workers_per_team: usize,
+    /// The orbit pattern.
+    pub orbit: OrbitPattern,
+    /// The map seed, which also seeds the asteroid pattern.
+    pub seed: u16,
+}
+
+impl Default for TestMapConfig {
+    fn default() -> TestMapConfig {
+        TestMapConfig {
+            width: MAP_WIDTH_MIN,
+            height: MAP_HEIGHT_MIN,
+            karbonite: TestKarbonite::Uniform(10),
+            earth_wall: None,
+            mars_wall: None,
+            workers_per_team: 1,
+            orbit: OrbitPattern::new(50, 100, 125),
+            seed: 1,
+        }
+    }
+}
+
+/// The initial karbonite on Earth in a test map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestKarbonite {
+    /// The same amount on every square.
+    Uniform(u32),
+    /// The amount on every square in the first half of Earth where x + y is
+    /// even, and none elsewhere in it. The other half is its rotation, so
+    /// its karbonite is where x + y is odd if width + height is odd.
+    Checkerboard(u32),
+}
+
+/// A wall across the middle of a test map, two squares thick (one if the
+/// map has an odd size across the wall), with a gap in its center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestWall {
+    /// A wall from the bottom to the top of the map, with a gap of about
+    /// `gap` squares.
+    Vertical { gap: usize },
+    /// A wall from the left to the right of the map, with a gap of about
+    /// `gap` squares.
+    Horizontal { gap: usize },
+}
+
+impl TestWall {
+    /// Whether the wall covers (x, y) on a map with the given dimensions.
+    fn contains(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        // Whether i is one of the middle indices of a dimension of size n,
+        // or within about `gap` squares of the middle.
+        let in_middle = |i: usize, n: usize| i == (n - 1) / 2 || i == n / 2;
+        let in_gap = |i: usize, n: usize, gap: usize| {
+            ((2 * i) as i32 - (n - 1) as i32).abs() < gap as i32
+        };
+        match *self {
+            TestWall::Vertical { gap } => in_middle(x, width) && !in_gap(y, height, gap),
+            TestWall::Horizontal { gap } => in_middle(y, height) && !in_gap(x, width, gap),
+        }
+    }
+}
+
+/// A symmetry of a planet map. Earth maps are always symmetric by at least
+/// one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapSymmetry {
+    /// A reflection across the vertical axis through the center of the map,
+    /// which flips the x-coordinate.
+    Horizontal,
+    /// A reflection across the horizontal axis through the center of the map,
+    /// which flips the y-coordinate.
+    Vertical,
+    /// A rotation by 180 degrees about the center of the map.
+    Rotational,
+}
+
+impl MapSymmetry {
+    /// All the symmetries.
+    pub fn all() -> Vec<MapSymmetry> {
+        vec![MapSymmetry::Horizontal, MapSymmetry::Vertical, MapSymmetry::Rotational]
+    }
+
+    /// The square opposite (x, y) under this symmetry, on a map with the
+    /// given dimensions.
+    pub fn opposite(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        match *self {
+            MapSymmetry::Horizontal => (width - 1 - x, y),
+            MapSymmetry::Vertical => (x, height - 1 - y),
+            MapSymmetry::Rotational => (width - 1 - x, height - 1 - y),
+        }
+    }
+
+    /// Whether (x, y) is in the first half of the map under this symmetry:
+    /// it comes before its opposite square, ordered by y and then x. This is
+    /// the left half for a horizontal symmetry, and the top half otherwise.
+    /// Squares that are their own opposite are in neither half.
+    pub fn in_first_half(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        let (other_x, other_y) = self.opposite(x, y, width, height);
+        (y, x) < (other_y, other_x)
+    }
+}
+
+/// Copies each square in the first half of the grid onto its opposite
+/// square, so that the grid is symmetric.
+pub(crate) fn symmetrize<T: Clone>(grid: &mut [Vec<T>], symmetry: MapSymmetry) {
+    let height = grid.len();
+    let width = grid[0].len();
+    for y in 0..height {
+        for x in 0..width {
+            if symmetry.in_first_half(x, y, width, height) {
+                let (other_x, other_y) = symmetry.opposite(x, y, width, height);
+                grid[other_y][other_x] = grid[y][x].clone();
+            }
+        }
+    }
 }
 
 /// The map for one of the planets in the Battlecode world. This information
@@ -104,6 +336,88 @@ pub struct PlanetMap {
 }
 
 impl PlanetMap {
+    /// An empty map of the planet with the given dimensions: every square is
+    /// passable, with no karbonite or initial units. Use the setters below
+    /// to edit it, and `validate()` to check the finished map.
+    ///
+    /// * InvalidMapObject - the dimensions are out of range.
+    pub fn new(planet: Planet, width: usize, height: usize) -> Result<PlanetMap, Error> {
+        if !(height >= MAP_HEIGHT_MIN && height <= MAP_HEIGHT_MAX &&
+             width >= MAP_WIDTH_MIN && width <= MAP_WIDTH_MAX) {
+            Err(invalid_map(format!("the dimensions {}x{} are out of range", width, height)))?
+        }
+        Ok(PlanetMap {
+            planet,
+            height,
+            width,
+            is_passable_terrain: vec![vec![true; width]; height],
+            initial_karbonite: vec![vec![0; width]; height],
+            initial_units: vec![],
+        })
+    }
+
+    /// Sets whether the square contains passable terrain.
+    ///
+    /// * LocationOffMap - the location is off the map.
+    /// * LocationNotEmpty - the square has an initial unit, so it cannot
+    ///   become impassable.
+    pub fn set_passable(&mut self, location: MapLocation, passable: bool) -> Result<(), Error> {
+        let (x, y) = self.index_of(location).ok_or(GameError::LocationOffMap { location })?;
+        if !passable && self.initial_unit_at(location).is_some() {
+            Err(GameError::LocationNotEmpty { location })?
+        }
+        self.is_passable_terrain[y][x] = passable;
+        Ok(())
+    }
+
+    /// Sets the initial karbonite deposit on the square.
+    ///
+    /// * LocationOffMap - the location is off the map.
+    /// * InvalidMapObject - the amount is outside [MAP_KARBONITE_MIN,
+    ///   MAP_KARBONITE_MAX] on Earth, or is not 0 on Mars.
+    pub fn set_karbonite(&mut self, location: MapLocation, amount: u32) -> Result<(), Error> {
+        let (x, y) = self.index_of(location).ok_or(GameError::LocationOffMap { location })?;
+        let valid = match self.planet {
+            Planet::Earth => amount <= MAP_KARBONITE_MAX,
+            Planet::Mars => amount == 0,
+        };
+        if !valid {
+            Err(invalid_map(format!("the karbonite at {:?} cannot be {}", location, amount)))?
+        }
+        self.initial_karbonite[y][x] = amount;
+        Ok(())
+    }
+
+    /// Adds an initial unit to the map, returning its ID, which follows the
+    /// largest ID on the map.
+    ///
+    /// * InvalidMapObject - the map is of Mars, or the team already has the
+    ///   most initial units it can have.
+    /// * InappropriateUnitType - the unit is not a worker.
+    /// * LocationOffMap - the location is off the map.
+    /// * LocationNotEmpty - the square is impassable or has another unit.
+    pub fn add_initial_unit(&mut self, team: Team, unit_type: UnitType,
+                            location: MapLocation) -> Result<UnitID, Error> {
+        let team_units = self.initial_units.iter().filter(|unit| unit.team() == team).count();
+        if self.planet == Planet::Mars || team_units >= MAP_INITIAL_WORKERS_MAX {
+            Err(invalid_map(format!("{:?} cannot have another initial unit on {:?}", team, self.planet)))?
+        }
+        if unit_type != UnitType::Worker {
+            Err(GameError::InappropriateUnitType)?
+        }
+        let (x, y) = self.index_of(location).ok_or(GameError::LocationOffMap { location })?;
+        if !self.is_passable_terrain[y][x] || self.initial_unit_at(location).is_some() {
+            Err(GameError::LocationNotEmpty { location })?
+        }
+        let id = self.initial_units.iter().map(|unit| unit.id()).max().unwrap_or(0) + 1;
+        self.initial_units.push(Unit::new(id, team, unit_type, 0, Location::OnMap(location))?);
+        Ok(id)
+    }
+
+    fn initial_unit_at(&self, location: MapLocation) -> Option<&Unit> {
+        self.initial_units.iter().find(|unit| unit.location() == Location::OnMap(location))
+    }
+
     /// Validates the map and checks some invariants are followed.
     ///
     /// * InvalidMapObject - the planet map is invalid.
@@ -111,32 +425,32 @@ impl PlanetMap {
         // The width and height are of valid dimensions.
         if !(self.height >= MAP_HEIGHT_MIN && self.height <= MAP_HEIGHT_MAX &&
              self.width >= MAP_WIDTH_MIN && self.width <= MAP_WIDTH_MAX) {
-            Err(GameError::InvalidMapObject)?
+            Err(invalid_map(format!("the dimensions {}x{} of {:?} are out of range", self.width, self.height, self.planet)))?
         }
 
         // The terrain definition is valid.
         if self.is_passable_terrain.len() != self.height ||
-           self.is_passable_terrain[0].len() != self.width {
-            Err(GameError::InvalidMapObject)?
+           self.is_passable_terrain.iter().any(|row| row.len() != self.width) {
+            Err(invalid_map(format!("the terrain of {:?} is not {}x{}", self.planet, self.width, self.height)))?
         }
 
         // The initial karbonite deposits are valid.
         if self.initial_karbonite.len() != self.height ||
-           self.initial_karbonite[0].len() != self.width {
-            Err(GameError::InvalidMapObject)?
+           self.initial_karbonite.iter().any(|row| row.len() != self.width) {
+            Err(invalid_map(format!("the karbonite of {:?} is not {}x{}", self.planet, self.width, self.height)))?
         }
         for y in 0..self.height {
             for x in 0..self.width {
                 match self.planet {
                     Planet::Mars => {
                         if self.initial_karbonite[y][x] != 0 {
-                            Err(GameError::InvalidMapObject)?
+                            Err(invalid_map(format!("Mars has karbonite at ({}, {})", x, y)))?
                         }
                     }
                     Planet::Earth => {
                         if self.initial_karbonite[y][x] < MAP_KARBONITE_MIN ||
                            self.initial_karbonite[y][x] > MAP_KARBONITE_MAX {
-                            Err(GameError::InvalidMapObject)?
+                            Err(invalid_map(format!("the karbonite on Earth at ({}, {}) is out of range", x, y)))?
                         }
                     }
                 }
@@ -148,35 +462,169 @@ impl PlanetMap {
         match self.planet {
             Planet::Mars => {
                 if num_units != 0 {
-                    Err(GameError::InvalidMapObject)?
+                    Err(invalid_map(format!("Mars has {} initial units", num_units)))?
                 }
             }
             Planet::Earth => {
-                if !(num_units > 0 && num_units % 2 == 0 && num_units <= 6) {
-                    Err(GameError::InvalidMapObject)?
+                if !(num_units > 0 && num_units % 2 == 0 && num_units <= 2 * MAP_INITIAL_WORKERS_MAX) {
+                    Err(invalid_map(format!("Earth has {} initial units", num_units)))?
                 }
             }
         }
-        for ref unit in &self.initial_units {
+        for (i, unit) in self.initial_units.iter().enumerate() {
             let location = unit.location().map_location()
-                               .or(Err(GameError::InvalidMapObject))?;
+                               .map_err(|_| invalid_map(format!("initial unit {} is not on a map", unit.id())))?;
+            if !self.on_map(location) {
+                Err(invalid_map(format!("initial unit {} is off the map", unit.id())))?
+            }
             let x = location.x as usize;
             let y = location.y as usize;
-            if location.planet != self.planet {
-                Err(GameError::InvalidMapObject)?
-            }
             if !self.is_passable_terrain[y][x] {
-                Err(GameError::InvalidMapObject)?
+                Err(invalid_map(format!("initial unit {} is on impassable terrain", unit.id())))?
+            }
+
+            // No two units share an ID or a square.
+            let collides = self.initial_units[..i].iter().any(|other| {
+                other.id() == unit.id() || other.location() == unit.location()
+            });
+            if collides {
+                Err(invalid_map(format!("initial unit {} shares an ID or a square with another unit", unit.id())))?
             }
         }
 
-        // The map is symmetric on Earth.
+        // On Earth, each team can reach karbonite, and the teams can reach
+        // each other.
         if self.planet == Planet::Earth {
-            // TODO
+            for team in [Team::Red, Team::Blue].iter() {
+                let reachable = self.initial_units.iter()
+                    .filter(|unit| unit.team() == *team)
+                    .map(|unit| self.reachable(unit.location().map_location().unwrap()))
+                    .collect::<Vec<_>>();
+                let reaches = |location: MapLocation| reachable.iter().any(|region| {
+                    region[location.y as usize][location.x as usize]
+                });
+
+                let mut reaches_karbonite = false;
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let location = MapLocation::new(self.planet, x as i32, y as i32);
+                        if self.initial_karbonite[y][x] > 0 && reaches(location) {
+                            reaches_karbonite = true;
+                        }
+                    }
+                }
+                if !reaches_karbonite {
+                    Err(invalid_map(format!("{:?} cannot reach any karbonite", team)))?
+                }
+
+                let reaches_other_team = self.initial_units.iter()
+                    .filter(|unit| unit.team() != *team)
+                    .any(|unit| reaches(unit.location().map_location().unwrap()));
+                if !reaches_other_team {
+                    Err(invalid_map(format!("{:?} cannot reach the other team", team)))?
+                }
+            }
+        }
+
+        // The map is symmetric on Earth.
+        if self.planet == Planet::Earth && self.detect_symmetry().is_empty() {
+            Err(invalid_map("Earth is not symmetric".to_string()))?
         }
         Ok(())
     }
 
+    /// The full symmetric map built from one half of this map. The terrain
+    /// and karbonite of each square in the first half, as defined by
+    /// `MapSymmetry::in_first_half`, are copied onto its opposite square.
+    /// Each initial unit is kept, and a copy is placed on its opposite square
+    /// on the other team. The copies' IDs follow the largest existing ID, in
+    /// the order of the units they copy.
+    ///
+    /// The initial units should be on the first half and on one team. Units
+    /// that are not on the map are kept but not copied, so the result fails
+    /// `validate()`.
+    pub fn mirrored(&self, symmetry: MapSymmetry) -> PlanetMap {
+        let mut map = self.clone();
+        symmetrize(&mut map.is_passable_terrain, symmetry);
+        symmetrize(&mut map.initial_karbonite, symmetry);
+
+        let mut next_id = self.initial_units.iter().map(|unit| unit.id()).max().unwrap_or(0) + 1;
+        for unit in self.initial_units.iter() {
+            let location = match unit.location() {
+                Location::OnMap(location) if self.on_map(location) => location,
+                _ => continue,
+            };
+            let (x, y) = symmetry.opposite(location.x as usize, location.y as usize,
+                                           self.width, self.height);
+            let location = MapLocation::new(self.planet, x as i32, y as i32);
+            map.initial_units.push(Unit::new(next_id, unit.team().other(),
+                                             unit.unit_type(), 0,
+                                             Location::OnMap(location)).unwrap());
+            next_id += 1;
+        }
+        map
+    }
+
+    /// The symmetries of the map, in the order horizontal, vertical,
+    /// rotational. A map is symmetric if its terrain, karbonite, and initial
+    /// units are unchanged by the symmetry, where initial units switch teams.
+    pub fn detect_symmetry(&self) -> Vec<MapSymmetry> {
+        MapSymmetry::all().into_iter()
+            .filter(|symmetry| self.is_symmetric(*symmetry))
+            .collect()
+    }
+
+    /// The squares that a robot could walk to from the given location, across
+    /// passable terrain, indexed by y-coordinate and then x-coordinate.
+    fn reachable(&self, location: MapLocation) -> Vec<Vec<bool>> {
+        let mut reachable = vec![vec![false; self.width]; self.height];
+        reachable[location.y as usize][location.x as usize] = true;
+        let mut stack = vec![location];
+        while let Some(location) = stack.pop() {
+            for direction in Direction::all() {
+                let next = location.add(direction);
+                if !self.on_map(next) {
+                    continue;
+                }
+                let (x, y) = (next.x as usize, next.y as usize);
+                if self.is_passable_terrain[y][x] && !reachable[y][x] {
+                    reachable[y][x] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Whether the terrain, karbonite, and initial units of the map are
+    /// unchanged by the given symmetry, where initial units switch teams.
+    fn is_symmetric(&self, symmetry: MapSymmetry) -> bool {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (other_x, other_y) = symmetry.opposite(x, y, self.width, self.height);
+                if self.is_passable_terrain[y][x] != self.is_passable_terrain[other_y][other_x] ||
+                   self.initial_karbonite[y][x] != self.initial_karbonite[other_y][other_x] {
+                    return false;
+                }
+            }
+        }
+        self.initial_units.iter().all(|unit| {
+            let location = unit.location().map_location().unwrap();
+            if !self.on_map(location) {
+                return false;
+            }
+            let (other_x, other_y) = symmetry.opposite(location.x as usize, location.y as usize,
+                                                       self.width, self.height);
+            self.initial_units.iter().any(|other| {
+                let other_location = other.location().map_location().unwrap();
+                other.team() == unit.team().other()
+                    && other.unit_type() == unit.unit_type()
+                    && other_location.x == other_x as i32
+                    && other_location.y == other_y as i32
+            })
+        })
+    }
+
     /// Whether a location is on the map.
     pub fn on_map(&self, location: MapLocation) -> bool {
         self.planet == location.planet
@@ -186,16 +634,26 @@ impl PlanetMap {
             && location.y < self.height as i32
     }
 
+    /// The (x, y) indices of the location in the map's grids, which are
+    /// indexed by y-coordinate and then x-coordinate. None if the location
+    /// is off the map.
+    pub fn index_of(&self, location: MapLocation) -> Option<(usize, usize)> {
+        if self.on_map(location) {
+            Some((location.x as usize, location.y as usize))
+        } else {
+            None
+        }
+    }
+
     /// Whether the location on the map contains passable terrain. Is only
     /// false when the square contains impassable terrain (distinct from
     /// containing a building, for instance).
     ///
     /// * LocationOffMap - the location is off the map.
     pub fn is_passable_terrain_at(&self, location: MapLocation) -> Result<bool, Error> {
-        if self.on_map(location) {
-            Ok(self.is_passable_terrain[location.y as usize][location.x as usize])
-        } else {
-            Err(GameError::LocationOffMap)?
+        match self.index_of(location) {
+            Some((x, y)) => Ok(self.is_passable_terrain[y][x]),
+            None => Err(GameError::LocationOffMap { location })?,
         }
     }
 
@@ -203,36 +661,11 @@ impl PlanetMap {
     ///
     /// * LocationOffMap - the location is off the map.
     pub fn initial_karbonite_at(&self, location: MapLocation) -> Result<u32, Error> {
-        if self.on_map(location) {
-            Ok(self.initial_karbonite[location.y as usize][location.x as usize])
-        } else {
-            Err(GameError::LocationOffMap)?
+        match self.index_of(location) {
+            Some((x, y)) => Ok(self.initial_karbonite[y][x]),
+            None => Err(GameError::LocationOffMap { location })?,
         }
     }
-
-    fn test_map(planet: Planet) -> PlanetMap {
-        let mut map = PlanetMap {
-            planet: planet,
-            height: MAP_HEIGHT_MIN,
-            width: MAP_WIDTH_MIN,
-            is_passable_terrain: vec![vec![true; MAP_WIDTH_MIN]; MAP_HEIGHT_MIN],
-            initial_karbonite: vec![vec![10; MAP_WIDTH_MIN]; MAP_HEIGHT_MIN],
-            initial_units: vec![],
-        };
-
-        if planet == Planet::Earth {
-            map.initial_units.push(Unit::new(
-                1, Team::Red, UnitType::Worker, 0,
-                Location::OnMap(MapLocation::new(planet, 1, 1))
-            ).expect("invalid test unit"));
-            map.initial_units.push(Unit::new(
-                2, Team::Blue, UnitType::Worker, 0,
-                Location::OnMap(MapLocation::new(planet, MAP_WIDTH_MIN as i32 - 1, MAP_HEIGHT_MIN as i32 - 1))
-            ).expect("invalid test unit"));
-        };
-
-        map
-    }
 }
 
 /// A single asteroid strike on Mars.
@@ -248,7 +681,9 @@ pub struct AsteroidStrike {
 /// strike.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AsteroidPattern {
-    pub(crate) pattern: FnvHashMap<Rounds, AsteroidStrike>,
+    /// The strikes by round, kept in order so strikes can be iterated over
+    /// chronologically.
+    pub(crate) pattern: BTreeMap<Rounds, AsteroidStrike>,
 }
 
 /// The orbit pattern that determines a rocket's flight duration. This pattern
@@ -281,7 +716,7 @@ impl AsteroidPattern {
     /// Constructs a new asteroid pattern from a map of round number to strike.
     pub fn new(pattern: &FnvHashMap<Rounds, AsteroidStrike>) -> AsteroidPattern {
         AsteroidPattern {
-            pattern: pattern.clone(),
+            pattern: pattern.iter().map(|(round, strike)| (*round, *strike)).collect(),
         }
     }
 
@@ -294,8 +729,7 @@ impl AsteroidPattern {
         let x_gen = Range::new(0, mars_map.width as i32);
         let y_gen = Range::new(0, mars_map.height as i32);
 
-        let seed: &[_] = &[seed as usize];
-        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let mut rng = GameRng::new(seed, "asteroids");
         let mut round = 0;
         loop {
             round += round_gen.ind_sample(&mut rng);
@@ -312,9 +746,7 @@ impl AsteroidPattern {
             });
         }
 
-        AsteroidPattern {
-            pattern: pattern,
-        }
+        AsteroidPattern::new(&pattern)
     }
 
     /// Validates the asteroid pattern.
@@ -323,16 +755,16 @@ impl AsteroidPattern {
     pub fn validate(&self) -> Result<(), Error> {
         // The Karbonite on each asteroid is in the range
         // [ASTEROID_KARB_MIN, ASTEROID_KARB_MAX], inclusive.
-        for (round, asteroid) in self.pattern.clone() {
+        for (&round, asteroid) in self.pattern.iter() {
             if round < 1 || round > ROUND_LIMIT {
-                Err(GameError::InvalidMapObject)?
+                Err(invalid_map(format!("the asteroid round {} is out of range", round)))?
             }
             if asteroid.karbonite < ASTEROID_KARB_MIN ||
                asteroid.karbonite > ASTEROID_KARB_MAX {
-                Err(GameError::InvalidMapObject)?
+                Err(invalid_map(format!("the asteroid on round {} has {} karbonite", round, asteroid.karbonite)))?
             }
             if asteroid.location.planet != Planet::Mars {
-                Err(GameError::InvalidMapObject)?
+                Err(invalid_map(format!("the asteroid on round {} is not on Mars", round)))?
             }
         }
 
@@ -340,16 +772,33 @@ impl AsteroidPattern {
         // ASTEROID_ROUND_MAX] rounds, inclusive.
         let mut rounds: Vec<&Rounds> = self.pattern.keys().collect();
         rounds.sort();
+        if rounds.is_empty() {
+            Err(invalid_map("there are no asteroids".to_string()))?
+        }
         if *rounds[0] > ASTEROID_ROUND_MAX {
-            Err(GameError::InvalidMapObject)?
+            Err(invalid_map(format!("the first asteroid, on round {}, is too late", rounds[0])))?
         }
         if ROUND_LIMIT - rounds[rounds.len() - 1] > ASTEROID_ROUND_MAX {
-            Err(GameError::InvalidMapObject)?
+            Err(invalid_map(format!("the last asteroid, on round {}, is too early", rounds[rounds.len() - 1])))?
         }
         for i in 0..rounds.len() - 1 {
             let diff = rounds[i + 1] - rounds[i];
             if diff < ASTEROID_ROUND_MIN || diff > ASTEROID_ROUND_MAX {
-                Err(GameError::InvalidMapObject)?
+                Err(invalid_map(format!("the asteroids on rounds {} and {} are too close or too far apart", rounds[i], rounds[i + 1])))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the asteroid pattern, and that every strike lands on the
+    /// given map of Mars.
+    ///
+    /// * InvalidMapObject - the asteroid pattern is invalid.
+    pub fn validate_against(&self, mars_map: &PlanetMap) -> Result<(), Error> {
+        self.validate()?;
+        for (&round, asteroid) in self.pattern.iter() {
+            if !mars_map.on_map(asteroid.location) {
+                Err(invalid_map(format!("the asteroid on round {} is off the map", round)))?
             }
         }
         Ok(())
@@ -373,7 +822,37 @@ impl AsteroidPattern {
 
     /// Get a map of round numbers to asteroid strikes.
     pub fn asteroid_map(&self) -> FnvHashMap<Rounds, AsteroidStrike> {
-        self.pattern.clone()
+        self.pattern.iter().map(|(round, strike)| (*round, *strike)).collect()
+    }
+
+    /// Iterate over the asteroid strikes in order of round.
+    pub fn iter<'a>(&'a self) -> AsteroidIter<'a> {
+        AsteroidIter { strikes: self.pattern.iter() }
+    }
+
+    /// Get the first asteroid strike strictly after the given round, and
+    /// the round it strikes on.
+    ///
+    /// * NullValue - There are no asteroid strikes after this round.
+    pub fn next_asteroid(&self, after_round: Rounds) -> Result<(Rounds, &AsteroidStrike), Error> {
+        if let Some((round, asteroid)) = self.pattern.range((Bound::Excluded(after_round), Bound::Unbounded)).next() {
+            Ok((*round, asteroid))
+        } else {
+            Err(GameError::NullValue)?
+        }
+    }
+}
+
+/// An iterator over the strikes of an asteroid pattern, in order of round.
+pub struct AsteroidIter<'a> {
+    strikes: btree_map::Iter<'a, Rounds, AsteroidStrike>,
+}
+
+impl<'a> Iterator for AsteroidIter<'a> {
+    type Item = (Rounds, &'a AsteroidStrike);
+
+    fn next(&mut self) -> Option<(Rounds, &'a AsteroidStrike)> {
+        self.strikes.next().map(|(round, strike)| (*round, strike))
     }
 }
 
@@ -398,22 +877,66 @@ impl OrbitPattern {
     ///
     /// * InvalidMapObject - the orbit pattern is invalid.
     pub fn validate(&self) -> Result<(), Error> {
+        // The pattern repeats.
+        if self.period == 0 {
+            Err(invalid_map("the orbit period is 0".to_string()))?
+        }
+
         // The flight times are within [ORIBIT_FLIGHT_MIN, ORBIT_FLIGHT_MAX].
-        if self.center - self.amplitude < ORBIT_FLIGHT_MIN {
-            Err(GameError::InvalidMapObject)?
+        if self.amplitude > self.center ||
+           self.center - self.amplitude < ORBIT_FLIGHT_MIN {
+            Err(invalid_map("the shortest flight is too short".to_string()))?
         }
         if self.center + self.amplitude > ORBIT_FLIGHT_MAX {
-            Err(GameError::InvalidMapObject)?
+            Err(invalid_map("the longest flight is too long".to_string()))?
         }
         Ok(())
     }
 
     /// Get the duration of flight if the rocket were to take off from either
-    /// planet on the given round.
+    /// planet on the given round. For a valid orbit pattern, the duration is
+    /// always in [ORBIT_FLIGHT_MIN, ORBIT_FLIGHT_MAX], on any round.
     pub fn duration(&self, round: Rounds) -> Rounds {
-        let arg = 2. * f32::consts::PI / self.period_s as f32 * round as f32;
-        let sin = ((self.amplitude_s as f32) * f32::sin(arg)) as i32;
-        (sin + self.center_s) as Rounds
+        if self.period == 0 {
+            return self.center;
+        }
+        // Reduce the round first so that the sine is just as precise on
+        // later rounds, and the pattern repeats exactly.
+        let phase = (round % self.period) as f32 / self.period as f32;
+        let sin = ((self.amplitude_s as f32) * f32::sin(2. * f32::consts::PI * phase)) as i32;
+        cmp::max(sin + self.center_s, 0) as Rounds
+    }
+
+    /// Get the round a rocket would land on if it were to take off from
+    /// either planet on the given round.
+    pub fn landing_round(&self, takeoff_round: Rounds) -> Rounds {
+        takeoff_round + self.duration(takeoff_round)
+    }
+
+    /// Get the first round at or after the given round on which the duration
+    /// of flight is the shortest it can be.
+    pub fn next_minimum(&self, after_round: Rounds) -> Rounds {
+        let minimum = (0..self.period).map(|round| self.duration(round)).min();
+        self.next_duration(after_round, minimum)
+    }
+
+    /// Get the first round at or after the given round on which the duration
+    /// of flight is the longest it can be.
+    pub fn next_maximum(&self, after_round: Rounds) -> Rounds {
+        let maximum = (0..self.period).map(|round| self.duration(round)).max();
+        self.next_duration(after_round, maximum)
+    }
+
+    /// The first round at or after the given round with the given duration,
+    /// which must occur in every period.
+    fn next_duration(&self, after_round: Rounds, duration: Option<Rounds>) -> Rounds {
+        let duration = match duration {
+            Some(duration) => duration,
+            None => return after_round,
+        };
+        (after_round..).take(self.period as usize)
+                       .find(|&round| self.duration(round) == duration)
+                       .unwrap_or(after_round)
     }
 }
 
@@ -442,12 +965,50 @@ mod tests {
         asteroid_map
     }
 
+    #[test]
+    fn test_planet_map_edges() {
+        let mut map = GameMap::test_map().earth_map;
+        map.is_passable_terrain[0][19] = false;
+        map.initial_karbonite[19][0] = 7;
+        let (w, h) = (map.width as i32, map.height as i32);
+
+        let corner = MapLocation::new(Planet::Earth, w - 1, 0);
+        assert!(map.on_map(corner));
+        assert_eq!(map.index_of(corner), Some((19, 0)));
+        assert!(!map.is_passable_terrain_at(corner).unwrap());
+        assert_eq!(map.initial_karbonite_at(MapLocation::new(Planet::Earth, 0, h - 1)).unwrap(), 7);
+        assert_eq!(map.initial_karbonite_at(MapLocation::new(Planet::Earth, w - 1, h - 1)).unwrap(), 10);
+
+        for &location in [MapLocation::new(Planet::Earth, w, 0),
+                          MapLocation::new(Planet::Earth, 0, h),
+                          MapLocation::new(Planet::Earth, -1, 0),
+                          MapLocation::new(Planet::Earth, 0, -1),
+                          MapLocation::new(Planet::Mars, 0, 0)].iter() {
+            assert!(!map.on_map(location));
+            assert_eq!(map.index_of(location), None);
+            assert_err!(map.is_passable_terrain_at(location), GameError::LocationOffMap { location });
+            assert_err!(map.initial_karbonite_at(location), GameError::LocationOffMap { location });
+        }
+    }
+
+    #[test]
+    fn test_asteroid_golden_values() {
+        // The same seed gives the same strikes on every platform.
+        let ref mars_map = GameMap::test_map().mars_map;
+        let pattern = AsteroidPattern::random(6147, mars_map);
+        let strikes: Vec<(Rounds, u32, i32, i32)> = pattern.iter()
+            .take(3)
+            .map(|(round, strike)| (round, strike.karbonite, strike.location.x, strike.location.y))
+            .collect();
+        assert_eq!(strikes, vec![(11, 90, 12, 7), (30, 68, 9, 19), (46, 77, 14, 4)]);
+    }
+
     #[test]
     fn validate_asteroid() {
         // Valid randomly-generated asteroid patterns.
-        let ref mars_map = super::PlanetMap::test_map(Planet::Mars);
+        let ref mars_map = GameMap::test_map().mars_map;
         for seed in 0..5 {
-            AsteroidPattern::random(seed, mars_map).validate().is_ok();
+            assert!(AsteroidPattern::random(seed, mars_map).validate().is_ok());
         }
 
         // Generate an asteroid pattern from a map.
@@ -472,10 +1033,47 @@ mod tests {
         assert_err!(AsteroidPattern::new(&asteroid_map).validate(), GameError::InvalidMapObject);
     }
 
+    #[test]
+    fn validate_asteroid_against_map() {
+        // A Mars map that is taller than it is wide.
+        let mut mars_map = GameMap::test_map().mars_map;
+        mars_map.width = 25;
+        mars_map.height = 30;
+        let asteroid_map = gen_asteroid_map(1, ASTEROID_ROUND_MAX);
+        let with_strike = |x, y| {
+            let mut pattern = asteroid_map.clone();
+            pattern.insert(1, AsteroidStrike::new(ASTEROID_KARB_MIN, MapLocation::new(Planet::Mars, x, y)));
+            AsteroidPattern::new(&pattern)
+        };
+
+        // Strikes in the corners are on the map.
+        for &(x, y) in [(0, 0), (24, 0), (0, 29), (24, 29)].iter() {
+            assert!(with_strike(x, y).validate_against(&mars_map).is_ok());
+        }
+
+        // Strikes one square past each edge are not, even though they
+        // are otherwise valid.
+        for &(x, y) in [(-1, 10), (25, 10), (10, -1), (10, 30)].iter() {
+            assert!(with_strike(x, y).validate().is_ok());
+            assert_err!(with_strike(x, y).validate_against(&mars_map), GameError::InvalidMapObject);
+        }
+        assert!(with_strike(24, 25).validate_against(&mars_map).is_ok());
+        assert_err!(with_strike(25, 24).validate_against(&mars_map), GameError::InvalidMapObject);
+
+        // Game maps check their strikes against their own Mars map.
+        let mut map = GameMap::random(3);
+        assert!(map.validate().is_ok());
+        let round = *map.asteroids.pattern.keys().next().unwrap();
+        let width = map.mars_map.width as i32;
+        map.asteroids.pattern.get_mut(&round).unwrap().location = MapLocation::new(Planet::Mars, width, 0);
+        assert_err!(map.validate(), GameError::InvalidMapObject);
+    }
+
     #[test]
     fn validate_orbit() {
         assert_err!(OrbitPattern::new(150, 200, 200).validate(), GameError::InvalidMapObject);
         assert_err!(OrbitPattern::new(150, 200, 300).validate(), GameError::InvalidMapObject);
+        assert_err!(OrbitPattern::new(200, 200, 150).validate(), GameError::InvalidMapObject);
         assert!(OrbitPattern::new(75, 200, 125).validate().is_ok());
     }
 
@@ -492,6 +1090,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_asteroid_iter() {
+        let asteroid_map = gen_asteroid_map(ASTEROID_ROUND_MAX, ASTEROID_ROUND_MAX);
+        let asteroids = AsteroidPattern::new(&asteroid_map);
+
+        let rounds: Vec<Rounds> = asteroids.iter().map(|(round, _)| round).collect();
+        let mut expected: Vec<Rounds> = asteroid_map.keys().cloned().collect();
+        expected.sort();
+        assert_eq!(rounds, expected);
+        for (round, strike) in asteroids.iter() {
+            assert_eq!(strike, &asteroid_map[&round]);
+        }
+
+        // The next strike is strictly after the given round.
+        assert_eq!(asteroids.next_asteroid(0).unwrap().0, ASTEROID_ROUND_MAX);
+        assert_eq!(asteroids.next_asteroid(ASTEROID_ROUND_MAX - 1).unwrap().0, ASTEROID_ROUND_MAX);
+        assert_eq!(asteroids.next_asteroid(ASTEROID_ROUND_MAX).unwrap().0, 2 * ASTEROID_ROUND_MAX);
+        let last = *rounds.last().unwrap();
+        assert_err!(asteroids.next_asteroid(last), GameError::NullValue);
+        assert_err!(asteroids.next_asteroid(Rounds::max_value()), GameError::NullValue);
+    }
+
+    #[test]
+    fn test_landing_round() {
+        let orbit = OrbitPattern::new(75, 140, 125);
+        assert!(orbit.validate().is_ok());
+        assert_eq!(orbit.duration(0), 125);
+        assert_eq!(orbit.landing_round(0), 125);
+        assert_eq!(orbit.landing_round(35), 35 + 200);
+        assert_eq!(orbit.landing_round(105), 105 + 50);
+
+        // A valid orbit stays in bounds on every round, even far beyond the
+        // end of the game, and repeats exactly every period.
+        let rounds = (0..3 * ROUND_LIMIT).chain(Rounds::max_value() - 1000..Rounds::max_value());
+        for round in rounds {
+            let duration = orbit.duration(round);
+            assert!(duration >= ORBIT_FLIGHT_MIN && duration <= ORBIT_FLIGHT_MAX);
+            assert_eq!(duration, orbit.duration(round % 140));
+        }
+
+        // Invalid orbits don't wrap around or divide by zero.
+        assert_err!(OrbitPattern::new(10, 0, 100).validate(), GameError::InvalidMapObject);
+        assert_eq!(OrbitPattern::new(10, 0, 100).duration(17), 100);
+        assert_eq!(OrbitPattern::new(100, 40, 50).duration(30), 0);
+    }
+
+    #[test]
+    fn test_next_extrema() {
+        let orbits = [(50, 40, 100), (75, 200, 125), (1, 7, 60), (0, 30, 80), (100, 150, 100)];
+        for &(amplitude, period, center) in orbits.iter() {
+            let orbit = OrbitPattern::new(amplitude, period, center);
+            let durations: Vec<Rounds> = (0..4 * period).map(|round| orbit.duration(round)).collect();
+            let minimum = *durations.iter().min().unwrap();
+            let maximum = *durations.iter().max().unwrap();
+            for round in 0..3 * period {
+                let brute_min = (round..).find(|&r| durations[r as usize] == minimum).unwrap();
+                let brute_max = (round..).find(|&r| durations[r as usize] == maximum).unwrap();
+                assert_eq!(orbit.next_minimum(round), brute_min);
+                assert_eq!(orbit.next_maximum(round), brute_max);
+            }
+        }
+    }
+
     #[test]
     fn test_duration() {
         let period = 200;
@@ -508,4 +1169,379 @@ mod tests {
             assert!(duration > 250 && duration < 400);
         }
     }
+
+    /// A temporary map file, deleted when it is dropped.
+    struct TempMapFile {
+        path: ::std::path::PathBuf,
+    }
+
+    impl AsRef<Path> for TempMapFile {
+        fn as_ref(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for TempMapFile {
+        fn drop(&mut self) {
+            let _ = ::std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// Writes the contents to a temporary map file with the given name,
+    /// unique to this process so that parallel runs don't collide.
+    fn temp_map_file(name: &str, contents: &str) -> TempMapFile {
+        let path = ::std::env::temp_dir().join(format!("bc-map-{}-{}", ::std::process::id(), name));
+        ::std::fs::write(&path, contents).unwrap();
+        TempMapFile { path }
+    }
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/maps/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_random_map() {
+        for seed in 0..300 {
+            let map = GameMap::random(seed);
+            assert!(map.validate().is_ok(), "seed {} is invalid", seed);
+            assert_eq!(map, GameMap::random(seed));
+        }
+        assert!(GameMap::random(1) != GameMap::random(2));
+    }
+
+    #[test]
+    fn validate_symmetry() {
+        let fixtures = [
+            include_str!("../../tests/maps/symmetry_horizontal.bc18t"),
+            include_str!("../../tests/maps/symmetry_vertical.bc18t"),
+            include_str!("../../tests/maps/symmetry_spiral.bc18t"),
+        ];
+        for fixture in fixtures.iter() {
+            let map = GameMap::parse_text_map(fixture).unwrap();
+            assert_eq!(map.earth_map.initial_units.len(), 4);
+            assert!(map.earth_map.validate().is_ok());
+        }
+
+        let map = GameMap::parse_text_map(include_str!("../../tests/maps/asymmetric.bc18t")).unwrap();
+        assert_err!(map.earth_map.validate(), GameError::InvalidMapObject);
+
+        // Changing a single deposit of karbonite breaks the symmetry.
+        let mut map = GameMap::parse_text_map(fixtures[1]).unwrap();
+        map.earth_map.initial_karbonite[0][1] += 1;
+        assert_err!(map.earth_map.validate(), GameError::InvalidMapObject);
+
+        // As do units that do not switch teams.
+        let mut map = GameMap::parse_text_map(fixtures[2]).unwrap();
+        let team = map.earth_map.initial_units[0].team();
+        map.earth_map.initial_units = map.earth_map.initial_units.iter()
+            .map(|unit| Unit::new(unit.id(), team, unit.unit_type(), 0,
+                                  unit.location()).unwrap())
+            .collect();
+        assert_err!(map.earth_map.validate(), GameError::InvalidMapObject);
+    }
+
+    #[test]
+    fn test_planet_map_setters() {
+        assert_err!(PlanetMap::new(Planet::Earth, MAP_WIDTH_MIN - 1, MAP_HEIGHT_MIN), GameError::InvalidMapObject);
+        assert_err!(PlanetMap::new(Planet::Mars, MAP_WIDTH_MIN, MAP_HEIGHT_MAX + 1), GameError::InvalidMapObject);
+
+        let loc = |x, y| MapLocation::new(Planet::Earth, x, y);
+        let off_map = loc(MAP_WIDTH_MIN as i32, 0);
+        let mut map = PlanetMap::new(Planet::Earth, MAP_WIDTH_MIN, MAP_HEIGHT_MIN).unwrap();
+
+        // Terrain.
+        assert!(map.set_passable(loc(5, 5), false).is_ok());
+        assert!(!map.is_passable_terrain[5][5]);
+        assert_err!(map.set_passable(off_map, false), GameError::LocationOffMap { location: off_map });
+        assert_err!(map.set_passable(MapLocation::new(Planet::Mars, 0, 0), false),
+                    GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 0, 0) });
+
+        // Karbonite.
+        assert!(map.set_karbonite(loc(0, 5), MAP_KARBONITE_MAX).is_ok());
+        assert_eq!(map.initial_karbonite[5][0], MAP_KARBONITE_MAX);
+        assert_err!(map.set_karbonite(loc(0, 5), MAP_KARBONITE_MAX + 1), GameError::InvalidMapObject);
+        assert_err!(map.set_karbonite(off_map, 1), GameError::LocationOffMap { location: off_map });
+        let mut mars = PlanetMap::new(Planet::Mars, MAP_WIDTH_MIN, MAP_HEIGHT_MIN).unwrap();
+        assert!(mars.set_karbonite(MapLocation::new(Planet::Mars, 1, 1), 0).is_ok());
+        assert_err!(mars.set_karbonite(MapLocation::new(Planet::Mars, 1, 1), 1), GameError::InvalidMapObject);
+
+        // Initial units.
+        assert_eq!(map.add_initial_unit(Team::Red, UnitType::Worker, loc(1, 1)).unwrap(), 1);
+        assert_err!(map.add_initial_unit(Team::Red, UnitType::Knight, loc(1, 2)), GameError::InappropriateUnitType);
+        assert_err!(map.add_initial_unit(Team::Blue, UnitType::Worker, loc(1, 1)), GameError::LocationNotEmpty { location: loc(1, 1) });
+        assert_err!(map.add_initial_unit(Team::Blue, UnitType::Worker, loc(5, 5)), GameError::LocationNotEmpty { location: loc(5, 5) });
+        assert_err!(map.add_initial_unit(Team::Blue, UnitType::Worker, off_map), GameError::LocationOffMap { location: off_map });
+        assert_err!(mars.add_initial_unit(Team::Red, UnitType::Worker, MapLocation::new(Planet::Mars, 1, 1)), GameError::InvalidMapObject);
+        assert_err!(map.set_passable(loc(1, 1), false), GameError::LocationNotEmpty { location: loc(1, 1) });
+        assert_eq!(map.add_initial_unit(Team::Red, UnitType::Worker, loc(1, 2)).unwrap(), 2);
+        assert_eq!(map.add_initial_unit(Team::Red, UnitType::Worker, loc(1, 3)).unwrap(), 3);
+        assert_err!(map.add_initial_unit(Team::Red, UnitType::Worker, loc(1, 4)), GameError::InvalidMapObject);
+
+        // The finished map is checked as a whole, including its symmetry.
+        assert_err!(map.validate(), GameError::InvalidMapObject);
+        let mut map = map.mirrored(MapSymmetry::Horizontal);
+        assert!(map.validate().is_ok());
+        map.set_karbonite(loc(0, 6), 1).unwrap();
+        assert_err!(map.validate(), GameError::InvalidMapObject);
+    }
+
+    #[test]
+    fn test_mirrored() {
+        // A half map with an impassable square, a karbonite deposit, and a
+        // red worker in the top left, which is in the first half under
+        // every symmetry. Squares outside the first half are overwritten.
+        let (width, height) = (MAP_WIDTH_MIN, MAP_HEIGHT_MIN + 1);
+        let loc = |x, y| MapLocation::new(Planet::Earth, x as i32, y as i32);
+        let mut half = PlanetMap::new(Planet::Earth, width, height).unwrap();
+        half.set_passable(loc(2, 3), false).unwrap();
+        half.set_karbonite(loc(4, 1), 30).unwrap();
+        half.set_karbonite(loc(width - 1, height - 1), 7).unwrap();
+        assert_eq!(half.add_initial_unit(Team::Red, UnitType::Worker, loc(1, 2)).unwrap(), 1);
+        assert!(half.detect_symmetry().is_empty());
+        assert_err!(half.validate(), GameError::InvalidMapObject);
+
+        for &symmetry in MapSymmetry::all().iter() {
+            let map = half.mirrored(symmetry);
+            assert!(map.validate().is_ok());
+            assert_eq!(map.detect_symmetry(), vec![symmetry]);
+
+            let (x, y) = symmetry.opposite(2, 3, width, height);
+            assert!(!map.is_passable_terrain[y][x]);
+            let (x, y) = symmetry.opposite(4, 1, width, height);
+            assert_eq!(map.initial_karbonite[y][x], 30);
+            assert_eq!(map.initial_karbonite[height - 1][width - 1], 0);
+
+            // The red worker is copied onto the blue team, with the next ID.
+            assert_eq!(map.initial_units.len(), 2);
+            assert_eq!(map.initial_units[0], half.initial_units[0]);
+            let blue = &map.initial_units[1];
+            let (x, y) = symmetry.opposite(1, 2, width, height);
+            assert_eq!(blue.id(), 2);
+            assert_eq!(blue.team(), Team::Blue);
+            assert_eq!(blue.unit_type(), UnitType::Worker);
+            assert_eq!(blue.location(), Location::OnMap(MapLocation::new(Planet::Earth, x as i32, y as i32)));
+        }
+
+        // Units off the map are not copied, though the units around them
+        // are, and the result is invalid.
+        let mut stray = half.clone();
+        let locations = [MapLocation::new(Planet::Earth, -1, 2),
+                         MapLocation::new(Planet::Earth, width as i32, 2),
+                         MapLocation::new(Planet::Mars, 1, 3),
+                         loc(1, 3)];
+        for (i, &location) in locations.iter().enumerate() {
+            stray.initial_units.push(Unit::new(2 + i as UnitID, Team::Red, UnitType::Worker, 0,
+                                               Location::OnMap(location)).unwrap());
+        }
+        stray.initial_units.push(Unit::new(6, Team::Red, UnitType::Worker, 0,
+                                           Location::InSpace).unwrap());
+        for &symmetry in MapSymmetry::all().iter() {
+            let map = stray.mirrored(symmetry);
+            assert_eq!(map.initial_units.len(), stray.initial_units.len() + 2);
+            assert_eq!(map.initial_units[map.initial_units.len() - 1].id(), 8);
+            assert_err!(map.validate(), GameError::InvalidMapObject);
+        }
+
+        // A map with no features has every symmetry.
+        let mut blank = half.clone();
+        blank.is_passable_terrain[3][2] = true;
+        blank.initial_karbonite = vec![vec![0; width]; height];
+        blank.initial_units = vec![];
+        assert_eq!(blank.detect_symmetry(), MapSymmetry::all());
+
+        // The fixtures report the symmetry they were drawn with.
+        let map = GameMap::parse_text_map(include_str!("../../tests/maps/symmetry_vertical.bc18t")).unwrap();
+        assert!(map.earth_map.detect_symmetry().contains(&MapSymmetry::Vertical));
+        let map = GameMap::parse_text_map(include_str!("../../tests/maps/asymmetric.bc18t")).unwrap();
+        assert!(map.earth_map.detect_symmetry().is_empty());
+    }
+
+    #[test]
+    fn validate_initial_units() {
+        let map = GameMap::parse_text_map(include_str!("../../tests/maps/symmetry_vertical.bc18t")).unwrap();
+        let red = map.earth_map.initial_units.iter()
+            .find(|unit| unit.team() == Team::Red).unwrap().clone();
+        let blue = map.earth_map.initial_units.iter()
+            .find(|unit| unit.team() == Team::Blue).unwrap().clone();
+        let copy = |unit: &Unit, id| {
+            Unit::new(id, unit.team(), unit.unit_type(), 0, unit.location()).unwrap()
+        };
+
+        // Units on the same squares are still symmetric, but collide.
+        let mut colliding = map.clone();
+        colliding.earth_map.initial_units.push(copy(&red, 9));
+        colliding.earth_map.initial_units.push(copy(&blue, 10));
+        assert_err!(colliding.earth_map.validate(), GameError::InvalidMapObject);
+
+        // Units with the same ID collide too.
+        let mut duplicate = map.clone();
+        duplicate.earth_map.initial_units = map.earth_map.initial_units.iter()
+            .map(|unit| copy(unit, red.id()))
+            .collect();
+        assert_err!(duplicate.earth_map.validate(), GameError::InvalidMapObject);
+    }
+
+    #[test]
+    fn validate_connectivity() {
+        let map = GameMap::parse_text_map(include_str!("../../tests/maps/walled.bc18t")).unwrap();
+        assert_err!(map.earth_map.validate(), GameError::InvalidMapObject);
+
+        let mut map = GameMap::parse_text_map(include_str!("../../tests/maps/maze.bc18t")).unwrap();
+        assert!(map.earth_map.validate().is_ok());
+
+        // Closing the exit of the maze separates the teams.
+        map.earth_map.is_passable_terrain[11][11] = false;
+        map.earth_map.is_passable_terrain[11][12] = false;
+        assert_err!(map.earth_map.validate(), GameError::InvalidMapObject);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let text = GameMap::from_file(fixture("valid.bc18t")).unwrap();
+        assert_eq!(text.seed, 42);
+        assert_eq!(text.earth_map.width, 20);
+        assert_eq!(text.mars_map.width, 25);
+        assert_eq!(text.earth_map.initial_units.len(), 4);
+        assert!(!text.earth_map.is_passable_terrain[9][6]);
+        assert!(!text.earth_map.is_passable_terrain[9][13]);
+        assert_eq!(text.earth_map.initial_karbonite[9][9], 50);
+        assert_eq!(text.orbit.center, 125);
+
+        let json = GameMap::from_file(fixture("valid.bc18map")).unwrap();
+        assert_eq!(json, text);
+    }
+
+    #[test]
+    fn test_json_without_starting_karbonite() {
+        let mut value: serde_json::Value = serde_json::from_str(include_str!("../../tests/maps/valid.bc18map")).unwrap();
+        assert!(value.as_object_mut().unwrap().remove("starting_karbonite").is_some());
+        let path = temp_map_file("old.bc18map", &value.to_string());
+        let map = GameMap::from_file(&path).unwrap();
+        assert_eq!(map.starting_karbonite, KARBONITE_STARTING);
+    }
+
+    #[test]
+    fn test_map_with() {
+        assert!(GameMap::test_map().validate().is_ok());
+
+        let config = TestMapConfig {
+            width: 25,
+            height: 30,
+            karbonite: TestKarbonite::Checkerboard(20),
+            earth_wall: Some(TestWall::Vertical { gap: 4 }),
+            mars_wall: Some(TestWall::Horizontal { gap: 3 }),
+            workers_per_team: 3,
+            orbit: OrbitPattern::new(25, 60, 100),
+            seed: 9,
+        };
+        let map = GameMap::test_map_with(config.clone());
+        assert!(map.validate().is_ok());
+        assert_eq!(map.seed, 9);
+        assert_eq!(map.orbit, config.orbit);
+        assert_eq!(map.earth_map.detect_symmetry(), vec![MapSymmetry::Rotational]);
+
+        // The vertical wall on Earth is one square thick, since the width is
+        // odd, with a gap of 4 in the middle.
+        let earth = &map.earth_map;
+        let wall: Vec<usize> = (0..30).filter(|&y| !earth.is_passable_terrain[y][12]).collect();
+        assert_eq!(wall, (0..13).chain(17..30).collect::<Vec<usize>>());
+        assert!((0..30).all(|y| earth.is_passable_terrain[y][11] && earth.is_passable_terrain[y][13]));
+        assert_eq!(earth.initial_karbonite[0][0], 20);
+        assert_eq!(earth.initial_karbonite[0][1], 0);
+
+        // The rotated half of the checkerboard has the opposite parity, since
+        // the width plus the height is odd.
+        assert_eq!(earth.initial_karbonite[29][24], 20);
+        assert_eq!(earth.initial_karbonite[29][23], 0);
+        assert_eq!(earth.initial_karbonite[28][24], 0);
+
+        // The horizontal wall on Mars is two squares thick.
+        let mars = &map.mars_map;
+        for &y in [14, 15].iter() {
+            let wall: Vec<usize> = (0..25).filter(|&x| !mars.is_passable_terrain[y][x]).collect();
+            assert_eq!(wall, (0..11).chain(14..25).collect::<Vec<usize>>());
+        }
+        assert!(mars.initial_karbonite.iter().all(|row| row.iter().all(|&k| k == 0)));
+
+        // Each team has its workers.
+        for team in [Team::Red, Team::Blue].iter() {
+            assert_eq!(earth.initial_units.iter().filter(|unit| unit.team() == *team).count(), 3);
+        }
+
+        // A wall with no gap separates the teams.
+        let map = GameMap::test_map_with(TestMapConfig {
+            earth_wall: Some(TestWall::Horizontal { gap: 0 }),
+            ..TestMapConfig::default()
+        });
+        assert_err!(map.validate(), GameError::InvalidMapObject);
+    }
+
+    #[test]
+    fn test_map_into_world() {
+        let maps = vec![
+            GameMap::random(7),
+            GameMap::from_file(fixture("valid.bc18t")).unwrap(),
+            GameMap::from_file(fixture("valid.bc18map")).unwrap(),
+        ];
+        for map in maps.into_iter() {
+            assert!(map.validate().is_ok());
+            let world = GameWorld::new(map.clone());
+            assert_eq!(world.starting_map(Planet::Earth), &map.earth_map);
+            assert_eq!(world.starting_map(Planet::Mars), &map.mars_map);
+            assert_eq!(world.asteroid_pattern(), map.asteroids);
+            assert_eq!(world.orbit_pattern(), map.orbit);
+            assert_eq!(world.karbonite(), map.starting_karbonite);
+        }
+    }
+
+    #[test]
+    fn test_from_file_malformed() {
+        let valid = include_str!("../../tests/maps/valid.bc18t");
+
+        // A row of the grid is too wide.
+        let wide = valid.replacen("> _ _ _ _ _ _ _ _ _ _", "> _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _", 1);
+        let path = temp_map_file("wide.bc18t", &wide);
+        let error = GameMap::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains("too wide"), "{}", error);
+        assert_err!(GameMap::from_file(&path), GameError::InvalidMapObject);
+
+        // The orbit can have flights of negative duration.
+        let orbit = valid.replace("orbit_amplitude: 50", "orbit_amplitude: 500");
+        let path = temp_map_file("orbit.bc18t", &orbit);
+        let error = GameMap::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains("flight is too short"), "{}", error);
+        assert_err!(GameMap::from_file(&path), GameError::InvalidMapObject);
+
+        // A unit is off the map.
+        let mut map: GameMap = serde_json::from_str(include_str!("../../tests/maps/valid.bc18map")).unwrap();
+        let unit = map.earth_map.initial_units[0].clone();
+        map.earth_map.initial_units[0] = Unit::new(
+            unit.id(), unit.team(), unit.unit_type(), 0,
+            Location::OnMap(MapLocation::new(Planet::Earth, 20, 3))).unwrap();
+        let path = temp_map_file("unit.bc18map", &serde_json::to_string(&map).unwrap());
+        let error = GameMap::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains(&format!("initial unit {} is off the map", unit.id())), "{}", error);
+        assert_err!(GameMap::from_file(&path), GameError::InvalidMapObject);
+
+        // A row of the grid is too narrow.
+        let mut map: GameMap = serde_json::from_str(include_str!("../../tests/maps/valid.bc18map")).unwrap();
+        map.mars_map.is_passable_terrain[4].pop();
+        let path = temp_map_file("narrow.bc18map", &serde_json::to_string(&map).unwrap());
+        let error = GameMap::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains("the terrain of Mars is not"), "{}", error);
+        assert_err!(GameMap::from_file(&path), GameError::InvalidMapObject);
+
+        // The file is not valid JSON.
+        let path = temp_map_file("truncated.bc18map", &include_str!("../../tests/maps/valid.bc18map")[..100]);
+        let error = GameMap::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains("is not a JSON map"), "{}", error);
+        assert!(error.to_string().contains("line"), "{}", error);
+        assert_err!(GameMap::from_file(&path), GameError::InvalidMapObject);
+
+        // The file does not exist.
+        let missing = ::std::env::temp_dir().join(format!("bc-map-{}-missing.bc18t", ::std::process::id()));
+        let error = GameMap::from_file(&missing).unwrap_err();
+        assert!(error.to_string().contains("can't be read"), "{}", error);
+        assert_err!(GameMap::from_file(&missing), GameError::InvalidMapObject);
+    }
 }
@@ -0,0 +1,239 @@
+//! The units on a planet's map, indexed by location.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use std::ops::Index;
+use std::sync::Arc;
+
+use super::constants::*;
+use super::location::*;
+use super::map::PlanetMap;
+use super::unit::UnitID;
+
+/// The unit on each square of a planet's map, if any. Stored as a dense grid
+/// with the same dimensions as the planet's map, so lookups by location are
+/// a single index. Locations off the map are never occupied.
+///
+/// Clones share the grid until one of them is modified, so cloning a world
+/// does not copy every square. It is serialized sparsely, as the occupied
+/// squares and their units.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OccupancyGrid {
+    planet: Planet,
+    width: usize,
+    height: usize,
+    /// The unit on each square, indexed by y * width + x.
+    squares: Arc<Vec<Option<UnitID>>>,
+    /// The number of occupied squares.
+    len: usize,
+}
+
+impl OccupancyGrid {
+    /// An empty grid covering the planet's map.
+    pub fn new(map: &PlanetMap) -> OccupancyGrid {
+        OccupancyGrid {
+            planet: map.planet,
+            width: map.width,
+            height: map.height,
+            squares: Arc::new(vec![None; map.width * map.height]),
+            len: 0,
+        }
+    }
+
+    /// Stops sharing the grid with any clones, copying it if necessary.
+    #[cfg(feature = "bench")]
+    pub fn unshare(&mut self) {
+        Arc::make_mut(&mut self.squares);
+    }
+
+    fn index_of(&self, location: &MapLocation) -> Option<usize> {
+        if location.planet != self.planet || location.x < 0 || location.y < 0 {
+            return None;
+        }
+        let (x, y) = (location.x as usize, location.y as usize);
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(y * self.width + x)
+    }
+
+    /// The unit at the location, if any.
+    pub fn get(&self, location: &MapLocation) -> Option<&UnitID> {
+        self.index_of(location).and_then(|i| self.squares[i].as_ref())
+    }
+
+    /// Whether there is a unit at the location.
+    pub fn contains_key(&self, location: &MapLocation) -> bool {
+        self.get(location).is_some()
+    }
+
+    /// Places the unit at the location, returning the unit that was there.
+    ///
+    /// Panics if the location is off the map.
+    pub fn insert(&mut self, location: MapLocation, id: UnitID) -> Option<UnitID> {
+        let i = self.index_of(&location).expect("location is on the map");
+        let squares = Arc::make_mut(&mut self.squares);
+        let old = squares[i].take();
+        if old.is_none() {
+            self.len += 1;
+        }
+        squares[i] = Some(id);
+        old
+    }
+
+    /// Removes the unit at the location, returning it.
+    pub fn remove(&mut self, location: &MapLocation) -> Option<UnitID> {
+        let old = match self.index_of(location) {
+            Some(i) if self.squares[i].is_some() => Arc::make_mut(&mut self.squares)[i].take(),
+            Some(_) => None,
+            None => None,
+        };
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    /// Removes every unit.
+    pub fn clear(&mut self) {
+        self.squares = Arc::new(vec![None; self.width * self.height]);
+        self.len = 0;
+    }
+
+    /// The number of occupied squares.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The occupied squares and their units, ordered by y-coordinate and
+    /// then x-coordinate.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (MapLocation, UnitID)> + 'a {
+        let planet = self.planet;
+        let width = self.width;
+        self.squares.iter().enumerate().filter_map(move |(i, square)| {
+            square.map(|id| {
+                (MapLocation::new(planet, (i % width) as i32, (i / width) as i32), id)
+            })
+        })
+    }
+}
+
+/// The serialized form of an `OccupancyGrid`.
+#[derive(Serialize, Deserialize)]
+struct SparseGrid {
+    planet: Planet,
+    width: usize,
+    height: usize,
+    units: Vec<(MapLocation, UnitID)>,
+}
+
+impl Serialize for OccupancyGrid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SparseGrid {
+            planet: self.planet,
+            width: self.width,
+            height: self.height,
+            units: self.iter().collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OccupancyGrid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<OccupancyGrid, D::Error> {
+        let sparse = SparseGrid::deserialize(deserializer)?;
+        if sparse.width > MAP_WIDTH_MAX || sparse.height > MAP_HEIGHT_MAX {
+            return Err(D::Error::custom(format!(
+                "occupancy grid is too large: {}x{}", sparse.width, sparse.height)));
+        }
+        let mut grid = OccupancyGrid {
+            planet: sparse.planet,
+            width: sparse.width,
+            height: sparse.height,
+            squares: Arc::new(vec![None; sparse.width * sparse.height]),
+            len: 0,
+        };
+        for (location, id) in sparse.units {
+            if grid.index_of(&location).is_none() || grid.insert(location, id).is_some() {
+                return Err(D::Error::custom(format!(
+                    "invalid square in occupancy grid: {:?}", location)));
+            }
+        }
+        Ok(grid)
+    }
+}
+
+impl Index<&MapLocation> for OccupancyGrid {
+    type Output = UnitID;
+
+    fn index(&self, location: &MapLocation) -> &UnitID {
+        self.get(location).expect("location is occupied")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map::GameMap;
+    use serde_json;
+
+    #[test]
+    fn test_occupancy_grid() {
+        let map = GameMap::test_map().earth_map;
+        let mut grid = OccupancyGrid::new(&map);
+        let a = MapLocation::new(Planet::Earth, 0, 0);
+        let b = MapLocation::new(Planet::Earth, 19, 3);
+        assert_eq!(grid.len(), 0);
+        assert_eq!(grid.insert(a, 1), None);
+        assert_eq!(grid.insert(b, 2), None);
+        assert_eq!(grid.insert(b, 3), Some(2));
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.get(&a), Some(&1));
+        assert_eq!(grid[&b], 3);
+        assert_eq!(grid.iter().collect::<Vec<_>>(), vec![(a, 1), (b, 3)]);
+
+        // Off-map and other-planet lookups are empty rather than panicking.
+        for &loc in [MapLocation::new(Planet::Earth, -1, 0),
+                     MapLocation::new(Planet::Earth, 20, 0),
+                     MapLocation::new(Planet::Earth, 0, 20),
+                     MapLocation::new(Planet::Mars, 0, 0)].iter() {
+            assert_eq!(grid.get(&loc), None);
+            assert!(!grid.contains_key(&loc));
+            assert_eq!(grid.remove(&loc), None);
+        }
+
+        assert_eq!(grid.remove(&a), Some(1));
+        assert_eq!(grid.remove(&a), None);
+        assert_eq!(grid.len(), 1);
+
+        // Clones don't see each other's changes.
+        let mut copy = grid.clone();
+        copy.insert(a, 4);
+        copy.remove(&b);
+        assert_eq!(grid.iter().collect::<Vec<_>>(), vec![(b, 3)]);
+        assert_eq!(copy.iter().collect::<Vec<_>>(), vec![(a, 4)]);
+
+        grid.clear();
+        assert_eq!(grid.len(), 0);
+        assert!(!grid.contains_key(&b));
+        assert_eq!(copy.len(), 1);
+    }
+
+    #[test]
+    fn test_occupancy_grid_serialization() {
+        let map = GameMap::test_map().earth_map;
+        let mut grid = OccupancyGrid::new(&map);
+        grid.insert(MapLocation::new(Planet::Earth, 3, 4), 1);
+        grid.insert(MapLocation::new(Planet::Earth, 19, 19), 2);
+
+        // Only the occupied squares are stored.
+        let serialized = serde_json::to_string(&grid).unwrap();
+        assert_eq!(serialized, r#"{"planet":"Earth","width":20,"height":20,"units":[[{"planet":"Earth","x":3,"y":4},1],[{"planet":"Earth","x":19,"y":19},2]]}"#);
+        assert_eq!(serde_json::from_str::<OccupancyGrid>(&serialized).unwrap(), grid);
+
+        // Squares off the grid, or occupied twice, are rejected.
+        let off_map = serialized.replace(r#""x":19"#, r#""x":20"#);
+        assert!(serde_json::from_str::<OccupancyGrid>(&off_map).is_err());
+        let twice = serialized.replace(r#""x":19,"y":19"#, r#""x":3,"y":4"#);
+        assert!(serde_json::from_str::<OccupancyGrid>(&twice).is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! Replays of entire games, recorded as the initial map and every turn
+//! applied to it since.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use failure::Error;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde_json;
+
+use super::constants::{TIME_POOL_MS, TIME_PER_TURN_MS};
+use super::map::GameMap;
+use super::schema::{FailedDelta, Indicator, TurnMessage, ViewerKeyframe, SCHEMA_VERSION};
+use super::world::{GameWorld, Player, Rounds};
+
+/// The version of the engine, recorded in every replay.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The time left passed to the world when replaying a turn. It only
+/// affects the start turn messages, which are discarded.
+const REPLAY_TIME_LEFT_MS: i32 = 0;
+
+/// The default number of rounds between keyframes.
+pub const DEFAULT_KEYFRAME_INTERVAL: Rounds = 50;
+
+/// The number of turns in a round, one for each player.
+const TURNS_PER_ROUND: usize = 4;
+
+/// A snapshot of the full, unfiltered game world, embedded in a replay so
+/// that a viewer can skip ahead without applying every earlier turn.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplayKeyframe {
+    /// The number of turns applied to the initial map to reach this world.
+    pub turn: usize,
+    /// The game world at the start of the keyframe's round.
+    pub keyframe: ViewerKeyframe,
+}
+
+/// A recording of a game, from which the game world at the end of the game
+/// can be reconstructed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GameReplay {
+    /// The version of the engine that recorded the game.
+    pub version: String,
+    /// The map the game was played on.
+    pub map: GameMap,
+    /// The turn applied by each player, in order.
+    pub turns: Vec<TurnMessage>,
+    /// The checksum of the game world after each turn was applied.
+    pub checksums: Vec<u64>,
+    /// The deltas of each turn that failed to apply, as logged by
+    /// `GameWorld::action_log()`.
+    pub errors: Vec<Vec<FailedDelta>>,
+    /// The total time used by the player who took each turn, after it was
+    /// taken out of the player's time pool.
+    pub times_used_ms: Vec<i32>,
+    /// The time in each player's pool at the start of the game, in
+    /// milliseconds.
+    pub time_pool_ms: i32,
+    /// The time added to a player's pool after each of its turns, in
+    /// milliseconds.
+    pub time_per_turn_ms: i32,
+    /// The number of rounds between keyframes.
+    pub keyframe_interval: Rounds,
+    /// A keyframe at the start of round 1 + k * `keyframe_interval`, for
+    /// every k > 0 reached by the recording, in order.
+    pub keyframes: Vec<ReplayKeyframe>,
+}
+
+impl GameReplay {
+    /// An empty replay of a game on the given map, with a keyframe every
+    /// `DEFAULT_KEYFRAME_INTERVAL` rounds.
+    pub fn new(map: GameMap) -> GameReplay {
+        GameReplay::with_keyframe_interval(map, DEFAULT_KEYFRAME_INTERVAL)
+    }
+
+    /// An empty replay of a game on the given map, with a keyframe every
+    /// `keyframe_interval` rounds. The time pools are the defaults until a
+    /// turn is recorded.
+    ///
+    /// Panics if the interval is zero.
+    pub fn with_keyframe_interval(map: GameMap, keyframe_interval: Rounds) -> GameReplay {
+        assert!(keyframe_interval > 0, "keyframe interval must be positive");
+        GameReplay {
+            version: ENGINE_VERSION.to_string(),
+            map,
+            turns: vec![],
+            checksums: vec![],
+            errors: vec![],
+            times_used_ms: vec![],
+            time_pool_ms: TIME_POOL_MS,
+            time_per_turn_ms: TIME_PER_TURN_MS,
+            keyframe_interval,
+            keyframes: vec![],
+        }
+    }
+
+    /// Records a turn, given the world after the turn was applied to it.
+    /// The world must be the manager's unfiltered world, so that keyframes
+    /// contain both teams, and the deltas that failed, the time used and the
+    /// time pools are read from it. The turn's debug indicators are capped
+    /// by `Indicator::cap()`.
+    pub fn record(&mut self, world: &GameWorld, turn: &TurnMessage) {
+        self.time_pool_ms = world.time_pool_ms();
+        self.time_per_turn_ms = world.time_per_turn_ms();
+        let mut turn = turn.clone();
+        turn.indicators = Indicator::cap(&turn.indicators);
+        self.turns.push(turn);
+        self.checksums.push(world.checksum());
+        self.errors.push(world.action_log().to_vec());
+        let player = Player::all()[(self.turns.len() - 1) % TURNS_PER_ROUND];
+        self.times_used_ms.push(world.time_used(player));
+
+        let round_started = self.turns.len() % TURNS_PER_ROUND == 0;
+        if round_started && (world.round() - 1) % self.keyframe_interval == 0 {
+            let mut world = world.clone();
+            world.cached_world.clear();
+            self.keyframes.push(ReplayKeyframe {
+                turn: self.turns.len(),
+                keyframe: ViewerKeyframe { schema_version: SCHEMA_VERSION, world },
+            });
+        }
+    }
+
+    /// Reconstructs the game world at the end of the recording by applying
+    /// each turn to the initial map.
+    ///
+    /// Errors if the replay was recorded by a different version of the
+    /// engine, or if the reconstructed world diverges from the recording.
+    pub fn play(&self) -> Result<GameWorld, Error> {
+        self.check()?;
+        let world = self.initial_world();
+        self.apply_turns(world, 0, self.turns.len())
+    }
+
+    /// Reconstructs the game world at the start of the given round, before
+    /// the first player has moved. Starts from the last keyframe at or
+    /// before that round, and applies only the turns after it.
+    ///
+    /// Errors if the replay does not reach the round, was recorded by a
+    /// different version of the engine, or diverges from the recording.
+    pub fn world_at_round(&self, round: Rounds) -> Result<GameWorld, Error> {
+        self.check()?;
+        if round == 0 {
+            bail!("Rounds start at 1");
+        }
+        let target = (round as usize - 1) * TURNS_PER_ROUND;
+        if target > self.turns.len() {
+            bail!("Replay ends before round {}", round);
+        }
+
+        let keyframe = self.keyframes.iter()
+            .rev()
+            .find(|keyframe| keyframe.turn <= target);
+        match keyframe {
+            Some(keyframe) => {
+                let mut world = keyframe.keyframe.world.clone();
+                world.cache_filtered_worlds();
+                self.apply_turns(world, keyframe.turn, target)
+            },
+            None => {
+                let world = self.initial_world();
+                self.apply_turns(world, 0, target)
+            },
+        }
+    }
+
+    /// The game world before any turn was applied.
+    fn initial_world(&self) -> GameWorld {
+        GameWorld::with_time(self.map.clone(), self.time_pool_ms, self.time_per_turn_ms)
+    }
+
+    /// Errors if this engine cannot play the replay.
+    fn check(&self) -> Result<(), Error> {
+        if self.version != ENGINE_VERSION {
+            bail!("Replay was recorded by engine {}, but this is engine {}",
+                  self.version, ENGINE_VERSION);
+        }
+        if self.turns.len() != self.checksums.len() {
+            bail!("Replay has {} turns but {} checksums",
+                  self.turns.len(), self.checksums.len());
+        }
+        if self.turns.len() != self.errors.len() {
+            bail!("Replay has {} turns but errors for {}",
+                  self.turns.len(), self.errors.len());
+        }
+        if self.turns.len() != self.times_used_ms.len() {
+            bail!("Replay has {} turns but times for {}",
+                  self.turns.len(), self.times_used_ms.len());
+        }
+        Ok(())
+    }
+
+    /// Applies the turns in the range [start, end) to a world that has had
+    /// the first `start` turns applied, verifying the checksum after each.
+    fn apply_turns(&self, mut world: GameWorld, start: usize, end: usize)
+                   -> Result<GameWorld, Error> {
+        for index in start..end {
+            let player = world.player_to_move();
+            let time_used_ms = self.times_used_ms[index] - world.time_used(player);
+            world.apply_time_used(player, time_used_ms);
+            world.apply_turn(&self.turns[index], REPLAY_TIME_LEFT_MS);
+            if world.checksum() != self.checksums[index] {
+                bail!("Replay diverged from the recording at turn {}", index);
+            }
+        }
+        Ok(world)
+    }
+
+    /// Writes the replay to a file, as gzipped JSON.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut writer = GzEncoder::new(BufWriter::new(File::create(path)?),
+                                        Compression::default());
+        serde_json::to_writer(&mut writer, self)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Reads a replay written by `write_to_file`.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<GameReplay, Error> {
+        let reader = GzDecoder::new(BufReader::new(File::open(path)?));
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use location::*;
+    use schema::Delta;
+    use unit::*;
+    use world::Team;
+    use std::{env, fs, process};
+
+    /// A short game where the red knight walks east along the bottom row.
+    fn scripted_game() -> (GameWorld, GameReplay) {
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = vec![
+            Unit::new(1, Team::Red, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 0, 0))).unwrap(),
+            Unit::new(2, Team::Blue, UnitType::Knight, 0,
+                Location::OnMap(MapLocation::new(Planet::Earth, 0, 10))).unwrap(),
+        ];
+        let mut world = GameWorld::new(map.clone());
+        let mut replay = GameReplay::new(map);
+
+        // The knight can move every other round, so it moves every eighth turn.
+        for turn_number in 0..24 {
+            let changes = if turn_number % 8 == 0 {
+                vec![Delta::Move { robot_id: 1, direction: Direction::East }]
+            } else {
+                vec![]
+            };
+            let turn = TurnMessage { changes, checksum: None, indicators: vec![] };
+            let player = world.player_to_move();
+            world.apply_time_used(player, 10 + turn_number);
+            let (results, _) = world.apply_turn(&turn, REPLAY_TIME_LEFT_MS);
+            assert!(results.iter().all(|result| result.is_ok()));
+            replay.record(&world, &turn);
+        }
+        (world, replay)
+    }
+
+    #[test]
+    fn test_replay_play() {
+        let (world, replay) = scripted_game();
+        let replayed = replay.play().unwrap();
+        assert_eq!(replayed.checksum(), world.checksum());
+        assert_eq!(replayed.planet_states[&Planet::Earth].units[&1].location(),
+                   Location::OnMap(MapLocation::new(Planet::Earth, 3, 0)));
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let (_, mut replay) = scripted_game();
+        replay.turns[8].changes.clear();
+        assert!(replay.play().is_err());
+
+        let (_, mut replay) = scripted_game();
+        replay.version = "0.0.0".to_string();
+        assert!(replay.play().is_err());
+    }
+
+    #[test]
+    fn test_replay_timeout() {
+        let map = GameMap::test_map();
+        let mut world = GameWorld::new(map.clone());
+        let mut replay = GameReplay::new(map);
+
+        // Red Earth spends too long on each turn and runs out of time.
+        for _ in 0..(TURNS_PER_ROUND * 4) {
+            let player = world.player_to_move();
+            if player == Player::new(Team::Red, Planet::Earth) {
+                world.apply_time_used(player, 4000);
+            }
+            let turn = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
+            world.apply_turn(&turn, REPLAY_TIME_LEFT_MS);
+            replay.record(&world, &turn);
+        }
+        assert!(world.is_timed_out(Player::new(Team::Red, Planet::Earth)));
+
+        let replayed = replay.play().unwrap();
+        assert!(replayed.is_timed_out(Player::new(Team::Red, Planet::Earth)));
+        assert_eq!(replayed.winner(), Some(Team::Blue));
+        assert_eq!(replayed.checksum(), world.checksum());
+    }
+
+    #[test]
+    fn test_replay_time_pool() {
+        let map = GameMap::test_map();
+        let mut world = GameWorld::with_time(map.clone(), 1000, 10);
+        let mut replay = GameReplay::new(map);
+
+        // Red Earth runs out of the small pool on its second turn, but
+        // would not have run out of the default pool.
+        for _ in 0..(TURNS_PER_ROUND * 2) {
+            let player = world.player_to_move();
+            if player == Player::new(Team::Red, Planet::Earth) {
+                world.apply_time_used(player, 600);
+            }
+            let turn = TurnMessage { changes: vec![], checksum: None, indicators: vec![] };
+            world.apply_turn(&turn, REPLAY_TIME_LEFT_MS);
+            replay.record(&world, &turn);
+        }
+        assert!(world.is_timed_out(Player::new(Team::Red, Planet::Earth)));
+        assert_eq!(replay.time_pool_ms, 1000);
+        assert_eq!(replay.time_per_turn_ms, 10);
+
+        let replayed = replay.play().unwrap();
+        assert!(replayed.is_timed_out(Player::new(Team::Red, Planet::Earth)));
+        assert_eq!(replayed.time_left(Player::new(Team::Blue, Planet::Earth)), 1000 + 2 * 10);
+        assert_eq!(replayed.checksum(), world.checksum());
+    }
+
+    #[test]
+    fn test_replay_world_at_round() {
+        let map = GameMap::test_map();
+        let mut world = GameWorld::new(map.clone());
+        let mut replay = GameReplay::with_keyframe_interval(map, 10);
+
+        // Each player writes the turn number to its team array, and
+        // blueprints a factory with its initial worker in the first round.
+        let mut worlds = vec![world.clone()];
+        for turn_number in 0..(TURNS_PER_ROUND * 60) {
+            let mut changes = vec![Delta::WriteTeamArray { index: 0, value: turn_number as i32 }];
+            if turn_number < 2 {
+                let worker_id = world.planet_states[&Planet::Earth].units.values()
+                    .find(|unit| unit.team() == world.team())
+                    .unwrap()
+                    .id();
+                let direction = match world.team() {
+                    Team::Red => Direction::North,
+                    Team::Blue => Direction::South,
+                };
+                changes.push(Delta::Blueprint {
+                    worker_id,
+                    structure_type: UnitType::Factory,
+                    direction,
+                });
+            }
+            let turn = TurnMessage { changes, checksum: None, indicators: vec![] };
+            let (results, _) = world.apply_turn(&turn, REPLAY_TIME_LEFT_MS);
+            assert!(results.iter().all(|result| result.is_ok()));
+            replay.record(&world, &turn);
+            if (turn_number + 1) % TURNS_PER_ROUND == 0 {
+                worlds.push(world.clone());
+            }
+        }
+        assert_eq!(replay.keyframes.len(), 6);
+        assert_eq!(replay.keyframes[0].turn, TURNS_PER_ROUND * 10);
+
+        // Keyframes survive serialization, and skip to the same state as
+        // replaying from scratch.
+        let replay: GameReplay = serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+        for round in [1, 2, 10, 11, 12, 33, 50, 51, 61].iter() {
+            let expected = &worlds[*round as usize - 1];
+            let actual = replay.world_at_round(*round).unwrap();
+            assert_eq!(actual.round(), *round);
+            assert_eq!(actual.checksum(), expected.checksum());
+            assert_eq!(actual.planet_states, expected.planet_states);
+            assert_eq!(actual.team_states, expected.team_states);
+        }
+        assert!(replay.world_at_round(0).is_err());
+        assert!(replay.world_at_round(62).is_err());
+    }
+
+    #[test]
+    fn test_replay_file_round_trip() {
+        let (world, replay) = scripted_game();
+        let path = env::temp_dir().join(format!("bc-replay-{}.json.gz", process::id()));
+        replay.write_to_file(&path).unwrap();
+
+        // The file is much smaller than the JSON it holds.
+        let json = serde_json::to_string(&replay).unwrap();
+        let compressed = fs::metadata(&path).unwrap().len() as usize;
+        let read = GameReplay::read_from_file(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_lt!(compressed, json.len() / 4);
+        assert_eq!(read, replay);
+        assert_eq!(read.play().unwrap().checksum(), world.checksum());
+    }
+}
@@ -0,0 +1,96 @@
+//! Deterministic random number generation for the engine.
+//!
+//! Everything random in a game is derived from the map seed, so that replays
+//! and every build of the engine agree on it. `StdRng` depends on the width
+//! of `usize`, so the engine uses its own small generator instead.
+
+use fnv::FnvHasher;
+use rand::Rng;
+use std::hash::Hasher;
+
+/// A PCG32 generator (XSH-RR output, 64-bit state), seeded by the map seed
+/// and a tag naming what the numbers are for. Different tags give
+/// independent streams from the same seed.
+///
+/// Only sample fixed-width types from it, such as `u32`, `i32` and `f64`.
+/// Sampling a `usize` draws a different number of bits on 32-bit and 64-bit
+/// platforms.
+#[derive(Debug, Clone)]
+pub(crate) struct GameRng {
+    state: u64,
+    increment: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// Scrambles the bits of a 64-bit value (SplitMix64's finalizer).
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl GameRng {
+    /// The generator for the given map seed and tag.
+    pub fn new(seed: u16, tag: &str) -> GameRng {
+        let mut hasher = FnvHasher::default();
+        hasher.write(tag.as_bytes());
+        let initial_state = mix(hasher.finish() ^ seed as u64);
+        let sequence = mix(initial_state);
+
+        let mut rng = GameRng {
+            state: 0,
+            increment: (sequence << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(initial_state);
+        rng.step();
+        rng
+    }
+
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+        xorshifted.rotate_right(rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::distributions::IndependentSample;
+    use rand::distributions::range::Range;
+
+    #[test]
+    fn test_golden_values() {
+        // These values must never change, or old replays will diverge.
+        let mut rng = GameRng::new(6147, "test");
+        let values: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+        assert_eq!(values, vec![1307382334, 61828179, 3318086409, 1539407953]);
+
+        let mut rng = GameRng::new(6147, "test");
+        let range = Range::new(0, 100);
+        let sampled: Vec<i32> = (0..4).map(|_| range.ind_sample(&mut rng)).collect();
+        assert_eq!(sampled, vec![34, 79, 9, 53]);
+    }
+
+    #[test]
+    fn test_streams() {
+        let sample = |seed, tag| {
+            let mut rng = GameRng::new(seed, tag);
+            (0..8).map(|_| rng.next_u32()).collect::<Vec<u32>>()
+        };
+        assert_eq!(sample(1, "a"), sample(1, "a"));
+        assert!(sample(1, "a") != sample(2, "a"));
+        assert!(sample(1, "a") != sample(1, "b"));
+    }
+}
@@ -1,7 +1,7 @@
 //! Rockets are the only unit that can initiate travel between planets.
 
-use fnv::FnvHashMap;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 use super::unit::UnitID;
 use super::world::Rounds;
@@ -38,66 +38,80 @@ impl RocketLanding {
     }
 }
 
-/// All rocket landings.
+/// All rocket landings, keyed by the round they land on.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RocketLandingInfo {
-    landings: FnvHashMap<Rounds, Vec<RocketLanding>>,
+    landings: BTreeMap<Rounds, Vec<RocketLanding>>,
 }
 
 impl RocketLandingInfo {
     /// Construct an empty rocket landing info.
     pub fn new() -> RocketLandingInfo {
         RocketLandingInfo {
-            landings: FnvHashMap::default(),
+            landings: BTreeMap::new(),
         }
     }
 
     /// Add a rocket landing on this round.
     pub(crate) fn add_landing(&mut self, round: Rounds, landing: RocketLanding) {
-        if !self.landings.contains_key(&round) {
-            self.landings.insert(round, vec![]);
-        }
-
-        self.landings.get_mut(&round)
-                     .expect("landing round should exist")
-                     .push(landing);
+        self.landings.entry(round).or_insert_with(Vec::new).push(landing);
     }
 
     /// Add many rocket landings on this round.
     #[cfg(test)]
     pub(crate) fn add_landings(&mut self, round: Rounds, landings: Vec<RocketLanding>) {
-        if !self.landings.contains_key(&round) {
-            self.landings.insert(round, vec![]);
-        }
+        self.landings.entry(round).or_insert_with(Vec::new).extend(landings);
+    }
 
-        self.landings.get_mut(&round)
-                     .expect("landing round should exist")
-                     .extend(landings);
+    /// Remove every landing of the given rocket.
+    pub(crate) fn remove_landings_of(&mut self, rocket_id: UnitID) {
+        for landings in self.landings.values_mut() {
+            landings.retain(|landing| landing.rocket_id != rocket_id);
+        }
+        self.landings.retain(|_, landings| !landings.is_empty());
     }
 
-    /// Get the rocket landings on this round.
+    /// Get the rocket landings on this round, ordered by rocket ID.
     pub fn landings_on(&self, round: Rounds) -> Vec<RocketLanding> {
         if let Some(landings) = self.landings.get(&round) {
-            landings.clone()
+            let mut landings = landings.clone();
+            landings.sort();
+            landings
         } else {
             vec![]
         }
     }
 
-    /// All rocket landings, ordered by round.
-    pub fn all(&self) -> Vec<(Rounds, RocketLanding)> {
+    /// Remove and return the rocket landings on this round, ordered by
+    /// rocket ID.
+    pub(crate) fn take_landings_on(&mut self, round: Rounds) -> Vec<RocketLanding> {
+        let mut landings = self.landings.remove(&round).unwrap_or_default();
+        landings.sort();
+        landings
+    }
+
+    /// The rocket landings in the rounds [start, end), ordered by round and
+    /// then by rocket ID.
+    pub fn landings_between(&self, start: Rounds, end: Rounds) -> Vec<(Rounds, RocketLanding)> {
+        if start >= end {
+            return vec![];
+        }
         let mut all_landings: Vec<(Rounds, RocketLanding)> = vec![];
-        for (round, landings) in self.landings.clone().into_iter() {
-            all_landings.extend(
-                landings.into_iter().map(|landing| (round, landing))
-            );
+        for (round, landings) in self.landings.range(start..end) {
+            let first = all_landings.len();
+            all_landings.extend(landings.iter().map(|landing| (*round, *landing)));
+            all_landings[first..].sort();
         }
-        all_landings.sort();
         all_landings
     }
 
-    /// All rocket landings, grouped by round but in no particular order.
-    pub fn all_grouped(&self) -> FnvHashMap<Rounds, Vec<RocketLanding>> {
+    /// All rocket landings, ordered by round and then by rocket ID.
+    pub fn all(&self) -> Vec<(Rounds, RocketLanding)> {
+        self.landings_between(Rounds::min_value(), Rounds::max_value())
+    }
+
+    /// All rocket landings, grouped by round.
+    pub fn all_grouped(&self) -> BTreeMap<Rounds, Vec<RocketLanding>> {
         self.landings.clone()
     }
 }
@@ -130,6 +144,13 @@ mod tests {
         landings.add_landings(2, vec![landing, landing, landing]);
         assert_eq!(landings.landings_on(2).len(), 5);
 
+        // Landings on the same round are ordered by rocket ID.
+        landings.add_landings(4, vec![RocketLanding::new(3, loc), RocketLanding::new(2, loc)]);
+        let ids: Vec<UnitID> = landings.landings_on(4).iter().map(|l| l.rocket_id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        landings.remove_landings_of(2);
+        landings.remove_landings_of(3);
+
         // Check when all landings are returned, they are ordered by round.
         let all = landings.all();
         assert_eq!(all.len(), 9);
@@ -144,4 +165,65 @@ mod tests {
         assert_eq!(all_grouped.get(&2).expect("key should exist").len(), 5);
         assert_eq!(all_grouped.get(&3).expect("key should exist").len(), 3);
     }
+
+    #[test]
+    fn test_remove_landings_of() {
+        let mut landings = RocketLandingInfo::new();
+        let loc = MapLocation::new(Planet::Mars, 0, 0);
+        landings.add_landing(1, RocketLanding::new(1, loc));
+        landings.add_landing(2, RocketLanding::new(1, loc));
+        landings.add_landing(2, RocketLanding::new(2, loc));
+
+        landings.remove_landings_of(1);
+        assert_eq!(landings.all(), vec![(2, RocketLanding::new(2, loc))]);
+        assert_eq!(landings.all_grouped().keys().len(), 1);
+
+        // Removing a rocket with no landings does nothing.
+        landings.remove_landings_of(3);
+        assert_eq!(landings.all().len(), 1);
+    }
+
+    #[test]
+    fn test_take_landings_on() {
+        let mut landings = RocketLandingInfo::new();
+        let loc = MapLocation::new(Planet::Mars, 0, 0);
+        landings.add_landing(1, RocketLanding::new(2, loc));
+        landings.add_landing(1, RocketLanding::new(1, loc));
+        landings.add_landing(2, RocketLanding::new(3, loc));
+
+        // Taking a round's landings returns them in order and forgets them.
+        let ids: Vec<UnitID> = landings.take_landings_on(1).iter().map(|l| l.rocket_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(landings.take_landings_on(1), vec![]);
+        assert_eq!(landings.all(), vec![(2, RocketLanding::new(3, loc))]);
+    }
+
+    #[test]
+    fn test_landings_between() {
+        let mut landings = RocketLandingInfo::new();
+        let loc = MapLocation::new(Planet::Mars, 0, 0);
+        landings.add_landing(10, RocketLanding::new(4, loc));
+        landings.add_landing(3, RocketLanding::new(2, loc));
+        landings.add_landing(7, RocketLanding::new(3, loc));
+        landings.add_landing(3, RocketLanding::new(1, loc));
+
+        // The range includes its start but not its end.
+        assert_eq!(landings.landings_between(3, 10), vec![
+            (3, RocketLanding::new(1, loc)),
+            (3, RocketLanding::new(2, loc)),
+            (7, RocketLanding::new(3, loc)),
+        ]);
+        assert_eq!(landings.landings_between(4, 11), vec![
+            (7, RocketLanding::new(3, loc)),
+            (10, RocketLanding::new(4, loc)),
+        ]);
+        assert_eq!(landings.landings_between(8, 10), vec![]);
+        assert_eq!(landings.landings_between(10, 3), vec![]);
+        assert_eq!(landings.all().len(), 4);
+
+        // Taking a round's landings removes them from later queries.
+        landings.take_landings_on(3);
+        assert_eq!(landings.landings_between(0, 100).len(), 2);
+        assert_eq!(landings.all_grouped().keys().cloned().collect::<Vec<_>>(), vec![7, 10]);
+    }
 }
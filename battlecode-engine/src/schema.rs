@@ -7,6 +7,20 @@
 //! Manager <----TurnMessage----- Red Earth
 //! Manager --StartTurnMessage--> Red Earth
 //! Manager <----TurnMessage----- Red Earth
+//!
+//! The JSON is also read by the viewer and by the Python bindings, so its
+//! format is kept stable:
+//!
+//! * Field names are snake_case, the same as in Rust and in the bindings.
+//!   The shipped `.bc18map` files use the same names.
+//! * The message enums, `Delta` and `ViewerDelta`, are adjacently tagged:
+//!   `{"type": "Move", "data": {"robot_id": 1, "direction": "North"}}`.
+//! * Other enums keep serde's default representation. In particular,
+//!   `Location` is stored in map files as `{"OnMap": {...}}`.
+//! * Grids of booleans, such as the visible squares, are stored compactly as
+//!   one string per row, with `1` for true and `0` for false: `["0110"]`.
+//! * Messages sent out by the engine carry `SCHEMA_VERSION`, which changes
+//!   whenever the format does.
 
 use super::id_generator::*;
 use super::location::*;
@@ -15,8 +29,68 @@ use super::rockets::*;
 use super::unit::*;
 use super::world::*;
 
+/// The version of the message format. Bump it whenever a message's JSON
+/// changes, so that consumers can tell they are out of date.
+pub const SCHEMA_VERSION: u32 = 7;
+
+/// The most indicators a player can draw in one turn. Any more are dropped.
+pub const MAX_INDICATORS_PER_TURN: usize = 1024;
+
+/// The longest indicator string, in bytes. Longer strings are truncated.
+pub const MAX_INDICATOR_STRING_BYTES: usize = 64;
+
+/// Serializes a grid of booleans as one string of `0`s and `1`s per row.
+/// Use it with `#[serde(with = "::schema::bool_grid")]`.
+pub(crate) mod bool_grid {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+
+    pub fn serialize<S: Serializer>(grid: &Vec<Vec<bool>>, serializer: S)
+                                    -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(grid.len()))?;
+        for row in grid {
+            let row: String = row.iter().map(|&square| if square { '1' } else { '0' }).collect();
+            seq.serialize_element(&row)?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D)
+                                                  -> Result<Vec<Vec<bool>>, D::Error> {
+        let rows = Vec::<String>::deserialize(deserializer)?;
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(D::Error::custom(format!(
+                "ragged grid: a row has {} squares, but the first has {}", row.len(), width)));
+        }
+        rows.iter().map(|row| row.chars().map(|square| match square {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(D::Error::custom(format!("invalid square in grid: {:?}", square))),
+        }).collect()).collect()
+    }
+
+    /// The same, for a grid shared between clones of the world.
+    pub mod shared {
+        use serde::{Deserializer, Serializer};
+        use std::sync::Arc;
+
+        pub fn serialize<S: Serializer>(grid: &Arc<Vec<Vec<bool>>>, serializer: S)
+                                        -> Result<S::Ok, S::Error> {
+            super::serialize(grid, serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D)
+                                                      -> Result<Arc<Vec<Vec<bool>>>, D::Error> {
+            Ok(Arc::new(super::deserialize(deserializer)?))
+        }
+    }
+}
+
 /// A single, atomic "change" in the game world.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "data")]
 pub enum Delta {
     /// Commands the given robot to attack a location.
     Attack { robot_id: UnitID, target_unit_id: UnitID },
@@ -65,14 +139,20 @@ pub enum Delta {
 /// The first message sent to each player by the manager.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StartGameMessage {
+    /// The `SCHEMA_VERSION` of the engine that sent the message.
+    pub schema_version: u32,
     /// The initial filtered world.
     pub world: GameWorld,
+    /// The player this message is addressed to.
+    pub player: Player,
 }
 
 /// A message sent to the viewer which contains all the information needed
 /// to initialize or reinitialize the viewer's state.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ViewerKeyframe {
+    /// The `SCHEMA_VERSION` of the engine that sent the message.
+    pub schema_version: u32,
     /// A full copy of the initial game state.
     pub world: GameWorld,
 }
@@ -81,18 +161,79 @@ pub struct ViewerKeyframe {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TurnMessage {
     /// The changes to the game world.
-    pub changes: Vec<Delta>
+    pub changes: Vec<Delta>,
+    /// The player's checksum of its own state after making the changes, so
+    /// that the manager can detect when the two have diverged. See
+    /// `GameWorld::player_checksum`.
+    #[serde(default)]
+    pub checksum: Option<u64>,
+    /// The debug indicators drawn during the turn.
+    #[serde(default)]
+    pub indicators: Vec<Indicator>,
+}
+
+/// A debug marker drawn by a player for the viewer. Indicators have no
+/// effect on the game, and are never shown to the other players.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "data")]
+pub enum Indicator {
+    /// A dot at the location, in the given color.
+    Dot { location: MapLocation, r: u8, g: u8, b: u8 },
+    /// A line between the locations, in the given color.
+    Line { from: MapLocation, to: MapLocation, r: u8, g: u8, b: u8 },
+    /// A string attached to the unit.
+    String { unit_id: UnitID, text: String },
+}
+
+impl Indicator {
+    /// The first `MAX_INDICATORS_PER_TURN` of the indicators, with their
+    /// strings truncated to `MAX_INDICATOR_STRING_BYTES`.
+    pub fn cap(indicators: &[Indicator]) -> Vec<Indicator> {
+        indicators.iter()
+            .take(MAX_INDICATORS_PER_TURN)
+            .map(|indicator| match *indicator {
+                Indicator::String { unit_id, ref text } => Indicator::String {
+                    unit_id,
+                    text: truncate(text, MAX_INDICATOR_STRING_BYTES).to_string(),
+                },
+                ref other => other.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The longest prefix of the string that fits in the given number of bytes,
+/// without splitting a character.
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// The debug indicators drawn by a team in one turn, for the viewer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TeamIndicators {
+    pub team: Team,
+    pub indicators: Vec<Indicator>,
 }
 
 /// A list of updates since the player's last turn sent to the player.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StartTurnMessage {
+    /// The `SCHEMA_VERSION` of the engine that sent the message.
+    pub schema_version: u32,
     // Time left, in milliseconds.
     pub time_left_ms: i32,
     // Current round.
     pub round: Rounds,
 
     // PlanetInfo
+    #[serde(with = "::schema::bool_grid")]
     pub visible_locs: Vec<Vec<bool>>,
     pub units_changed: Vec<Unit>,
     pub units_vanished: Vec<UnitID>,
@@ -106,6 +247,7 @@ pub struct StartTurnMessage {
     pub rocket_landings: RocketLandingInfo,
     pub research: ResearchInfo,
     pub karbonite: u32,
+    pub stats: TeamStats,
 }
 
 /// The truncated unit info needed by the viewer.
@@ -117,25 +259,56 @@ pub struct ViewerUnitInfo {
     pub location: MapLocation,
 }
 
-/// Additional information that the viewer may need.
+/// Additional information that the viewer may need, in the order it happened.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "data")]
 pub enum ViewerDelta {
     AsteroidStrike { location: MapLocation, karbonite: u32 },
     KarboniteChanged { location: MapLocation, new_amount: u32 },
     ProductionDone { factory_id: UnitID, unit_type: UnitType },
+    /// The game ran out of unit IDs, so the robot was not produced, and its
+    /// cost was returned to the team.
+    ProductionRefunded { factory_id: UnitID, unit_type: UnitType, karbonite: u32 },
     RangerSnipe { ranger_id: UnitID, target_location: MapLocation },
     ResearchComplete { branch: UnitType },
+    RocketLaunch { rocket_id: UnitID, destination: MapLocation },
     RocketLanding { rocket_id: UnitID, location: MapLocation },
+    /// The health the unit lost, after its defense.
+    UnitDamaged { unit_id: UnitID, damage: u32 },
+    /// The health the unit gained, up to its maximum health.
+    UnitHealed { unit_id: UnitID, amount: u32 },
+    /// The location is where the unit was when it was destroyed, which may
+    /// be inside a structure or in space.
+    UnitDestroyed { unit_id: UnitID, location: Location, cause: Destruction },
+}
+
+/// A delta from a player's turn that the manager could not apply.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FailedDelta {
+    /// The index of the delta in the player's TurnMessage.
+    pub index: usize,
+    /// The player whose turn it was.
+    pub player: Player,
+    /// The delta that failed.
+    pub delta: Delta,
+    /// The error raised when applying the delta.
+    pub error: String,
 }
 
 /// A description of the current game state, for the viewer.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ViewerMessage {
+    /// The `SCHEMA_VERSION` of the engine that sent the message.
+    pub schema_version: u32,
     pub changes: Vec<Delta>,
+    /// The deltas in `changes` that failed to apply, and were skipped.
+    pub errors: Vec<FailedDelta>,
     pub units: Vec<ViewerUnitInfo>,
     pub additional_changes: Vec<ViewerDelta>,
     /// the amount of karbonite at the end of this player's turn.
     pub karbonite: u32,
+    /// The debug indicators drawn by the player during the turn.
+    pub indicators: TeamIndicators,
 }
 
 /// An error message in response to some error.
@@ -167,16 +340,211 @@ pub struct SentMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::{from_str, to_string};
+    use location::Planet;
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_json::{from_str, to_string, to_string_pretty};
+    use std::fmt::Debug;
+    use std::path::Path;
+    use std::{env, fs};
 
     #[test]
     fn turn_round_trip() {
         let turn = TurnMessage {
-            changes: vec![Delta::Nothing]
+            changes: vec![Delta::Nothing],
+            checksum: Some(u64::max_value()),
+            indicators: vec![
+                Indicator::Dot { location: MapLocation::new(Planet::Earth, 1, 2), r: 255, g: 0, b: 0 },
+                Indicator::Line {
+                    from: MapLocation::new(Planet::Mars, 0, 0),
+                    to: MapLocation::new(Planet::Mars, 3, 4),
+                    r: 0, g: 128, b: 255,
+                },
+                Indicator::String { unit_id: 7, text: "hello".to_string() },
+            ],
         };
         let serialized = to_string(&turn).expect("failed to serialize");
         let deserialized: TurnMessage = from_str(&serialized).expect("failed to deserialize");
         assert_eq!(deserialized, turn);
+
+        // Turns without a checksum are still accepted.
+        let deserialized: TurnMessage = from_str(r#"{"changes":[]}"#).expect("failed to deserialize");
+        assert_eq!(deserialized.checksum, None);
+    }
+
+    #[test]
+    fn bool_grid_round_trip() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Grid {
+            #[serde(with = "::schema::bool_grid")]
+            squares: Vec<Vec<bool>>,
+        }
+
+        let grid = Grid { squares: vec![vec![false, true, true], vec![true, false, false]] };
+        let serialized = to_string(&grid).expect("failed to serialize");
+        assert_eq!(serialized, r#"{"squares":["011","100"]}"#);
+        let deserialized: Grid = from_str(&serialized).expect("failed to deserialize");
+        assert_eq!(deserialized, grid);
+
+        // Empty grids and rows survive, and other characters are rejected.
+        for squares in vec![vec![], vec![vec![]]] {
+            let grid = Grid { squares };
+            let serialized = to_string(&grid).expect("failed to serialize");
+            assert_eq!(from_str::<Grid>(&serialized).expect("failed to deserialize"), grid);
+        }
+        assert!(from_str::<Grid>(r#"{"squares":["012"]}"#).is_err());
+
+        // Every row has the same length as the first.
+        assert!(from_str::<Grid>(r#"{"squares":["011","10"]}"#).is_err());
+        assert!(from_str::<Grid>(r#"{"squares":["01","100"]}"#).is_err());
+        assert!(from_str::<Grid>(r#"{"squares":["", "1"]}"#).is_err());
+        assert!(from_str::<Grid>(r#"{"squares":[[true]]}"#).is_err());
+    }
+
+    #[test]
+    fn start_game_round_trip() {
+        let player = Player::new(Team::Blue, Planet::Mars);
+        let start = StartGameMessage {
+            schema_version: SCHEMA_VERSION,
+            world: GameWorld::test_world().filter(player),
+            player,
+        };
+        let serialized = to_string(&start).expect("failed to serialize");
+        let deserialized: StartGameMessage = from_str(&serialized).expect("failed to deserialize");
+        assert_eq!(deserialized, start);
+    }
+
+    #[test]
+    fn viewer_round_trip() {
+        let viewer = ViewerMessage {
+            schema_version: SCHEMA_VERSION,
+            changes: vec![Delta::Nothing, Delta::Disintegrate { unit_id: 1 }],
+            errors: vec![FailedDelta {
+                index: 1,
+                player: Player::new(Team::Red, Planet::Earth),
+                delta: Delta::Disintegrate { unit_id: 1 },
+                error: "no such unit".into(),
+            }],
+            units: vec![],
+            additional_changes: vec![],
+            karbonite: 100,
+            indicators: TeamIndicators {
+                team: Team::Blue,
+                indicators: vec![Indicator::String { unit_id: 1, text: "hi".to_string() }],
+            },
+        };
+        let serialized = to_string(&viewer).expect("failed to serialize");
+        let deserialized: ViewerMessage = from_str(&serialized).expect("failed to deserialize");
+        assert_eq!(deserialized, viewer);
+    }
+
+    #[test]
+    fn indicator_cap() {
+        let dot = Indicator::Dot { location: MapLocation::new(Planet::Earth, 0, 0), r: 0, g: 0, b: 0 };
+        let indicators = vec![dot.clone(); MAX_INDICATORS_PER_TURN + 10];
+        let capped = Indicator::cap(&indicators);
+        assert_eq!(capped.len(), MAX_INDICATORS_PER_TURN);
+        assert!(capped.iter().all(|indicator| *indicator == dot));
+
+        // Strings are truncated without splitting a character.
+        let long = "é".repeat(MAX_INDICATOR_STRING_BYTES);
+        let capped = Indicator::cap(&[
+            Indicator::String { unit_id: 1, text: long },
+            Indicator::String { unit_id: 2, text: "short".to_string() },
+        ]);
+        assert_eq!(capped[0], Indicator::String {
+            unit_id: 1, text: "é".repeat(MAX_INDICATOR_STRING_BYTES / 2),
+        });
+        assert_eq!(capped[1], Indicator::String { unit_id: 2, text: "short".to_string() });
+
+        let text = format!("a{}", "é".repeat(MAX_INDICATOR_STRING_BYTES));
+        match Indicator::cap(&[Indicator::String { unit_id: 1, text }])[0] {
+            Indicator::String { ref text, .. } => {
+                assert_eq!(text.len(), MAX_INDICATOR_STRING_BYTES - 1);
+            },
+            _ => panic!("expected a string"),
+        }
+    }
+
+    /// Checks the JSON of a value against its golden file in `src/golden/`,
+    /// so that accidental changes to the message format fail. After a
+    /// deliberate change, bump `SCHEMA_VERSION` and rerun the test with
+    /// `BC_UPDATE_GOLDEN=1` to rewrite the file.
+    fn assert_golden<T>(value: &T, name: &str)
+            where T: Serialize + DeserializeOwned + PartialEq + Debug {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("golden").join(name);
+        let serialized = to_string_pretty(value).expect("failed to serialize") + "\n";
+        if env::var("BC_UPDATE_GOLDEN").is_ok() {
+            fs::write(&path, &serialized).expect("failed to write golden file");
+        }
+        let golden = fs::read_to_string(&path).expect("failed to read golden file");
+        assert!(serialized == golden, "{} no longer matches {:?}", name, path);
+        let deserialized: T = from_str(&golden).expect("failed to deserialize");
+        assert_eq!(&deserialized, value);
+    }
+
+    #[test]
+    fn deltas_golden() {
+        let location = MapLocation::new(Planet::Earth, 3, 4);
+        let deltas = vec![
+            Delta::Attack { robot_id: 1, target_unit_id: 2 },
+            Delta::BeginSnipe { ranger_id: 1, location },
+            Delta::Blueprint { worker_id: 1, structure_type: UnitType::Factory, direction: Direction::North },
+            Delta::Blink { mage_id: 1, location },
+            Delta::Build { worker_id: 1, blueprint_id: 2 },
+            Delta::Disintegrate { unit_id: 1 },
+            Delta::Harvest { worker_id: 1, direction: Direction::Center },
+            Delta::Heal { healer_id: 1, target_robot_id: 2 },
+            Delta::Javelin { knight_id: 1, target_unit_id: 2 },
+            Delta::LaunchRocket { rocket_id: 1, location: MapLocation::new(Planet::Mars, 5, 6) },
+            Delta::Load { structure_id: 1, robot_id: 2 },
+            Delta::Move { robot_id: 1, direction: Direction::Southwest },
+            Delta::Overcharge { healer_id: 1, target_robot_id: 2 },
+            Delta::ProduceRobot { factory_id: 1, robot_type: UnitType::Mage },
+            Delta::QueueResearch { branch: UnitType::Rocket },
+            Delta::Repair { worker_id: 1, structure_id: 2 },
+            Delta::Replicate { worker_id: 1, direction: Direction::East },
+            Delta::ResetResearchQueue,
+            Delta::Unload { structure_id: 1, direction: Direction::West },
+            Delta::WriteTeamArray { index: 7, value: -8 },
+            Delta::Nothing,
+        ];
+        assert_golden(&deltas, "deltas.json");
+    }
+
+    #[test]
+    fn viewer_deltas_golden() {
+        let location = MapLocation::new(Planet::Mars, 3, 4);
+        let deltas = vec![
+            ViewerDelta::AsteroidStrike { location, karbonite: 20 },
+            ViewerDelta::KarboniteChanged { location, new_amount: 17 },
+            ViewerDelta::ProductionDone { factory_id: 1, unit_type: UnitType::Healer },
+            ViewerDelta::ProductionRefunded { factory_id: 1, unit_type: UnitType::Mage, karbonite: 20 },
+            ViewerDelta::RangerSnipe { ranger_id: 1, target_location: location },
+            ViewerDelta::ResearchComplete { branch: UnitType::Worker },
+            ViewerDelta::RocketLaunch { rocket_id: 1, destination: location },
+            ViewerDelta::RocketLanding { rocket_id: 1, location },
+            ViewerDelta::UnitDamaged { unit_id: 1, damage: 30 },
+            ViewerDelta::UnitHealed { unit_id: 1, amount: 10 },
+            ViewerDelta::UnitDestroyed { unit_id: 1, location: Location::InGarrison(2), cause: Destruction::Rocket },
+        ];
+        assert_golden(&deltas, "viewer_deltas.json");
+    }
+
+    #[test]
+    fn filtered_world_golden() {
+        let mut world = GameWorld::test_world();
+        let red = MapLocation::new(Planet::Earth, 1, 1);
+        let blue = MapLocation::new(Planet::Earth, 4, 3);
+        world.create_unit(Team::Red, red, UnitType::Ranger).unwrap();
+        world.create_unit(Team::Blue, blue, UnitType::Factory).unwrap();
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 18, 18), UnitType::Worker).unwrap();
+        let start = StartGameMessage {
+            schema_version: SCHEMA_VERSION,
+            world: world.filter(Player::new(Team::Red, Planet::Earth)),
+            player: Player::new(Team::Red, Planet::Earth),
+        };
+        assert_golden(&start, "start_game.json");
     }
 
     #[test]
@@ -0,0 +1,224 @@
+//! Simple built-in players, for running games without any outside bots.
+//!
+//! The strategies only use the player API of the `GameController`, so a game
+//! between them also exercises that API end to end. They are deterministic,
+//! so a game between two strategies on the same map always plays out the
+//! same way.
+
+use failure::Error;
+
+use super::controller::GameController;
+use super::location::*;
+use super::unit::*;
+use super::unit::UnitType::*;
+
+/// A player, called once per turn to make its moves.
+pub type Strategy = fn(&mut GameController) -> Result<(), Error>;
+
+/// The names of the built-in strategies, as accepted by `by_name()`.
+pub const STRATEGY_NAMES: [&str; 3] = ["idle", "rush", "econ"];
+
+/// The built-in strategy with the given name, if any.
+pub fn by_name(name: &str) -> Option<Strategy> {
+    match name {
+        "idle" => Some(idle),
+        "rush" => Some(rush),
+        "econ" => Some(econ),
+        _ => None,
+    }
+}
+
+/// Does nothing.
+pub fn idle(_gc: &mut GameController) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Sends every robot at the nearest enemy it knows of, and has workers build
+/// factories that produce knights.
+pub fn rush(gc: &mut GameController) -> Result<(), Error> {
+    for unit in gc.my_units() {
+        match unit.unit_type() {
+            Worker => {
+                build_or_blueprint(gc, &unit, Factory)?;
+            },
+            Factory => {
+                unload_all(gc, &unit)?;
+                if gc.can_produce_robot(unit.id(), Knight) {
+                    gc.produce_robot(unit.id(), Knight)?;
+                }
+            },
+            Rocket => {},
+            _ => {
+                attack_nearest(gc, &unit)?;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Harvests karbonite and grows the number of workers and factories, only
+/// fighting back against enemies in range.
+pub fn econ(gc: &mut GameController) -> Result<(), Error> {
+    for unit in gc.my_units() {
+        match unit.unit_type() {
+            Worker => {
+                if !build_or_blueprint(gc, &unit, Factory)? {
+                    harvest(gc, &unit)?;
+                }
+                replicate(gc, &unit)?;
+            },
+            Factory => {
+                unload_all(gc, &unit)?;
+                if gc.can_produce_robot(unit.id(), Ranger) {
+                    gc.produce_robot(unit.id(), Ranger)?;
+                }
+            },
+            Rocket => {},
+            _ => {
+                if let Some(target) = nearest_enemy(gc, &unit) {
+                    if gc.is_attack_ready(unit.id()) && gc.can_attack(unit.id(), target.id()) {
+                        gc.attack(unit.id(), target.id())?;
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The directions to try moving in, starting from one that depends on the
+/// unit and the round, so that robots spread out without randomness.
+fn directions_for(gc: &GameController, unit: &Unit) -> Vec<Direction> {
+    let all = Direction::all();
+    let start = (unit.id() as usize + gc.round() as usize) % all.len();
+    (0..all.len()).map(|i| all[(start + i) % all.len()]).collect()
+}
+
+/// Builds an adjacent blueprint, or else blueprints a new structure if the
+/// team can afford one. Returns whether the worker did either.
+fn build_or_blueprint(gc: &mut GameController, worker: &Unit, structure: UnitType)
+                      -> Result<bool, Error> {
+    let location = match worker.location().map_location() {
+        Ok(location) => location,
+        Err(_) => return Ok(false),
+    };
+    for other in gc.sense_nearby_units_by_team(location, 2, worker.team()) {
+        if gc.can_build(worker.id(), other.id()) {
+            gc.build(worker.id(), other.id())?;
+            return Ok(true);
+        }
+    }
+    for direction in directions_for(gc, worker) {
+        if gc.can_blueprint(worker.id(), structure, direction) {
+            gc.blueprint(worker.id(), structure, direction)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Harvests from the adjacent square with the most karbonite, or moves on
+/// if there is none left nearby.
+fn harvest(gc: &mut GameController, worker: &Unit) -> Result<(), Error> {
+    let location = match worker.location().map_location() {
+        Ok(location) => location,
+        Err(_) => return Ok(()),
+    };
+    let mut best = None;
+    for direction in Direction::all().into_iter().chain(Some(Direction::Center)) {
+        if !gc.can_harvest(worker.id(), direction) {
+            continue;
+        }
+        let karbonite = gc.karbonite_at(location.add(direction))?;
+        if best.map(|(_, most)| karbonite > most).unwrap_or(true) {
+            best = Some((direction, karbonite));
+        }
+    }
+    match best {
+        Some((direction, _)) => gc.harvest(worker.id(), direction),
+        None => wander(gc, worker),
+    }
+}
+
+/// Replicates the worker while the team has karbonite to spare.
+fn replicate(gc: &mut GameController, worker: &Unit) -> Result<(), Error> {
+    if gc.karbonite() < 2 * Factory.blueprint_cost()? {
+        return Ok(());
+    }
+    for direction in directions_for(gc, worker) {
+        if gc.can_replicate(worker.id(), direction) {
+            return gc.replicate(worker.id(), direction);
+        }
+    }
+    Ok(())
+}
+
+/// Unloads as many robots from the structure as there is room for.
+fn unload_all(gc: &mut GameController, structure: &Unit) -> Result<(), Error> {
+    for direction in directions_for(gc, structure) {
+        if gc.can_unload(structure.id(), direction) {
+            gc.unload(structure.id(), direction)?;
+        }
+    }
+    Ok(())
+}
+
+/// The nearest enemy the robot can see, by distance and then by ID.
+fn nearest_enemy(gc: &GameController, robot: &Unit) -> Option<Unit> {
+    let location = robot.location().map_location().ok()?;
+    let enemies = gc.sense_nearby_units_by_team(location, robot.vision_range(),
+                                                robot.team().other());
+    enemies.into_iter()
+        .filter(|enemy| enemy.location().is_on_map())
+        .min_by_key(|enemy| {
+            let distance = enemy.location().map_location().unwrap().distance_squared_to(location);
+            (distance, enemy.id())
+        })
+}
+
+/// Attacks the nearest enemy in range, or else moves towards the nearest
+/// enemy in sight, or the enemy's starting position.
+fn attack_nearest(gc: &mut GameController, robot: &Unit) -> Result<(), Error> {
+    let location = match robot.location().map_location() {
+        Ok(location) => location,
+        Err(_) => return Ok(()),
+    };
+    let target = match nearest_enemy(gc, robot) {
+        Some(enemy) => {
+            if gc.is_attack_ready(robot.id()) && gc.can_attack(robot.id(), enemy.id()) {
+                return gc.attack(robot.id(), enemy.id());
+            }
+            enemy.location().map_location()?
+        },
+        None => {
+            let starting_units = &gc.starting_map(location.planet).initial_units;
+            match starting_units.iter().find(|unit| unit.team() != robot.team()) {
+                Some(enemy) => enemy.location().map_location()?,
+                None => return wander(gc, robot),
+            }
+        },
+    };
+    if !gc.is_move_ready(robot.id()) || target == location {
+        return Ok(());
+    }
+    let direction = location.direction_to(target)?;
+    for &direction in [direction, direction.rotate_left(), direction.rotate_right()].iter() {
+        if gc.can_move(robot.id(), direction) {
+            return gc.move_robot(robot.id(), direction);
+        }
+    }
+    Ok(())
+}
+
+/// Moves the robot in the first direction it can.
+fn wander(gc: &mut GameController, robot: &Unit) -> Result<(), Error> {
+    if !gc.is_move_ready(robot.id()) {
+        return Ok(());
+    }
+    for direction in directions_for(gc, robot) {
+        if gc.can_move(robot.id(), direction) {
+            return gc.move_robot(robot.id(), direction);
+        }
+    }
+    Ok(())
+}
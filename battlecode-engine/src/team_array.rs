@@ -7,6 +7,7 @@
 use failure::Error;
 use fnv::FnvHashMap;
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 use super::constants::*;
 use super::location::*;
@@ -15,9 +16,12 @@ use super::error::*;
 /// A team-shared communication array for a single player.
 pub type TeamArray = Vec<i32>;
 
+/// The recent history of each planet's team array. The history is shared
+/// between clones until one of them changes it, and each round's array is
+/// shared with the rounds that have not changed it.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) struct TeamArrayInfo {
-    history: FnvHashMap<Planet, VecDeque<TeamArray>>,
+    history: Arc<FnvHashMap<Planet, VecDeque<Arc<TeamArray>>>>,
 }
 
 impl TeamArrayInfo {
@@ -25,19 +29,31 @@ impl TeamArrayInfo {
     pub fn new() -> TeamArrayInfo {
         // The length of the history is COMMUNICATION_DELAY + 1 for each array
         // from 1 to COMMUNICATION_DELAY rounds ago, and the current round.
-        let mut history: FnvHashMap<Planet, VecDeque<TeamArray>> = FnvHashMap::default();
-        let mut deque: VecDeque<TeamArray> = VecDeque::with_capacity(COMMUNICATION_DELAY + 1);
+        let mut history: FnvHashMap<Planet, VecDeque<Arc<TeamArray>>> = FnvHashMap::default();
+        let mut deque: VecDeque<Arc<TeamArray>> = VecDeque::with_capacity(COMMUNICATION_DELAY + 1);
+        let empty = Arc::new(vec![0; COMMUNICATION_ARRAY_LENGTH]);
         for _ in 0..COMMUNICATION_DELAY + 1 {
-            deque.push_back(vec![0; COMMUNICATION_ARRAY_LENGTH]);
+            deque.push_back(empty.clone());
         }
         history.insert(Planet::Earth, deque.clone());
         history.insert(Planet::Mars, deque);
         TeamArrayInfo {
-            history: history,
+            history: Arc::new(history),
         }
     }
 
-    fn get_arrays(&self, planet: Planet) -> &VecDeque<TeamArray> {
+    /// Stops sharing the arrays with any other round or clone, copying them
+    /// if necessary.
+    #[cfg(feature = "bench")]
+    pub fn unshare(&mut self) {
+        for deque in Arc::make_mut(&mut self.history).values_mut() {
+            for array in deque.iter_mut() {
+                *array = Arc::new((**array).clone());
+            }
+        }
+    }
+
+    fn get_arrays(&self, planet: Planet) -> &VecDeque<Arc<TeamArray>> {
         if let Some(array) = self.history.get(&planet) {
             array
         } else {
@@ -45,8 +61,8 @@ impl TeamArrayInfo {
         }
     }
 
-    fn get_arrays_mut(&mut self, planet: Planet) -> &mut VecDeque<TeamArray> {
-        if let Some(array) = self.history.get_mut(&planet) {
+    fn get_arrays_mut(&mut self, planet: Planet) -> &mut VecDeque<Arc<TeamArray>> {
+        if let Some(array) = Arc::make_mut(&mut self.history).get_mut(&planet) {
             array
         } else {
             unreachable!();
@@ -59,17 +75,17 @@ impl TeamArrayInfo {
     pub fn filter(&self, planet: Planet) -> TeamArrayInfo {
         let this_array = self.get_arrays(planet).front().unwrap().clone();
         let that_array = self.get_arrays(planet.other()).back().unwrap().clone();
-        let mut history: FnvHashMap<Planet, VecDeque<TeamArray>> = FnvHashMap::default();
+        let mut history: FnvHashMap<Planet, VecDeque<Arc<TeamArray>>> = FnvHashMap::default();
 
-        let mut this_deque: VecDeque<TeamArray> = VecDeque::with_capacity(1);
-        let mut that_deque: VecDeque<TeamArray> = VecDeque::with_capacity(1);
+        let mut this_deque: VecDeque<Arc<TeamArray>> = VecDeque::with_capacity(1);
+        let mut that_deque: VecDeque<Arc<TeamArray>> = VecDeque::with_capacity(1);
         this_deque.push_back(this_array);
         that_deque.push_back(that_array);
 
         history.insert(planet, this_deque);
         history.insert(planet.other(), that_deque);
         TeamArrayInfo {
-            history: history,
+            history: Arc::new(history),
         }
     }
 
@@ -83,13 +99,14 @@ impl TeamArrayInfo {
         self.get_arrays(planet).back().unwrap()
     }
 
-    /// Writes the value at the index of this planet's team array.
+    /// Writes the value at the index of this planet's team array, copying
+    /// the array first if it is shared.
     ///
     /// * ArrayOutOfBounds - the array index is accessed out of bounds.
     pub fn write(&mut self, planet: Planet, index: usize, value: i32) -> Result<(), Error> {
         let array = self.get_arrays_mut(planet).front_mut().unwrap();
         if index < array.len() {
-            array[index] = value;
+            Arc::make_mut(array)[index] = value;
             Ok(())
         } else {
             Err(GameError::ArrayOutOfBounds)?
@@ -219,4 +236,17 @@ mod tests {
             assert_eq!(mars.last_array(Planet::Earth)[index], 0);
         }
     }
+
+    #[test]
+    fn test_array_history_bounded() {
+        let mut arrays = TeamArrayInfo::new();
+        for round in 0..1000 {
+            assert!(arrays.write(Planet::Earth, 0, round).is_ok());
+            arrays.end_round();
+            assert_eq!(arrays.get_arrays(Planet::Earth).len(), COMMUNICATION_DELAY + 1);
+            assert_eq!(arrays.get_arrays(Planet::Mars).len(), COMMUNICATION_DELAY + 1);
+        }
+        assert_eq!(arrays.first_array(Planet::Earth)[0], 999);
+        assert_eq!(arrays.last_array(Planet::Earth)[0], 1000 - COMMUNICATION_DELAY as i32);
+    }
 }
@@ -195,6 +195,90 @@ impl UnitType {
         }
     }
 
+    /// The number of rounds it takes a factory to produce the unit.
+    ///
+    /// * InappropriateUnitType - the unit type cannot be produced in a factory.
+    pub fn factory_rounds(self) -> Result<Rounds, Error> {
+        if self.is_robot() {
+            Ok(FACTORY_PRODUCTION_ROUNDS)
+        } else {
+            Err(GameError::InappropriateUnitType)?
+        }
+    }
+
+    /// The stats of a new unit of this type at the given research level.
+    ///
+    /// * ResearchNotUnlocked - the research level does not exist for this
+    ///   unit type.
+    fn stats(self, level: Level) -> Result<Unit, Error> {
+        Unit::new(0, Team::Red, self, level, Unknown)
+    }
+
+    /// The maximum health of the unit at the given research level.
+    ///
+    /// * ResearchNotUnlocked - the research level does not exist for this
+    ///   unit type.
+    pub fn max_health(self, level: Level) -> Result<u32, Error> {
+        Ok(self.stats(level)?.max_health())
+    }
+
+    /// The damage inflicted by a normal attack at the given research level.
+    /// Healers inflict negative damage.
+    ///
+    /// * InappropriateUnitType - the unit type is not a robot.
+    /// * ResearchNotUnlocked - the research level does not exist for this
+    ///   unit type.
+    pub fn damage(self, level: Level) -> Result<i32, Error> {
+        self.stats(level)?.damage()
+    }
+
+    /// The attack range, which research does not change.
+    ///
+    /// * InappropriateUnitType - the unit type is not a robot.
+    pub fn attack_range(self) -> Result<u32, Error> {
+        self.stats(0)?.attack_range()
+    }
+
+    /// The range within which a ranger cannot attack, which research does
+    /// not change.
+    ///
+    /// * InappropriateUnitType - the unit type is not a ranger.
+    pub fn ranger_cannot_attack_range(self) -> Result<u32, Error> {
+        self.stats(0)?.ranger_cannot_attack_range()
+    }
+
+    /// The vision range of the unit at the given research level.
+    ///
+    /// * ResearchNotUnlocked - the research level does not exist for this
+    ///   unit type.
+    pub fn vision_range(self, level: Level) -> Result<u32, Error> {
+        Ok(self.stats(level)?.vision_range())
+    }
+
+    /// The movement cooldown at the given research level.
+    ///
+    /// * InappropriateUnitType - the unit type is not a robot.
+    /// * ResearchNotUnlocked - the research level does not exist for this
+    ///   unit type.
+    pub fn movement_cooldown(self, level: Level) -> Result<u32, Error> {
+        self.stats(level)?.movement_cooldown()
+    }
+
+    /// The attack cooldown, which research does not change.
+    ///
+    /// * InappropriateUnitType - the unit type is not a robot.
+    pub fn attack_cooldown(self) -> Result<u32, Error> {
+        self.stats(0)?.attack_cooldown()
+    }
+
+    /// The ability cooldown, which research does not change. For workers,
+    /// this is the cooldown of replication.
+    ///
+    /// * InappropriateUnitType - the unit type is not a robot.
+    pub fn ability_cooldown(self) -> Result<u32, Error> {
+        self.stats(0)?.ability_cooldown()
+    }
+
     /// The value of a unit, as relevant to tiebreakers.
     pub fn value(self) -> u32 {
         match self {
@@ -310,7 +394,7 @@ impl Default for Unit {
             self_heal_amount: 1,
             factory_unit_type: None,
             factory_rounds_left: None,
-            factory_max_rounds_left: 5,
+            factory_max_rounds_left: FACTORY_PRODUCTION_ROUNDS,
             is_used: false,
             blast_damage: 50,
             travel_time_decrease: 0,
@@ -489,11 +573,23 @@ impl Unit {
     /// * Overheated - the unit is not ready to move.
     pub(crate) fn ok_if_move_ready(&self) -> Result<(), Error> {
         if self.movement_heat()? >= MAX_HEAT_TO_ACT {
-            Err(GameError::Overheated)?;
+            Err(GameError::Overheated { unit_id: self.id })?;
         }
         Ok(())
     }
 
+    /// The number of rounds until the movement heat drops below the maximum
+    /// heat to act, which is 0 if the unit is ready to move.
+    ///
+    /// * InappropriateUnitType - the unit is not a robot.
+    pub(crate) fn rounds_until_move_ready(&self) -> Result<Rounds, Error> {
+        let heat = self.movement_heat()?;
+        if heat < MAX_HEAT_TO_ACT {
+            return Ok(0);
+        }
+        Ok((heat - MAX_HEAT_TO_ACT) / HEAT_LOSS_PER_ROUND + 1)
+    }
+
     /// Updates the unit's location as it if has moved, and increases the
     /// movement heat.
     pub(crate) fn move_to(&mut self, location: MapLocation) {
@@ -501,16 +597,28 @@ impl Unit {
         self.location = OnMap(location);
     }
 
+    /// Updates the robot as though it has just appeared on the map, after
+    /// being replicated or unloaded. It cannot move, attack, use its ability
+    /// or perform a worker action until the next round.
+    pub(crate) fn arrive_on_map(&mut self) {
+        self.movement_heat = cmp::max(self.movement_heat, MAX_HEAT_TO_ACT);
+        self.attack_heat = cmp::max(self.attack_heat, MAX_HEAT_TO_ACT);
+        self.ability_heat = cmp::max(self.ability_heat, MAX_HEAT_TO_ACT);
+        self.has_worker_acted = true;
+    }
+
     /// Ok if the robot can attack the target location. Overloaded for the
     /// healer's heal.
     ///
     /// * InappropriateUnitType - the unit is not a robot.
     /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
     pub(crate) fn ok_if_within_attack_range(&self, target_loc: Location) -> Result<(), Error> {
         self.ok_if_robot()?;
         if self.unit_type() == UnitType::Ranger {
             if self.location().is_within_range(self.cannot_attack_range, target_loc) {
-                Err(GameError::OutOfRange)?;
+                Err(GameError::TooClose)?;
             }
         }
         if !self.location().is_within_range(self.attack_range, target_loc) {
@@ -526,7 +634,7 @@ impl Unit {
     /// * Overheated - the unit is not ready to attack.
     pub(crate) fn ok_if_attack_ready(&self) -> Result<(), Error> {
         if self.attack_heat()? >= MAX_HEAT_TO_ACT {
-            Err(GameError::Overheated)?;
+            Err(GameError::Overheated { unit_id: self.id })?;
         }
         Ok(())        
     }
@@ -615,7 +723,7 @@ impl Unit {
     /// * Overheated - the unit is not ready to use its ability.
     pub(crate) fn ok_if_ability_ready(&self) -> Result<(), Error> {
         if self.ability_heat()? >= MAX_HEAT_TO_ACT {
-            Err(GameError::Overheated)?;
+            Err(GameError::Overheated { unit_id: self.id })?;
         }
         Ok(())
     }
@@ -669,7 +777,7 @@ impl Unit {
     }
 
     /// Ok if the worker can perform a worker action (building, blueprinting,
-    /// harvesting, or replicating).
+    /// harvesting, or repairing).
     ///
     /// * InappropriateUnitType - the unit is not a worker.
     /// * Overheated - the worker is not ready to perform a worker action.
@@ -678,7 +786,7 @@ impl Unit {
             Err(GameError::InappropriateUnitType)?;
         }
         if self.has_worker_acted {
-            Err(GameError::Overheated)?;
+            Err(GameError::Overheated { unit_id: self.id })?;
         }
         Ok(())
     }
@@ -896,11 +1004,17 @@ impl Unit {
         Ok(())
     }
 
-    /// Resets a unit's move, attack, and ability cooldowns.
+    /// Resets a unit's move, attack, and ability cooldowns. A sniping ranger
+    /// could otherwise move and attack during its countdown, so its snipe is
+    /// cancelled instead.
     pub(crate) fn be_overcharged(&mut self) {
         self.movement_heat = 0;
         self.attack_heat = 0;
         self.ability_heat = 0;
+        if self.target_location.is_some() {
+            self.target_location = None;
+            self.countdown = 0;
+        }
     }
 
     // ************************************************************************
@@ -931,11 +1045,20 @@ impl Unit {
         Ok(self.garrison.clone())
     }
 
-    /// Updates this structure as though a worker has just built it.
-    pub(crate) fn be_built(&mut self, build_health: u32) {
-        if self.be_healed(build_health) {
+    /// Whether the unit contributes to its team's vision. Blueprints do not
+    /// see anything until they are built.
+    pub(crate) fn has_vision(&self) -> bool {
+        !self.unit_type.is_structure() || self.is_built
+    }
+
+    /// Updates this structure as though a worker has just built it. Returns
+    /// true if this completed the structure.
+    pub(crate) fn be_built(&mut self, build_health: u32) -> bool {
+        if !self.is_built && self.be_healed(build_health) {
             self.is_built = true;
+            return true;
         }
+        false
     }
 
     /// Ok if the structure has been built.
@@ -1143,6 +1266,7 @@ impl Unit {
     /// * StructureNotYetBuilt - the rocket has not yet been built.
     /// * RocketUsed - the rocket has already been used.
     pub(crate) fn ok_if_can_launch_rocket(&self) -> Result<(), Error> {
+        self.ok_if_unit_type(Rocket)?;
         self.ok_if_structure_built()?;
         if self.is_used {
             Err(GameError::RocketUsed)?;
@@ -1246,6 +1370,62 @@ impl Unit {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_unit_type_stats() {
+        // (type, level, max health, damage, attack range, vision range,
+        //  movement cooldown, attack cooldown, ability cooldown)
+        let table = [
+            (Worker, 0, 100, 0, 0, 50, 20, 0, 500),
+            (Knight, 0, 250, 60, 1, 50, 15, 20, 100),
+            (Ranger, 0, 200, 40, 50, 70, 20, 20, 200),
+            (Ranger, 2, 200, 40, 50, 100, 15, 20, 200),
+            (Mage, 0, 80, 60, 30, 30, 20, 20, 250),
+            (Mage, 3, 80, 105, 30, 30, 20, 20, 250),
+            (Healer, 0, 100, -10, 30, 50, 25, 10, 100),
+            (Healer, 2, 100, -17, 30, 50, 25, 10, 100),
+        ];
+        for &(unit_type, level, max_health, damage, attack_range, vision_range,
+              movement_cooldown, attack_cooldown, ability_cooldown) in table.iter() {
+            assert_eq!(unit_type.max_health(level).unwrap(), max_health);
+            assert_eq!(unit_type.damage(level).unwrap(), damage);
+            assert_eq!(unit_type.attack_range().unwrap(), attack_range);
+            assert_eq!(unit_type.vision_range(level).unwrap(), vision_range);
+            assert_eq!(unit_type.movement_cooldown(level).unwrap(), movement_cooldown);
+            assert_eq!(unit_type.attack_cooldown().unwrap(), attack_cooldown);
+            assert_eq!(unit_type.ability_cooldown().unwrap(), ability_cooldown);
+            assert_eq!(unit_type.factory_rounds().unwrap(), FACTORY_PRODUCTION_ROUNDS);
+        }
+
+        // Structures have health and vision, but no combat stats.
+        for &(unit_type, max_health) in [(Factory, 300), (Rocket, 200)].iter() {
+            assert_eq!(unit_type.max_health(0).unwrap(), max_health);
+            assert_eq!(unit_type.vision_range(0).unwrap(), 2);
+            assert_err!(unit_type.damage(0), GameError::InappropriateUnitType);
+            assert_err!(unit_type.attack_range(), GameError::InappropriateUnitType);
+            assert_err!(unit_type.movement_cooldown(0), GameError::InappropriateUnitType);
+            assert_err!(unit_type.attack_cooldown(), GameError::InappropriateUnitType);
+            assert_err!(unit_type.ability_cooldown(), GameError::InappropriateUnitType);
+            assert_err!(unit_type.factory_rounds(), GameError::InappropriateUnitType);
+        }
+
+        // Costs apply only to the types they make sense for.
+        assert_eq!(Factory.blueprint_cost().unwrap(), BLUEPRINT_FACTORY_COST);
+        assert_eq!(Rocket.blueprint_cost().unwrap(), BLUEPRINT_ROCKET_COST);
+        assert_eq!(Worker.replicate_cost().unwrap(), REPLICATE_WORKER_COST);
+        assert_eq!(Mage.factory_cost().unwrap(), FACTORY_MAGE_COST);
+        assert_err!(Worker.blueprint_cost(), GameError::InappropriateUnitType);
+        assert_err!(Knight.replicate_cost(), GameError::InappropriateUnitType);
+        assert_err!(Rocket.factory_cost(), GameError::InappropriateUnitType);
+
+        // Only rangers have a minimum attack range.
+        assert_eq!(Ranger.ranger_cannot_attack_range().unwrap(), 10);
+        assert_err!(Knight.ranger_cannot_attack_range(), GameError::InappropriateUnitType);
+
+        // Levels beyond the research tree do not exist.
+        assert!(Knight.max_health(4).is_err());
+        assert!(Factory.vision_range(1).is_err());
+    }
+
     #[test]
     fn test_movement() {
         let loc_a = MapLocation::new(Planet::Earth, 0, 0);
@@ -1261,12 +1441,12 @@ mod tests {
         unit.move_to(loc_b);
         assert_eq!(unit.location(), OnMap(loc_b));
         assert_gt!(unit.movement_heat().unwrap(), 0);
-        assert_err!(unit.ok_if_move_ready(), GameError::Overheated);
+        assert_err!(unit.ok_if_move_ready(), GameError::Overheated { unit_id: unit.id() });
 
         // Wait one round, and the unit is still not ready to move.
         unit.end_round();
         assert_gte!(unit.movement_heat().unwrap(), MAX_HEAT_TO_ACT);
-        assert_err!(unit.ok_if_move_ready(), GameError::Overheated);
+        assert_err!(unit.ok_if_move_ready(), GameError::Overheated { unit_id: unit.id() });
 
         // Wait one more round, and succesfully move.
         unit.end_round();
@@ -1290,6 +1470,44 @@ mod tests {
         assert!(rocket.movement_cooldown().is_err());
     }
 
+    #[test]
+    fn test_heat_decay() {
+        let loc = MapLocation::new(Planet::Earth, 0, 0);
+        let mut knight = Unit::new(1, Team::Red, Knight, 0, OnMap(loc)).unwrap();
+        assert_eq!(knight.attack_cooldown().unwrap(), 20);
+
+        // A knight with an attack cooldown of 20 is ready again after the
+        // heat falls below the threshold, which takes two rounds.
+        knight.use_attack();
+        assert_eq!(knight.attack_heat().unwrap(), 20);
+        let mut rounds = 0;
+        while knight.ok_if_attack_ready().is_err() {
+            knight.end_round();
+            rounds += 1;
+        }
+        assert_eq!(rounds, (20 - MAX_HEAT_TO_ACT) / HEAT_LOSS_PER_ROUND + 1);
+        assert_eq!(rounds, 2);
+
+        // Ability heat decays at the same rate and uses the same threshold.
+        let cooldown = knight.ability_cooldown().unwrap();
+        knight.javelin();
+        let mut rounds = 0;
+        while knight.ok_if_ability_ready().is_err() {
+            knight.end_round();
+            rounds += 1;
+        }
+        assert_eq!(rounds, (cooldown - MAX_HEAT_TO_ACT) / HEAT_LOSS_PER_ROUND + 1);
+
+        // Heat never drops below zero.
+        knight.move_to(MapLocation::new(Planet::Earth, 0, 1));
+        for _ in 0..100 {
+            knight.end_round();
+        }
+        assert_eq!(knight.movement_heat().unwrap(), 0);
+        assert_eq!(knight.attack_heat().unwrap(), 0);
+        assert_eq!(knight.ability_heat().unwrap(), 0);
+    }
+
     #[test]
     fn test_combat() {
     }
@@ -1319,9 +1537,9 @@ mod tests {
         // Unit cannot use ability when ability heat >= max heat to act 
         let mut ranger = Unit::new(1, Team::Red, Ranger, 3, OnMap(loc)).unwrap();
         ranger.ability_heat = MAX_HEAT_TO_ACT;
-        assert_err!(ranger.ok_if_ability_ready(), GameError::Overheated);
+        assert_err!(ranger.ok_if_ability_ready(), GameError::Overheated { unit_id: ranger.id() });
         ranger.ability_heat = MAX_HEAT_TO_ACT + 10;
-        assert_err!(ranger.ok_if_ability_ready(), GameError::Overheated);
+        assert_err!(ranger.ok_if_ability_ready(), GameError::Overheated { unit_id: ranger.id() });
     }
 
     #[test]
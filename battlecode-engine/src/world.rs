@@ -1,10 +1,19 @@
 //! The core battlecode engine.
 
-use fnv::FnvHashMap;
-use rand;
+use fnv::{FnvHashMap, FnvHasher};
+use rand::distributions::IndependentSample;
+use rand::distributions::range::Range;
 use std::cmp;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Serialize;
+use serde_json;
 
 use super::constants::*;
 use super::schema::*;
@@ -15,8 +24,10 @@ use super::map::*;
 use super::unit::*;
 use super::unit::UnitType as Branch;
 use super::research::*;
+use super::rng::GameRng;
 use super::rockets::*;
 use super::team_array::*;
+use super::occupancy::OccupancyGrid;
 use super::error::GameError;
 use failure::Error;
 
@@ -47,8 +58,9 @@ pub struct PlanetInfo {
     ///
     /// Stored as a two-dimensional array, where the first index
     /// represents a square's y-coordinate, and the second index its
-    /// x-coordinate.
-    visible_locs: Vec<Vec<bool>>,
+    /// x-coordinate. Shared between clones of the world until modified.
+    #[serde(with = "::schema::bool_grid::shared")]
+    visible_locs: Arc<Vec<Vec<bool>>>,
 
     /// The units in the vision range.
     ///
@@ -60,38 +72,21 @@ pub struct PlanetInfo {
     ///    structures of other teams.
     pub units: FnvHashMap<UnitID, Unit>,
 
-    /// All the units on the map, by map location. Cached for performance.
+    /// All the units on the map, by map location. Cached for performance as
+    /// a grid the size of the planet's map.
     ///
     /// Invariants:
     /// 1. Has every unit with a visible location on this planet.
     /// 2. Every entry has a corresponding entry in `units`.
-    #[serde(serialize_with = "serialize_structymap")]
-    #[serde(deserialize_with = "deserialize_structymap")]
-    pub(crate) units_by_loc: FnvHashMap<MapLocation, UnitID>,
+    pub(crate) units_by_loc: OccupancyGrid,
 
     /// The amount of Karbonite deposited on the specified square.
     ///
     /// Stored as a two-dimensional array, where the first index 
     /// represents a square's y-coordinate, and the second index its 
-    /// x-coordinate.
-    pub(crate) karbonite: Vec<Vec<u32>>,
-}
-
-use serde::{Serialize, Deserialize, Serializer, Deserializer};
-
-type StructyMap = FnvHashMap<MapLocation, UnitID>;
-
-// fuckin garbage no-good nonsense my goodnus
-fn serialize_structymap<S: Serializer> (map: &StructyMap, s: S) -> Result<S::Ok, S::Error> {
-    map.iter().map(|(a,b)| (a.clone(),b.clone())).collect::<Vec<(_,_)>>().serialize(s)
-}
-fn deserialize_structymap<'de, D: Deserializer<'de>>(d: D) -> Result<StructyMap, D::Error> {
-    let vec = <Vec<(MapLocation, UnitID)>>::deserialize(d)?;
-    let mut map = StructyMap::default();
-    for (k, v) in vec {
-        map.insert(k, v);
-    }
-    Ok(map)
+    /// x-coordinate. Shared between clones of the world until modified;
+    /// write to it through `karbonite_mut()`.
+    pub(crate) karbonite: Arc<Vec<Vec<u32>>>,
 }
 
 impl PlanetInfo {
@@ -99,12 +94,66 @@ impl PlanetInfo {
     /// deposits are initialized with the map's initial deposits.
     pub fn new(map: &PlanetMap) -> PlanetInfo {
         PlanetInfo {
-            visible_locs: vec![vec![true; map.width]; map.height],
+            visible_locs: Arc::new(vec![vec![true; map.width]; map.height]),
             units: FnvHashMap::default(),
-            units_by_loc: FnvHashMap::default(),
-            karbonite: map.initial_karbonite.clone(),
+            units_by_loc: OccupancyGrid::new(map),
+            karbonite: Arc::new(map.initial_karbonite.clone()),
         }
     }
+
+    /// The karbonite deposits, copied first if they are shared with another
+    /// world.
+    pub(crate) fn karbonite_mut(&mut self) -> &mut Vec<Vec<u32>> {
+        Arc::make_mut(&mut self.karbonite)
+    }
+}
+
+/// A player's time budget, kept by the Teh Devs engine.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerTiming {
+    /// The time left in the player's pool, in milliseconds.
+    pub time_left_ms: i32,
+    /// The total time the player has used, in milliseconds.
+    pub time_used_ms: i32,
+    /// Whether the player's pool has gone negative. The rest of its turns
+    /// are skipped, and its team loses.
+    pub timed_out: bool,
+}
+
+impl PlayerTiming {
+    fn new(time_pool_ms: i32) -> PlayerTiming {
+        PlayerTiming { time_left_ms: time_pool_ms, time_used_ms: 0, timed_out: false }
+    }
+}
+
+/// Running totals of a team's units and karbonite over the game.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TeamStats {
+    /// The combined value of the team's living units, used in tiebreakers.
+    pub units_value: u32,
+    /// The karbonite the team's workers have harvested.
+    pub karbonite_mined: u32,
+    /// The units the team has created during the game by blueprinting,
+    /// replicating, or producing them in a factory.
+    pub units_produced: u32,
+    /// The team's units destroyed by attacks.
+    pub units_destroyed_in_combat: u32,
+    /// The team's units destroyed by rocket blasts or landings, including
+    /// rockets lost on launch or landing.
+    pub units_destroyed_by_rockets: u32,
+    /// The team's units that were disintegrated.
+    pub units_disintegrated: u32,
+    /// The team's units destroyed by the flood on Earth.
+    pub units_destroyed_by_flood: u32,
+}
+
+/// What destroyed a unit, for the team statistics and the viewer.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Destruction {
+    Combat,
+    Rocket,
+    Disintegration,
+    Flood,
 }
 
 /// Persistent info specific to a single team. Teams are only able to access
@@ -125,19 +174,51 @@ pub struct TeamInfo {
 
     /// The karbonite in the team's resource pool.
     karbonite: u32,
+
+    /// Running totals of the team's units and karbonite.
+    stats: TeamStats,
+
+    /// The time budget of the team's player on each planet.
+    timing: FnvHashMap<Planet, PlayerTiming>,
 }
 
 impl TeamInfo {
-    /// Construct a team with the default properties.
-    fn new() -> TeamInfo {
+    /// Construct a team with the default properties, the given karbonite in
+    /// its resource pool, and the given time in each player's time pool.
+    fn new(karbonite: u32, time_pool_ms: i32) -> TeamInfo {
         TeamInfo {
             team_arrays: TeamArrayInfo::new(),
             rocket_landings: RocketLandingInfo::new(),
             research: ResearchInfo::new(),
             units_in_space: FnvHashMap::default(),
-            karbonite: KARBONITE_STARTING,
+            karbonite: karbonite,
+            stats: TeamStats::default(),
+            timing: [Planet::Earth, Planet::Mars].iter()
+                .map(|planet| (*planet, PlayerTiming::new(time_pool_ms)))
+                .collect(),
         }
     }
+
+    /// Feeds the team's units in space, landings, research and karbonite
+    /// into a checksum.
+    fn hash_into(&self, hasher: &mut FnvHasher) {
+        let mut units: Vec<&Unit> = self.units_in_space.values().collect();
+        units.sort_by_key(|unit| unit.id());
+        hash_json(&units, hasher);
+        hash_json(&self.rocket_landings.all(), hasher);
+        let levels: Vec<Level> = Branch::all().iter()
+            .map(|branch| self.research.get_level(branch))
+            .collect();
+        hash_json(&(levels, self.research.queue(), self.research.rounds_left().ok()), hasher);
+        hash_json(&self.karbonite, hasher);
+        hash_json(&self.stats, hasher);
+    }
+}
+
+/// Feeds a value into a checksum as JSON. Unlike `Hash`, JSON does not depend
+/// on the width of `usize`.
+fn hash_json<T: Serialize>(value: &T, hasher: &mut FnvHasher) {
+    hasher.write(serde_json::to_string(value).expect("game state serializes").as_bytes());
 }
 
 /// A player represents a program controlling some group of units.
@@ -172,6 +253,61 @@ impl Player {
     }
 }
 
+/// Whether a robot can move in a direction, as given by
+/// `GameWorld::move_status()`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MoveStatus {
+    /// The robot can move now.
+    Ready,
+    /// The destination is clear, but the robot's movement heat is too high.
+    /// Carries the number of rounds until it can move again.
+    OnCooldown(Rounds),
+    /// The destination, which is carried, is occupied by a unit or
+    /// impassable terrain.
+    Blocked(MapLocation),
+    /// The destination, which is carried, is off the map.
+    OffMap(MapLocation),
+}
+
+impl MoveStatus {
+    /// Whether the robot can move now.
+    pub fn is_ready(&self) -> bool {
+        *self == MoveStatus::Ready
+    }
+
+    /// Whether the destination is clear, but the robot must first cool down.
+    pub fn is_on_cooldown(&self) -> bool {
+        self.cooldown_rounds().unwrap_or(0) > 0
+    }
+
+    /// Whether the destination is occupied or off the map.
+    pub fn is_blocked(&self) -> bool {
+        self.blocked_location().is_ok()
+    }
+
+    /// The number of rounds until the robot can move, which is 0 if it is
+    /// ready.
+    ///
+    /// * NullValue - the destination is occupied or off the map.
+    pub fn cooldown_rounds(&self) -> Result<Rounds, Error> {
+        match *self {
+            MoveStatus::Ready => Ok(0),
+            MoveStatus::OnCooldown(rounds) => Ok(rounds),
+            MoveStatus::Blocked(_) | MoveStatus::OffMap(_) => Err(GameError::NullValue)?,
+        }
+    }
+
+    /// The destination the robot cannot move to.
+    ///
+    /// * NullValue - the destination is clear.
+    pub fn blocked_location(&self) -> Result<MapLocation, Error> {
+        match *self {
+            MoveStatus::Blocked(location) | MoveStatus::OffMap(location) => Ok(location),
+            MoveStatus::Ready | MoveStatus::OnCooldown(_) => Err(GameError::NullValue)?,
+        }
+    }
+}
+
 /// The full world of the Battlecode game.
 ///
 /// The contents of the game world differ depending on whether it exists in the
@@ -179,23 +315,37 @@ impl Player {
 /// that your player the visibility it's supposed to have!
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct GameWorld {
+    /// Whether this is the full game world, or a player's filtered world.
+    /// Only the full world may process the end of a round.
+    mode: WorldMode,
+
     /// The current round, starting at 1.
     round: Rounds,
 
     /// The player whose turn it is.
     player_to_move: Player,
 
+    /// The time in each player's pool at the start of the game, in
+    /// milliseconds.
+    time_pool_ms: i32,
+
+    /// The time added to a player's pool after each of its turns, in
+    /// milliseconds.
+    time_per_turn_ms: i32,
+
     /// Unit ID generator.
     id_generator: IDGenerator,
 
-    /// The asteroid strike pattern on Mars.
-    pub(crate) asteroids: AsteroidPattern,
+    /// The asteroid strike pattern on Mars. Never changes, so it is shared
+    /// like the maps.
+    pub(crate) asteroids: Arc<AsteroidPattern>,
 
     /// The orbit pattern that determines a rocket's flight duration.
-    orbit: OrbitPattern,
+    orbit: Arc<OrbitPattern>,
 
-    /// The map of each planet.
-    pub planet_maps: FnvHashMap<Planet, PlanetMap>,
+    /// The map of each planet. The maps never change, so they are shared
+    /// with the filtered worlds instead of copied.
+    pub planet_maps: FnvHashMap<Planet, Arc<PlanetMap>>,
 
     /// The state of each planet.
     pub planet_states: FnvHashMap<Planet, PlanetInfo>,
@@ -206,39 +356,77 @@ pub struct GameWorld {
     /// Cached game worlds per player, to calculate start turn messages.
     /// These worlds were filtered at the start of the turn.
     #[serde(skip)]
-    pub cached_world: FnvHashMap<Player, GameWorld>,
+    pub cached_world: FnvHashMap<Player, Arc<GameWorld>>,
+
+    /// The number of each player's units on the map that can see each square
+    /// of the player's planet, indexed by y-coordinate and then x-coordinate.
+    /// Kept up to date as units are placed and removed, so that filtering
+    /// does not need to recompute vision. Only counted in the Teh Devs
+    /// engine; when missing, vision is computed from scratch instead.
+    #[serde(skip)]
+    vision: FnvHashMap<Player, Arc<Vec<Vec<u32>>>>,
 
     /// A list of additional messages to be sent to the viewer. Flushed
     /// at the end of each round.
     viewer_changes: Vec<ViewerDelta>,
+
+    /// The deltas from the last call to `apply_many` that failed to apply.
+    action_log: Vec<FailedDelta>,
+}
+
+/// Whether a game world is the full world of the Teh Devs engine, or the
+/// world of a single player, filtered to what they can see.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) enum WorldMode {
+    DevEngine,
+    Player,
 }
 
+/// The first line of every game world saved with `GameWorld::to_file()`.
+/// Change the version whenever the serialized game world changes, so that
+/// old files are rejected instead of being misread.
+const SNAPSHOT_HEADER: &str = "battlecode-world 8";
+
 impl GameWorld {
-    /// Initialize a new game world with maps from both planets.
+    /// Initialize a new game world with maps from both planets, and the
+    /// default time pools.
+    #[cfg(test)]
     pub(crate) fn new(map: GameMap) -> GameWorld {
+        GameWorld::with_time(map, TIME_POOL_MS, TIME_PER_TURN_MS)
+    }
+
+    /// Initialize a new game world with maps from both planets. Each player's
+    /// pool starts with `time_pool_ms`, and gains `time_per_turn_ms` after
+    /// each of its turns.
+    pub(crate) fn with_time(map: GameMap, time_pool_ms: i32, time_per_turn_ms: i32) -> GameWorld {
         let mut planet_states = FnvHashMap::default();
         planet_states.insert(Planet::Earth, PlanetInfo::new(&map.earth_map));
         planet_states.insert(Planet::Mars, PlanetInfo::new(&map.mars_map));
 
         let mut team_states = FnvHashMap::default();
-        team_states.insert(Team::Red, TeamInfo::new());
-        team_states.insert(Team::Blue, TeamInfo::new());
+        team_states.insert(Team::Red, TeamInfo::new(map.starting_karbonite, time_pool_ms));
+        team_states.insert(Team::Blue, TeamInfo::new(map.starting_karbonite, time_pool_ms));
 
         let mut planet_maps = FnvHashMap::default();
-        planet_maps.insert(Planet::Earth, map.earth_map.clone());
-        planet_maps.insert(Planet::Mars, map.mars_map.clone());
+        planet_maps.insert(Planet::Earth, Arc::new(map.earth_map.clone()));
+        planet_maps.insert(Planet::Mars, Arc::new(map.mars_map.clone()));
 
         let mut world = GameWorld {
+            mode: WorldMode::DevEngine,
             round: 1,
             player_to_move: Player { team: Team::Red, planet: Planet::Earth },
+            time_pool_ms: time_pool_ms,
+            time_per_turn_ms: time_per_turn_ms,
             id_generator: IDGenerator::new(map.seed),
-            asteroids: map.asteroids,
-            orbit: map.orbit,
+            asteroids: Arc::new(map.asteroids),
+            orbit: Arc::new(map.orbit),
             planet_maps: planet_maps,
             planet_states: planet_states,
             team_states: team_states,
             cached_world: FnvHashMap::default(),
+            vision: FnvHashMap::default(),
             viewer_changes: Vec::new(),
+            action_log: Vec::new(),
         };
 
         // Insert initial units.
@@ -250,55 +438,95 @@ impl GameWorld {
         }
 
         // Cache the initial filtered states.
+        world.cache_filtered_worlds();
+        world
+    }
+
+    /// Caches the filtered world of each player as they are now, and counts
+    /// each player's vision. Needed before applying turns to a world that
+    /// was deserialized, since neither is serialized.
+    pub(crate) fn cache_filtered_worlds(&mut self) {
+        self.count_vision();
         let mut cached_world = HashMap::default();
         for player in Player::all() {
-            cached_world.insert(player, world.filter(player));
+            cached_world.insert(player, Arc::new(self.filter(player)));
         }
-        world.cached_world = cached_world;
-        world
+        self.cached_world = cached_world;
     }
 
     /// Generate a test world with empty maps.
     #[cfg(test)]
     pub(crate) fn test_world() -> GameWorld {
-        let map = GameMap::test_map();
+        GameWorld::test_world_with(TestMapConfig::default())
+    }
+
+    /// Generate a test world on the terrain and karbonite of a test map laid
+    /// out by the config, but without its initial units.
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn test_world_with(config: TestMapConfig) -> GameWorld {
+        let map = GameMap::test_map_with(config);
 
         let mut planet_states = FnvHashMap::default();
         planet_states.insert(Planet::Earth, PlanetInfo::new(&map.earth_map));
         planet_states.insert(Planet::Mars, PlanetInfo::new(&map.mars_map));
 
         let mut team_states = FnvHashMap::default();
-        team_states.insert(Team::Red, TeamInfo::new());
-        team_states.insert(Team::Blue, TeamInfo::new());
+        team_states.insert(Team::Red, TeamInfo::new(map.starting_karbonite, TIME_POOL_MS));
+        team_states.insert(Team::Blue, TeamInfo::new(map.starting_karbonite, TIME_POOL_MS));
 
         let mut planet_maps = FnvHashMap::default();
-        planet_maps.insert(Planet::Earth, map.earth_map);
-        planet_maps.insert(Planet::Mars, map.mars_map);
+        planet_maps.insert(Planet::Earth, Arc::new(map.earth_map));
+        planet_maps.insert(Planet::Mars, Arc::new(map.mars_map));
 
         let mut world = GameWorld {
+            mode: WorldMode::DevEngine,
             round: 1,
             player_to_move: Player { team: Team::Red, planet: Planet::Earth },
+            time_pool_ms: TIME_POOL_MS,
+            time_per_turn_ms: TIME_PER_TURN_MS,
             id_generator: IDGenerator::new(map.seed),
-            asteroids: map.asteroids,
-            orbit: map.orbit,
+            asteroids: Arc::new(map.asteroids),
+            orbit: Arc::new(map.orbit),
             planet_maps: planet_maps,
             planet_states: planet_states,
             team_states: team_states,
             cached_world: HashMap::default(),
+            vision: FnvHashMap::default(),
             viewer_changes: Vec::new(),
+            action_log: Vec::new(),
         };
 
         // Cache the initial filtered states.
-        let mut cached_world = HashMap::default();
-        for player in Player::all() {
-            cached_world.insert(player, world.filter(player));
-        }
-        world.cached_world = cached_world;
+        world.cache_filtered_worlds();
         world
     }
 
+    /// Completes the structure immediately, as though it had been fully
+    /// built by workers.
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn finish_structure(&mut self, id: UnitID) {
+        let max_health = self.get_unit(id).unwrap().max_health();
+        if self.get_unit_mut(id).unwrap().be_built(max_health) {
+            self.update_vision(id, true);
+        }
+    }
+
+    /// Researches every level of each branch for the current team, without
+    /// waiting for the rounds it would take.
+    #[cfg(test)]
+    fn unlock_research(&mut self, branches: &[Branch]) {
+        let research = self.my_research_mut();
+        for branch in branches {
+            while research.add_to_queue(branch) {}
+        }
+        while research.has_next_in_queue() {
+            research.end_round();
+        }
+    }
+
     /// Filters the game world from the perspective of the current player. All
-    /// units are within the player's vision range. Private player information
+    /// units are within the player's vision range, except for the player's own
+    /// blueprints, which have no vision of their own. Private player information
     /// like communication arrays and rockets in space should only be stored
     /// for the current player.
     ///
@@ -307,47 +535,57 @@ impl GameWorld {
     pub(crate) fn filter(&self, player: Player) -> GameWorld {
         let team = player.team;
         let planet = player.planet;
-        let map = self.starting_map(planet);
-
-        // First find your units that are on the map to calculate the visible locations.
-        let mut locs_vision: Vec<(MapLocation, u32)> = vec![];
-        for unit in self.get_planet(planet).units.values().into_iter() {
-            if unit.team() == team && unit.location().is_on_map() {
-                locs_vision.push(
-                    (unit.location().map_location().unwrap(), unit.vision_range())
-                );
-            }
-        }
-
-        // Calculate the visible locations on this team that are on the map.
-        let mut visible_locs = vec![vec![false; map.width]; map.height];
-        for &(loc, vision_range) in locs_vision.iter() {
-            let locs = self.all_locations_within(loc, vision_range);
-            for loc in locs {
-                visible_locs[loc.y as usize][loc.x as usize] = true;
-            }
-        }
+        let planet_info = self.get_planet(planet);
+
+        // Read the visible locations off the vision counts, or calculate them
+        // from your units that are on the map if vision is not counted.
+        let counted;
+        let counts = match self.vision.get(&player) {
+            Some(counts) => counts,
+            None => {
+                counted = self.vision_counts(player);
+                &counted
+            },
+        };
+        let visible_locs: Vec<Vec<bool>> = counts.iter()
+            .map(|row| row.iter().map(|&count| count > 0).collect())
+            .collect();
+
+        // Squares out of vision show the karbonite they started with, rather
+        // than how much is left there now.
+        let initial_karbonite = &self.starting_map(planet).initial_karbonite;
+        let karbonite: Vec<Vec<u32>> = planet_info.karbonite.iter().enumerate()
+            .map(|(y, row)| row.iter().enumerate()
+                .map(|(x, &amount)| if visible_locs[y][x] { amount } else { initial_karbonite[y][x] })
+                .collect())
+            .collect();
 
         // Find all the units within these visible locations, and also index
         // them by location. Includes units in enemy rockets.
         let mut units: FnvHashMap<UnitID, Unit> = FnvHashMap::default();
-        let mut units_by_loc: FnvHashMap<MapLocation, UnitID> = FnvHashMap::default();
-        for (id, unit) in self.get_planet(planet).units.iter() {
-            if let OnMap(loc) = unit.location() {
-                if !visible_locs[loc.y as usize][loc.x as usize] {
-                    continue;
-                }
-                units_by_loc.insert(loc, *id);
-                units.insert(*id, unit.clone());
+        let mut units_by_loc = OccupancyGrid::new(self.starting_map(planet));
+        for (id, unit) in planet_info.units.iter() {
+            let location = match unit.location() {
+                InGarrison(structure_id) => match planet_info.units.get(&structure_id) {
+                    Some(structure) => structure.location(),
+                    None => continue,
+                },
+                location => location,
+            };
+            let loc = match location {
+                OnMap(loc) => loc,
+                _ => continue,
+            };
+            // Blueprints have no vision, but their team still knows where
+            // they are.
+            if !visible_locs[loc.y as usize][loc.x as usize] && unit.team() != team {
+                continue;
             }
-        };
-        for (id, unit) in self.get_planet(planet).units.iter() {
-            if let InGarrison(structure_id) = unit.location() {
-                if units.contains_key(&structure_id) {
-                    units.insert(*id, unit.clone());
-                }
+            if unit.location().is_on_map() {
+                units_by_loc.insert(loc, *id);
             }
-        };
+            units.insert(*id, unit.clone());
+        }
 
         // Filter the team states.
         let mut team_states: FnvHashMap<Team, TeamInfo> = FnvHashMap::default();
@@ -358,22 +596,27 @@ impl GameWorld {
             research: old_team_state.research.clone(),
             units_in_space: old_team_state.units_in_space.clone(),
             karbonite: old_team_state.karbonite,
+            stats: old_team_state.stats.clone(),
+            timing: old_team_state.timing.clone(),
         };
         team_states.insert(team, new_team_state);
 
         // Planet state.
         let mut planet_states: FnvHashMap<Planet, PlanetInfo> = FnvHashMap::default();
         let planet_info = PlanetInfo {
-            visible_locs: visible_locs,
+            visible_locs: Arc::new(visible_locs),
             units: units,
             units_by_loc: units_by_loc,
-            karbonite: self.get_planet(planet).karbonite.clone(),
+            karbonite: Arc::new(karbonite),
         };
         planet_states.insert(planet, planet_info);
 
         GameWorld {
+            mode: WorldMode::Player,
             round: self.round,
             player_to_move: player,
+            time_pool_ms: self.time_pool_ms,
+            time_per_turn_ms: self.time_per_turn_ms,
             id_generator: self.id_generator.clone(),
             asteroids: self.asteroids.clone(),
             orbit: self.orbit.clone(),
@@ -381,7 +624,9 @@ impl GameWorld {
             planet_states: planet_states,
             team_states: team_states,
             cached_world: HashMap::default(),
+            vision: FnvHashMap::default(),
             viewer_changes: Vec::new(),
+            action_log: Vec::new(),
         }
     }
 
@@ -395,6 +640,17 @@ impl GameWorld {
         self.round
     }
 
+    /// Ok if the game has not passed the round limit. No actions can be
+    /// taken after the last round.
+    ///
+    /// * GameOver - the game is over.
+    fn ok_if_game_not_over(&self) -> Result<(), Error> {
+        if self.round > ROUND_LIMIT {
+            Err(GameError::GameOver)?;
+        }
+        Ok(())
+    }
+
     /// The current planet.
     pub fn planet(&self) -> Planet {
         self.player_to_move.planet
@@ -405,21 +661,104 @@ impl GameWorld {
         self.player_to_move.team
     }
 
+    /// The player whose turn it is.
+    pub fn player_to_move(&self) -> Player {
+        self.player_to_move
+    }
+
     /// The starting map of the given planet. Includes the map's planet,
     /// dimensions, impassable terrain, and initial units and karbonite.
     pub fn starting_map(&self, planet: Planet) -> &PlanetMap {
         if let Some(map) = self.planet_maps.get(&planet) {
             map
         } else {
-            unreachable!();
+            // Stays infallible: every world is built with both maps, filter()
+            // copies them all, and from_file() rejects snapshots without
+            // them. Players call this for every planet.
+            unreachable!("the world has no map of {:?}", planet);
         }
     }
 
+    /// The units the given team started the game with, on both planets,
+    /// sorted by ID. They are read from the starting maps, which are public,
+    /// so players can see the other team's starting positions too.
+    pub fn initial_units(&self, team: Team) -> Vec<Unit> {
+        let mut units: Vec<Unit> = [Planet::Earth, Planet::Mars].iter()
+            .flat_map(|planet| self.starting_map(*planet).initial_units.iter())
+            .filter(|unit| unit.team() == team)
+            .cloned()
+            .collect();
+        units.sort_by_key(|u| u.id());
+        units
+    }
+
     /// The karbonite in the team's resource pool.
     pub fn karbonite(&self) -> u32 {
         self.my_team().karbonite
     }
 
+    /// The running totals of the team's units and karbonite. A player can
+    /// only see the stats of their own team.
+    ///
+    /// * TeamNotAllowed - the team is not visible in this world.
+    pub fn team_stats(&self, team: Team) -> Result<TeamStats, Error> {
+        match self.team_states.get(&team) {
+            Some(team_info) => Ok(team_info.stats.clone()),
+            None => Err(GameError::TeamNotAllowed)?,
+        }
+    }
+
+    /// The time in each player's pool at the start of the game, in
+    /// milliseconds.
+    pub fn time_pool_ms(&self) -> i32 {
+        self.time_pool_ms
+    }
+
+    /// The time added to a player's pool after each of its turns, in
+    /// milliseconds.
+    pub fn time_per_turn_ms(&self) -> i32 {
+        self.time_per_turn_ms
+    }
+
+    /// The time left in the player's pool, in milliseconds. Pools are only
+    /// kept in the Teh Devs engine, and start at `time_pool_ms()`.
+    pub fn time_left(&self, player: Player) -> i32 {
+        self.timing(player).time_left_ms
+    }
+
+    /// The total time the player has used, in milliseconds.
+    pub fn time_used(&self, player: Player) -> i32 {
+        self.timing(player).time_used_ms
+    }
+
+    /// Whether the player has run out of time. Its turns are skipped for the
+    /// rest of the game, and its team loses.
+    pub fn is_timed_out(&self, player: Player) -> bool {
+        self.timing(player).timed_out
+    }
+
+    /// Takes the time the player spent on a turn out of its pool. The
+    /// manager calls this after receiving each turn, before applying it. If
+    /// the pool goes negative, the player times out, and the turn is
+    /// skipped along with the rest of its turns.
+    pub fn apply_time_used(&mut self, player: Player, time_used_ms: i32) {
+        debug_assert_eq!(self.mode, WorldMode::DevEngine);
+        let timing = self.timing_mut(player);
+        timing.time_left_ms -= time_used_ms;
+        timing.time_used_ms += time_used_ms;
+        if timing.time_left_ms < 0 {
+            timing.timed_out = true;
+        }
+    }
+
+    fn timing(&self, player: Player) -> &PlayerTiming {
+        &self.get_team(player.team).timing[&player.planet]
+    }
+
+    fn timing_mut(&mut self, player: Player) -> &mut PlayerTiming {
+        self.get_team_mut(player.team).timing.get_mut(&player.planet).unwrap()
+    }
+
     fn process_karbonite(&mut self, team: Team) {
         let karbonite_current: u32 = self.get_team(team).karbonite;
         let karbonite_lost: u32 = cmp::min(KARBONITE_PER_ROUND, karbonite_current / KARBONITE_DECREASE_RATIO);
@@ -431,60 +770,95 @@ impl GameWorld {
     // ************************************************************************
 
     /// The single unit with this ID. Use this method to get detailed
-    /// statistics on a unit: heat, cooldowns, and properties of special
-    /// abilities like units garrisoned in a rocket.
+    /// statistics on a unit on your team: heat, cooldowns, and properties of
+    /// special abilities like units garrisoned in a rocket.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team.
     pub fn unit_ref(&self, id: UnitID) -> Result<&Unit, Error> {
-        if let Some(unit) = self.my_planet().units.get(&id) {
-            Ok(unit)
-        } else if let Some(unit) = self.my_team().units_in_space.get(&id) {
-            Ok(unit)
-        } else {
-            Err(GameError::NoSuchUnit)?
-        }
+        self.my_unit(id)
     }
 
     /// The single unit with this ID. Use this method to get detailed
-    /// statistics on a unit: heat, cooldowns, and properties of special
-    /// abilities like units garrisoned in a rocket.
+    /// statistics on a unit on your team: heat, cooldowns, and properties of
+    /// special abilities like units garrisoned in a rocket.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    fn unit_mut(&mut self, id: UnitID) -> Result<&mut Unit, Error> {
-        if self.my_planet().units.contains_key(&id) {
-            Ok(self.my_planet_mut().units.get_mut(&id).unwrap())
-        } else if self.my_team().units_in_space.contains_key(&id) {
-            Ok(self.my_team_mut().units_in_space.get_mut(&id).unwrap())
-        } else {
-            Err(GameError::NoSuchUnit)?
-        }
+    /// * TeamNotAllowed - the unit is not on the current player's team.
+    pub fn unit(&self, id: UnitID) -> Result<Unit, Error> {
+        Ok(self.my_unit(id)?.clone())
     }
 
-    /// The single unit with this ID. Use this method to get detailed
-    /// statistics on a unit: heat, cooldowns, and properties of special
-    /// abilities like units garrisoned in a rocket.
+    /// The single unit with this ID, on either team, if it is within the
+    /// vision range or in space on the current player's team.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    pub fn unit(&self, id: UnitID) -> Result<Unit, Error> {
+    fn visible_unit(&self, id: UnitID) -> Result<&Unit, Error> {
         if let Some(unit) = self.my_planet().units.get(&id) {
-            Ok(unit.clone())
+            Ok(unit)
         } else if let Some(unit) = self.my_team().units_in_space.get(&id) {
-            Ok(unit.clone())
+            Ok(unit)
         } else {
             Err(GameError::NoSuchUnit)?
         }
     }
 
-    /// All the units within the vision range, in no particular order.
+    /// The single unit with this ID, on either team, if it is on the map
+    /// within the vision range. Used for the targets of actions.
+    ///
+    /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * UnitNotOnMap - the unit is in a garrison or in space.
+    fn visible_unit_on_map(&self, id: UnitID) -> Result<&Unit, Error> {
+        let unit = self.visible_unit(id)?;
+        unit.ok_if_on_map()?;
+        Ok(unit)
+    }
+
+    /// All the units within the vision range, sorted by ID.
     /// Does not include units in space.
     pub fn units_ref(&self) -> Vec<&Unit> {
-        self.my_planet().units.values().collect::<Vec<&Unit>>()
+        let mut units = self.my_planet().units.values().collect::<Vec<&Unit>>();
+        units.sort_by_key(|u| u.id());
+        units
     }
 
-    /// All the units within the vision range, in no particular order.
+    /// All the units within the vision range, sorted by ID.
     /// Does not include units in space.
     pub fn units(&self) -> Vec<Unit> {
-        self.my_planet().units.values().map(|u| u.clone()).collect()
+        self.units_ref().into_iter().cloned().collect()
+    }
+
+    /// All the units on the current team on the current planet, sorted by ID.
+    /// Includes units in garrisons, but not units in space.
+    pub fn my_units(&self) -> Vec<Unit> {
+        let team = self.team();
+        self.units_ref().into_iter()
+            .filter(|unit| unit.team() == team)
+            .cloned()
+            .collect()
+    }
+
+    /// All the units on the current team on the current planet with the
+    /// given type, sorted by ID. Includes units in garrisons, but not units
+    /// in space.
+    pub fn my_units_by_type(&self, unit_type: UnitType) -> Vec<Unit> {
+        let team = self.team();
+        self.units_ref().into_iter()
+            .filter(|unit| unit.team() == team && unit.unit_type() == unit_type)
+            .cloned()
+            .collect()
+    }
+
+    /// All the units on the given planet within the vision range, sorted by
+    /// ID. Does not include units in space. Players cannot see the other
+    /// planet, so it has no units in their worlds.
+    pub fn units_on_planet(&self, planet: Planet) -> Vec<Unit> {
+        let mut units: Vec<Unit> = match self.planet_states.get(&planet) {
+            Some(planet_info) => planet_info.units.values().cloned().collect(),
+            None => vec![],
+        };
+        units.sort_by_key(|u| u.id());
+        units
     }
 
     /// All the units within the vision range, by ID.
@@ -496,22 +870,27 @@ impl GameWorld {
     /// All the units within the vision range, by location.
     /// Does not include units in garrisons or in space.
     pub fn units_by_loc(&self) -> FnvHashMap<MapLocation, UnitID> {
-        self.my_planet().units_by_loc.clone()
+        self.my_planet().units_by_loc.iter().collect()
     }
 
-    /// All the units of this team that are in space. You cannot see units
-    /// on the other team that are in space.
+    /// All the units of this team that are in space, sorted by ID. Includes
+    /// both the rockets and the units garrisoned inside them. You cannot see
+    /// units on the other team that are in space.
     pub fn units_in_space(&self) -> Vec<Unit> {
-        self.my_team().units_in_space.values().map(|u| u.clone()).collect()
+        let mut units: Vec<Unit> = self.my_team().units_in_space.values().cloned().collect();
+        units.sort_by_key(|u| u.id());
+        units
     }
 
-    /// The karbonite at the given location.
+    /// The karbonite at the given location. The dev engine can sense the
+    /// karbonite at every location on the map.
     ///
     /// * LocationOffMap - the location is off the map.
     /// * LocationNotVisible - the location is outside the vision range.
     pub fn karbonite_at(&self, location: MapLocation) -> Result<u32, Error> {
         self.ok_if_can_sense_location(location)?;
-        Ok(self.my_planet().karbonite[location.y as usize][location.x as usize])
+        let (x, y) = self.starting_map(location.planet).index_of(location).unwrap();
+        Ok(self.my_planet().karbonite[y][x])
     }
 
     /// Returns an array of all locations within a certain radius squared of
@@ -521,72 +900,90 @@ impl GameWorld {
     /// y-coordinate. The radius squared is inclusive.
     pub fn all_locations_within(&self, location: MapLocation,
                                 radius_squared: u32) -> Vec<MapLocation> {
-        let mut locations = vec![];
         let map = self.starting_map(location.planet);
-
-        let radius = (radius_squared as f32).sqrt() as i32;
-        let min_x = cmp::max(location.x - radius, 0);
-        let max_x = cmp::min(location.x + radius, map.width as i32 - 1);
-        let min_y = cmp::max(location.y - radius, 0);
-        let max_y = cmp::min(location.y + radius, map.height as i32 - 1);
-
-        for x in min_x..max_x + 1 {
-            for y in min_y..max_y + 1 {
-                let loc = MapLocation::new(location.planet, x, y);
-                if location.distance_squared_to(loc) <= radius_squared {
-                    locations.push(loc);
-                }
-            }
-        }
-
-        locations
+        location.all_locations_within_clipped(radius_squared, map).collect()
     }
 
     /// * LocationOffMap - the location is off the map.
     /// * LocationNotVisible - the location is outside the vision range.
     fn ok_if_can_sense_location(&self, location: MapLocation) -> Result<(), Error> {
         if self.planet() != location.planet {
-            return Err(GameError::LocationOffMap)?;
-        }
-
-        if location.x < 0 || location.y < 0 {
-            return Err(GameError::LocationOffMap)?;
-        }
-
-        let map = self.starting_map(location.planet);
-        if location.x >= map.width as i32 || location.y >= map.height as i32 {
-            return Err(GameError::LocationOffMap)?;
+            return Err(GameError::LocationOffMap { location })?;
         }
+        let (x, y) = match self.starting_map(location.planet).index_of(location) {
+            Some(index) => index,
+            None => return Err(GameError::LocationOffMap { location })?,
+        };
 
-        let x = location.x as usize;
-        let y = location.y as usize;
-        if !self.my_planet().visible_locs[y][x] {
+        if !self.is_visible_square(x, y) {
             return Err(GameError::LocationNotVisible)?;
         }
         Ok(())
     }
 
+    /// Whether the square at the given index on the current planet is within
+    /// the vision range. An empty grid places no restriction on vision.
+    /// Otherwise, the grid covers the whole map, as in both the dev and the
+    /// filtered worlds.
+    fn is_visible_square(&self, x: usize, y: usize) -> bool {
+        let visible_locs = &self.my_planet().visible_locs;
+        visible_locs.is_empty() || visible_locs[y][x]
+    }
+
     /// Whether the location is on the map and within the vision range.
     pub fn can_sense_location(&self, location: MapLocation) -> bool {
-        self.ok_if_can_sense_location(location).is_ok()
+        // Bots call this often, so it avoids building an error.
+        if self.planet() != location.planet {
+            return false;
+        }
+        match self.starting_map(location.planet).index_of(location) {
+            Some((x, y)) => self.is_visible_square(x, y),
+            None => false,
+        }
     }
 
     /// Whether there is a unit with this ID within the vision range.
     pub fn can_sense_unit(&self, id: UnitID) -> bool {
-        self.unit(id).is_ok()
+        self.visible_unit(id).is_ok()
     }
 
     /// Sense units near the location within the given radius, inclusive, in
     /// distance squared. The units are within the vision range.
+    ///
+    /// The units are ordered first by the x-coordinate, then the y-coordinate
+    /// of their locations. The location itself does not need to be on the map.
     pub fn sense_nearby_units(&self, location: MapLocation, radius: u32)
                               -> Vec<Unit> {
-        let mut units: Vec<Unit> = vec![];
-        for nearby_loc in self.all_locations_within(location, radius) {
-            if let Some(id) = self.my_planet().units_by_loc.get(&nearby_loc) {
-                units.push(self.unit(*id).expect("unit exists").clone());
+        let planet_info = self.my_planet();
+
+        // Scan whichever is smaller: the squares in range, or the units.
+        let map = self.starting_map(location.planet);
+        let r = (radius as f32).sqrt() as i64;
+        let width = cmp::min(location.x as i64 + r, map.width as i64 - 1)
+                  - cmp::max(location.x as i64 - r, 0) + 1;
+        let height = cmp::min(location.y as i64 + r, map.height as i64 - 1)
+                   - cmp::max(location.y as i64 - r, 0) + 1;
+        let num_squares = cmp::max(width, 0) * cmp::max(height, 0);
+
+        let mut ids: Vec<(MapLocation, UnitID)> = vec![];
+        if num_squares <= planet_info.units_by_loc.len() as i64 {
+            for nearby_loc in location.all_locations_within_clipped(radius, map) {
+                if let Some(id) = planet_info.units_by_loc.get(&nearby_loc) {
+                    ids.push((nearby_loc, *id));
+                }
+            }
+        } else {
+            for (loc, id) in planet_info.units_by_loc.iter() {
+                if location.distance_squared_to(loc) <= radius {
+                    ids.push((loc, id));
+                }
             }
+            ids.sort_by_key(|&(loc, _)| (loc.x, loc.y));
         }
-        units
+
+        ids.into_iter()
+           .map(|(_, id)| planet_info.units[&id].clone())
+           .collect::<Vec<Unit>>()
     }
 
     /// Sense units near the location within the given radius, inclusive, in
@@ -617,7 +1014,7 @@ impl GameWorld {
                                   -> Result<Option<Unit>, Error> {
         self.ok_if_can_sense_location(location)?;
         let unit_id = self.my_planet().units_by_loc.get(&location);
-        Ok(unit_id.map(|id| self.unit(*id).expect("unit exists").clone()))
+        Ok(unit_id.map(|id| self.visible_unit(*id).expect("unit exists").clone()))
     }
 
     // ************************************************************************
@@ -626,12 +1023,12 @@ impl GameWorld {
 
     /// The asteroid strike pattern on Mars.
     pub fn asteroid_pattern(&self) -> AsteroidPattern {
-        self.asteroids.clone()
+        (*self.asteroids).clone()
     }
 
     /// The orbit pattern that determines a rocket's flight duration.
     pub fn orbit_pattern(&self) -> OrbitPattern {
-        self.orbit.clone()
+        (*self.orbit).clone()
     }
 
     /// The current duration of flight if a rocket were to be launched this
@@ -640,6 +1037,20 @@ impl GameWorld {
         self.orbit.duration(self.round)
     }
 
+    /// The round within the next `window` rounds, starting with the current
+    /// round, on which a rocket launched would have the shortest flight. The
+    /// earliest such round is chosen in case of a tie. Research on rockets
+    /// shortens every flight by the same amount, so it does not change the
+    /// best round.
+    pub fn best_launch_round_within(&self, window: Rounds) -> Rounds {
+        // Durations repeat every period, so a longer window can't do better.
+        let window = cmp::min(window, self.orbit.period.saturating_sub(1));
+        let end = self.round.saturating_add(window).saturating_add(1);
+        (self.round..end)
+            .min_by_key(|&round| (self.orbit.duration(round), round))
+            .unwrap_or(self.round)
+    }
+
     fn process_asteroids(&mut self) {
         if self.asteroids.has_asteroid(self.round) {
             let (location, karbonite) = {
@@ -647,20 +1058,38 @@ impl GameWorld {
                 (asteroid.location, asteroid.karbonite)
             };
             self.viewer_changes.push(ViewerDelta::AsteroidStrike { location, karbonite });
+            let (x, y) = match self.starting_map(location.planet).index_of(location) {
+                Some(index) => index,
+                None => return,
+            };
             if let Some(id) = self.get_planet(location.planet).units_by_loc.get(&location) {
                 if self.get_unit(*id).unwrap().unit_type().is_structure() {
                     return;
                 }
             }
 
-            let new_amount = {
-                let planet_info = self.get_planet_mut(location.planet);
-                planet_info.karbonite[location.y as usize][location.x as usize] += karbonite;
-                planet_info.karbonite[location.y as usize][location.x as usize]
-            };
+            self.deposit_karbonite(location.planet, x, y, karbonite);
+        }
+    }
+
+    /// Adds karbonite to the square at (x, y), up to MAX_KARBONITE_PER_SQUARE.
+    /// A square that already holds more than that, from the map, keeps what
+    /// it has. Impassable squares still take the deposit, so it can be
+    /// harvested by a worker next to the square. Any change is reported to
+    /// the viewer.
+    fn deposit_karbonite(&mut self, planet: Planet, x: usize, y: usize, karbonite: u32) {
+        let (old_amount, new_amount) = {
+            let planet_info = self.get_planet_mut(planet);
+            let square = &mut planet_info.karbonite_mut()[y][x];
+            let old_amount = *square;
+            *square = cmp::max(old_amount, cmp::min(old_amount.saturating_add(karbonite),
+                                                    MAX_KARBONITE_PER_SQUARE));
+            (old_amount, *square)
+        };
+        if new_amount != old_amount {
             self.viewer_changes.push(ViewerDelta::KarboniteChanged {
-                location: location,
-                new_amount: new_amount,
+                location: MapLocation::new(planet, x as i32, y as i32),
+                new_amount,
             });
         }
     }
@@ -698,7 +1127,10 @@ impl GameWorld {
         if let Some(planet_info) = self.planet_states.get(&planet) {
             planet_info
         } else {
-            unreachable!();
+            // Stays infallible: a full world holds both planets, filter()
+            // keeps the planet of the player it filters for, and from_file()
+            // rejects snapshots that break either rule.
+            unreachable!("the world has no state for its own planet, {:?}", planet);
         }
     }
 
@@ -707,7 +1139,10 @@ impl GameWorld {
         if let Some(planet_info) = self.planet_states.get_mut(&planet) {
             planet_info
         } else {
-            unreachable!();
+            // Stays infallible for the same reason as my_planet(). About
+            // eighty call sites would otherwise check a lookup that cannot
+            // fail.
+            unreachable!("the world has no state for its own planet, {:?}", planet);
         }
     }
 
@@ -716,7 +1151,10 @@ impl GameWorld {
         if let Some(team_info) = self.team_states.get(&team) {
             team_info
         } else {
-            unreachable!();
+            // Stays infallible: a full world holds both teams, filter()
+            // keeps the team of the player it filters for, and from_file()
+            // rejects snapshots that break either rule.
+            unreachable!("the world has no state for its own team, {:?}", team);
         }
     }
 
@@ -725,7 +1163,8 @@ impl GameWorld {
         if let Some(team_info) = self.team_states.get_mut(&team) {
             team_info
         } else {
-            unreachable!();
+            // Stays infallible for the same reason as my_team().
+            unreachable!("the world has no state for its own team, {:?}", team);
         }
     }
 
@@ -733,7 +1172,11 @@ impl GameWorld {
         if let Some(planet_info) = self.planet_states.get(&planet) {
             planet_info
         } else {
-            unreachable!();
+            // Stays infallible: a full world holds both planets. A player's
+            // world holds only its own, and callers only name another planet
+            // in the round processing, checksum() and the viewer, which the
+            // dev engine runs on the full world.
+            unreachable!("the world has no state for {:?}", planet);
         }
     }
 
@@ -741,7 +1184,9 @@ impl GameWorld {
         if let Some(planet_info) = self.planet_states.get_mut(&planet) {
             planet_info
         } else {
-            unreachable!();
+            // Stays infallible for the same reason as get_planet(). Only the
+            // dev engine mutates a planet other than the player's own.
+            unreachable!("the world has no state for {:?}", planet);
         }
     }
 
@@ -749,7 +1194,11 @@ impl GameWorld {
         if let Some(team_info) = self.team_states.get(&team) {
             team_info
         } else {
-            unreachable!();
+            // Stays infallible: a full world holds both teams. A player's
+            // world holds only its own, and callers only name the other team
+            // in the round processing and in the manager-only methods
+            // (winner(), time_left() and the like).
+            unreachable!("the world has no state for {:?}", team);
         }
     }
 
@@ -757,7 +1206,9 @@ impl GameWorld {
         if let Some(team_info) = self.team_states.get_mut(&team) {
             team_info
         } else {
-            unreachable!();
+            // Stays infallible for the same reason as get_team(). Only the
+            // dev engine mutates the other team's state.
+            unreachable!("the world has no state for {:?}", team);
         }
     }
 
@@ -765,13 +1216,16 @@ impl GameWorld {
     /// is the same as the current team.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the unit is not on the current player's team.
+    /// * TeamNotAllowed - the unit is not on the current player's team, or is
+    ///   on the other planet and is controlled by another player.
     fn my_unit(&self, id: UnitID) -> Result<&Unit, Error> {
         let unit = {
             if let Some(unit) = self.my_planet().units.get(&id) {
                 unit
             } else if let Some(unit) = self.my_team().units_in_space.get(&id) {
                 unit
+            } else if self.get_unit(id).is_ok() {
+                Err(GameError::TeamNotAllowed)?
             } else {
                 Err(GameError::NoSuchUnit)?
             }
@@ -783,6 +1237,19 @@ impl GameWorld {
         }
     }
 
+    /// Gets this unit from the current planet, checking that it is on the
+    /// current team and on the map. Every action checks the units it
+    /// involves this way, so units in a garrison or in space can't act.
+    ///
+    /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team.
+    /// * UnitNotOnMap - the unit is in a garrison or in space.
+    fn my_unit_on_map(&self, id: UnitID) -> Result<&Unit, Error> {
+        let unit = self.my_unit(id)?;
+        unit.ok_if_on_map()?;
+        Ok(unit)
+    }
+
     /// Gets a mutable version of this unit from space or the current planet.
     /// Checks that its team is the same as the current team.
     ///
@@ -802,95 +1269,192 @@ impl GameWorld {
         }
     }
 
-    /// Gets this unit from space or the current planet.
+    /// Gets this unit from space or from any planet in this world.
     ///
     /// * NoSuchUnit - the unit does not exist.
     fn get_unit(&self, id: UnitID) -> Result<&Unit, Error> {
-        if let Some(unit) = self.get_planet(Planet::Earth).units.get(&id) {
-            Ok(unit)
-        } else if let Some(unit) = self.get_planet(Planet::Mars).units.get(&id) {
-            Ok(unit)
-        } else if let Some(unit) = self.get_team(Team::Red).units_in_space.get(&id) {
-            Ok(unit)
-        } else if let Some(unit) = self.get_team(Team::Blue).units_in_space.get(&id) {
-            Ok(unit)
-        } else {
-            Err(GameError::NoSuchUnit)?
+        for planet_info in self.planet_states.values() {
+            if let Some(unit) = planet_info.units.get(&id) {
+                return Ok(unit);
+            }
+        }
+        for team_info in self.team_states.values() {
+            if let Some(unit) = team_info.units_in_space.get(&id) {
+                return Ok(unit);
+            }
         }
+        Err(GameError::NoSuchUnit)?
     }
 
-    /// Gets a mutable version of this unit from space or the current planet.
+    /// Gets a mutable version of this unit from space or from any planet in
+    /// this world.
     ///
     /// * NoSuchUnit - the unit does not exist.
     fn get_unit_mut(&mut self, id: UnitID) -> Result<&mut Unit, Error> {
-        if self.get_planet(Planet::Earth).units.contains_key(&id) {
-            Ok(self.get_planet_mut(Planet::Earth).units.get_mut(&id).expect("key exists"))
-        } else if self.get_planet(Planet::Mars).units.contains_key(&id) {
-            Ok(self.get_planet_mut(Planet::Mars).units.get_mut(&id).expect("key exists"))
-        } else if self.get_team(Team::Red).units_in_space.contains_key(&id) {
-            Ok(self.get_team_mut(Team::Red).units_in_space.get_mut(&id).expect("key exists"))
-        } else if self.get_team(Team::Blue).units_in_space.contains_key(&id) {
-            Ok(self.get_team_mut(Team::Blue).units_in_space.get_mut(&id).expect("key exists"))
-        } else {
-            Err(GameError::NoSuchUnit)?
+        for planet_info in self.planet_states.values_mut() {
+            if let Some(unit) = planet_info.units.get_mut(&id) {
+                return Ok(unit);
+            }
+        }
+        for team_info in self.team_states.values_mut() {
+            if let Some(unit) = team_info.units_in_space.get_mut(&id) {
+                return Ok(unit);
+            }
         }
+        Err(GameError::NoSuchUnit)?
     }
 
     // ************************************************************************
     // **************** UNIT CREATION / DESTRUCTION METHODS *******************
     // ************************************************************************
 
-    /// Places the unit in location-based indexing and/or marks the unit info.
-    /// Must be called after changing a unit's location within a planet.
-    fn place_unit(&mut self, id: UnitID) {
-        match self.my_unit(id)
-                  .expect("Unit does not exist and cannot be placed.")
-                  .location() {
-            OnMap(map_loc) => {
-                self.my_planet_mut().units_by_loc.insert(map_loc, id);
-            },
-            _ => panic!("Unit is not on a map and cannot be placed."),
+    /// The number of the player's units on the map that can see each square
+    /// of the player's planet, counted from scratch.
+    fn vision_counts(&self, player: Player) -> Vec<Vec<u32>> {
+        let map = self.starting_map(player.planet);
+        let mut counts = vec![vec![0; map.width]; map.height];
+        for unit in self.get_planet(player.planet).units.values() {
+            if unit.team() != player.team || !unit.has_vision() {
+                continue;
+            }
+            if let OnMap(loc) = unit.location() {
+                for loc in loc.all_locations_within_clipped(unit.vision_range(), map) {
+                    counts[loc.y as usize][loc.x as usize] += 1;
+                }
+            }
         }
+        counts
     }
 
-    /// Temporarily removes this unit from any location-based indexing.
-    /// Must be on the current planet and team before being removed.
-    fn remove_unit(&mut self, id: UnitID) {
-        match self.my_unit(id)
-                  .expect("Unit does not exist and cannot be removed.")
-                  .location() {
-            OnMap(loc) => {
-                self.my_planet_mut().units_by_loc.remove(&loc);
-            },
-            _ => panic!("Unit is not on a map and cannot be removed."),
+    /// Counts the vision of every player from scratch.
+    fn count_vision(&mut self) {
+        let mut vision = FnvHashMap::default();
+        for player in Player::all() {
+            vision.insert(player, Arc::new(self.vision_counts(player)));
         }
+        self.vision = vision;
     }
 
-    /// Moves this rocket and any location-based indexing to space. Must be
-    /// on the current planet and team. Also moves all the units inside it.
-    fn move_to_space(&mut self, rocket_id: UnitID) {
-        self.remove_unit(rocket_id);
+    /// Adds the vision of a unit on the map to its team's vision counts, or
+    /// takes it away if `add` is false. Does nothing if vision is not counted,
+    /// or if the unit is a blueprint.
+    fn update_vision(&mut self, id: UnitID, add: bool) {
+        let (player, location, vision_range) = {
+            let unit = self.get_unit(id).expect("unit exists");
+            if !unit.has_vision() {
+                return;
+            }
+            let location = unit.location().map_location().expect("unit is on the map");
+            (Player::new(unit.team(), location.planet), location, unit.vision_range())
+        };
+        if !self.vision.contains_key(&player) {
+            return;
+        }
+        let map = self.planet_maps[&player.planet].clone();
+        let counts = Arc::make_mut(self.vision.get_mut(&player).unwrap());
+        for loc in location.all_locations_within_clipped(vision_range, &map) {
+            let count = &mut counts[loc.y as usize][loc.x as usize];
+            if add {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+
+    /// Places the unit in location-based indexing and/or marks the unit info.
+    /// Must be called after changing a unit's location within a planet.
+    ///
+    /// * NoSuchUnit - the unit does not exist.
+    /// * InternalEngineError - the unit is not on the map.
+    fn place_unit(&mut self, id: UnitID) -> Result<(), Error> {
+        match self.my_unit(id)?.location() {
+            OnMap(map_loc) => {
+                self.my_planet_mut().units_by_loc.insert(map_loc, id);
+                self.update_vision(id, true);
+            },
+            _ => Err(GameError::InternalEngineError)?,
+        }
+        Ok(())
+    }
+
+    /// Temporarily removes this unit from any location-based indexing.
+    /// Must be on the current planet and team before being removed.
+    ///
+    /// * NoSuchUnit - the unit does not exist.
+    /// * InternalEngineError - the unit is not on the map.
+    fn remove_unit(&mut self, id: UnitID) -> Result<(), Error> {
+        match self.my_unit(id)?.location() {
+            OnMap(loc) => {
+                self.my_planet_mut().units_by_loc.remove(&loc);
+                self.update_vision(id, false);
+            },
+            _ => Err(GameError::InternalEngineError)?,
+        }
+        Ok(())
+    }
+
+    /// Moves this rocket and any location-based indexing to space. Must be
+    /// on the current planet and team. Also moves all the units inside it.
+    ///
+    /// * NoSuchUnit - the rocket does not exist.
+    /// * InappropriateUnitType - the unit is not a structure.
+    /// * InternalEngineError - the rocket is not on the map, or a unit in
+    ///   its garrison does not exist.
+    fn move_to_space(&mut self, rocket_id: UnitID) -> Result<(), Error> {
+        let garrison = self.my_unit(rocket_id)?.structure_garrison()?;
+        if !garrison.iter().all(|id| self.my_planet().units.contains_key(id)) {
+            Err(GameError::InternalEngineError)?;
+        }
+        self.remove_unit(rocket_id)?;
 
-        let rocket = self.my_planet_mut().units.remove(&rocket_id).expect("unit exists");
-        for id in rocket.structure_garrison().expect("unit is a rocket") {
-            let unit = self.my_planet_mut().units.remove(&id).expect("unit exists");
+        let rocket = self.my_planet_mut().units.remove(&rocket_id)
+                         .ok_or(GameError::InternalEngineError)?;
+        for id in garrison {
+            let unit = self.my_planet_mut().units.remove(&id)
+                           .ok_or(GameError::InternalEngineError)?;
             self.my_team_mut().units_in_space.insert(id, unit);
         }
         self.my_team_mut().units_in_space.insert(rocket_id, rocket);
+        Ok(())
     }
 
-    /// Moves this rocket and any location-based indexing from space to this
-    /// planet. Must currently be in space. Also move all the units inside it.
-    fn move_from_space(&mut self, rocket_id: UnitID) {
-        let rocket = self.my_team_mut().units_in_space.remove(&rocket_id).expect("unit exists");
+    /// Moves this rocket and any location-based indexing from space to the
+    /// planet it has landed on. Must currently be in the team's space, with
+    /// its location already set to the landing site. Also move all the units
+    /// inside it.
+    ///
+    /// * NoSuchUnit - the rocket does not exist.
+    /// * InappropriateUnitType - the unit is not a structure.
+    /// * InternalEngineError - the rocket is not in space, its location is
+    ///   not a landing site, or a unit in its garrison does not exist.
+    fn move_from_space(&mut self, rocket_id: UnitID) -> Result<(), Error> {
+        let (team, location, garrison) = {
+            let rocket = self.get_unit(rocket_id)?;
+            let location = rocket.location().map_location()
+                                 .map_err(|_| GameError::InternalEngineError)?;
+            (rocket.team(), location, rocket.structure_garrison()?)
+        };
+        let units_in_space = &self.get_team(team).units_in_space;
+        if !units_in_space.contains_key(&rocket_id)
+                || !garrison.iter().all(|id| units_in_space.contains_key(id))
+                || !self.starting_map(location.planet).on_map(location) {
+            Err(GameError::InternalEngineError)?;
+        }
+        let rocket = self.get_team_mut(team).units_in_space.remove(&rocket_id)
+                         .ok_or(GameError::InternalEngineError)?;
 
-        for id in rocket.structure_garrison().expect("unit is a rocket") {
-            let unit = self.my_team_mut().units_in_space.remove(&id).expect("unit exists");
-            self.my_planet_mut().units.insert(id, unit);
+        for id in garrison {
+            let unit = self.get_team_mut(team).units_in_space.remove(&id)
+                           .ok_or(GameError::InternalEngineError)?;
+            self.get_planet_mut(location.planet).units.insert(id, unit);
         }
 
-        self.my_planet_mut().units.insert(rocket_id, rocket);
-        self.place_unit(rocket_id);
+        let planet_info = self.get_planet_mut(location.planet);
+        planet_info.units.insert(rocket_id, rocket);
+        planet_info.units_by_loc.insert(location, rocket_id);
+        self.update_vision(rocket_id, true);
+        Ok(())
     }
 
     /// Inserts a new unit into the internal data structures of the game world,
@@ -898,58 +1462,128 @@ impl GameWorld {
     fn insert_unit(&mut self, unit: Unit) {
         let id = unit.id();
         let location = unit.location().map_location().expect("unit is on map");
+        self.record_value(unit.team(), unit.unit_type().value() as i64);
         self.get_planet_mut(location.planet).units.insert(id, unit);
         self.get_planet_mut(location.planet).units_by_loc.insert(location, id);
+        self.update_vision(id, true);
     }
 
     /// Creates and inserts a new unit into the game world, so that it can be
-    /// referenced by ID. Used for testing only!!!
+    /// referenced by ID. Blueprinting and replicating create units this way,
+    /// in player worlds too; otherwise, used for testing only!!!
     ///
+    /// * LocationOffMap - the location is off the map.
+    /// * LocationNotEmpty - the location is occupied by a unit.
+    /// * NoMoreUnitIDs - the game has run out of unit IDs.
     /// * ResearchLevelInvalid - the research level is invalid.
     pub(crate) fn create_unit(&mut self, team: Team, location: MapLocation,
                        unit_type: UnitType) -> Result<UnitID, Error> {
-        let id = self.id_generator.next_id();
+        if !self.starting_map(location.planet).on_map(location) {
+            Err(GameError::LocationOffMap { location })?;
+        }
+        if self.get_planet(location.planet).units_by_loc.contains_key(&location) {
+            Err(GameError::LocationNotEmpty { location })?;
+        }
+
+        // Only take the ID if the unit is successfully created.
+        let mut id_generator = self.id_generator.clone();
+        let id = id_generator.next_id()?;
         let level = self.get_team(team).research.get_level(&unit_type);
         let unit = Unit::new(id, team, unit_type, level, OnMap(location))?;
 
+        self.id_generator = id_generator;
         self.insert_unit(unit);
+        if let Some(team_info) = self.team_states.get_mut(&team) {
+            team_info.stats.units_produced += 1;
+        }
         Ok(id)
     }
 
-    /// Destroys a unit. Removes any traces of it.
+    /// Adds to the value of the team's living units. Does nothing for teams
+    /// filtered out of this world.
+    fn record_value(&mut self, team: Team, value: i64) {
+        if let Some(team_info) = self.team_states.get_mut(&team) {
+            team_info.stats.units_value = (team_info.stats.units_value as i64 + value) as u32;
+        }
+    }
+
+    /// Records the destruction of a unit in its team's stats.
+    fn record_destroyed(&mut self, team: Team, unit_type: UnitType, cause: Destruction) {
+        self.record_value(team, -(unit_type.value() as i64));
+        if let Some(team_info) = self.team_states.get_mut(&team) {
+            let stats = &mut team_info.stats;
+            match cause {
+                Destruction::Combat => stats.units_destroyed_in_combat += 1,
+                Destruction::Rocket => stats.units_destroyed_by_rockets += 1,
+                Destruction::Disintegration => stats.units_disintegrated += 1,
+                Destruction::Flood => stats.units_destroyed_by_flood += 1,
+            }
+        }
+    }
+
+    /// Destroys a unit. Removes any traces of it, and records its destruction
+    /// in its team's stats.
     ///
     /// If the unit is a rocket or factory, also destroys units in its garrison.
-    fn destroy_unit(&mut self, id: UnitID) {
-        match self.unit(id)
-                  .expect("Unit does not exist and cannot be destroyed.")
-                  .location() {
+    ///
+    /// * NoSuchUnit - the unit does not exist.
+    /// * InternalEngineError - the unit is in a garrison, or is in space but
+    ///   is not a rocket.
+    fn destroy_unit(&mut self, id: UnitID, cause: Destruction) -> Result<(), Error> {
+        let (location, unit_type, team, garrison) = {
+            let unit = self.get_unit(id)?;
+            let garrison = if unit.unit_type().is_structure() {
+                unit.structure_garrison()?
+            } else {
+                vec![]
+            };
+            (unit.location(), unit.unit_type(), unit.team(), garrison)
+        };
+        // Only rockets can be in space, and a garrisoned unit is destroyed
+        // through its structure. Check both before touching anything.
+        match location {
+            OnMap(_) => {},
+            InSpace if unit_type == UnitType::Rocket => {},
+            _ => Err(GameError::InternalEngineError)?,
+        }
+
+        self.record_destroyed(team, unit_type, cause);
+        self.viewer_changes.push(ViewerDelta::UnitDestroyed { unit_id: id, location, cause });
+        let planet = match location {
             OnMap(loc) => {
-                self.my_planet_mut().units_by_loc.remove(&loc);
+                self.get_planet_mut(loc.planet).units_by_loc.remove(&loc);
+                self.update_vision(id, false);
+                loc.planet
             },
-            InSpace => {
-                // Units only die in space after a landing on their turn.
-                // Thus we are guaranteed that my_unit() will find the unit.
-                for utd_id in self.my_unit(id).unwrap().structure_garrison()
-                                  .expect("only rockets can die in space") {
-                    self.my_team_mut().units_in_space.remove(&utd_id);
+            _ => {
+                // Destroy the rocket's passengers and cancel any landing it
+                // still has scheduled.
+                for utd_id in garrison {
+                    if let Some(unit) = self.get_team_mut(team).units_in_space.remove(&utd_id) {
+                        self.record_destroyed(team, unit.unit_type(), cause);
+                        self.viewer_changes.push(ViewerDelta::UnitDestroyed {
+                            unit_id: utd_id, location: unit.location(), cause,
+                        });
+                    }
                 }
-                self.my_team_mut().units_in_space.remove(&id);
-                return;
+                self.get_team_mut(team).units_in_space.remove(&id);
+                self.get_team_mut(team).rocket_landings.remove_landings_of(id);
+                return Ok(());
             },
-            _ => panic!("Unit is in ???, this should not be possible"),
         };
 
         // If this unit's garrison is visible, destroy those units too.
-        let unit_type = self.unit(id).unwrap().unit_type();
-        if unit_type == UnitType::Rocket || unit_type == UnitType::Factory {
-            let units_to_destroy = self.unit_mut(id).unwrap()
-                                       .structure_garrison().unwrap();
-            for utd_id in units_to_destroy.iter() {
-                self.my_planet_mut().units.remove(&utd_id);
+        for utd_id in garrison {
+            if let Some(unit) = self.get_planet_mut(planet).units.remove(&utd_id) {
+                self.record_destroyed(unit.team(), unit.unit_type(), cause);
+                self.viewer_changes.push(ViewerDelta::UnitDestroyed {
+                    unit_id: utd_id, location: unit.location(), cause,
+                });
             }
         }
 
-        self.my_planet_mut().units.remove(&id);
+        self.get_planet_mut(planet).units.remove(&id);
+        Ok(())
     }
 
     /// Disintegrates the unit and removes it from the map. If the unit is a
@@ -957,10 +1591,12 @@ impl GameWorld {
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - the unit is not on the current player's team.
+    /// * UnitNotOnMap - the unit is in a garrison.
     pub fn disintegrate_unit(&mut self, id: UnitID) -> Result<(), Error> {
-        self.my_unit(id)?;
-        self.destroy_unit(id);
-        Ok(())
+        if let InGarrison(_) = self.my_unit(id)?.location() {
+            Err(GameError::UnitNotOnMap)?;
+        }
+        self.destroy_unit(id, Destruction::Disintegration)
     }
 
     // ************************************************************************
@@ -968,31 +1604,36 @@ impl GameWorld {
     // ************************************************************************
 
     /// Whether the location is clear for a unit to occupy, either by movement
-    /// or by construction.
+    /// or by construction. Locations off the map, or on the other planet, are
+    /// never clear.
     ///
-    /// * LocationOffMap - the location is off the map.
     /// * LocationNotVisible - the location is outside the vision range.
     pub fn is_occupiable(&self, location: MapLocation) -> Result<bool, Error> {
+        if location.planet != self.planet() || !self.starting_map(location.planet).on_map(location) {
+            return Ok(false);
+        }
         self.ok_if_can_sense_location(location)?;
 
         let planet_map = &self.starting_map(location.planet);
-        Ok(planet_map.is_passable_terrain_at(location).unwrap() &&
+        Ok(planet_map.is_passable_terrain_at(location)? &&
             !self.my_planet().units_by_loc.contains_key(&location))
     }
 
     /// * NoSuchUnit - the robot does not exist (within the vision range).
     /// * TeamNotAllowed - the robot is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a robot.
     /// * UnitNotOnMap - the robot is not on the map.
     /// * LocationNotVisible - the location is outside the vision range.
     /// * LocationOffMap - the location is off the map.
     /// * LocationNotEmpty - the location is occupied by a unit or terrain.
     fn ok_if_can_move(&self, robot_id: UnitID, direction: Direction) -> Result<(), Error> {
-        let unit = self.my_unit(robot_id)?;
+        let unit = self.my_unit_on_map(robot_id)?;
+        unit.ok_if_robot()?;
         let new_location = unit.location().map_location()?.add(direction);
 
         self.ok_if_can_sense_location(new_location)?;
-        if !self.is_occupiable(new_location).unwrap() {
-            Err(GameError::LocationNotEmpty)?;
+        if !self.is_occupiable(new_location)? {
+            Err(GameError::LocationNotEmpty { location: new_location })?;
         }
         Ok(())
     }
@@ -1004,6 +1645,32 @@ impl GameWorld {
         self.ok_if_can_move(robot_id, direction).is_ok()
     }
 
+    /// Whether the robot can move in the given direction now, or after
+    /// cooling down for some rounds, or is blocked by the edge of the map,
+    /// terrain, or another unit. The robot can move now if and only if
+    /// `can_move()` and `is_move_ready()` are both true.
+    ///
+    /// * NoSuchUnit - the robot does not exist (within the vision range).
+    /// * TeamNotAllowed - the robot is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a robot.
+    /// * UnitNotOnMap - the robot is not on the map.
+    /// * LocationNotVisible - the location is outside the vision range.
+    pub fn move_status(&self, robot_id: UnitID, direction: Direction) -> Result<MoveStatus, Error> {
+        let unit = self.my_unit_on_map(robot_id)?;
+        unit.ok_if_robot()?;
+        let new_location = unit.location().map_location()?.add(direction);
+        if !self.starting_map(new_location.planet).on_map(new_location) {
+            return Ok(MoveStatus::OffMap(new_location));
+        }
+        if !self.is_occupiable(new_location)? {
+            return Ok(MoveStatus::Blocked(new_location));
+        }
+        Ok(match unit.rounds_until_move_ready()? {
+            0 => MoveStatus::Ready,
+            rounds => MoveStatus::OnCooldown(rounds),
+        })
+    }
+
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - the unit is not on the current player's team.
     /// * Overheated - the robot is not ready to move again.
@@ -1012,7 +1679,7 @@ impl GameWorld {
         Ok(())
     }
 
-    /// Whether the robot is ready to move. Tests whether the robot's attack
+    /// Whether the robot is ready to move. Tests whether the robot's movement
     /// heat is sufficiently low.
     pub fn is_move_ready(&self, robot_id: UnitID) -> bool {
         self.ok_if_move_ready(robot_id).is_ok()
@@ -1022,6 +1689,7 @@ impl GameWorld {
     ///
     /// * NoSuchUnit - the robot does not exist (within the vision range).
     /// * TeamNotAllowed - the robot is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a robot.
     /// * UnitNotOnMap - the robot is not on the map.
     /// * LocationNotVisible - the location is outside the vision range.
     /// * LocationOffMap - the location is off the map.
@@ -1034,55 +1702,89 @@ impl GameWorld {
             OnMap(loc) => loc.add(direction),
             _ => unreachable!(),
         };
-        self.move_to(robot_id, dest);
-        Ok(())
+        self.move_to(robot_id, dest)
     }
 
-    fn move_to(&mut self, robot_id: UnitID, location: MapLocation) {
-        self.remove_unit(robot_id);
-        self.my_unit_mut(robot_id).unwrap().move_to(location);
-        self.place_unit(robot_id);
+    pub(crate) fn move_to(&mut self, robot_id: UnitID, location: MapLocation) -> Result<(), Error> {
+        self.remove_unit(robot_id)?;
+        self.my_unit_mut(robot_id)?.move_to(location);
+        self.place_unit(robot_id)
     }
 
     // ************************************************************************
     // *************************** ATTACK METHODS *****************************
     // ************************************************************************
 
-    fn damage_unit(&mut self, unit_id: UnitID, damage: i32) {
-        let should_destroy_unit = {
-            let unit = self.unit_mut(unit_id).unwrap();
-            unit.take_damage(damage)
+    /// Deals damage to the unit, or heals it if the damage is negative, and
+    /// destroys it if its health drops to zero.
+    fn damage_unit(&mut self, unit_id: UnitID, damage: i32, cause: Destruction)
+                   -> Result<(), Error> {
+        let (should_destroy_unit, old_health, new_health) = {
+            let unit = self.get_unit_mut(unit_id)?;
+            let old_health = unit.health();
+            let should_destroy_unit = unit.take_damage(damage);
+            (should_destroy_unit, old_health, unit.health())
         };
+        if new_health < old_health {
+            self.viewer_changes.push(ViewerDelta::UnitDamaged {
+                unit_id, damage: old_health - new_health,
+            });
+        } else if new_health > old_health {
+            self.viewer_changes.push(ViewerDelta::UnitHealed {
+                unit_id, amount: new_health - old_health,
+            });
+        }
         if should_destroy_unit {
-            self.destroy_unit(unit_id);
+            self.destroy_unit(unit_id, cause)?;
         }
+        Ok(())
     }
 
     /// Deals damage to any unit in the target square, potentially destroying it.
-    fn damage_location(&mut self, location: MapLocation, damage: i32) {
-        let id = if let Some(id) = self.my_planet().units_by_loc.get(&location) {
-            *id
-        } else {
-            return;
+    /// The square may be on either planet.
+    fn damage_location(&mut self, location: MapLocation, damage: i32, cause: Destruction)
+                       -> Result<(), Error> {
+        let id = match self.planet_states.get(&location.planet)
+                           .and_then(|planet_info| planet_info.units_by_loc.get(&location)) {
+            Some(id) => *id,
+            None => return Ok(()),
         };
 
-        self.damage_unit(id, damage)
+        self.damage_unit(id, damage, cause)
+    }
+
+    /// Deals damage to each of the eight squares adjacent to the location,
+    /// exactly once. The unit at the location itself is not damaged, nor
+    /// are any units garrisoned inside it.
+    fn damage_adjacent(&mut self, location: MapLocation, damage: i32) -> Result<(), Error> {
+        for dir in Direction::all() {
+            self.damage_location(location.add(dir), damage, Destruction::Rocket)?;
+        }
+        Ok(())
     }
 
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the unit is not on the current player's team.
-    /// * InappropriateUnitType - the unit is not a robot, or is a healer.
+    /// * TeamNotAllowed - the unit is not on the current player's team, or
+    ///   the target is.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
     /// * UnitNotOnMap - the unit or target is not on the map.
     /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
     fn ok_if_can_attack(&self, robot_id: UnitID, target_id: UnitID) -> Result<(), Error> {
-        if self.my_unit(robot_id)?.unit_type() == UnitType::Healer {
+        let robot = self.my_unit(robot_id)?;
+        robot.ok_if_robot()?;
+        if robot.unit_type() == UnitType::Worker || robot.unit_type() == UnitType::Healer {
             Err(GameError::InappropriateUnitType)?;
         }
-        self.my_unit(robot_id)?.ok_if_on_map()?;
-        self.unit(target_id)?.ok_if_on_map()?;
-
-        let target_loc = self.unit(target_id).unwrap().location();
-        self.my_unit(robot_id).unwrap().ok_if_within_attack_range(target_loc)?;
+        let robot = self.my_unit_on_map(robot_id)?;
+        let target = self.visible_unit_on_map(target_id)?;
+        // Direct attacks can't target friendly units.
+        if target.team() == self.team() {
+            Err(GameError::TeamNotAllowed)?;
+        }
+        robot.ok_if_within_attack_range(target.location())?;
         Ok(())
     }
 
@@ -1090,17 +1792,37 @@ impl GameWorld {
     /// account the robot's attack heat. Takes into account only the robot's
     /// attack range, and the location of the robot and target.
     ///
-    /// Healers cannot attack, and should use `can_heal()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `can_heal()` instead.
     pub fn can_attack(&self, robot_id: UnitID, target_id: UnitID) -> bool {
-        self.ok_if_can_attack(robot_id, target_id).is_ok()
+        self.attack_status(robot_id, target_id).is_ok()
+    }
+
+    /// Ok if the robot can attack the given unit, and otherwise the reason
+    /// it cannot. Agrees with `can_attack()`, so it does not take into
+    /// account the robot's attack heat either.
+    ///
+    /// * NoSuchUnit - the unit does not exist (inside the vision range).
+    /// * TeamNotAllowed - the unit is not on the current player's team, or
+    ///   the target is.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
+    /// * UnitNotOnMap - the unit or target is not on the map.
+    /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
+    pub fn attack_status(&self, robot_id: UnitID, target_id: UnitID) -> Result<(), Error> {
+        self.ok_if_can_attack(robot_id, target_id)
     }
 
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - the unit is not on the current player's team.
-    /// * InappropriateUnitType - the unit is not a robot, or is a healer.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
     /// * Overheated - the unit is not ready to attack.
     fn ok_if_attack_ready(&self, robot_id: UnitID) -> Result<(), Error> {
-        if self.my_unit(robot_id)?.unit_type() == UnitType::Healer {
+        let unit_type = self.my_unit(robot_id)?.unit_type();
+        if unit_type == UnitType::Worker || unit_type == UnitType::Healer {
             Err(GameError::InappropriateUnitType)?;
         }
         self.my_unit(robot_id)?.ok_if_attack_ready()?;
@@ -1110,7 +1832,8 @@ impl GameWorld {
     /// Whether the robot is ready to attack. Tests whether the robot's attack
     /// heat is sufficiently low.
     ///
-    /// Healers cannot attack, and should use `is_heal_ready()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `is_heal_ready()` instead.
     pub fn is_attack_ready(&self, robot_id: UnitID) -> bool {
         self.ok_if_attack_ready(robot_id).is_ok()
     }
@@ -1118,26 +1841,30 @@ impl GameWorld {
     /// Commands a robot to attack a unit, dealing the 
     /// robot's standard amount of damage.
     ///
-    /// Healers cannot attack, and should use `heal()` instead.
+    /// Workers and healers cannot attack. Healers should use
+    /// `heal()` instead.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the unit is not on the current player's team.
-    /// * InappropriateUnitType - the unit is not a robot, or is a healer.
+    /// * TeamNotAllowed - the unit is not on the current player's team, or
+    ///   the target is.
+    /// * InappropriateUnitType - the unit is not a robot, or is a worker or
+    ///   healer.
     /// * UnitNotOnMap - the unit or target is not on the map.
     /// * OutOfRange - the target location is not in range.
+    /// * TooClose - the target location is within a ranger's cannot attack
+    ///   range.
     /// * Overheated - the unit is not ready to attack.
     pub fn attack(&mut self, robot_id: UnitID, target_id: UnitID) -> Result<(), Error> {
         self.ok_if_can_attack(robot_id, target_id)?;
         self.ok_if_attack_ready(robot_id)?;
         let damage = self.my_unit_mut(robot_id).unwrap().use_attack();
         if self.my_unit(robot_id).unwrap().unit_type() == UnitType::Mage {
-            let epicenter = self.unit(target_id).unwrap().location().map_location().unwrap();
+            let epicenter = self.visible_unit(target_id).unwrap().location().map_location().unwrap();
             for direction in Direction::all().iter() {
-                self.damage_location(epicenter.add(*direction), damage);
+                self.damage_location(epicenter.add(*direction), damage, Destruction::Combat)?;
             }
         }
-        self.damage_unit(target_id, damage);
-        Ok(())
+        self.damage_unit(target_id, damage, Destruction::Combat)
     }
 
     // ************************************************************************
@@ -1160,6 +1887,24 @@ impl GameWorld {
         self.my_research()
     }
 
+    /// The branch currently being researched by the current team, or None if
+    /// the research queue is empty.
+    pub fn current_research(&self) -> Option<Branch> {
+        self.my_team().research.next_in_queue().ok()
+    }
+
+    /// The number of rounds left until the current team's current research
+    /// is completed, or None if the research queue is empty.
+    pub fn research_rounds_left(&self) -> Option<Rounds> {
+        self.my_team().research.rounds_left().ok()
+    }
+
+    /// The research queue of the current team, where the branch currently
+    /// being researched is at the beginning of the list.
+    pub fn research_queue(&self) -> Vec<Branch> {
+        self.my_team().research.queue()
+    }
+
     /// Resets the research queue to be empty. Returns true if the queue was
     /// not empty before, and false otherwise.
     pub fn reset_research(&mut self) -> bool {
@@ -1177,14 +1922,25 @@ impl GameWorld {
     /// Update the current research and process any completed upgrades.
     fn process_research(&mut self, team: Team) {
         if let Some(branch) = self.get_team_mut(team).research.end_round() {
-            for (_, unit) in self.get_planet_mut(Planet::Earth).units.iter_mut() {
-                if unit.unit_type() == branch && unit.team() == team {
-                    unit.research().expect("research level is valid");
+            // Research can change a unit's vision range, so units on the map
+            // are removed from the vision counts and added back afterwards.
+            let mut ids = vec![];
+            for planet in [Planet::Earth, Planet::Mars].iter() {
+                for (&id, unit) in self.get_planet(*planet).units.iter() {
+                    if unit.unit_type() == branch && unit.team() == team {
+                        ids.push(id);
+                    }
                 }
             }
-            for (_, unit) in self.get_planet_mut(Planet::Mars).units.iter_mut() {
-                if unit.unit_type() == branch && unit.team() == team {
-                    unit.research().expect("research level is valid");
+            for id in ids {
+                let on_map = self.get_unit(id).expect("unit exists").location().is_on_map();
+                if on_map {
+                    self.update_vision(id, false);
+                }
+                self.get_unit_mut(id).expect("unit exists")
+                    .research().expect("research level is valid");
+                if on_map {
+                    self.update_vision(id, true);
                 }
             }
             for (_, unit) in self.get_team_mut(team).units_in_space.iter_mut() {
@@ -1200,8 +1956,18 @@ impl GameWorld {
     // *************************** WORKER METHODS *****************************
     // ************************************************************************
 
+    /// Whether your worker has already acted (harvested, blueprinted, built, or
+    /// repaired) this round. Moving and replicating are not worker actions.
+    ///
+    /// * NoSuchUnit - the worker does not exist (inside the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker.
+    pub fn worker_has_acted(&self, worker_id: UnitID) -> Result<bool, Error> {
+        self.my_unit(worker_id)?.worker_has_acted()
+    }
+
     fn ok_if_can_harvest(&self, worker_id: UnitID, direction: Direction) -> Result<(), Error> {
-        let unit = self.my_unit(worker_id)?;
+        let unit = self.my_unit_on_map(worker_id)?;
         unit.ok_if_can_worker_act()?;
         let harvest_loc = unit.location().map_location()?.add(direction);
         // Check to see if we can sense the harvest location, (e.g. it is on the map).
@@ -1215,7 +1981,22 @@ impl GameWorld {
     /// karbonite to harvest. The worker cannot already have performed an action 
     /// this round.
     pub fn can_harvest(&self, worker_id: UnitID, direction: Direction) -> bool {
-        self.ok_if_can_harvest(worker_id, direction).is_ok()
+        self.harvest_status(worker_id, direction).is_ok()
+    }
+
+    /// Ok if the worker can harvest in the given direction, and otherwise
+    /// the reason it cannot. Agrees with `can_harvest()`.
+    ///
+    /// * NoSuchUnit - the worker does not exist (within the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker.
+    /// * Overheated - the worker has already performed an action this turn.
+    /// * UnitNotOnMap - the worker is not on the map.
+    /// * LocationOffMap - the location in the target direction is off the map.
+    /// * LocationNotVisible - the location is not in the vision range.
+    /// * KarboniteDepositEmpty - the location described contains no Karbonite.
+    pub fn harvest_status(&self, worker_id: UnitID, direction: Direction) -> Result<(), Error> {
+        self.ok_if_can_harvest(worker_id, direction)
     }
 
     /// Harvests up to the worker's harvest amount of karbonite from the given
@@ -1240,7 +2021,9 @@ impl GameWorld {
         };
         let amount_mined = cmp::min(self.karbonite_at(harvest_loc).unwrap(), harvest_amount);
         self.my_team_mut().karbonite += amount_mined;
-        self.my_planet_mut().karbonite[harvest_loc.y as usize][harvest_loc.x as usize] -= amount_mined;
+        self.my_team_mut().stats.karbonite_mined += amount_mined;
+        let (x, y) = self.starting_map(harvest_loc.planet).index_of(harvest_loc).unwrap();
+        self.my_planet_mut().karbonite_mut()[y][x] -= amount_mined;
         let new_amount = self.karbonite_at(harvest_loc).unwrap();
         self.viewer_changes.push(ViewerDelta::KarboniteChanged {
             location: harvest_loc,
@@ -1251,7 +2034,7 @@ impl GameWorld {
 
     fn ok_if_can_blueprint(&self, worker_id: UnitID, unit_type: UnitType,
                          direction: Direction) -> Result<(), Error> {
-        let unit = self.my_unit(worker_id)?;
+        let unit = self.my_unit_on_map(worker_id)?;
         // Players should never attempt to build a non-structure.
         if !unit_type.is_structure() {
             Err(GameError::InappropriateUnitType)?;
@@ -1259,8 +2042,9 @@ impl GameWorld {
         unit.ok_if_can_worker_act()?;
         let build_loc = unit.location().map_location()?.add(direction);
         // The build location must be unoccupied, and we must be able to sense it.
+        self.ok_if_can_sense_location(build_loc)?;
         if !self.is_occupiable(build_loc)? {
-            Err(GameError::LocationNotEmpty)?;
+            Err(GameError::LocationNotEmpty { location: build_loc })?;
         }
         // Structures can never be built on Mars.
         if build_loc.planet == Planet::Mars {
@@ -1283,7 +2067,30 @@ impl GameWorld {
     /// pool. The worker cannot already have performed an action this round.
     pub fn can_blueprint(&self, worker_id: UnitID, unit_type: UnitType,
                          direction: Direction) -> bool {
-        self.ok_if_can_blueprint(worker_id, unit_type, direction).is_ok()
+        self.blueprint_status(worker_id, unit_type, direction).is_ok()
+    }
+
+    /// Ok if the worker can blueprint a structure of the given type in the
+    /// given direction, and otherwise the reason it cannot. Agrees with
+    /// `can_blueprint()`.
+    ///
+    /// * NoSuchUnit - the worker does not exist (within the vision range).
+    /// * TeamNotAllowed - the worker is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a worker, or the unit type
+    ///   is not a structure.
+    /// * Overheated - the worker has already performed an action this turn.
+    /// * UnitNotOnMap - the unit is not on the map.
+    /// * LocationOffMap - the location in the target direction is off the map.
+    /// * LocationNotVisible - the location is outside the vision range.
+    /// * LocationNotEmpty - the location in the target direction is already
+    ///   occupied.
+    /// * CannotBuildOnMars - you cannot blueprint a structure on Mars.
+    /// * ResearchNotUnlocked - you do not have the needed research to blueprint rockets.
+    /// * InsufficientKarbonite - your team does not have enough Karbonite to
+    ///   build the requested structure.
+    pub fn blueprint_status(&self, worker_id: UnitID, unit_type: UnitType,
+                            direction: Direction) -> Result<(), Error> {
+        self.ok_if_can_blueprint(worker_id, unit_type, direction)
     }
 
     /// Blueprints a unit of the given type in the given direction. Subtract
@@ -1307,24 +2114,21 @@ impl GameWorld {
     pub fn blueprint(&mut self, worker_id: UnitID, unit_type: UnitType,
                      direction: Direction) -> Result<(), Error> {
         self.ok_if_can_blueprint(worker_id, unit_type, direction)?;
-        let build_loc = {
-            let worker = self.my_unit_mut(worker_id).unwrap();
-            worker.worker_act();
-            worker.location().map_location().unwrap().add(direction)
-        };
+        let build_loc = self.my_unit(worker_id).unwrap()
+            .location().map_location().unwrap().add(direction);
         let team = self.team();
-        self.create_unit(team, build_loc, unit_type).unwrap();
+        self.create_unit(team, build_loc, unit_type)?;
+        self.my_unit_mut(worker_id).unwrap().worker_act();
         self.my_team_mut().karbonite -= unit_type.blueprint_cost().unwrap();
-        self.my_planet_mut().karbonite[build_loc.y as usize][build_loc.x as usize] = 0;
+        let (x, y) = self.starting_map(build_loc.planet).index_of(build_loc).unwrap();
+        self.my_planet_mut().karbonite_mut()[y][x] = 0;
         Ok(())
     }
 
     fn ok_if_can_build(&self, worker_id: UnitID, blueprint_id: UnitID)
                        -> Result<(), Error> {
-        let worker = self.my_unit(worker_id)?;
-        let blueprint = self.my_unit(blueprint_id)?;
-        // The worker must be on the map.
-        worker.ok_if_on_map()?;
+        let worker = self.my_unit_on_map(worker_id)?;
+        let blueprint = self.my_unit_on_map(blueprint_id)?;
         // The worker must be able to act.
         worker.ok_if_can_worker_act()?;
         // The worker must be adjacent to the blueprint.
@@ -1363,14 +2167,15 @@ impl GameWorld {
             worker.worker_act();
             worker.worker_build_health().unwrap()
         };
-        self.my_unit_mut(blueprint_id).unwrap().be_built(build_health);
+        if self.my_unit_mut(blueprint_id).unwrap().be_built(build_health) {
+            self.update_vision(blueprint_id, true);
+        }
         Ok(())
     }
 
     fn ok_if_can_repair(&self, worker_id: UnitID, structure_id: UnitID) -> Result<(), Error> {
-        let worker = self.my_unit(worker_id)?;
-        let structure = self.my_unit(structure_id)?;
-        worker.ok_if_on_map()?;
+        let worker = self.my_unit_on_map(worker_id)?;
+        let structure = self.my_unit_on_map(structure_id)?;
         worker.ok_if_can_worker_act()?;
         if !worker.location().is_adjacent_to(structure.location()) {
             Err(GameError::OutOfRange)?;
@@ -1378,12 +2183,15 @@ impl GameWorld {
         if !structure.structure_is_built()? {
             Err(GameError::StructureNotYetBuilt)?;
         }
+        if structure.health() == structure.max_health() {
+            Err(GameError::StructureAtMaxHealth)?;
+        }
         Ok(())
     }
 
     /// Whether the given worker can repair the given strucutre. Tests that the worker
-    /// is able to execute a worker action, that the structure is built, and that the
-    /// structure is within range.
+    /// is able to execute a worker action, that the structure is built and
+    /// damaged, and that the structure is within range.
     pub fn can_repair(&self, worker_id: UnitID, structure_id: UnitID) -> bool {
         self.ok_if_can_repair(worker_id, structure_id).is_ok()
     }
@@ -1399,6 +2207,7 @@ impl GameWorld {
     /// * Overheated - the worker has already performed an action this turn.
     /// * OutOfRange - the worker is not adjacent to the structure.
     /// * StructureNotYetBuilt - the structure has not been completed.
+    /// * StructureAtMaxHealth - the structure is not damaged.
     pub fn repair(&mut self, worker_id: UnitID, structure_id: UnitID) -> Result<(), Error> {
         self.ok_if_can_repair(worker_id, structure_id)?;
         self.my_unit_mut(worker_id).unwrap().worker_act();
@@ -1410,14 +2219,15 @@ impl GameWorld {
 
     fn ok_if_can_replicate(&self, worker_id: UnitID, direction: Direction) 
                            -> Result<(), Error> {
-        let worker = self.my_unit(worker_id)?;
+        let worker = self.my_unit_on_map(worker_id)?;
         worker.ok_if_ability_ready()?;
         if self.karbonite() < worker.unit_type().replicate_cost()? {
             Err(GameError::InsufficientKarbonite)?;
         }
         let replicate_loc = worker.location().map_location()?.add(direction);
+        self.ok_if_can_sense_location(replicate_loc)?;
         if !self.is_occupiable(replicate_loc)? {
-            Err(GameError::LocationNotEmpty)?;
+            Err(GameError::LocationNotEmpty { location: replicate_loc })?;
         }
         Ok(())
     }
@@ -1431,7 +2241,8 @@ impl GameWorld {
     }
 
     /// Replicates a worker in the given direction. Subtracts the cost of the
-    /// worker from the team's resource pool.
+    /// worker from the team's resource pool. The new worker cannot act until
+    /// the next round.
     ///
     /// * NoSuchUnit - the worker does not exist (within the vision range).
     /// * TeamNotAllowed - the worker is not on the current player's team.
@@ -1447,14 +2258,14 @@ impl GameWorld {
     pub fn replicate(&mut self, worker_id: UnitID, direction: Direction)
                      -> Result<(), Error> {
         self.ok_if_can_replicate(worker_id, direction)?;
-        self.my_unit_mut(worker_id)?.worker_act();
-        self.my_unit_mut(worker_id)?.replicate();
         let (team, location) = {
-            let worker = self.my_unit(worker_id)?;
-            (worker.team(), worker.location().map_location()?.add(direction))
+            let worker = self.my_unit(worker_id).unwrap();
+            (worker.team(), worker.location().map_location().unwrap().add(direction))
         };
-        self.create_unit(team, location, UnitType::Worker)?;
-        self.my_team_mut().karbonite -= UnitType::Worker.replicate_cost()?;
+        let child_id = self.create_unit(team, location, UnitType::Worker)?;
+        self.my_unit_mut(child_id).unwrap().arrive_on_map();
+        self.my_unit_mut(worker_id).unwrap().replicate();
+        self.my_team_mut().karbonite -= UnitType::Worker.replicate_cost().unwrap();
         Ok(())
     }
 
@@ -1463,9 +2274,11 @@ impl GameWorld {
     // ************************************************************************
 
     fn ok_if_can_javelin(&self, knight_id: UnitID, target_id: UnitID) -> Result<(), Error> {
-        let knight = self.my_unit(knight_id)?;
-        let target = self.unit(target_id)?;
-        knight.ok_if_on_map()?;
+        let knight = self.my_unit_on_map(knight_id)?;
+        let target = self.visible_unit_on_map(target_id)?;
+        if target.team() == knight.team() {
+            Err(GameError::TeamNotAllowed)?;
+        }
         knight.ok_if_javelin_unlocked()?;
         knight.ok_if_within_ability_range(target.location())?;
         Ok(())
@@ -1494,8 +2307,9 @@ impl GameWorld {
     /// Javelins the robot, dealing the knight's standard damage.
     ///
     /// * NoSuchUnit - either unit does not exist (inside the vision range).
-    /// * TeamNotAllowed - the knight is not on the current player's team.
-    /// * UnitNotOnMap - the knight is not on the map.
+    /// * TeamNotAllowed - the knight is not on the current player's team, or
+    ///   the target is.
+    /// * UnitNotOnMap - the knight or target is not on the map.
     /// * InappropriateUnitType - the unit is not a knight.
     /// * ResearchNotUnlocked - you do not have the needed research to use javelin.
     /// * OutOfRange - the target does not lie within ability range of the knight.
@@ -1504,8 +2318,7 @@ impl GameWorld {
         self.ok_if_can_javelin(knight_id, target_id)?;
         self.ok_if_javelin_ready(knight_id)?;
         let damage = self.my_unit_mut(knight_id).unwrap().javelin();
-        self.damage_unit(target_id, damage);
-        Ok(())
+        self.damage_unit(target_id, damage, Destruction::Combat)
     }
 
     // ************************************************************************
@@ -1513,12 +2326,11 @@ impl GameWorld {
     // ************************************************************************
 
     fn ok_if_can_begin_snipe(&self, ranger_id: UnitID, location: MapLocation) -> Result<(), Error> {
-        let ranger = self.my_unit(ranger_id)?;
-        ranger.ok_if_on_map()?;
+        let ranger = self.my_unit_on_map(ranger_id)?;
         ranger.ok_if_snipe_unlocked()?;
         let planet = self.planet();
         if !self.starting_map(planet).on_map(location) {
-            Err(GameError::LocationOffMap)?
+            Err(GameError::LocationOffMap { location })?
         }
         Ok(())
     }
@@ -1574,7 +2386,12 @@ impl GameWorld {
             let target_location = self.get_planet_mut(planet).units.get_mut(&id).unwrap().process_snipe();
             if target_location.is_some() {
                 let damage = self.get_planet(planet).units.get(&id).unwrap().damage().unwrap();
-                self.damage_location(target_location.unwrap(), damage);
+                // Like a landing, a snipe the engine cannot resolve is
+                // dropped rather than taking the game down.
+                if self.damage_location(target_location.unwrap(), damage, Destruction::Combat)
+                       .is_err() {
+                    continue;
+                }
                 self.viewer_changes.push(ViewerDelta::RangerSnipe { 
                     ranger_id: id, 
                     target_location: target_location.unwrap(), 
@@ -1588,12 +2405,12 @@ impl GameWorld {
     // ************************************************************************
     
     fn ok_if_can_blink(&self, mage_id: UnitID, location: MapLocation) -> Result<(), Error> {
-        let mage = self.my_unit(mage_id)?;
-        mage.ok_if_on_map()?;
+        let mage = self.my_unit_on_map(mage_id)?;
         mage.ok_if_blink_unlocked()?;
         mage.ok_if_within_ability_range(OnMap(location))?;
+        self.ok_if_can_sense_location(location)?;
         if !self.is_occupiable(location)? {
-            Err(GameError::LocationNotEmpty)?;
+            Err(GameError::LocationNotEmpty { location })?;
         }
         Ok(())
     }
@@ -1634,10 +2451,9 @@ impl GameWorld {
     pub fn blink(&mut self, mage_id: UnitID, location: MapLocation) -> Result<(), Error> {
         self.ok_if_can_blink(mage_id, location)?;
         self.ok_if_blink_ready(mage_id)?;
-        self.remove_unit(mage_id);
-        self.my_unit_mut(mage_id).unwrap().blink(location);
-        self.place_unit(mage_id);
-        Ok(())
+        self.remove_unit(mage_id)?;
+        self.my_unit_mut(mage_id)?.blink(location);
+        self.place_unit(mage_id)
     }
 
     // ************************************************************************
@@ -1645,14 +2461,11 @@ impl GameWorld {
     // ************************************************************************
 
     fn ok_if_can_heal(&self, healer_id: UnitID, robot_id: UnitID) -> Result<(), Error> {
-        self.my_unit(healer_id)?.ok_if_on_map()?;
-
-        let target_loc = self.my_unit(robot_id)?.location();
-        if !target_loc.is_on_map() {
-            Err(GameError::UnitNotOnMap)?;
-        }
-        self.my_unit(healer_id).unwrap().ok_if_within_attack_range(target_loc)?;
-        self.my_unit(robot_id).unwrap().ok_if_robot()?;
+        self.my_unit(healer_id)?.ok_if_unit_type(UnitType::Healer)?;
+        let healer = self.my_unit_on_map(healer_id)?;
+        let robot = self.my_unit_on_map(robot_id)?;
+        healer.ok_if_within_attack_range(robot.location())?;
+        robot.ok_if_robot()?;
         Ok(())
     }
 
@@ -1664,6 +2477,7 @@ impl GameWorld {
     }
 
     fn ok_if_heal_ready(&self, healer_id: UnitID) -> Result<(), Error> {
+        self.my_unit(healer_id)?.ok_if_unit_type(UnitType::Healer)?;
         Ok(self.my_unit(healer_id)?.ok_if_attack_ready()?)
     }
 
@@ -1679,22 +2493,20 @@ impl GameWorld {
     /// * InappropriateUnitType - the unit is not a healer, or the target is not
     ///   a robot.
     /// * TeamNotAllowed - either robot is not on the current player's team.
-    /// * UnitNotOnMap - the healer is not on the map.
+    /// * UnitNotOnMap - either unit is not on the map.
     /// * OutOfRange - the target does not lie within "attack" range of the healer.
     /// * Overheated - the healer is not ready to heal again.
     pub fn heal(&mut self, healer_id: UnitID, robot_id: UnitID) -> Result<(), Error> {
         self.ok_if_can_heal(healer_id, robot_id)?;
         self.ok_if_heal_ready(healer_id)?;
         let damage = self.my_unit_mut(healer_id).unwrap().use_attack();
-        self.damage_unit(robot_id, damage);
-        Ok(())
+        self.damage_unit(robot_id, damage, Destruction::Combat)
     }
 
     fn ok_if_can_overcharge(&self, healer_id: UnitID, robot_id: UnitID)
                             -> Result<(), Error> {
-        let healer = self.my_unit(healer_id)?;
-        let robot = self.my_unit(robot_id)?;
-        healer.ok_if_on_map()?;
+        let healer = self.my_unit_on_map(healer_id)?;
+        let robot = self.my_unit_on_map(robot_id)?;
         robot.ok_if_can_be_overcharged()?;
         healer.ok_if_overcharge_unlocked()?;
         healer.ok_if_within_ability_range(robot.location())?;
@@ -1722,11 +2534,12 @@ impl GameWorld {
     }
 
     /// Overcharges the robot, resetting the robot's cooldowns. The robot must
-    /// be on the same team as you.
+    /// be on the same team as you. Overcharging a sniping ranger cancels its
+    /// snipe.
     ///
     /// * NoSuchUnit - either unit does not exist (inside the vision range).
     /// * TeamNotAllowed - either robot is not on the current player's team.
-    /// * UnitNotOnMap - the healer is not on the map.
+    /// * UnitNotOnMap - either unit is not on the map.
     /// * InappropriateUnitType - the unit is not a healer, or the target is not
     ///   a robot.
     /// * ResearchNotUnlocked - you do not have the needed research to use overcharge.
@@ -1736,8 +2549,8 @@ impl GameWorld {
                       -> Result<(), Error> {
         self.ok_if_can_overcharge(healer_id, robot_id)?;
         self.ok_if_overcharge_ready(healer_id)?;
-        self.my_unit_mut(healer_id)?.overcharge();
-        self.my_unit_mut(robot_id)?.be_overcharged();
+        self.my_unit_mut(healer_id).unwrap().overcharge();
+        self.my_unit_mut(robot_id).unwrap().be_overcharged();
         Ok(())
     }
 
@@ -1745,12 +2558,22 @@ impl GameWorld {
     // ************************* STRUCTURE METHODS ****************************
     // ************************************************************************
 
+    /// The robots in the garrison of your structure, in the order they will be
+    /// unloaded.
+    ///
+    /// * NoSuchUnit - the structure does not exist (inside the vision range).
+    /// * TeamNotAllowed - the structure is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a structure.
+    pub fn structure_garrison(&self, structure_id: UnitID) -> Result<Vec<UnitID>, Error> {
+        self.my_unit(structure_id)?.structure_garrison()
+    }
+
     fn ok_if_can_load(&self, structure_id: UnitID, robot_id: UnitID)
                       -> Result<(), Error> {
         let robot = self.my_unit(robot_id)?;
-        let structure = self.my_unit(structure_id)?;
-        robot.ok_if_on_map()?;
-        structure.ok_if_on_map()?;
+        robot.ok_if_robot()?;
+        let robot = self.my_unit_on_map(robot_id)?;
+        let structure = self.my_unit_on_map(structure_id)?;
         robot.ok_if_move_ready()?;
         structure.ok_if_can_load()?;
         if !structure.location().is_adjacent_to(robot.location()) {
@@ -1780,7 +2603,7 @@ impl GameWorld {
     pub fn load(&mut self, structure_id: UnitID, robot_id: UnitID)
                     -> Result<(), Error> {
         self.ok_if_can_load(structure_id, robot_id)?;
-        self.remove_unit(robot_id);
+        self.remove_unit(robot_id)?;
         self.my_unit_mut(structure_id).unwrap().load(robot_id);
         self.my_unit_mut(robot_id).unwrap().board_rocket(structure_id);
         Ok(())
@@ -1788,13 +2611,13 @@ impl GameWorld {
 
     fn ok_if_can_unload(&self, structure_id: UnitID, direction: Direction)
                         -> Result<(), Error> {
-        let structure = self.my_unit(structure_id)?;
-        structure.ok_if_on_map()?;
+        let structure = self.my_unit_on_map(structure_id)?;
         structure.ok_if_can_unload_unit()?;
         let robot = self.my_unit(structure.structure_garrison()?[0])?;
         let loc = structure.location().map_location()?.add(direction);
+        self.ok_if_can_sense_location(loc)?;
         if !self.is_occupiable(loc)? {
-            Err(GameError::LocationNotEmpty)?;
+            Err(GameError::LocationNotEmpty { location: loc })?;
         }
         robot.ok_if_move_ready()?;
         Ok(())
@@ -1808,7 +2631,8 @@ impl GameWorld {
     }
 
     /// Unloads a robot from the garrison of the specified structure into an 
-    /// adjacent space. Robots are unloaded in the order they were loaded.
+    /// adjacent space. Robots are unloaded in the order they were loaded, and
+    /// cannot act again until the next round.
     ///
     /// * NoSuchUnit - the unit does not exist (inside the vision range).
     /// * TeamNotAllowed - either unit is not on the current player's team.
@@ -1823,23 +2647,41 @@ impl GameWorld {
     pub fn unload(&mut self, structure_id: UnitID, direction: Direction)
                   -> Result<(), Error> {
         self.ok_if_can_unload(structure_id, direction)?;
-        let (robot_id, structure_loc) = {
-            let structure = self.my_unit_mut(structure_id)?;
-            (structure.unload_unit(), structure.location().map_location()?)
-        };
+        let structure_loc = self.my_unit(structure_id).unwrap().location().map_location().unwrap();
+        let robot_id = self.my_unit_mut(structure_id).unwrap().unload_unit();
         let robot_loc = structure_loc.add(direction);
         self.my_unit_mut(robot_id)?.move_to(robot_loc);
-        self.place_unit(robot_id);
-        Ok(())
+        self.my_unit_mut(robot_id)?.arrive_on_map();
+        self.place_unit(robot_id)
     }
 
     // ************************************************************************
     // ************************** FACTORY METHODS *****************************
     // ************************************************************************
 
+    /// The type of robot your factory is producing.
+    ///
+    /// * NoSuchUnit - the factory does not exist (inside the vision range).
+    /// * TeamNotAllowed - the factory is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a factory.
+    /// * NullValue - the factory is not producing.
+    pub fn factory_unit_type(&self, factory_id: UnitID) -> Result<UnitType, Error> {
+        self.my_unit(factory_id)?.factory_unit_type()
+    }
+
+    /// The number of rounds left until your factory produces its robot.
+    ///
+    /// * NoSuchUnit - the factory does not exist (inside the vision range).
+    /// * TeamNotAllowed - the factory is not on the current player's team.
+    /// * InappropriateUnitType - the unit is not a factory.
+    /// * NullValue - the factory is not producing.
+    pub fn factory_rounds_left(&self, factory_id: UnitID) -> Result<Rounds, Error> {
+        self.my_unit(factory_id)?.factory_rounds_left()
+    }
+
     fn ok_if_can_produce_robot(&self, factory_id: UnitID, robot_type: UnitType)
                                  -> Result<(), Error> {
-        let factory = self.my_unit(factory_id)?;
+        let factory = self.my_unit_on_map(factory_id)?;
         factory.ok_if_can_produce_robot(robot_type)?;
         let cost = robot_type.factory_cost().expect("unit type is ok");
         if self.karbonite() < cost {
@@ -1886,6 +2728,9 @@ impl GameWorld {
                 factory_ids.push(unit.id());
             }
         }
+        // Factories take new unit IDs in order of ID, so that production
+        // does not depend on the order the units happen to be stored in.
+        factory_ids.sort();
 
         for factory_id in factory_ids {
             let (unit_type, team) = {
@@ -1896,15 +2741,27 @@ impl GameWorld {
                 }
                 (new_unit_type.unwrap(), factory.team())
             };
+            // If the game has run out of IDs, the robot is never produced,
+            // and the team gets back the karbonite it spent.
+            let id = match self.id_generator.next_id() {
+                Ok(id) => id,
+                Err(_) => {
+                    let cost = unit_type.factory_cost().expect("unit type is ok");
+                    self.get_team_mut(team).karbonite += cost;
+                    self.viewer_changes.push(ViewerDelta::ProductionRefunded { factory_id, unit_type, karbonite: cost });
+                    continue;
+                }
+            };
             self.viewer_changes.push(ViewerDelta::ProductionDone { factory_id, unit_type});
 
-            let id = self.id_generator.next_id();
             let level = self.get_team(team).research.get_level(&unit_type);
             let new_unit = Unit::new(id, team, unit_type, level, InGarrison(factory_id))
                 .expect("research_level is valid");
 
             self.get_planet_mut(planet).units.insert(id, new_unit);
             self.get_planet_mut(planet).units.get_mut(&factory_id).unwrap().load(id);
+            self.record_value(team, unit_type.value() as i64);
+            self.get_team_mut(team).stats.units_produced += 1;
         }
     }
 
@@ -1918,96 +2775,169 @@ impl GameWorld {
         self.my_team().rocket_landings.clone()
     }
 
+    /// The landings of the current team's rockets in the rounds [start, end),
+    /// ordered by round and then by rocket ID.
+    pub fn landings_between(&self, start: Rounds, end: Rounds) -> Vec<(Rounds, RocketLanding)> {
+        self.my_team().rocket_landings.landings_between(start, end)
+    }
+
+    /// The rockets in space that belong to the current team, grouped by the
+    /// round they are scheduled to land. Includes the units garrisoned in each
+    /// rocket. Landings of rockets that no longer exist are skipped.
+    pub fn rockets_in_space(&self) -> FnvHashMap<Rounds, Vec<Unit>> {
+        let mut rockets: FnvHashMap<Rounds, Vec<Unit>> = FnvHashMap::default();
+        for (round, landing) in self.my_team().rocket_landings.all() {
+            if let Some(rocket) = self.my_team().units_in_space.get(&landing.rocket_id) {
+                rockets.entry(round).or_insert_with(Vec::new).push(rocket.clone());
+            }
+        }
+        rockets
+    }
+
     fn ok_if_can_launch_rocket(&self, rocket_id: UnitID, destination: MapLocation)
                                -> Result<(), Error> {
-        let rocket = self.my_unit(rocket_id)?;
+        let rocket = self.my_unit_on_map(rocket_id)?;
         if destination.planet == self.planet() {
             Err(GameError::SamePlanet)?;
         }
         rocket.ok_if_can_launch_rocket()?;
         let map = &self.starting_map(destination.planet);
-        if !map.on_map(destination) {
-            Err(GameError::LocationOffMap)?;
-        }
-        if !map.is_passable_terrain_at(destination)? {
-            Err(GameError::LocationNotEmpty)?;
+        if map.on_map(destination) && !map.is_passable_terrain_at(destination)? {
+            Err(GameError::LocationNotEmpty { location: destination })?;
         }
         Ok(())
     }
 
     /// Whether the rocket can launch into space to the given destination. The
     /// rocket can launch if the it has never been used before. The destination
-    /// is valid if it contains passable terrain on the other planet.
+    /// is valid if it contains passable terrain on the other planet, or if it
+    /// is off the other planet's map.
     pub fn can_launch_rocket(&self, rocket_id: UnitID, destination: MapLocation)
                              -> bool {
-        self.ok_if_can_launch_rocket(rocket_id, destination).is_ok()
+        self.launch_rocket_status(rocket_id, destination).is_ok()
+    }
+
+    /// Ok if the rocket can launch to the given destination, and otherwise
+    /// the reason it cannot. Agrees with `can_launch_rocket()`.
+    ///
+    /// * NoSuchUnit - the rocket does not exist (inside the vision range).
+    /// * TeamNotAllowed - the rocket is not on the current player's team.
+    /// * UnitNotOnMap - the rocket is not on the map.
+    /// * SamePlanet - the rocket cannot fly to a location on the same planet.
+    /// * InappropriateUnitType - the unit is not a rocket.
+    /// * StructureNotYetBuilt - the rocket has not yet been completed.
+    /// * RocketUsed - the rocket has already been used.
+    /// * LocationNotEmpty - the given location contains impassable terrain.
+    pub fn launch_rocket_status(&self, rocket_id: UnitID, destination: MapLocation)
+                                -> Result<(), Error> {
+        self.ok_if_can_launch_rocket(rocket_id, destination)
     }
 
     /// Launches the rocket into space, damaging the units adjacent to the
-    /// takeoff location.
+    /// takeoff location. If the destination is not on the map of the other
+    /// planet, the rocket flies off, never to be seen again: the rocket and
+    /// its garrison are destroyed.
     ///
     /// * NoSuchUnit - the rocket does not exist (inside the vision range).
     /// * TeamNotAllowed - the rocket is not on the current player's team.
+    /// * UnitNotOnMap - the rocket is not on the map.
     /// * SamePlanet - the rocket cannot fly to a location on the same planet.
     /// * InappropriateUnitType - the unit is not a rocket.
     /// * StructureNotYetBuilt - the rocket has not yet been completed.
     /// * RocketUsed - the rocket has already been used.
-    /// * LocationOffMap - the given location is off the map.
     /// * LocationNotEmpty - the given location contains impassable terrain.
     pub fn launch_rocket(&mut self, rocket_id: UnitID, destination: MapLocation)
                          -> Result<(), Error> {
         self.ok_if_can_launch_rocket(rocket_id, destination)?;
-        let takeoff_loc = self.my_unit(rocket_id)?.location().map_location()?;
-        let blast_damage = self.my_unit(rocket_id)?.rocket_blast_damage()?;
-        for dir in Direction::all() {
-            self.damage_location(takeoff_loc.add(dir), blast_damage);
-        }
-        self.move_to_space(rocket_id);
-        self.my_unit_mut(rocket_id)?.launch_rocket();
+        let takeoff_loc = self.my_unit(rocket_id).unwrap().location().map_location().unwrap();
+        let blast_damage = self.my_unit(rocket_id).unwrap().rocket_blast_damage().unwrap();
 
-        let landing_round = self.round + self.orbit.duration(self.round)
-            + self.my_unit(rocket_id)?.rocket_travel_time_decrease().unwrap();
-        self.my_team_mut().rocket_landings.add_landing(
-            landing_round, RocketLanding::new(rocket_id, destination)
-        );
+        // Everything that can fail is done before the blast, so that a
+        // failed launch leaves the units around the rocket unharmed.
+        if self.starting_map(destination.planet).on_map(destination) {
+            self.move_to_space(rocket_id)?;
+            self.my_unit_mut(rocket_id).unwrap().launch_rocket();
+
+            let landing_round = self.orbit.landing_round(self.round)
+                - self.my_unit(rocket_id).unwrap().rocket_travel_time_decrease().unwrap();
+            self.my_team_mut().rocket_landings.add_landing(
+                landing_round, RocketLanding::new(rocket_id, destination)
+            );
+            self.viewer_changes.push(ViewerDelta::RocketLaunch { rocket_id, destination });
+        } else {
+            self.viewer_changes.push(ViewerDelta::RocketLaunch { rocket_id, destination });
+            self.destroy_unit(rocket_id, Destruction::Rocket)?;
+        }
+        self.damage_adjacent(takeoff_loc, blast_damage)?;
         Ok(())
     }
 
     /// Lands the rocket, damaging the units in adjacent squares. The rocket
-    /// is destroyed if it lands on a factory, rocket, or impassable terrain.
+    /// is destroyed if it lands on a factory, rocket, or impassable terrain,
+    /// or on Earth after the apocalypse.
     ///
     /// Also resets the amount of karbonite at that location if the rocket
     /// successfully lands.
-    fn land_rocket(&mut self, rocket_id: UnitID, destination: MapLocation) {
-        let blast_damage = self.my_unit(rocket_id).unwrap().rocket_blast_damage().unwrap();
-        if self.my_planet().units_by_loc.contains_key(&destination) {
-            let victim_id = *self.my_planet().units_by_loc.get(&destination).unwrap();
-            let should_destroy_rocket = match self.unit(victim_id).unwrap().unit_type() {
-                UnitType::Rocket => true,
-                UnitType::Factory => true,
-                _ => false,
-            };
-            if should_destroy_rocket {
-                self.destroy_unit(rocket_id);
-            } else {
-                self.my_planet_mut().karbonite[destination.y as usize][destination.x as usize] = 0;
+    ///
+    /// * NoSuchUnit - the rocket does not exist.
+    /// * InappropriateUnitType - the unit is not a rocket.
+    /// * InternalEngineError - the rocket is not in space, or the
+    ///   destination is off the map.
+    fn land_rocket(&mut self, rocket_id: UnitID, destination: MapLocation)
+                   -> Result<(), Error> {
+        let planet = destination.planet;
+        let blast_damage = self.get_unit(rocket_id)?.rocket_blast_damage()?;
+        let (x, y) = self.starting_map(planet).index_of(destination)
+                         .ok_or(GameError::InternalEngineError)?;
+        if planet == Planet::Earth && self.round >= APOCALYPSE_ROUND {
+            self.destroy_unit(rocket_id, Destruction::Flood)?;
+            return Ok(());
+        }
+
+        // A rocket landing on a unit destroys it. Landing on a structure
+        // destroys the rocket too, but a rocket that lands on a robot takes
+        // its square.
+        if let Some(&victim_id) = self.get_planet(planet).units_by_loc.get(&destination) {
+            let victim_is_structure = self.get_unit(victim_id)?.unit_type().is_structure();
+            self.destroy_unit(victim_id, Destruction::Rocket)?;
+            if victim_is_structure {
+                self.destroy_unit(rocket_id, Destruction::Rocket)?;
+                self.damage_adjacent(destination, blast_damage)?;
+                return Ok(());
             }
-            self.destroy_unit(victim_id);
-        } else {
-            self.my_unit_mut(rocket_id).unwrap().land_rocket(destination);
-            self.move_from_space(rocket_id);
-            self.my_planet_mut().karbonite[destination.y as usize][destination.x as usize] = 0;
         }
 
-        for dir in Direction::all() {
-            self.damage_location(destination.add(dir), blast_damage);
+        self.get_unit_mut(rocket_id)?.land_rocket(destination);
+        if let Err(e) = self.move_from_space(rocket_id) {
+            self.get_unit_mut(rocket_id)?.launch_rocket();
+            return Err(e);
         }
+        let team = self.get_unit(rocket_id)?.team();
+        self.get_team_mut(team).rocket_landings.remove_landings_of(rocket_id);
+        self.get_planet_mut(planet).karbonite_mut()[y][x] = 0;
+        self.damage_adjacent(destination, blast_damage)?;
+        Ok(())
     }
 
+    /// Lands the team's rockets scheduled for this round, in order of rocket
+    /// ID, so that rockets landing on the same square resolve the same way
+    /// regardless of the order they were launched.
+    ///
+    /// Each landing is consumed as it is processed, so a landing can never
+    /// outlive its rocket.
     fn process_rockets(&mut self, team: Team) {
-        let landings = self.get_team(team).rocket_landings.landings_on(self.round);
+        let round = self.round;
+        let landings = self.get_team_mut(team).rocket_landings.take_landings_on(round);
         for landing in landings.iter() {
-            self.land_rocket(landing.rocket_id, landing.destination);
+            // Skip the landing if the rocket was destroyed before it landed.
+            if !self.get_team(team).units_in_space.contains_key(&landing.rocket_id) {
+                continue;
+            }
+            // A landing the engine cannot resolve leaves the rocket in space
+            // rather than taking the game down.
+            if self.land_rocket(landing.rocket_id, landing.destination).is_err() {
+                continue;
+            }
             self.viewer_changes.push(ViewerDelta::RocketLanding { 
                 rocket_id: landing.rocket_id, 
                 location: landing.destination 
@@ -2035,9 +2965,10 @@ impl GameWorld {
         }
 
         StartTurnMessage {
+            schema_version: SCHEMA_VERSION,
             time_left_ms,
             round: world.round,
-            visible_locs: world.my_planet().visible_locs.clone(),
+            visible_locs: (*world.my_planet().visible_locs).clone(),
             units_changed: vec![],
             units_vanished: vec![],
             karbonite_changed: vec![],
@@ -2048,6 +2979,7 @@ impl GameWorld {
             rocket_landings: world.my_team().rocket_landings.clone(),
             research: world.my_team().research.clone(),
             karbonite: world.my_team().karbonite,
+            stats: world.my_team().stats.clone(),
         }
     }
 
@@ -2059,6 +2991,14 @@ impl GameWorld {
         use self::Team::*;
         use self::Planet::*;
 
+        self.check_integrity();
+
+        let time_per_turn_ms = self.time_per_turn_ms;
+        let timing = self.timing_mut(self.player_to_move);
+        if !timing.timed_out {
+            timing.time_left_ms += time_per_turn_ms;
+        }
+
         self.player_to_move = match self.player_to_move {
             Player { team: Red, planet: Earth } => Player::new(Blue, Earth),
             Player { team: Blue, planet: Earth } => Player::new(Red, Mars),
@@ -2071,7 +3011,7 @@ impl GameWorld {
         };
 
         // Land rockets.
-        if self.planet() == Mars {
+        if self.planet() == Mars && self.ok_if_game_not_over().is_ok() {
             let team = self.team();
             self.process_rockets(team);
         }
@@ -2079,9 +3019,10 @@ impl GameWorld {
         let player = self.player_to_move;
         let world = self.filter(player);
         let mut stm = StartTurnMessage {
+            schema_version: SCHEMA_VERSION,
             time_left_ms,
             round: world.round,
-            visible_locs: world.my_planet().visible_locs.clone(),
+            visible_locs: (*world.my_planet().visible_locs).clone(),
             units_changed: vec![],
             units_vanished: vec![],
             karbonite_changed: vec![],
@@ -2092,6 +3033,7 @@ impl GameWorld {
             rocket_landings: world.my_team().rocket_landings.clone(),
             research: world.my_team().research.clone(),
             karbonite: world.my_team().karbonite,
+            stats: world.my_team().stats.clone(),
         };
         {
             let old_world = self.cached_world.get(&player).unwrap();
@@ -2117,6 +3059,10 @@ impl GameWorld {
                     stm.units_in_space_vanished.push(*id);
                 }
             }
+            stm.units_changed.sort_by_key(|unit| unit.id());
+            stm.units_vanished.sort();
+            stm.units_in_space_changed.sort_by_key(|unit| unit.id());
+            stm.units_in_space_vanished.sort();
             let old_array = old_world.get_team_array(player.planet.other());
             let new_array = world.get_team_array(player.planet.other());
             for index in 0..COMMUNICATION_ARRAY_LENGTH {
@@ -2135,20 +3081,50 @@ impl GameWorld {
                 }
             }
         }
-        self.cached_world.insert(player, world);
+        self.cached_world.insert(player, Arc::new(world));
 
         stm
     }
 
     fn end_round(&mut self) {
+        debug_assert_eq!(self.mode, WorldMode::DevEngine, "players cannot end the round");
+        // Nothing changes after the last round.
+        if self.ok_if_game_not_over().is_err() {
+            return;
+        }
         self.round += 1;
+        if self.ok_if_game_not_over().is_err() {
+            return;
+        }
 
         // Annihilate Earth, if necessary.
         if self.round == APOCALYPSE_ROUND {
             // Destroy all units by clearing Earth's unit data structures.
-            let earth = self.get_planet_mut(Planet::Earth);
-            earth.units.clear();
-            earth.units_by_loc.clear();
+            let mut flooded: Vec<(UnitID, Location, Team, UnitType)> = self.get_planet(Planet::Earth)
+                .units.values()
+                .map(|unit| (unit.id(), unit.location(), unit.team(), unit.unit_type()))
+                .collect();
+            flooded.sort_by_key(|&(id, ..)| id);
+            for (unit_id, location, team, unit_type) in flooded {
+                self.record_destroyed(team, unit_type, Destruction::Flood);
+                self.viewer_changes.push(ViewerDelta::UnitDestroyed {
+                    unit_id, location, cause: Destruction::Flood,
+                });
+            }
+            {
+                let earth = self.get_planet_mut(Planet::Earth);
+                earth.units.clear();
+                earth.units_by_loc.clear();
+            }
+            for (player, counts) in self.vision.iter_mut() {
+                if player.planet == Planet::Earth {
+                    for row in Arc::make_mut(counts).iter_mut() {
+                        for count in row.iter_mut() {
+                            *count = 0;
+                        }
+                    }
+                }
+            }
         }
 
         // Update unit cooldowns.
@@ -2180,11 +3156,16 @@ impl GameWorld {
         // Update the current research and process any completed upgrades.
         self.process_research(Team::Red);
         self.process_research(Team::Blue);
+
+        self.check_integrity();
     }
 
     /// Applies a single delta to this GameWorld.
+    ///
+    /// * GameOver - the game is over.
     pub(crate) fn apply(&mut self, delta: &Delta) -> Result<(), Error> {
-        match *delta {
+        self.ok_if_game_not_over()?;
+        let result = match *delta {
             Delta::Attack {robot_id, target_unit_id} => self.attack(robot_id, target_unit_id),
             Delta::BeginSnipe {ranger_id, location} => self.begin_snipe(ranger_id, location),
             Delta::Blueprint {worker_id, structure_type, direction} => self.blueprint(worker_id, structure_type, direction),
@@ -2206,45 +3187,188 @@ impl GameWorld {
             Delta::Unload {structure_id, direction} => self.unload(structure_id, direction),
             Delta::WriteTeamArray {index, value} => self.write_team_array(index, value),
             Delta::Nothing => Ok(()),
+        };
+        self.check_integrity();
+        result
+    }
+
+    /// Applies each delta in order, returning the result of each one. A
+    /// failed delta leaves the world unchanged and does not stop the rest
+    /// from being applied. Only the first MAX_DELTAS_PER_TURN deltas are
+    /// applied, and the rest are dropped without a result.
+    ///
+    /// Replaces the action log with the first MAX_LOGGED_FAILURES_PER_TURN
+    /// deltas that failed. See `take_action_log()`.
+    ///
+    /// If the player to move has timed out, none of the deltas are applied.
+    pub(crate) fn apply_many(&mut self, deltas: &[Delta]) -> Vec<Result<(), Error>> {
+        self.action_log.clear();
+        let player = self.player_to_move;
+        if self.is_timed_out(player) {
+            return vec![];
         }
+        deltas.iter()
+              .take(MAX_DELTAS_PER_TURN)
+              .enumerate()
+              .map(|(index, delta)| {
+                  let result = self.apply(delta);
+                  if let Err(ref e) = result {
+                      if self.action_log.len() < MAX_LOGGED_FAILURES_PER_TURN {
+                          self.action_log.push(FailedDelta {
+                              index, player, delta: delta.clone(), error: e.to_string(),
+                          });
+                      }
+                  }
+                  result
+              })
+              .collect()
+    }
+
+    /// The deltas that failed in the last turn applied by the manager.
+    pub fn action_log(&self) -> &[FailedDelta] {
+        &self.action_log
+    }
+
+    /// Takes the deltas that failed in the last turn applied by the manager,
+    /// leaving the log empty.
+    pub fn take_action_log(&mut self) -> Vec<FailedDelta> {
+        mem::replace(&mut self.action_log, Vec::new())
     }
 
     /// Applies a turn message to this GameWorld, and ends the current turn.
-    /// Returns the message to send to the next player.
-    pub(crate) fn apply_turn(&mut self, turn: &TurnMessage, time_left_ms: i32) -> StartTurnMessage {
-        for delta in turn.changes.iter() {
-            self.apply(delta).unwrap();
-        }
-        self.end_turn(time_left_ms)
+    /// Returns the result of each delta in the turn, and the message to send
+    /// to the next player.
+    pub(crate) fn apply_turn(&mut self, turn: &TurnMessage, time_left_ms: i32)
+                             -> (Vec<Result<(), Error>>, StartTurnMessage) {
+        let results = self.apply_many(&turn.changes);
+        (results, self.end_turn(time_left_ms))
     }
 
-    /// Determines if the game has ended, returning the winning team if so.
-    pub(crate) fn is_game_over(&self) -> Option<Team> {
-        // Calculate the value of all units.
-        let mut red_units_value = 0;
-        let mut blue_units_value = 0;
-        for unit in self.get_planet(Planet::Earth).units.values() {
-            match unit.team() {
-                Team::Red => { red_units_value += unit.unit_type().value(); },
-                Team::Blue => { blue_units_value += unit.unit_type().value(); },
-            }
+    /// A checksum of the game state, which is identical for two worlds that
+    /// reached the same state by applying the same changes. The cached
+    /// worlds and pending viewer changes are not included.
+    ///
+    /// The checksum does not depend on the order of any hash map, and is
+    /// the same on every platform and across serialization round trips.
+    pub(crate) fn checksum(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hash_json(&(self.round, self.player_to_move), &mut hasher);
+        for planet in [Planet::Earth, Planet::Mars].iter() {
+            let planet_info = self.get_planet(*planet);
+            let mut units: Vec<&Unit> = planet_info.units.values().collect();
+            units.sort_by_key(|unit| unit.id());
+            hash_json(&units, &mut hasher);
+            hash_json(&planet_info.karbonite, &mut hasher);
         }
-        for unit in self.get_planet(Planet::Mars).units.values() {
-            match unit.team() {
-                Team::Red => { red_units_value += unit.unit_type().value(); },
-                Team::Blue => { blue_units_value += unit.unit_type().value(); },
-            }
+        for team in [Team::Red, Team::Blue].iter() {
+            self.get_team(*team).hash_into(&mut hasher);
+        }
+        let timing: Vec<&PlayerTiming> = Player::all().iter()
+            .map(|player| self.timing(*player))
+            .collect();
+        hash_json(&timing, &mut hasher);
+        hasher.finish()
+    }
+
+    /// A checksum of the state the player to move controls: its units on its
+    /// planet and its team's state. A player's own world and the manager's
+    /// world agree on it after applying the same turn, so comparing the two
+    /// detects when they have diverged. Like `checksum()`, it is the same on
+    /// every platform.
+    pub(crate) fn player_checksum(&self) -> u64 {
+        let player = self.player_to_move;
+        let mut hasher = FnvHasher::default();
+        hash_json(&(self.round, player), &mut hasher);
+        let mut units: Vec<&Unit> = self.get_planet(player.planet).units.values()
+            .filter(|unit| unit.team() == player.team)
+            .collect();
+        units.sort_by_key(|unit| unit.id());
+        hash_json(&units, &mut hasher);
+        self.get_team(player.team).hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Saves the game world to the given file, so that it can be restored
+    /// exactly with `from_file()`. Only the full game world of the Teh Devs
+    /// engine can be saved, at any point during a turn.
+    ///
+    /// * InvalidSnapshot - the game world is filtered for a single player.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        if self.mode != WorldMode::DevEngine {
+            Err(GameError::InvalidSnapshot)?;
+        }
+        let mut cached_world: Vec<(&Player, &Arc<GameWorld>)> = self.cached_world.iter().collect();
+        cached_world.sort_by_key(|&(player, _)| Player::all().iter().position(|p| p == player));
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", SNAPSHOT_HEADER)?;
+        serde_json::to_writer(&mut file, &(self, cached_world))?;
+        Ok(())
+    }
+
+    /// Loads a game world saved with `to_file()`, including the filtered
+    /// worlds cached at the start of each player's last turn.
+    ///
+    /// * InvalidSnapshot - the file was not saved by this version of the
+    ///   engine, or is corrupt.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<GameWorld, Error> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let mut parts = contents.splitn(2, '\n');
+        if parts.next() != Some(SNAPSHOT_HEADER) {
+            Err(GameError::InvalidSnapshot)?;
+        }
+        let (mut world, cached_world): (GameWorld, Vec<(Player, GameWorld)>) =
+            serde_json::from_str(parts.next().unwrap_or(""))
+                .map_err(|_| GameError::InvalidSnapshot)?;
+        if world.mode != WorldMode::DevEngine || world.planet_maps.len() != 2
+                || world.planet_states.len() != 2 || world.team_states.len() != 2 {
+            Err(GameError::InvalidSnapshot)?;
         }
-        for unit in self.get_team(Team::Red).units_in_space.values() {
-            red_units_value += unit.unit_type().value();
+        for &(player, ref cached) in cached_world.iter() {
+            if cached.player_to_move != player || cached.planet_maps.len() != 2
+                    || !cached.planet_states.contains_key(&player.planet)
+                    || !cached.team_states.contains_key(&player.team) {
+                Err(GameError::InvalidSnapshot)?;
+            }
         }
-        for unit in self.get_team(Team::Blue).units_in_space.values() {
-            blue_units_value += unit.unit_type().value();
+
+        // Vision is counted whenever the filtered worlds are cached.
+        if !cached_world.is_empty() {
+            world.count_vision();
         }
+        world.cached_world = cached_world.into_iter()
+            .map(|(player, cached)| (player, Arc::new(cached)))
+            .collect();
+        Ok(world)
+    }
+
+    /// Whether the game has ended, either because a team has no units left
+    /// on either planet or in space, or because the round limit has passed.
+    pub fn is_over(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// The winning team, if the game has ended. On the round limit, ties are
+    /// broken by the total value of living units, then by karbonite, and
+    /// finally by a coin flip seeded by the map.
+    pub fn winner(&self) -> Option<Team> {
+        // A team loses as soon as one of its players times out. If both
+        // teams have, the game ends and goes to the tiebreakers.
+        let timed_out = |team| Player::all().iter()
+            .any(|player| player.team == team && self.is_timed_out(*player));
+        let both_timed_out = match (timed_out(Team::Red), timed_out(Team::Blue)) {
+            (true, false) => return Some(Team::Blue),
+            (false, true) => return Some(Team::Red),
+            (red, blue) => red && blue,
+        };
+
+        let red_units_value = self.get_team(Team::Red).stats.units_value;
+        let blue_units_value = self.get_team(Team::Blue).stats.units_value;
 
         // The game should not end if both teams still have units, and we are
         // not at the round limit.
-        if self.round() <= ROUND_LIMIT && red_units_value > 0 && blue_units_value > 0 {
+        if !both_timed_out && self.round() <= ROUND_LIMIT
+                && red_units_value > 0 && blue_units_value > 0 {
             return None;
         }
 
@@ -2263,8 +3387,9 @@ impl GameWorld {
             Ordering::Greater => { return Some(Team::Red); },
         }
 
-        // 3. "RNG"
-        match rand::random::<u8>() % 2 {
+        // 3. A coin flip seeded by the map, so replays agree on the winner
+        let mut rng = GameRng::new(self.id_generator.seed(), "coin flip");
+        match Range::new(0, 2).ind_sample(&mut rng) {
             0 => Some(Team::Blue),
             1 => Some(Team::Red),
             _ => unreachable!(),
@@ -2316,7 +3441,7 @@ impl GameWorld {
     /// also increment the round and reindex units by location.
     pub(crate) fn start_turn(&mut self, turn: &StartTurnMessage) {
         self.round = turn.round;
-        self.my_planet_mut().visible_locs = turn.visible_locs.clone();
+        self.my_planet_mut().visible_locs = Arc::new(turn.visible_locs.clone());
         for unit in &turn.units_changed {
             self.my_planet_mut().units.insert(unit.id(), unit.clone());
         }
@@ -2324,9 +3449,8 @@ impl GameWorld {
             self.my_planet_mut().units.remove(unit_id);
         }
         for &(location, karbonite) in &turn.karbonite_changed {
-            let x = location.x as usize;
-            let y = location.y as usize;
-            self.my_planet_mut().karbonite[y][x] = karbonite;
+            let (x, y) = self.starting_map(location.planet).index_of(location).unwrap();
+            self.my_planet_mut().karbonite_mut()[y][x] = karbonite;
         }
         for unit in &turn.units_in_space_changed {
             self.my_team_mut().units_in_space.insert(unit.id(), unit.clone());
@@ -2342,8 +3466,9 @@ impl GameWorld {
         self.my_team_mut().rocket_landings = turn.rocket_landings.clone();
         self.my_team_mut().research = turn.research.clone();
         self.my_team_mut().karbonite = turn.karbonite;
+        self.my_team_mut().stats = turn.stats.clone();
 
-        let mut units_by_loc = FnvHashMap::default();
+        let mut units_by_loc = OccupancyGrid::new(self.starting_map(self.planet()));
         for (id, unit) in self.my_planet().units.iter() {
             if let OnMap(loc) = unit.location() {
                 units_by_loc.insert(loc, *id);
@@ -2352,14 +3477,319 @@ impl GameWorld {
         self.my_planet_mut().units_by_loc = units_by_loc;
     }
 
+    /// Panics if the game world violates an invariant. It walks the whole
+    /// world, so it is only checked in debug builds.
+    #[cfg(debug_assertions)]
+    fn check_integrity(&self) {
+        assert_eq!(self.validate_integrity(), Ok(()));
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn check_integrity(&self) {}
+
+    /// Cross-checks the redundant indices of the game world against each
+    /// other, returning every invariant that does not hold. Only a bug in
+    /// the engine can cause a violation, so debug builds check this after
+    /// every delta and every turn.
+    pub fn validate_integrity(&self) -> Result<(), Vec<String>> {
+        let mut violations = vec![];
+        let player = self.player_to_move;
+        if !self.planet_states.contains_key(&player.planet) {
+            violations.push(format!("{:?} is to move, but its planet has no state", player));
+        }
+        if !self.team_states.contains_key(&player.team) {
+            violations.push(format!("{:?} is to move, but its team has no state", player));
+        }
+
+        // Every unit is in exactly one place.
+        let mut seen = FnvHashMap::default();
+        for (&planet, planet_info) in self.planet_states.iter() {
+            for (&id, unit) in planet_info.units.iter() {
+                if id != unit.id() {
+                    violations.push(format!("unit {} is stored under ID {}", unit.id(), id));
+                }
+                if let Some(other) = seen.insert(id, format!("{:?}", planet)) {
+                    violations.push(format!("unit {} is on {:?} and {}", id, planet, other));
+                }
+                match unit.location() {
+                    OnMap(loc) => {
+                        if loc.planet != planet {
+                            violations.push(format!("unit {} is on {:?} but located at {:?}", id, planet, loc));
+                        } else if planet_info.units_by_loc.get(&loc) != Some(&id) {
+                            violations.push(format!("unit {} at {:?} is missing from the occupancy grid", id, loc));
+                        }
+                    },
+                    InGarrison(structure_id) => {
+                        let in_garrison = planet_info.units.get(&structure_id)
+                            .and_then(|structure| structure.structure_garrison().ok())
+                            .map_or(false, |garrison| garrison.contains(&id));
+                        if !in_garrison {
+                            violations.push(format!("unit {} is not in the garrison of its structure {}", id, structure_id));
+                        }
+                    },
+                    location => {
+                        violations.push(format!("unit {} is on {:?} but located {:?}", id, planet, location));
+                    },
+                }
+                if let Ok(garrison) = unit.structure_garrison() {
+                    for garrisoned_id in garrison {
+                        let located = planet_info.units.get(&garrisoned_id)
+                            .map(|garrisoned| garrisoned.location());
+                        if located != Some(InGarrison(id)) {
+                            violations.push(format!("structure {} holds unit {}, which is not inside it", id, garrisoned_id));
+                        }
+                    }
+                }
+            }
+            for (loc, id) in planet_info.units_by_loc.iter() {
+                let located = planet_info.units.get(&id).map(|unit| unit.location());
+                if located != Some(OnMap(loc)) {
+                    violations.push(format!("the occupancy grid places unit {} at {:?}, where it is not", id, loc));
+                }
+            }
+        }
+
+        // Units in space are in a rocket in space, and every landing belongs
+        // to a rocket in space.
+        for (&team, team_info) in self.team_states.iter() {
+            for (&id, unit) in team_info.units_in_space.iter() {
+                if let Some(other) = seen.insert(id, format!("{:?}'s space", team)) {
+                    violations.push(format!("unit {} is in {:?}'s space and {}", id, team, other));
+                }
+                match unit.location() {
+                    InSpace => {},
+                    InGarrison(rocket_id) => {
+                        let in_rocket = team_info.units_in_space.get(&rocket_id)
+                            .and_then(|rocket| rocket.structure_garrison().ok())
+                            .map_or(false, |garrison| garrison.contains(&id));
+                        if !in_rocket {
+                            violations.push(format!("unit {} in space is not in the garrison of its rocket {}", id, rocket_id));
+                        }
+                    },
+                    location => {
+                        violations.push(format!("unit {} is in space but located {:?}", id, location));
+                    },
+                }
+            }
+            for (round, landing) in team_info.rocket_landings.all() {
+                let located = team_info.units_in_space.get(&landing.rocket_id)
+                    .map(|rocket| rocket.location());
+                if located != Some(InSpace) {
+                    violations.push(format!("rocket {} is scheduled to land in round {}, but is not in space",
+                                            landing.rocket_id, round));
+                }
+            }
+        }
+
+        // In a full world, each team's stats count the value of all its units.
+        if self.mode == WorldMode::DevEngine {
+            let mut units_value: FnvHashMap<Team, u32> = FnvHashMap::default();
+            let units = self.planet_states.values().flat_map(|planet_info| planet_info.units.values())
+                .chain(self.team_states.values().flat_map(|team_info| team_info.units_in_space.values()));
+            for unit in units {
+                *units_value.entry(unit.team()).or_insert(0) += unit.unit_type().value();
+            }
+            for (&team, team_info) in self.team_states.iter() {
+                let expected = units_value.get(&team).cloned().unwrap_or(0);
+                if team_info.stats.units_value != expected {
+                    violations.push(format!("{:?}'s stats value its units at {}, but they are worth {}",
+                                            team, team_info.stats.units_value, expected));
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            violations.sort();
+            Err(violations)
+        }
+    }
+
     pub(crate) fn manager_karbonite(&self, team: Team) -> u32 {
         self.team_states.get(&team).expect("oy, stop being nefarious").karbonite
     }
 }
 
+/// The setup shared by the tests and the benchmarks in `benches/`, and the
+/// older, slower implementations the benchmarks compare against.
+#[cfg(any(test, feature = "bench"))]
+impl GameWorld {
+    /// A world with units of both teams on both planets, including units
+    /// garrisoned in structures, and a rocket in space. Red Earth is to move.
+    #[cfg(test)]
+    pub(crate) fn populated_world() -> GameWorld {
+        GameWorld::populated_world_with(TestMapConfig::default())
+    }
+
+    /// A world populated like `populated_world()`, on the terrain and
+    /// karbonite of a test map laid out by the config.
+    pub(crate) fn populated_world_with(config: TestMapConfig) -> GameWorld {
+        let (width, height) = (config.width as i32, config.height as i32);
+        let mut world = GameWorld::test_world_with(config);
+
+        // The middle three rows are left for the structures.
+        let middle = height / 2 + 1;
+        for y in 0..height {
+            for x in 0..width {
+                if (x * 7 + y * 3) % 5 != 0 || (y >= middle - 1 && y <= middle + 1) {
+                    continue;
+                }
+                let team = if x < width / 2 { Team::Red } else { Team::Blue };
+                let unit_type = UnitType::all()[((x + y) % 5) as usize];
+                for planet in [Planet::Earth, Planet::Mars].iter() {
+                    let loc = MapLocation::new(*planet, x, y);
+                    world.create_unit(team, loc, unit_type).unwrap();
+                }
+            }
+        }
+        let mut rockets = vec![];
+        for &(team, x) in [(Team::Red, 4), (Team::Blue, width - 5)].iter() {
+            world.player_to_move = Player::new(team, Planet::Earth);
+            let loc = MapLocation::new(Planet::Earth, x, middle);
+            let factory = world.create_unit(team, loc, UnitType::Factory).unwrap();
+            world.finish_structure(factory);
+            let knight = world.create_unit(team, loc.add(Direction::North), UnitType::Knight).unwrap();
+            assert![world.load(factory, knight).is_ok()];
+            let rocket = world.create_unit(team, loc.add(Direction::South), UnitType::Rocket).unwrap();
+            world.finish_structure(rocket);
+            let mage = world.create_unit(team, loc.add(Direction::Southeast), UnitType::Mage).unwrap();
+            assert![world.load(rocket, mage).is_ok()];
+            rockets.push(rocket);
+        }
+        assert![world.launch_rocket(rockets[1], MapLocation::new(Planet::Mars, 1, 1)).is_ok()];
+        world.player_to_move = Player::first_to_move();
+        world
+    }
+
+    /// The filtered world, computed the straightforward way.
+    pub(crate) fn naive_filter(&self, player: Player) -> GameWorld {
+        let team = player.team;
+        let planet = player.planet;
+        let map = self.starting_map(planet);
+
+        // First find your units that are on the map to calculate the visible locations.
+        let mut locs_vision: Vec<(MapLocation, u32)> = vec![];
+        for unit in self.get_planet(planet).units.values().into_iter() {
+            if unit.team() == team && unit.location().is_on_map() {
+                locs_vision.push(
+                    (unit.location().map_location().unwrap(), unit.vision_range())
+                );
+            }
+        }
+
+        // Calculate the visible locations on this team that are on the map.
+        let mut visible_locs = vec![vec![false; map.width]; map.height];
+        for &(loc, vision_range) in locs_vision.iter() {
+            for loc in loc.all_locations_within_clipped(vision_range, map) {
+                visible_locs[loc.y as usize][loc.x as usize] = true;
+            }
+        }
+
+        // Unseen squares hold their initial karbonite.
+        let mut karbonite = (*self.get_planet(planet).karbonite).clone();
+        for y in 0..map.height {
+            for x in 0..map.width {
+                if !visible_locs[y][x] {
+                    karbonite[y][x] = map.initial_karbonite[y][x];
+                }
+            }
+        }
+
+        // Find all the units within these visible locations, and also index
+        // them by location. Includes units in enemy rockets.
+        let mut units: FnvHashMap<UnitID, Unit> = FnvHashMap::default();
+        let mut units_by_loc = OccupancyGrid::new(map);
+        for (id, unit) in self.get_planet(planet).units.iter() {
+            if let OnMap(loc) = unit.location() {
+                if !visible_locs[loc.y as usize][loc.x as usize] {
+                    continue;
+                }
+                units_by_loc.insert(loc, *id);
+                units.insert(*id, unit.clone());
+            }
+        };
+        for (id, unit) in self.get_planet(planet).units.iter() {
+            if let InGarrison(structure_id) = unit.location() {
+                if units.contains_key(&structure_id) {
+                    units.insert(*id, unit.clone());
+                }
+            }
+        };
+
+        // Filter the team states.
+        let mut team_states: FnvHashMap<Team, TeamInfo> = FnvHashMap::default();
+        let old_team_state = self.get_team(team);
+        let new_team_state = TeamInfo {
+            team_arrays: old_team_state.team_arrays.filter(planet),
+            rocket_landings: old_team_state.rocket_landings.clone(),
+            research: old_team_state.research.clone(),
+            units_in_space: old_team_state.units_in_space.clone(),
+            karbonite: old_team_state.karbonite,
+            stats: old_team_state.stats.clone(),
+            timing: old_team_state.timing.clone(),
+        };
+        team_states.insert(team, new_team_state);
+
+        // Planet state.
+        let mut planet_states: FnvHashMap<Planet, PlanetInfo> = FnvHashMap::default();
+        let planet_info = PlanetInfo {
+            visible_locs: Arc::new(visible_locs),
+            units: units,
+            units_by_loc: units_by_loc,
+            karbonite: Arc::new(karbonite),
+        };
+        planet_states.insert(planet, planet_info);
+
+        GameWorld {
+            mode: WorldMode::Player,
+            round: self.round,
+            player_to_move: player,
+            time_pool_ms: self.time_pool_ms,
+            time_per_turn_ms: self.time_per_turn_ms,
+            id_generator: self.id_generator.clone(),
+            asteroids: self.asteroids.clone(),
+            orbit: self.orbit.clone(),
+            planet_maps: self.planet_maps.clone(),
+            planet_states: planet_states,
+            team_states: team_states,
+            cached_world: HashMap::default(),
+            vision: FnvHashMap::default(),
+            viewer_changes: Vec::new(),
+            action_log: Vec::new(),
+        }
+    }
+
+    /// A clone of the world that shares no state with it, as cloning worked
+    /// before the bulky parts of the world were shared between clones.
+    #[cfg(feature = "bench")]
+    pub(crate) fn unshared_clone(&self) -> GameWorld {
+        let mut copy = self.clone();
+        Arc::make_mut(&mut copy.asteroids);
+        Arc::make_mut(&mut copy.orbit);
+        for planet_info in copy.planet_states.values_mut() {
+            Arc::make_mut(&mut planet_info.visible_locs);
+            planet_info.karbonite_mut();
+            planet_info.units_by_loc.unshare();
+        }
+        for counts in copy.vision.values_mut() {
+            Arc::make_mut(counts);
+        }
+        for team_info in copy.team_states.values_mut() {
+            team_info.team_arrays.unshare();
+        }
+        for cached in copy.cached_world.values_mut() {
+            *cached = Arc::new(cached.unshared_clone());
+        }
+        copy
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{SeedableRng, StdRng};
+    use std::{env, fs, process};
 
     // a filler time that only has meaning in the context of actual games
     // run under time duress
@@ -2375,6 +3805,121 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vision_counts() {
+        let mut world = GameWorld::test_world();
+        let seed: &[_] = &[1337];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        // Each team has a line of robots and a rocket with a knight inside.
+        for &(team, y) in [(Team::Red, 2), (Team::Blue, 17)].iter() {
+            world.player_to_move = Player::new(team, Planet::Earth);
+            let unit_types = [UnitType::Knight, UnitType::Ranger, UnitType::Mage,
+                              UnitType::Healer, UnitType::Worker];
+            for i in 0..6 {
+                let loc = MapLocation::new(Planet::Earth, 2 + 3 * i, y);
+                world.create_unit(team, loc, unit_types[i as usize % 5]).unwrap();
+            }
+            let loc = MapLocation::new(Planet::Earth, 10, (y + 10) / 2);
+            let rocket = world.create_unit(team, loc, UnitType::Rocket).unwrap();
+            world.finish_structure(rocket);
+            let knight = world.create_unit(team, loc.add(Direction::North), UnitType::Knight).unwrap();
+            assert![world.load(rocket, knight).is_ok()];
+
+            // The second level of ranger research extends their vision.
+            for _ in 0..2 {
+                assert!(world.get_team_mut(team).research.add_to_queue(&Branch::Ranger));
+            }
+        }
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        world.count_vision();
+
+        // Units move, attack, replicate, launch, land, unload, are researched,
+        // and are flooded at random, and the counts always match counting from
+        // scratch.
+        let direction = Range::new(0, 8);
+        let chance = Range::new(0, 20);
+        let coordinate = Range::new(0, 20);
+        let mut saw_ranger_research = false;
+        while world.round() < APOCALYPSE_ROUND + 2 {
+            // Once the rockets have landed and the research is done, skip
+            // ahead to shortly before the flood.
+            let rockets_landed = world.team_states.values()
+                .all(|team_info| team_info.units_in_space.is_empty());
+            let research_done = world.team_states.values()
+                .all(|team_info| !team_info.research.has_next_in_queue());
+            if world.round() >= 100 && world.round() < APOCALYPSE_ROUND - 10
+                    && rockets_landed && research_done {
+                world.round = APOCALYPSE_ROUND - 10;
+            }
+            saw_ranger_research |= world.get_planet(Planet::Earth).units.values()
+                .any(|unit| unit.unit_type() == UnitType::Ranger && unit.research_level() == 2);
+            let team = world.team();
+            let mut ids: Vec<UnitID> = world.my_planet().units.values()
+                .filter(|unit| unit.team() == team && unit.location().is_on_map())
+                .map(|unit| unit.id())
+                .collect();
+            ids.sort();
+            for id in ids {
+                let dir = Direction::all()[direction.ind_sample(&mut rng)];
+                let unit_type = match world.get_unit(id) {
+                    Ok(unit) => unit.unit_type(),
+                    Err(_) => continue,
+                };
+                match unit_type {
+                    UnitType::Rocket => {
+                        if chance.ind_sample(&mut rng) == 0 {
+                            let destination = MapLocation::new(Planet::Mars,
+                                coordinate.ind_sample(&mut rng), coordinate.ind_sample(&mut rng));
+                            let _ = world.launch_rocket(id, destination);
+                        } else {
+                            let _ = world.unload(id, dir);
+                        }
+                    },
+                    UnitType::Worker => {
+                        let _ = world.replicate(id, dir);
+                        let _ = world.move_robot(id, dir);
+                    },
+                    UnitType::Factory => {},
+                    _ => {
+                        let _ = world.move_robot(id, dir);
+                        let targets: Vec<UnitID> = world.my_planet().units.values()
+                            .filter(|unit| unit.team() != team)
+                            .map(|unit| unit.id())
+                            .collect();
+                        for target in targets {
+                            if world.attack(id, target).is_ok() {
+                                break;
+                            }
+                        }
+                    },
+                }
+            }
+            world.end_turn(FILLER_TIME);
+            for player in Player::all() {
+                assert_eq!(*world.vision[&player], world.vision_counts(player),
+                           "round {}, {:?}", world.round(), player);
+            }
+        }
+        assert!(saw_ranger_research);
+        assert!(world.get_planet(Planet::Earth).units.is_empty());
+        assert!(!world.get_planet(Planet::Mars).units.is_empty());
+    }
+
+    #[test]
+    fn test_filter_matches_naive() {
+        let world = GameWorld::populated_world();
+        for player in Player::all() {
+            let filtered = world.filter(player);
+            let naive = world.naive_filter(player);
+            assert_eq!(filtered.planet_states, naive.planet_states);
+            assert_eq!(filtered.team_states, naive.team_states);
+            assert_eq!(filtered.planet_maps, naive.planet_maps);
+            assert_eq!(filtered.player_to_move, naive.player_to_move);
+            assert!(!filtered.my_planet().units.is_empty());
+        }
+    }
+
     #[test]
     fn test_all_locations_within() {
         let world = GameWorld::test_world();
@@ -2403,7 +3948,7 @@ mod tests {
         for i in 0..3 {
             let stm = world.end_turn(FILLER_TIME);
             assert_eq!(stm.round, new_rounds[i]);
-            assert_eq!(stm.visible_locs, old_worlds[i].my_planet().visible_locs);
+            assert_eq!(stm.visible_locs, *old_worlds[i].my_planet().visible_locs);
             assert_eq!(stm.units_changed.len(), 0);
             assert_eq!(stm.units_vanished.len(), 0);
             assert_eq!(stm.karbonite_changed.len(), 0);
@@ -2416,6 +3961,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_initial_units() {
+        let mut map = GameMap::test_map();
+        map.earth_map.initial_units = (0..6).map(|i| {
+            let team = if i % 2 == 0 { Team::Red } else { Team::Blue };
+            let location = MapLocation::new(Planet::Earth, i * 3, i);
+            Unit::new(i as UnitID + 1, team, UnitType::Worker, 0, OnMap(location)).unwrap()
+        }).collect();
+        let world = GameWorld::new(map.clone());
+
+        // Every initial unit is on its square, with its team and type.
+        let earth = &world.planet_states[&Planet::Earth];
+        assert_eq!(earth.units.len(), 6);
+        assert_eq!(earth.units_by_loc.len(), 6);
+        for unit in &map.earth_map.initial_units {
+            let location = unit.location().map_location().unwrap();
+            let placed = &earth.units[&earth.units_by_loc[&location]];
+            assert_eq!(placed.team(), unit.team());
+            assert_eq!(placed.unit_type(), UnitType::Worker);
+            assert_eq!(placed.location(), unit.location());
+        }
+        assert_eq!(world.planet_states[&Planet::Mars].units.len(), 0);
+
+        // Each player sees its own workers from the first turn.
+        for team in [Team::Red, Team::Blue].iter() {
+            let player_world = world.cached_world(Player::new(*team, Planet::Earth));
+            let mine = player_world.my_planet().units.values()
+                .filter(|unit| unit.team() == *team)
+                .count();
+            assert_eq!(mine, 3);
+        }
+
+        // Each player can read both teams' starting positions from the
+        // starting map, whether or not it can see them.
+        let blue_world = world.cached_world(Player::new(Team::Blue, Planet::Earth));
+        assert_eq!(blue_world.round(), 1);
+        let red_locations: Vec<MapLocation> = blue_world.initial_units(Team::Red).iter()
+            .map(|unit| unit.location().map_location().unwrap())
+            .collect();
+        assert_eq!(red_locations, vec![
+            MapLocation::new(Planet::Earth, 0, 0),
+            MapLocation::new(Planet::Earth, 6, 2),
+            MapLocation::new(Planet::Earth, 12, 4),
+        ]);
+        assert_eq!(blue_world.initial_units(Team::Blue).len(), 3);
+        let mars_world = world.cached_world(Player::new(Team::Red, Planet::Mars));
+        assert_eq!(mars_world.initial_units(Team::Blue), world.initial_units(Team::Blue));
+    }
+
+    #[test]
+    fn test_filter_hides_unseen_karbonite() {
+        let mut world = GameWorld::test_world();
+        let seen = MapLocation::new(Planet::Mars, 2, 2);
+        let unseen = MapLocation::new(Planet::Mars, 18, 18);
+        world.create_unit(Team::Red, seen, UnitType::Worker).unwrap();
+        let initial = world.starting_map(Planet::Mars).initial_karbonite[18][18];
+        {
+            let karbonite = world.get_planet_mut(Planet::Mars).karbonite_mut();
+            karbonite[2][2] = 47;
+            karbonite[18][18] = initial + 50;
+        }
+
+        // The Mars player sees the current karbonite near their worker, and
+        // the initial karbonite everywhere else.
+        let mars_world = world.filter(Player::new(Team::Red, Planet::Mars));
+        assert_eq!(mars_world.mode, WorldMode::Player);
+        assert_eq!(mars_world.karbonite_at(seen).unwrap(), 47);
+        assert_eq!(mars_world.get_planet(Planet::Mars).karbonite[18][18], initial);
+        assert_err!(mars_world.karbonite_at(unseen), GameError::LocationNotVisible);
+
+        // The Earth player carries no state for Mars at all.
+        let earth_world = world.filter(Player::new(Team::Red, Planet::Earth));
+        assert!(!earth_world.planet_states.contains_key(&Planet::Mars));
+
+        // Filtering again changes nothing, and only the full world can be
+        // saved.
+        assert_eq!(mars_world.filter(Player::new(Team::Red, Planet::Mars)), mars_world);
+        assert_err!(mars_world.to_file(env::temp_dir().join(format!("bc-filtered-{}.json", process::id()))),
+                    GameError::InvalidSnapshot);
+    }
+
     #[test]
     fn test_filter_visibility() {
         let initial_units_earth = vec![
@@ -2439,29 +4065,290 @@ mod tests {
         let red_world = world.cached_world(Player::new(Team::Red, Planet::Earth)).clone();
         let mut blue_world = world.cached_world(Player::new(Team::Blue, Planet::Earth)).clone();
 
-        // The Devs engine can see all the units.
+        // The Devs engine can see all the units, but only get Red's.
         assert!(world.unit(1).is_ok());
         assert!(world.unit(2).is_ok());
         assert!(world.unit(3).is_ok());
-        assert!(world.unit(4).is_ok());
-        assert!(world.unit(5).is_ok());
+        assert_err!(world.unit(4), GameError::TeamNotAllowed);
+        assert_err!(world.unit(5), GameError::TeamNotAllowed);
 
         // The Red Earth engine cannot see 5, which is not in range.
         assert!(red_world.unit(1).is_ok());
         assert!(red_world.unit(2).is_ok());
         assert!(red_world.unit(3).is_ok());
-        assert!(red_world.unit(4).is_ok());
+        assert_err!(red_world.unit(4), GameError::TeamNotAllowed);
         assert_err!(red_world.unit(5), GameError::NoSuchUnit);
+        assert!(red_world.can_sense_unit(4));
+        assert!(!red_world.can_sense_unit(5));
 
         // The Blue Earth engine cannot see 1, which is not in range.
         blue_world.start_turn(&world.end_turn(FILLER_TIME));
         assert_err!(blue_world.unit(1), GameError::NoSuchUnit);
-        assert!(blue_world.unit(2).is_ok());
-        assert!(blue_world.unit(3).is_ok());
+        assert_err!(blue_world.unit(2), GameError::TeamNotAllowed);
+        assert_err!(blue_world.unit(3), GameError::TeamNotAllowed);
         assert!(blue_world.unit(4).is_ok());
         assert!(blue_world.unit(5).is_ok());
     }
 
+    #[test]
+    fn test_unit_in_garrison() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+        let worker = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Worker).unwrap();
+        world.finish_structure(rocket);
+        assert!(world.load(rocket, worker).is_ok());
+
+        // A unit garrisoned in my rocket is still one of my units.
+        let red_world = world.filter(Player::new(Team::Red, Planet::Earth));
+        assert_eq!(red_world.unit(worker).unwrap().location(), InGarrison(rocket));
+        assert_eq!(red_world.unit_ref(worker).unwrap().id(), worker);
+
+        // Blue can sense the rocket, but can't get either unit.
+        let blue_unit = world.create_unit(Team::Blue, loc.add(Direction::South), UnitType::Mage).unwrap();
+        let blue_world = world.filter(Player::new(Team::Blue, Planet::Earth));
+        assert!(blue_world.can_sense_unit(rocket));
+        assert!(blue_world.unit(blue_unit).is_ok());
+        assert_err!(blue_world.unit(rocket), GameError::TeamNotAllowed);
+        assert_err!(blue_world.unit(worker), GameError::TeamNotAllowed);
+        assert_err!(blue_world.unit(9999), GameError::NoSuchUnit);
+    }
+
+    #[test]
+    fn test_bulk_sensing() {
+        let mut world = GameWorld::test_world();
+        let red_a = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let red_b = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 6), UnitType::Factory).unwrap();
+        let blue = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 6, 5), UnitType::Mage).unwrap();
+        let red_c = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 4, 5), UnitType::Worker).unwrap();
+        world.finish_structure(red_b);
+        assert!(world.load(red_b, red_c).is_ok());
+
+        // The dev engine and the filtered world agree on everything.
+        let red_world = world.filter(Player::new(Team::Red, Planet::Earth));
+        for w in vec![&world, &red_world] {
+            let ids = w.units().iter().map(|u| u.id()).collect::<Vec<UnitID>>();
+            let mut sorted_ids = ids.clone();
+            sorted_ids.sort();
+            assert_eq!(ids, sorted_ids);
+            assert_eq!(ids.len(), 4);
+            assert_eq!(w.units_ref().len(), 4);
+
+            // Garrisoned units are indexed by ID, but not by location.
+            let by_id = w.units_by_id();
+            assert_eq!(by_id.len(), 4);
+            assert!(by_id.contains_key(&red_c));
+            let by_loc = w.units_by_loc();
+            assert_eq!(by_loc.len(), 3);
+            assert!(!by_loc.values().any(|id| *id == red_c));
+            assert_eq!(by_loc[&MapLocation::new(Planet::Earth, 6, 5)], blue);
+
+            // The opponent's unit is visible, but isn't ours to use.
+            assert!(w.units().iter().any(|u| u.id() == blue));
+            assert!(w.unit(red_a).is_ok());
+            assert_err!(w.unit(blue), GameError::TeamNotAllowed);
+        }
+    }
+
+    #[test]
+    fn test_best_launch_round_within() {
+        // The test map's orbit is shortest on rounds 75, 175, 275, ...
+        let mut world = GameWorld::test_world();
+        assert_eq!(world.best_launch_round_within(0), 1);
+        assert_eq!(world.best_launch_round_within(50), 51);
+        assert_eq!(world.best_launch_round_within(100), 75);
+        world.round = 80;
+        assert_eq!(world.best_launch_round_within(0), 80);
+        assert_eq!(world.best_launch_round_within(1000), 175);
+        let brute = (80..150).min_by_key(|&round| (world.orbit.duration(round), round)).unwrap();
+        assert_eq!(world.best_launch_round_within(69), brute);
+
+        // Windows longer than the orbit's period give the same answer.
+        assert_eq!(world.best_launch_round_within(Rounds::max_value()), 175);
+        world.round = Rounds::max_value() - 5;
+        let brute = (world.round..Rounds::max_value())
+            .min_by_key(|&round| (world.orbit.duration(round), round)).unwrap();
+        assert_eq!(world.best_launch_round_within(Rounds::max_value()), brute);
+    }
+
+    #[test]
+    fn test_can_sense_location_edges() {
+        let mut world = GameWorld::test_world();
+        world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Knight).unwrap();
+        world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 19, 19), UnitType::Knight).unwrap();
+        let world = world.filter(Player::new(Team::Red, Planet::Earth));
+
+        // Squares just off each edge of the map are off the map.
+        for &(x, y) in [(-1, 0), (0, -1), (20, 19), (19, 20), (-1, -1), (20, 20)].iter() {
+            let loc = MapLocation::new(Planet::Earth, x, y);
+            assert!(!world.can_sense_location(loc));
+            assert_err!(world.karbonite_at(loc), GameError::LocationOffMap { location: loc });
+            assert_err!(world.sense_unit_at_location(loc), GameError::LocationOffMap { location: loc });
+        }
+
+        // Squares in the corners are visible up to the knights' vision range.
+        let vision_range = world.my_planet().units.values().next().unwrap().vision_range();
+        for &(x, y) in [(0, 0), (7, 0), (0, 7), (19, 19), (12, 19), (19, 12)].iter() {
+            let loc = MapLocation::new(Planet::Earth, x, y);
+            assert!(world.can_sense_location(loc));
+            assert!(world.karbonite_at(loc).is_ok());
+            assert!(world.sense_unit_at_location(loc).is_ok());
+        }
+        for &(x, y) in [(8, 0), (0, 8), (11, 19), (19, 11), (19, 0), (0, 19)].iter() {
+            let loc = MapLocation::new(Planet::Earth, x, y);
+            assert_gt!(MapLocation::new(Planet::Earth, 0, 0).distance_squared_to(loc)
+                .min(MapLocation::new(Planet::Earth, 19, 19).distance_squared_to(loc)),
+                vision_range);
+            assert!(!world.can_sense_location(loc));
+            assert_err!(world.karbonite_at(loc), GameError::LocationNotVisible);
+            assert_err!(world.sense_unit_at_location(loc), GameError::LocationNotVisible);
+        }
+
+        // The other planet is off the map.
+        assert!(!world.can_sense_location(MapLocation::new(Planet::Mars, 0, 0)));
+    }
+
+    #[test]
+    fn test_karbonite_at() {
+        let mut world = GameWorld::test_world();
+        let strike_loc = MapLocation::new(Planet::Mars, 3, 4);
+        let mut pattern = FnvHashMap::default();
+        pattern.insert(2, AsteroidStrike::new(50, strike_loc));
+        world.asteroids = Arc::new(AsteroidPattern::new(&pattern));
+
+        // The asteroid lands on Mars at the start of round 2.
+        world.round = 2;
+        world.process_asteroids();
+
+        // The dev engine can sense karbonite anywhere on the current planet.
+        world.player_to_move = Player::new(Team::Red, Planet::Mars);
+        assert_eq!(world.karbonite_at(strike_loc).unwrap(), 50);
+        assert_eq!(world.karbonite_at(MapLocation::new(Planet::Mars, 19, 19)).unwrap(), 0);
+        assert_err!(world.karbonite_at(MapLocation::new(Planet::Mars, 20, 0)), GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 20, 0) });
+
+        // Strikes at the edge of the map credit the square they hit, and
+        // strikes off the map are ignored.
+        let edge_loc = MapLocation::new(Planet::Mars, 19, 0);
+        pattern.insert(3, AsteroidStrike::new(20, edge_loc));
+        pattern.insert(4, AsteroidStrike::new(20, MapLocation::new(Planet::Mars, 20, 0)));
+        world.asteroids = Arc::new(AsteroidPattern::new(&pattern));
+        world.round = 3;
+        world.process_asteroids();
+        world.round = 4;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(edge_loc).unwrap(), 20);
+        assert_eq!(world.karbonite_at(MapLocation::new(Planet::Mars, 0, 19)).unwrap(), 0);
+        assert_eq!(world.karbonite_at(MapLocation::new(Planet::Mars, 0, 0)).unwrap(), 0);
+        assert_err!(world.karbonite_at(MapLocation::new(Planet::Mars, 0, -1)), GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 0, -1) });
+        assert_err!(world.karbonite_at(MapLocation::new(Planet::Earth, 3, 4)), GameError::LocationOffMap { location: MapLocation::new(Planet::Earth, 3, 4) });
+
+        // A player can only sense karbonite within the vision range.
+        world.create_unit(Team::Red, MapLocation::new(Planet::Mars, 3, 5), UnitType::Worker).unwrap();
+        let mut red_world = world.filter(Player::new(Team::Red, Planet::Mars));
+        assert_eq!(red_world.karbonite_at(strike_loc).unwrap(), 50);
+        assert_err!(red_world.karbonite_at(MapLocation::new(Planet::Mars, 19, 19)), GameError::LocationNotVisible);
+
+        // Without a vision grid, there's no vision restriction.
+        red_world.my_planet_mut().visible_locs = Arc::new(vec![]);
+        assert_eq!(red_world.karbonite_at(MapLocation::new(Planet::Mars, 19, 19)).unwrap(), 0);
+        assert_err!(red_world.karbonite_at(MapLocation::new(Planet::Mars, 20, 0)), GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 20, 0) });
+    }
+
+    #[test]
+    fn test_asteroid_deposit_cap() {
+        let mut world = GameWorld::test_world();
+        let strike_loc = MapLocation::new(Planet::Mars, 3, 4);
+        let corner_loc = MapLocation::new(Planet::Mars, 0, 0);
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Mars).unwrap()).is_passable_terrain[0][0] = false;
+        let mut pattern = FnvHashMap::default();
+        pattern.insert(2, AsteroidStrike::new(ASTEROID_KARB_MAX, strike_loc));
+        pattern.insert(3, AsteroidStrike::new(ASTEROID_KARB_MAX, strike_loc));
+        pattern.insert(4, AsteroidStrike::new(ASTEROID_KARB_MAX, strike_loc));
+        pattern.insert(5, AsteroidStrike::new(30, corner_loc));
+        pattern.insert(6, AsteroidStrike::new(ASTEROID_KARB_MAX, strike_loc));
+        world.asteroids = Arc::new(AsteroidPattern::new(&pattern));
+        world.player_to_move = Player::new(Team::Red, Planet::Mars);
+        world.get_planet_mut(Planet::Mars).karbonite_mut()[4][3] = 10;
+
+        // Repeated strikes stack up to the cap.
+        world.round = 2;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(strike_loc).unwrap(), 10 + ASTEROID_KARB_MAX);
+        world.round = 3;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(strike_loc).unwrap(), MAX_KARBONITE_PER_SQUARE);
+        assert!(world.viewer_changes.contains(&ViewerDelta::KarboniteChanged {
+            location: strike_loc, new_amount: MAX_KARBONITE_PER_SQUARE,
+        }));
+
+        // A strike on a full square changes nothing.
+        world.viewer_changes.clear();
+        world.round = 4;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(strike_loc).unwrap(), MAX_KARBONITE_PER_SQUARE);
+        assert!(!world.viewer_changes.iter().any(|change| match *change {
+            ViewerDelta::KarboniteChanged { .. } => true,
+            _ => false,
+        }));
+
+        // Strikes on impassable terrain still deposit karbonite.
+        world.round = 5;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(corner_loc).unwrap(), 30);
+
+        // A square the map filled past the cap never loses karbonite.
+        world.get_planet_mut(Planet::Mars).karbonite_mut()[4][3] = MAX_KARBONITE_PER_SQUARE + 50;
+        world.viewer_changes.clear();
+        world.round = 6;
+        world.process_asteroids();
+        assert_eq!(world.karbonite_at(strike_loc).unwrap(), MAX_KARBONITE_PER_SQUARE + 50);
+        assert!(!world.viewer_changes.iter().any(|change| match *change {
+            ViewerDelta::KarboniteChanged { .. } => true,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn test_sense_nearby_units() {
+        let mut world = GameWorld::test_world();
+        let locs = vec![(0, 0), (0, 5), (3, 3), (4, 2), (7, 7), (12, 1), (19, 19)];
+        for (i, &(x, y)) in locs.iter().enumerate() {
+            let team = if i % 2 == 0 { Team::Red } else { Team::Blue };
+            world.create_unit(team, MapLocation::new(Planet::Earth, x, y), UnitType::Ranger).unwrap();
+        }
+
+        // Both the small and the large radius scans agree with brute force,
+        // including when the center is off the map.
+        let centers = vec![(3, 3), (0, 0), (-3, -3), (25, 10), (10, 10)];
+        let radii = vec![0, 2, 8, 50, 1000];
+        for &(x, y) in centers.iter() {
+            for &radius in radii.iter() {
+                let center = MapLocation::new(Planet::Earth, x, y);
+                let mut expected = locs.iter()
+                    .map(|&(x, y)| MapLocation::new(Planet::Earth, x, y))
+                    .filter(|loc| center.distance_squared_to(*loc) <= radius)
+                    .collect::<Vec<MapLocation>>();
+                expected.sort_by_key(|loc| (loc.x, loc.y));
+                let actual = world.sense_nearby_units(center, radius).iter()
+                    .map(|unit| unit.location().map_location().unwrap())
+                    .collect::<Vec<MapLocation>>();
+                assert_eq!(actual, expected);
+            }
+        }
+
+        // The variants filter by team and by type.
+        let center = MapLocation::new(Planet::Earth, 3, 3);
+        assert_eq!(world.sense_nearby_units(center, 1000).len(), 7);
+        assert_eq!(world.sense_nearby_units_by_team(center, 1000, Team::Red).len(), 4);
+        assert_eq!(world.sense_nearby_units_by_team(center, 1000, Team::Blue).len(), 3);
+        assert_eq!(world.sense_nearby_units_by_type(center, 1000, UnitType::Ranger).len(), 7);
+        assert_eq!(world.sense_nearby_units_by_type(center, 1000, UnitType::Worker).len(), 0);
+
+        // Nothing is sensed on the other planet.
+        let mars_loc = MapLocation::new(Planet::Mars, 3, 3);
+        assert_eq!(world.sense_nearby_units(mars_loc, 1000).len(), 0);
+    }
+
     #[test]
     fn test_sensing_with_filter() {
         // Create a world with some units on Earth, on Mars, and in space.
@@ -2482,7 +4369,7 @@ mod tests {
             initial_karbonite: vec![vec![0; 30]; 30],
         };
         let mut world = GameWorld::new(map);
-        world.get_unit_mut(3).unwrap().be_built(1000);
+        world.finish_structure(3);
 
         // Red can see 4 units initially on Earth.
         let mut red_world = world.filter(world.player_to_move);
@@ -2532,6 +4419,64 @@ mod tests {
             MapLocation::new(Planet::Earth, 11, 10)).unwrap().is_some());
     }
 
+    #[test]
+    fn test_team_array_filter() {
+        let mut world = GameWorld::test_world();
+
+        // Red writes to its Earth array, and can't write out of bounds.
+        assert!(world.write_team_array(0, 42).is_ok());
+        assert_err!(world.write_team_array(COMMUNICATION_ARRAY_LENGTH, 1), GameError::ArrayOutOfBounds);
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 42);
+
+        // Blue writes to its own Earth array, without touching Red's.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 0);
+        assert!(world.write_team_array(0, 7).is_ok());
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 42);
+
+        // Each filtered world only sees its own team's arrays.
+        let red_earth = world.filter(Player::new(Team::Red, Planet::Earth));
+        let blue_earth = world.filter(Player::new(Team::Blue, Planet::Earth));
+        let blue_mars = world.filter(Player::new(Team::Blue, Planet::Mars));
+        assert_eq!(red_earth.get_team_array(Planet::Earth)[0], 42);
+        assert_eq!(blue_earth.get_team_array(Planet::Earth)[0], 7);
+        assert_eq!(blue_mars.get_team_array(Planet::Earth)[0], 0);
+        assert!(red_earth.get_team_array(Planet::Mars).iter().all(|&value| value == 0));
+        assert_eq!(red_earth.team_states.len(), 1);
+        assert!(!red_earth.team_states.contains_key(&Team::Blue));
+
+        // The arrays survive another round trip through the filter.
+        let red_earth_again = red_earth.filter(Player::new(Team::Red, Planet::Earth));
+        assert_eq!(red_earth_again.get_team_array(Planet::Earth), red_earth.get_team_array(Planet::Earth));
+    }
+
+    #[test]
+    fn test_team_array_delay() {
+        let mut world = GameWorld::test_world();
+        assert!(world.write_team_array(3, 42).is_ok());
+
+        // Mars doesn't see the write until COMMUNICATION_DELAY rounds later.
+        for _ in 0..COMMUNICATION_DELAY - 1 {
+            world.end_round();
+            assert_eq!(world.filter(Player::new(Team::Red, Planet::Mars)).get_team_array(Planet::Earth)[3], 0);
+            assert_eq!(world.filter(Player::new(Team::Red, Planet::Earth)).get_team_array(Planet::Earth)[3], 42);
+        }
+        world.end_round();
+        let red_mars = world.filter(Player::new(Team::Red, Planet::Mars));
+        assert_eq!(red_mars.get_team_array(Planet::Earth)[3], 42);
+        assert_eq!(world.filter(Player::new(Team::Blue, Planet::Mars)).get_team_array(Planet::Earth)[3], 0);
+
+        // Overwriting the value is also delayed.
+        assert!(world.write_team_array(3, 43).is_ok());
+        world.end_round();
+        assert_eq!(world.filter(Player::new(Team::Red, Planet::Mars)).get_team_array(Planet::Earth)[3], 42);
+        for _ in 0..COMMUNICATION_DELAY - 1 {
+            world.end_round();
+        }
+        assert_eq!(world.filter(Player::new(Team::Red, Planet::Mars)).get_team_array(Planet::Earth)[3], 43);
+    }
+
     #[test]
     fn test_unit_disintegrate() {
         let mut world = GameWorld::test_world();
@@ -2547,7 +4492,7 @@ mod tests {
         assert_err!(world.disintegrate_unit(id_b), GameError::TeamNotAllowed);
 
         // But the Dev engine can "destroy" a blue unit if necessary.
-        world.destroy_unit(id_b);
+        world.destroy_unit(id_b, Destruction::Combat).unwrap();
 
         // Either way, no one can disintegrate a unit that does not exist.
         assert_err!(world.disintegrate_unit(id_b), GameError::NoSuchUnit);
@@ -2561,7 +4506,7 @@ mod tests {
         let loc_b = MapLocation::new(Planet::Earth, 0, 2);
         let loc_c = MapLocation::new(Planet::Earth, 0, 3);
         let id_a = world.create_unit(Team::Red, loc_a, UnitType::Rocket).unwrap();
-        world.get_unit_mut(id_a).unwrap().be_built(1000);
+        world.finish_structure(id_a);
         let id_b = world.create_unit(Team::Red, loc_b, UnitType::Knight).unwrap();
         world.create_unit(Team::Blue, loc_c, UnitType::Knight).unwrap();
 
@@ -2574,14 +4519,14 @@ mod tests {
         // Destroy the loaded rocket in the Dev engine.
         assert_eq!(world.my_planet().units.len(), 3);
         assert_eq!(world.my_planet().units_by_loc.len(), 2);
-        world.destroy_unit(id_a);
+        world.destroy_unit(id_a, Destruction::Combat).unwrap();
         assert_eq!(world.my_planet().units.len(), 1);
         assert_eq!(world.my_planet().units_by_loc.len(), 1);
 
         // Destroy the loaded rocket in the Blue engine.
         assert_eq!(blue_world.my_planet().units.len(), 3);
         assert_eq!(blue_world.my_planet().units_by_loc.len(), 2);
-        blue_world.destroy_unit(id_a);
+        blue_world.destroy_unit(id_a, Destruction::Combat).unwrap();
         assert_eq!(blue_world.my_planet().units.len(), 1);
         assert_eq!(blue_world.my_planet().units_by_loc.len(), 1);
     }
@@ -2633,7 +4578,7 @@ mod tests {
         // A robot cannot move again until its cooldowns are reset.
         assert![!world.is_move_ready(a)];
         assert![world.can_move(a, Direction::South)];
-        assert![world.move_robot(a, Direction::South).is_err()];
+        assert_err!(world.move_robot(a, Direction::South), GameError::Overheated { unit_id: a });
         world.end_round();
 
         // Finally, let's test that A cannot move back to its old square.
@@ -2644,16 +4589,134 @@ mod tests {
     }
 
     #[test]
-    fn test_knight_javelin() {
-        // Create the game world.
+    fn test_unit_ids_unique() {
+        // Units created for both teams on both planets never share an ID.
         let mut world = GameWorld::test_world();
+        let mut ids = FnvHashMap::default();
+        for planet in [Planet::Earth, Planet::Mars].iter() {
+            for y in 0..20 {
+                for x in 0..20 {
+                    let team = if (x + y) % 2 == 0 { Team::Red } else { Team::Blue };
+                    let location = MapLocation::new(*planet, x, y);
+                    let id = world.create_unit(team, location, UnitType::Knight).unwrap();
+                    assert_eq!(ids.insert(id, location), None);
+                }
+            }
+        }
+        for (id, location) in ids.iter() {
+            assert_eq!(world.get_unit(*id).unwrap().location(), OnMap(*location));
+        }
 
-        // Unlock knight's javelin ability through research.
-        let unlock_level = 3;
-        let rounds = 200;
+        // Once the IDs run out, units can no longer be created.
+        while world.id_generator.next_id().is_ok() {}
+        let location = MapLocation::new(Planet::Earth, 0, 0);
+        let victim = world.planet_states[&Planet::Earth].units_by_loc[&location];
+        world.destroy_unit(victim, Destruction::Combat).unwrap();
+        assert_err!(world.create_unit(Team::Red, location, UnitType::Knight),
+                    GameError::NoMoreUnitIDs);
+    }
 
-        for _ in 0..unlock_level {
-            let my_research = world.my_research_mut();
+    #[test]
+    fn test_movement_heat() {
+        let mut world = GameWorld::test_world();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 5), UnitType::Healer).unwrap();
+
+        // Moving adds the movement cooldown to the movement heat.
+        for &id in [knight, healer].iter() {
+            let cooldown = world.my_unit(id).unwrap().movement_cooldown().unwrap();
+            assert_eq!(world.my_unit(id).unwrap().movement_heat().unwrap(), 0);
+            assert!(world.move_robot(id, Direction::North).is_ok());
+            assert_eq!(world.my_unit(id).unwrap().movement_heat().unwrap(), cooldown);
+            assert!(!world.is_move_ready(id));
+        }
+
+        // The heat decays each round until it drops below the threshold.
+        for &id in [knight, healer].iter() {
+            let cooldown = world.my_unit(id).unwrap().movement_cooldown().unwrap();
+            let mut world = world.clone();
+            let mut rounds = 0;
+            while !world.is_move_ready(id) {
+                world.end_round();
+                rounds += 1;
+            }
+            assert_eq!(rounds, (cooldown - MAX_HEAT_TO_ACT) / HEAT_LOSS_PER_ROUND + 1);
+            assert!(world.move_robot(id, Direction::North).is_ok());
+        }
+
+        // A healer's longer cooldown takes more than one round to decay.
+        world.end_round();
+        assert!(world.is_move_ready(knight));
+        assert!(!world.is_move_ready(healer));
+        assert_err!(world.move_robot(healer, Direction::North), GameError::Overheated { unit_id: healer });
+        world.end_round();
+        assert!(world.is_move_ready(healer));
+    }
+
+    #[test]
+    fn test_knight_defense() {
+        let mut world = GameWorld::test_world();
+        let knight = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let mage = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 10, 5), UnitType::Mage).unwrap();
+        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 15, 5), UnitType::Ranger).unwrap();
+        let ranger_damage = world.my_unit(ranger).unwrap().damage().unwrap();
+
+        // Bring the knight down to the mage's health.
+        let defense = world.get_unit(knight).unwrap().knight_defense().unwrap() as i32;
+        let extra_health = world.get_unit(knight).unwrap().health() - world.get_unit(mage).unwrap().health();
+        world.damage_unit(knight, extra_health as i32 + defense, Destruction::Combat).unwrap();
+        assert_eq!(world.get_unit(knight).unwrap().health(), world.get_unit(mage).unwrap().health());
+
+        // A weak hit never heals the knight.
+        let health = world.get_unit(knight).unwrap().health();
+        world.damage_unit(knight, defense - 1, Destruction::Combat).unwrap();
+        assert_eq!(world.get_unit(knight).unwrap().health(), health);
+
+        // The knight survives more ranger shots than the mage.
+        let mut shots = vec![];
+        for &id in [knight, mage].iter() {
+            let mut num_shots = 0;
+            while world.get_unit(id).is_ok() {
+                world.damage_unit(id, ranger_damage, Destruction::Combat).unwrap();
+                num_shots += 1;
+            }
+            shots.push(num_shots);
+        }
+        assert_gt!(shots[0], shots[1]);
+
+        // Rocket blasts are also reduced.
+        let rocket_loc = MapLocation::new(Planet::Earth, 3, 3);
+        let rocket = world.create_unit(Team::Red, rocket_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let knight = world.create_unit(Team::Blue, rocket_loc.add(Direction::North), UnitType::Knight).unwrap();
+        let mage = world.create_unit(Team::Blue, rocket_loc.add(Direction::South), UnitType::Mage).unwrap();
+        let blast = world.my_unit(rocket).unwrap().rocket_blast_damage().unwrap();
+        let knight_health = world.get_unit(knight).unwrap().health();
+        let mage_health = world.get_unit(mage).unwrap().health();
+        assert!(world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 3, 3)).is_ok());
+        assert_eq!(world.get_unit(knight).unwrap().health(), knight_health - (blast - defense) as u32);
+        assert_eq!(world.get_unit(mage).unwrap().health(), mage_health - blast as u32);
+
+        // Researching knights increases the defense of existing knights.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.queue_research(Branch::Knight));
+        for _ in 0..cost_of(&Branch::Knight, 1).unwrap() {
+            world.end_round();
+        }
+        assert_gt!(world.get_unit(knight).unwrap().knight_defense().unwrap(), defense as u32);
+    }
+
+    #[test]
+    fn test_knight_javelin() {
+        // Create the game world.
+        let mut world = GameWorld::test_world();
+
+        // Unlock knight's javelin ability through research.
+        let unlock_level = 3;
+        let rounds = 200;
+
+        for _ in 0..unlock_level {
+            let my_research = world.my_research_mut();
             assert!(my_research.add_to_queue(&Branch::Knight));
             for _ in 0..rounds {
                 my_research.end_round();
@@ -2663,10 +4726,10 @@ mod tests {
         // Create knight and target robots
         let loc_a = MapLocation::new(Planet::Earth, 0, 0);
         let loc_b = MapLocation::new(Planet::Earth, 0, 1);
-        let loc_c = MapLocation::new(Planet::Earth, 0, 20);
+        let loc_c = MapLocation::new(Planet::Earth, 0, 19);
         let knight = world.create_unit(Team::Red, loc_a, UnitType::Knight).unwrap();
-        let robot_a = world.create_unit(Team::Red, loc_b, UnitType::Knight).unwrap();
-        let robot_b = world.create_unit(Team::Red, loc_c, UnitType::Knight).unwrap();
+        let robot_a = world.create_unit(Team::Blue, loc_b, UnitType::Knight).unwrap();
+        let robot_b = world.create_unit(Team::Blue, loc_c, UnitType::Knight).unwrap();
     
         // Knight Javelin is ready
         assert!(world.is_javelin_ready(knight));
@@ -2677,15 +4740,67 @@ mod tests {
         // Knight should be able to javelin target within range
         assert!(world.can_javelin(knight, robot_a));
 
-        // Javelin target. 
+        // Javelin target, which has none of the Red knight's defense research.
         let robot_max_health = 250;
-        let robot_damaged_health = 205; 
+        let robot_damaged_health = 195;
         assert_eq!(world.get_unit(robot_a).unwrap().health(), robot_max_health);
         assert!(world.javelin(knight, robot_a).is_ok());
         assert_eq!(world.get_unit(robot_a).unwrap().health(), robot_damaged_health);
         assert!(!world.is_javelin_ready(knight));
     }
 
+    #[test]
+    fn test_knight_javelin_through_research() {
+        // A wall on rows 9 and 10 separates the knight and the mage.
+        let mut world = GameWorld::test_world_with(TestMapConfig {
+            earth_wall: Some(TestWall::Horizontal { gap: 2 }),
+            ..TestMapConfig::default()
+        });
+        let knight_loc = MapLocation::new(Planet::Earth, 5, 8);
+        let mage_loc = MapLocation::new(Planet::Earth, 5, 11);
+        let knight = world.create_unit(Team::Red, knight_loc, UnitType::Knight).unwrap();
+        let mage = world.create_unit(Team::Blue, mage_loc, UnitType::Mage).unwrap();
+
+        // Javelin is locked until the third level of Knight research.
+        let unlock_level = 3;
+        for _ in 0..unlock_level {
+            assert!(world.queue_research(Branch::Knight));
+        }
+        for level in 1..unlock_level + 1 {
+            assert!(!world.can_javelin(knight, mage));
+            assert_err!(world.javelin(knight, mage),
+                        GameError::ResearchNotUnlocked { unit_type: UnitType::Knight });
+            for _ in 0..cost_of(&Branch::Knight, level).unwrap() {
+                world.end_round();
+            }
+        }
+        assert_eq!(world.research_info().get_level(&Branch::Knight), unlock_level);
+
+        // The knight javelins the mage to death, straight across the wall.
+        let mut javelins = 0;
+        while world.can_sense_unit(mage) {
+            assert!(world.can_javelin(knight, mage));
+            if world.is_javelin_ready(knight) {
+                assert!(world.javelin(knight, mage).is_ok());
+                javelins += 1;
+            } else {
+                world.end_round();
+            }
+        }
+        assert_gt!(javelins, 0);
+        assert!(!world.units_by_loc().contains_key(&mage_loc));
+
+        // Units in garrisons can't be targeted.
+        let factory = world.create_unit(Team::Blue, knight_loc.add(Direction::West), UnitType::Factory).unwrap();
+        let worker = world.create_unit(Team::Blue, knight_loc.add(Direction::Southwest), UnitType::Worker).unwrap();
+        world.finish_structure(factory);
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.load(factory, worker).is_ok());
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert!(!world.can_javelin(knight, worker));
+        assert_err!(world.javelin(knight, worker), GameError::UnitNotOnMap);
+    }
+
     #[test]
     fn test_mage_blink() {
         // Create the game world.
@@ -2725,6 +4840,48 @@ mod tests {
         assert!(!world.is_blink_ready(mage));
     }
 
+    #[test]
+    fn test_mage_blink_across_wall() {
+        // A map of odd height has a wall one square thick, on row 10.
+        let mut world = GameWorld::test_world_with(TestMapConfig {
+            height: MAP_HEIGHT_MIN + 1,
+            earth_wall: Some(TestWall::Horizontal { gap: 2 }),
+            ..TestMapConfig::default()
+        });
+        let mage_loc = MapLocation::new(Planet::Earth, 5, 9);
+        let across_wall = MapLocation::new(Planet::Earth, 5, 11);
+        let occupied = MapLocation::new(Planet::Earth, 6, 11);
+        let mage = world.create_unit(Team::Red, mage_loc, UnitType::Mage).unwrap();
+        world.create_unit(Team::Blue, occupied, UnitType::Knight).unwrap();
+
+        // Blink is locked until the fourth level of Mage research.
+        let unlock_level = 4;
+        for _ in 0..unlock_level {
+            assert!(world.queue_research(Branch::Mage));
+        }
+        for level in 1..unlock_level + 1 {
+            assert!(!world.can_blink(mage, across_wall));
+            assert_err!(world.blink(mage, across_wall),
+                        GameError::ResearchNotUnlocked { unit_type: UnitType::Mage });
+            for _ in 0..cost_of(&Branch::Mage, level).unwrap() {
+                world.end_round();
+            }
+        }
+
+        // The mage can't walk through the wall, nor blink into it or onto a unit.
+        assert!(!world.can_move(mage, Direction::North));
+        assert_err!(world.blink(mage, MapLocation::new(Planet::Earth, 5, 10)), GameError::LocationNotEmpty { location: MapLocation::new(Planet::Earth, 5, 10) });
+        assert_err!(world.blink(mage, occupied), GameError::LocationNotEmpty { location: occupied });
+
+        // It can blink across the wall, which updates the location index.
+        assert!(world.blink(mage, across_wall).is_ok());
+        assert_eq!(world.my_unit(mage).unwrap().location(), OnMap(across_wall));
+        assert_eq!(world.my_planet().units_by_loc.get(&across_wall), Some(&mage));
+        assert!(!world.my_planet().units_by_loc.contains_key(&mage_loc));
+        assert!(!world.is_blink_ready(mage));
+        assert_err!(world.blink(mage, MapLocation::new(Planet::Earth, 4, 11)), GameError::Overheated { unit_id: mage });
+    }
+
     #[test]
     fn test_ranger_snipe() {
         // Create the game world.
@@ -2765,6 +4922,65 @@ mod tests {
         assert_eq!(world.get_unit(robot).unwrap().health(), robot_damaged_health);
     }
 
+    #[test]
+    fn test_ranger_snipe_countdown() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Ranger]);
+
+        let loc_a = MapLocation::new(Planet::Earth, 0, 0);
+        let loc_b = MapLocation::new(Planet::Earth, 10, 10);
+        let loc_c = MapLocation::new(Planet::Earth, 15, 15);
+        let ranger = world.create_unit(Team::Red, loc_a, UnitType::Ranger).unwrap();
+        let doomed_ranger = world.create_unit(Team::Red, loc_a.add(Direction::North), UnitType::Ranger).unwrap();
+        let target_b = world.create_unit(Team::Blue, loc_b, UnitType::Knight).unwrap();
+        let target_c = world.create_unit(Team::Blue, loc_c, UnitType::Knight).unwrap();
+        let max_health = world.get_unit(target_b).unwrap().max_health();
+
+        // The target must be on the ranger's planet.
+        assert_err!(world.begin_snipe(ranger, MapLocation::new(Planet::Mars, 10, 10)),
+                    GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 10, 10) });
+
+        // Aiming exposes the countdown and target, and stops the ranger from acting.
+        assert!(world.begin_snipe(ranger, loc_b).is_ok());
+        let max_countdown = world.my_unit(ranger).unwrap().ranger_max_countdown().unwrap();
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_target_location_opt().unwrap(), Some(loc_b));
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_countdown_opt().unwrap(), Some(max_countdown));
+        assert!(!world.is_move_ready(ranger));
+        assert!(!world.is_attack_ready(ranger));
+        assert!(world.begin_snipe(doomed_ranger, loc_c).is_ok());
+
+        // Retargeting resets the countdown.
+        for _ in 0..2 {
+            world.end_round();
+        }
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_countdown_opt().unwrap(), Some(max_countdown - 2));
+        assert!(world.begin_snipe(ranger, loc_c).is_ok());
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_target_location_opt().unwrap(), Some(loc_c));
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_countdown_opt().unwrap(), Some(max_countdown));
+
+        // A ranger that dies mid-countdown never fires.
+        world.destroy_unit(doomed_ranger, Destruction::Combat).unwrap();
+
+        // The snipe lands once the countdown runs out, even when the round
+        // ends on another player's turn.
+        for _ in 0..max_countdown - 1 {
+            for _ in 0..4 {
+                world.end_turn(FILLER_TIME);
+            }
+        }
+        assert_eq!(world.get_unit(target_c).unwrap().health(), max_health);
+        for _ in 0..4 {
+            world.end_turn(FILLER_TIME);
+        }
+        assert_lt!(world.get_unit(target_c).unwrap().health(), max_health - 1);
+        assert_eq!(world.get_unit(target_b).unwrap().health(), max_health);
+        let damage = world.my_unit(ranger).unwrap().damage().unwrap();
+        let defense = world.get_unit(target_c).unwrap().knight_defense().unwrap();
+        assert_eq!(world.get_unit(target_c).unwrap().health(), max_health - (damage as u32 - defense));
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_target_location_opt().unwrap(), None);
+        assert!(world.is_move_ready(ranger));
+    }
+
     #[test]
     fn test_healer_overcharge() {
         // Create the game world.
@@ -2797,7 +5013,7 @@ mod tests {
         // Create healer and target robots.
         let loc_a = MapLocation::new(Planet::Earth, 0, 0);
         let loc_b = MapLocation::new(Planet::Earth, 0, 1);
-        let loc_c = MapLocation::new(Planet::Earth, 0, 20);
+        let loc_c = MapLocation::new(Planet::Earth, 0, 19);
         let healer = world.create_unit(Team::Red, loc_a, UnitType::Healer).unwrap();
         let robot_a = world.create_unit(Team::Red, loc_b, UnitType::Knight).unwrap();
         let robot_b = world.create_unit(Team::Red, loc_c, UnitType::Knight).unwrap();
@@ -2813,8 +5029,8 @@ mod tests {
 
         // Robot uses ability.
         let loc_d = MapLocation::new(Planet::Earth, 0, 2);
-        world.move_to(robot_b, loc_d);
-        assert!(world.javelin(robot_a, robot_b).is_ok());
+        let enemy = world.create_unit(Team::Blue, loc_d, UnitType::Knight).unwrap();
+        assert!(world.javelin(robot_a, enemy).is_ok());
         assert!(!world.get_unit(robot_a).unwrap().ok_if_ability_ready().is_ok());
 
         // Healer uses overcharge to reset robot's ablity cooldown
@@ -2822,6 +5038,265 @@ mod tests {
         assert!(world.get_unit(robot_a).unwrap().ok_if_ability_ready().is_ok());
     }
 
+    #[test]
+    fn test_overcharge_attack_twice() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Healer, Branch::Knight]);
+
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Healer).unwrap();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 6, 5), UnitType::Knight).unwrap();
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 4, 5), UnitType::Factory).unwrap();
+        let enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 7, 5), UnitType::Knight).unwrap();
+        let enemy_health = world.get_unit(enemy).unwrap().health();
+
+        // The knight attacks, and is too hot to attack again.
+        assert!(world.attack(knight, enemy).is_ok());
+        let health_after_one = world.get_unit(enemy).unwrap().health();
+        assert_lt!(health_after_one, enemy_health);
+        assert_err!(world.attack(knight, enemy), GameError::Overheated { unit_id: knight });
+
+        // Structures and enemy robots can't be overcharged.
+        assert_err!(world.overcharge(healer, factory), GameError::InappropriateUnitType);
+        assert_err!(world.overcharge(healer, enemy), GameError::TeamNotAllowed);
+
+        // After an overcharge, the knight attacks again in the same turn.
+        assert!(world.overcharge(healer, knight).is_ok());
+        assert!(world.is_attack_ready(knight));
+        assert!(world.is_move_ready(knight));
+        assert!(world.attack(knight, enemy).is_ok());
+        assert_eq!(enemy_health - world.get_unit(enemy).unwrap().health(),
+                   2 * (enemy_health - health_after_one));
+
+        // The healer can't overcharge again until its ability heat drops.
+        assert!(!world.is_overcharge_ready(healer));
+        assert_err!(world.overcharge(healer, knight), GameError::Overheated { unit_id: healer });
+    }
+
+    #[test]
+    fn test_overcharge_cancels_snipe() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Healer, Branch::Ranger]);
+
+        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Ranger).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 6, 5), UnitType::Healer).unwrap();
+        let target_loc = MapLocation::new(Planet::Earth, 15, 15);
+        let enemy = world.create_unit(Team::Blue, target_loc, UnitType::Knight).unwrap();
+        let enemy_health = world.get_unit(enemy).unwrap().health();
+
+        // Overcharge the ranger partway through its countdown.
+        assert!(world.begin_snipe(ranger, target_loc).is_ok());
+        let max_countdown = world.my_unit(ranger).unwrap().ranger_max_countdown().unwrap();
+        world.end_round();
+        assert_eq!(world.my_unit(ranger).unwrap().ranger_countdown_opt().unwrap(), Some(max_countdown - 1));
+        assert!(!world.is_move_ready(ranger));
+        assert!(world.overcharge(healer, ranger).is_ok());
+
+        // The snipe is cancelled, and the ranger's heats are reset.
+        let unit = world.my_unit(ranger).unwrap();
+        assert!(!unit.ranger_is_sniping().unwrap());
+        assert_eq!(unit.ranger_target_location_opt().unwrap(), None);
+        assert_eq!(unit.ranger_countdown_opt().unwrap(), None);
+        assert_eq!(unit.movement_heat().unwrap(), 0);
+        assert_eq!(unit.attack_heat().unwrap(), 0);
+        assert_eq!(unit.ability_heat().unwrap(), 0);
+        assert!(world.is_move_ready(ranger));
+        assert!(world.is_attack_ready(ranger));
+        assert!(world.is_begin_snipe_ready(ranger));
+
+        // The cancelled snipe never lands.
+        for _ in 0..max_countdown {
+            world.end_round();
+        }
+        assert_eq!(world.get_unit(enemy).unwrap().health(), enemy_health);
+    }
+
+    #[test]
+    fn test_rockets_in_space() {
+        let mut world = GameWorld::test_world();
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        let mut rockets = vec![];
+        for x in 0..3 {
+            let loc = MapLocation::new(Planet::Earth, 5 * x, 10);
+            let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+            world.finish_structure(rocket);
+            rockets.push(rocket);
+        }
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 9), UnitType::Knight).unwrap();
+        assert!(world.load(rockets[0], knight).is_ok());
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        let enemy_rocket = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 15, 15), UnitType::Rocket).unwrap();
+        world.finish_structure(enemy_rocket);
+        assert!(world.launch_rocket(enemy_rocket, MapLocation::new(Planet::Mars, 15, 15)).is_ok());
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert!(world.rockets_in_space().is_empty());
+
+        // Launch two rockets on different rounds.
+        assert!(world.launch_rocket(rockets[0], mars_loc).is_ok());
+        world.end_round();
+        assert!(world.launch_rocket(rockets[1], mars_loc.add(Direction::East)).is_ok());
+        let landings = world.rocket_landings().all();
+        assert_eq!(landings.len(), 2);
+        assert_ne!(landings[0].0, landings[1].0);
+
+        // The filtered world only shows my own rockets, with their garrisons.
+        let player_world = world.filter(Player::new(Team::Red, Planet::Earth));
+        let in_space = player_world.rockets_in_space();
+        assert_eq!(in_space.len(), 2);
+        for &(round, landing) in landings.iter() {
+            let rockets_landing = &in_space[&round];
+            assert_eq!(rockets_landing.len(), 1);
+            assert_eq!(rockets_landing[0].id(), landing.rocket_id);
+            assert_eq!(rockets_landing[0].location(), InSpace);
+        }
+        assert_eq!(in_space[&landings[0].0][0].structure_garrison().unwrap(), vec![knight]);
+        assert!(in_space[&landings[1].0][0].structure_garrison().unwrap().is_empty());
+
+        // Rockets destroyed in flight are skipped, and never land.
+        world.destroy_unit(rockets[1], Destruction::Combat).unwrap();
+        let in_space = world.rockets_in_space();
+        assert_eq!(in_space.len(), 1);
+        assert!(in_space.contains_key(&landings[0].0));
+        while world.round <= landings[1].0 {
+            world.end_turn(FILLER_TIME);
+        }
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert!(world.rockets_in_space().is_empty());
+        assert_eq!(world.get_unit(rockets[0]).unwrap().location(), OnMap(mars_loc));
+        assert!(world.get_unit(rockets[1]).is_err());
+    }
+
+    #[test]
+    fn test_landings_between() {
+        let mut world = GameWorld::test_world();
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+
+        // Launch a rocket on each of three rounds.
+        for x in 0..3 {
+            let loc = MapLocation::new(Planet::Earth, 5 * x, 10);
+            let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+            world.finish_structure(rocket);
+            assert!(world.launch_rocket(rocket, mars_loc.translate(x, 0)).is_ok());
+            world.end_round();
+        }
+        let landings = world.rocket_landings().all();
+        assert_eq!(landings.len(), 3);
+        let (first, last) = (landings[0].0, landings[2].0);
+
+        // Ranges include their start but not their end.
+        assert_eq!(world.landings_between(first, last + 1), landings);
+        assert_eq!(world.landings_between(first, last), &landings[..2]);
+        assert_eq!(world.landings_between(first + 1, last + 1), &landings[1..]);
+        assert_eq!(world.landings_between(0, first), vec![]);
+
+        // Landings are removed once they are processed.
+        while world.round <= first {
+            world.end_turn(FILLER_TIME);
+        }
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert_eq!(world.landings_between(0, last + 1), &landings[1..]);
+        assert_eq!(world.rocket_landings().all_grouped().len(), 2);
+    }
+
+    #[test]
+    fn test_my_units() {
+        let mut world = GameWorld::test_world();
+        let earth = |x, y| MapLocation::new(Planet::Earth, x, y);
+        let mars = |x, y| MapLocation::new(Planet::Mars, x, y);
+
+        // A rocket in space, with a knight inside.
+        let space_rocket = world.create_unit(Team::Red, earth(15, 2), UnitType::Rocket).unwrap();
+        world.finish_structure(space_rocket);
+        let space_knight = world.create_unit(Team::Red, earth(15, 3), UnitType::Knight).unwrap();
+        assert!(world.load(space_rocket, space_knight).is_ok());
+        assert!(world.launch_rocket(space_rocket, mars(10, 10)).is_ok());
+
+        // A rocket on Earth, with a knight inside, and a worker next to it.
+        let rocket = world.create_unit(Team::Red, earth(5, 5), UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let knight = world.create_unit(Team::Red, earth(5, 6), UnitType::Knight).unwrap();
+        assert!(world.load(rocket, knight).is_ok());
+        let worker = world.create_unit(Team::Red, earth(4, 4), UnitType::Worker).unwrap();
+
+        // Enemies on Earth, one near and one far away, and units on Mars.
+        let near_enemy = world.create_unit(Team::Blue, earth(3, 3), UnitType::Knight).unwrap();
+        let far_enemy = world.create_unit(Team::Blue, earth(19, 19), UnitType::Knight).unwrap();
+        let ranger = world.create_unit(Team::Red, mars(1, 1), UnitType::Ranger).unwrap();
+        let mage = world.create_unit(Team::Blue, mars(2, 2), UnitType::Mage).unwrap();
+
+        // Unit IDs are random, so compare against sorted expectations.
+        let ids = |units: Vec<Unit>| units.iter().map(|u| u.id()).collect::<Vec<UnitID>>();
+        let sorted = |mut expected: Vec<UnitID>| { expected.sort(); expected };
+
+        // The dev engine sees both planets.
+        assert_eq!(ids(world.units_on_planet(Planet::Earth)),
+                   sorted(vec![rocket, knight, worker, near_enemy, far_enemy]));
+        assert_eq!(ids(world.units_on_planet(Planet::Mars)), sorted(vec![ranger, mage]));
+
+        // A player sees its own units on its planet, including garrisons, but
+        // not its units in space, the enemy's units, or the other planet.
+        let red_earth = world.filter(Player::new(Team::Red, Planet::Earth));
+        assert_eq!(ids(red_earth.my_units()), sorted(vec![rocket, knight, worker]));
+        assert_eq!(ids(red_earth.my_units_by_type(UnitType::Knight)), vec![knight]);
+        assert_eq!(ids(red_earth.my_units_by_type(UnitType::Worker)), vec![worker]);
+        assert!(red_earth.my_units_by_type(UnitType::Ranger).is_empty());
+        assert_eq!(ids(red_earth.units_on_planet(Planet::Earth)),
+                   sorted(vec![rocket, knight, worker, near_enemy]));
+        assert!(red_earth.units_on_planet(Planet::Mars).is_empty());
+        assert_eq!(ids(red_earth.units_in_space()), sorted(vec![space_rocket, space_knight]));
+
+        let red_mars = world.filter(Player::new(Team::Red, Planet::Mars));
+        assert_eq!(ids(red_mars.my_units()), vec![ranger]);
+        assert_eq!(ids(red_mars.units_on_planet(Planet::Mars)), sorted(vec![ranger, mage]));
+        assert!(red_mars.units_on_planet(Planet::Earth).is_empty());
+
+        let blue_earth = world.filter(Player::new(Team::Blue, Planet::Earth));
+        assert_eq!(ids(blue_earth.my_units()), sorted(vec![near_enemy, far_enemy]));
+        assert_eq!(ids(blue_earth.my_units_by_type(UnitType::Knight)), sorted(vec![near_enemy, far_enemy]));
+    }
+
+    #[test]
+    fn test_units_in_space() {
+        let mut world = GameWorld::test_world();
+        let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        let rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let mut passengers = vec![];
+        for dir in [Direction::North, Direction::South].iter() {
+            let id = world.create_unit(Team::Red, earth_loc.add(*dir), UnitType::Knight).unwrap();
+            assert![world.load(rocket, id).is_ok()];
+            passengers.push(id);
+        }
+        assert![world.units_in_space().is_empty()];
+        assert![world.launch_rocket(rocket, mars_loc).is_ok()];
+
+        // The owning team sees the rocket and its garrison from both planets,
+        // and the other team sees nothing.
+        let mut expected = vec![rocket, passengers[0], passengers[1]];
+        expected.sort();
+        for planet in [Planet::Earth, Planet::Mars].iter() {
+            let red_world = world.filter(Player::new(Team::Red, *planet));
+            let ids: Vec<UnitID> = red_world.units_in_space().iter().map(|u| u.id()).collect();
+            assert_eq![ids, expected];
+            let rocket_in_space = red_world.units_in_space().into_iter()
+                .find(|u| u.id() == rocket).unwrap();
+            assert_eq![rocket_in_space.location(), InSpace];
+            assert_eq![rocket_in_space.structure_garrison().unwrap(), passengers];
+
+            let blue_world = world.filter(Player::new(Team::Blue, *planet));
+            assert![blue_world.units_in_space().is_empty()];
+        }
+
+        // Destroying the rocket removes it and its garrison everywhere.
+        world.destroy_unit(rocket, Destruction::Combat).unwrap();
+        assert![world.units_in_space().is_empty()];
+        for id in expected.iter() {
+            assert_err![world.get_unit(*id), GameError::NoSuchUnit];
+        }
+        assert![world.rockets_in_space().is_empty()];
+        assert![world.rocket_landings().all().is_empty()];
+    }
+
     #[test]
     fn test_rocket_success() {
         // Create the game world.
@@ -2829,7 +5304,7 @@ mod tests {
         let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
         let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
         let rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
-        world.get_unit_mut(rocket).unwrap().be_built(1000);
+        world.finish_structure(rocket);
 
         // Create units around the target location.
         let mut earth_bystanders: Vec<UnitID> = vec![];
@@ -2853,46 +5328,124 @@ mod tests {
         world.end_turn(FILLER_TIME);
 
         // Force land the rocket.
-        world.land_rocket(rocket, mars_loc);
+        world.land_rocket(rocket, mars_loc).unwrap();
         assert_eq![world.my_unit(rocket).unwrap().location(), OnMap(mars_loc)];
             for id in mars_bystanders.iter() {
             assert_eq![world.my_unit(*id).unwrap().health(), damaged_knight_health];
         }
     }
 
+    #[test]
+    fn test_rocket_blast() {
+        let mut world = GameWorld::test_world();
+        let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        let rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let passenger = world.create_unit(Team::Red, earth_loc.add(Direction::West), UnitType::Knight).unwrap();
+        assert![world.load(rocket, passenger).is_ok()];
+
+        // A rocket next to the takeoff site, with a knight inside.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        let neighbor = world.create_unit(Team::Blue, earth_loc.add(Direction::North), UnitType::Rocket).unwrap();
+        world.finish_structure(neighbor);
+        let neighbor_passenger = world.create_unit(Team::Blue, earth_loc.add(Direction::Northeast), UnitType::Knight).unwrap();
+        assert![world.load(neighbor, neighbor_passenger).is_ok()];
+
+        // A nearly destroyed rocket next to the takeoff site, with a knight
+        // inside, followed clockwise by a knight in the open.
+        let doomed = world.create_unit(Team::Blue, earth_loc.add(Direction::East), UnitType::Rocket).unwrap();
+        world.finish_structure(doomed);
+        let doomed_passenger = world.create_unit(Team::Blue, earth_loc.add(Direction::Southeast), UnitType::Knight).unwrap();
+        assert![world.load(doomed, doomed_passenger).is_ok()];
+        world.damage_unit(doomed, 160, Destruction::Combat).unwrap();
+        let bystander = world.create_unit(Team::Blue, earth_loc.add(Direction::Southeast), UnitType::Knight).unwrap();
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+
+        // Each adjacent unit takes the blast once, and the launching rocket
+        // and its passenger are spared.
+        assert![world.launch_rocket(rocket, mars_loc).is_ok()];
+        assert_eq![world.get_unit(rocket).unwrap().health(), 200];
+        assert_eq![world.get_unit(passenger).unwrap().health(), 250];
+        assert_eq![world.get_unit(neighbor).unwrap().health(), 150];
+        assert_eq![world.get_unit(neighbor_passenger).unwrap().health(), 250];
+        assert_eq![world.get_unit(bystander).unwrap().health(), 205];
+
+        // Destroying a rocket destroys its garrison, without disturbing the
+        // rest of the blast.
+        assert_err![world.get_unit(doomed), GameError::NoSuchUnit];
+        assert_err![world.get_unit(doomed_passenger), GameError::NoSuchUnit];
+        assert![!world.get_planet(Planet::Earth).units_by_loc.contains_key(&earth_loc.add(Direction::East))];
+
+        // Land next to a factory and a rocket.
+        let factory = world.create_unit(Team::Blue, mars_loc.add(Direction::South), UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let mars_rocket = world.create_unit(Team::Blue, mars_loc.add(Direction::Southwest), UnitType::Rocket).unwrap();
+        world.finish_structure(mars_rocket);
+        world.end_turn(FILLER_TIME);
+        world.end_turn(FILLER_TIME);
+        world.land_rocket(rocket, mars_loc).unwrap();
+        assert_eq![world.get_unit(rocket).unwrap().location(), OnMap(mars_loc)];
+        assert_eq![world.get_unit(rocket).unwrap().health(), 200];
+        assert_eq![world.get_unit(passenger).unwrap().health(), 250];
+        assert_eq![world.get_unit(factory).unwrap().health(), 250];
+        assert_eq![world.get_unit(mars_rocket).unwrap().health(), 150];
+    }
+
+    #[test]
+    fn test_failed_launch_does_no_damage() {
+        let mut world = GameWorld::test_world();
+        let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let passenger = world.create_unit(Team::Red, earth_loc.add(Direction::West), UnitType::Knight).unwrap();
+        assert![world.load(rocket, passenger).is_ok()];
+        let bystander = world.create_unit(Team::Blue, earth_loc.add(Direction::North), UnitType::Knight).unwrap();
+
+        // The rocket's garrison is missing, so it cannot move into space,
+        // and the blast never happens.
+        world.my_planet_mut().units.remove(&passenger);
+        world.flush_viewer_changes();
+        assert_err![world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 5, 5)),
+                    GameError::InternalEngineError];
+        assert_eq![world.get_unit(bystander).unwrap().health(), 250];
+        assert_eq![world.flush_viewer_changes(), vec![]];
+    }
+
     #[test]
     fn test_rocket_failure() {
         // Create the game world.
-        let mut world = GameWorld::test_world();
+        let mut world = GameWorld::test_world_with(TestMapConfig {
+            mars_wall: Some(TestWall::Vertical { gap: 2 }),
+            ..TestMapConfig::default()
+        });
         let earth_loc_a = MapLocation::new(Planet::Earth, 0, 0);
         let earth_loc_b = MapLocation::new(Planet::Earth, 0, 2);
-        let mars_loc_off_map = MapLocation::new(Planet::Mars, 10000, 10000);
-        let mars_loc_impassable = MapLocation::new(Planet::Mars, 0, 0);
-        world.planet_maps.get_mut(&Planet::Mars).unwrap().is_passable_terrain[0][0] = false;
+        let mars_loc_impassable = MapLocation::new(Planet::Mars, 9, 0);
         let mars_loc_knight = MapLocation::new(Planet::Mars, 0, 1);
         let mars_loc_factory = MapLocation::new(Planet::Mars, 0, 2);
         let rocket_a = world.create_unit(Team::Red, earth_loc_a, UnitType::Rocket).unwrap();
-        world.get_unit_mut(rocket_a).unwrap().be_built(1000);
+        world.finish_structure(rocket_a);
         let rocket_b = world.create_unit(Team::Red, earth_loc_b, UnitType::Rocket).unwrap();
-        world.get_unit_mut(rocket_b).unwrap().be_built(1000);
+        world.finish_structure(rocket_b);
         let knight = world.create_unit(Team::Blue, mars_loc_knight, UnitType::Knight).unwrap();
         let factory = world.create_unit(Team::Blue, mars_loc_factory, UnitType::Factory).unwrap();
 
         // Failed launches.
         assert![!world.can_launch_rocket(rocket_a, earth_loc_b)];
         assert_err![world.launch_rocket(rocket_a, earth_loc_b), GameError::SamePlanet];
-        assert![!world.can_launch_rocket(rocket_a, mars_loc_off_map)];
-        assert_err![world.launch_rocket(rocket_a, mars_loc_off_map), GameError::LocationOffMap];
         assert![!world.can_launch_rocket(rocket_a, mars_loc_impassable)];
-        assert_err![world.launch_rocket(rocket_a, mars_loc_impassable), GameError::LocationNotEmpty];
+        assert_err![world.launch_rocket(rocket_a, mars_loc_impassable), GameError::LocationNotEmpty { location: mars_loc_impassable }];
 
         // Rocket landing on a robot should destroy the robot.
         assert![world.can_launch_rocket(rocket_a, mars_loc_knight)];
         assert![world.launch_rocket(rocket_a, mars_loc_knight).is_ok()];
         world.end_turn(FILLER_TIME);
         world.end_turn(FILLER_TIME);
-        world.land_rocket(rocket_a, mars_loc_knight);
-        assert![world.my_unit(rocket_a).is_ok()];
+        world.land_rocket(rocket_a, mars_loc_knight).unwrap();
+        assert_eq![world.my_unit(rocket_a).unwrap().location(), OnMap(mars_loc_knight)];
+        assert_eq![world.my_planet().units_by_loc.get(&mars_loc_knight), Some(&rocket_a)];
+        assert![world.my_team().units_in_space.is_empty()];
         world.end_turn(FILLER_TIME);
         assert_err![world.my_unit(knight), GameError::NoSuchUnit];
 
@@ -2906,18 +5459,206 @@ mod tests {
         world.end_turn(FILLER_TIME);
 
         // Rocket landing on a factory should destroy both units.
-        world.land_rocket(rocket_b, mars_loc_factory);
+        world.land_rocket(rocket_b, mars_loc_factory).unwrap();
         assert_err![world.my_unit(rocket_b), GameError::NoSuchUnit];
         assert_err![world.my_unit(factory), GameError::NoSuchUnit];
     }
 
+    #[test]
+    fn test_rocket_simultaneous_landings() {
+        // Launches two red rockets at the same square in the same round, and
+        // schedules their landings in the given order of IDs.
+        let land_both = |reverse: bool| {
+            let mut world = GameWorld::test_world();
+            let mars_loc = MapLocation::new(Planet::Mars, 10, 10);
+            let mut rockets = vec![];
+            for x in [5, 15].iter() {
+                let earth_loc = MapLocation::new(Planet::Earth, *x, 10);
+                let rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
+                world.finish_structure(rocket);
+                assert![world.launch_rocket(rocket, mars_loc).is_ok()];
+                rockets.push(rocket);
+            }
+
+            let (round, _) = world.rocket_landings().all()[0];
+            let mut landings: Vec<RocketLanding> = rockets.iter()
+                .map(|id| RocketLanding::new(*id, mars_loc))
+                .collect();
+            if reverse {
+                landings.reverse();
+            }
+            for id in rockets.iter() {
+                world.get_team_mut(Team::Red).rocket_landings.remove_landings_of(*id);
+            }
+            world.get_team_mut(Team::Red).rocket_landings.add_landings(round, landings);
+
+            while world.round <= round {
+                world.end_turn(FILLER_TIME);
+            }
+            let landed: Vec<UnitID> = world.flush_viewer_changes().into_iter()
+                .filter_map(|delta| match delta {
+                    ViewerDelta::RocketLanding { rocket_id, .. } => Some(rocket_id),
+                    _ => None,
+                })
+                .collect();
+            (rockets, landed, world.get_planet(Planet::Mars).units.clone())
+        };
+
+        // The rocket with the lower ID lands first, and the other rocket
+        // lands on it, destroying both, regardless of the schedule's order.
+        let (rockets, landed, mars_units) = land_both(false);
+        let mut sorted = rockets.clone();
+        sorted.sort();
+        assert_eq!(landed, sorted);
+        assert!(mars_units.is_empty());
+        assert_eq!(land_both(true), (rockets, landed, mars_units));
+    }
+
+    #[test]
+    fn test_rocket_destroyed_in_space() {
+        let mut world = GameWorld::test_world();
+        let takeoff_loc = MapLocation::new(Planet::Earth, 10, 10);
+        let mars_loc = MapLocation::new(Planet::Mars, 10, 10);
+        let rocket = world.create_unit(Team::Red, takeoff_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let boarder = world.create_unit(Team::Red, takeoff_loc.add(Direction::North), UnitType::Knight).unwrap();
+        assert![world.load(rocket, boarder).is_ok()];
+        assert![world.launch_rocket(rocket, mars_loc).is_ok()];
+        assert_eq![world.rocket_landings().all().len(), 1];
+
+        // Disintegrating the rocket in space cancels its landing.
+        assert![world.disintegrate_unit(rocket).is_ok()];
+        assert![world.rocket_landings().all().is_empty()];
+        assert![world.get_team(Team::Red).units_in_space.is_empty()];
+
+        // A stale landing left behind is an integrity violation, but is
+        // skipped and consumed rather than panicking.
+        let next_round = world.round + 1;
+        world.get_team_mut(Team::Red).rocket_landings.add_landing(
+            next_round, RocketLanding::new(rocket, mars_loc));
+        assert![world.validate_integrity().is_err()];
+        world.round = next_round;
+        world.process_rockets(Team::Red);
+        assert_eq![world.validate_integrity(), Ok(())];
+        for _ in 0..8 {
+            world.end_turn(FILLER_TIME);
+            for player in Player::all() {
+                world.filter(player);
+            }
+        }
+        assert_err![world.get_unit(rocket), GameError::NoSuchUnit];
+        assert![world.get_planet(Planet::Mars).units_by_loc.get(&mars_loc).is_none()];
+    }
+
+    #[test]
+    fn test_rocket_lands_on_factory() {
+        let mut world = GameWorld::test_world();
+        let takeoff_loc = MapLocation::new(Planet::Earth, 10, 10);
+        let mars_loc = MapLocation::new(Planet::Mars, 10, 10);
+        let rocket = world.create_unit(Team::Red, takeoff_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let boarder = world.create_unit(Team::Red, takeoff_loc.add(Direction::North), UnitType::Knight).unwrap();
+        let factory = world.create_unit(Team::Blue, mars_loc, UnitType::Factory).unwrap();
+        assert![world.load(rocket, boarder).is_ok()];
+        assert![world.launch_rocket(rocket, mars_loc).is_ok()];
+
+        // Duplicate the landing, as a buggy delta might, both in the same
+        // round and a round later.
+        let (round, landing) = world.rocket_landings().all()[0];
+        world.get_team_mut(Team::Red).rocket_landings.add_landing(round, landing);
+        world.get_team_mut(Team::Red).rocket_landings.add_landing(round + 1, landing);
+
+        // The rocket, its garrison and the factory are destroyed, and every
+        // landing of the rocket goes with them.
+        while world.round <= round + 5 {
+            world.end_turn(FILLER_TIME);
+            for player in Player::all() {
+                world.filter(player);
+            }
+        }
+        for id in [rocket, boarder, factory].iter() {
+            assert_err![world.get_unit(*id), GameError::NoSuchUnit];
+        }
+        assert![world.get_team(Team::Red).rocket_landings.all().is_empty()];
+        assert![world.get_planet(Planet::Mars).units_by_loc.get(&mars_loc).is_none()];
+    }
+
+    #[test]
+    fn test_validate_integrity() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let knight = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Knight).unwrap();
+        assert![world.load(factory, knight).is_ok()];
+        let rocket = world.create_unit(Team::Red, loc.add(Direction::South), UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        assert![world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 1, 1)).is_ok()];
+        assert_eq![world.validate_integrity(), Ok(())];
+
+        // Each corruption is reported on its own.
+        let check = |corrupt: &dyn Fn(&mut GameWorld), expected: &str| {
+            let mut world = world.clone();
+            corrupt(&mut world);
+            let violations = world.validate_integrity().unwrap_err();
+            assert_eq!(violations.len(), 1, "{:?}", violations);
+            assert!(violations[0].contains(expected), "{:?}", violations);
+        };
+        check(&|world| { world.my_planet_mut().units_by_loc.remove(&loc); },
+              "missing from the occupancy grid");
+        check(&|world| { world.my_planet_mut().units_by_loc.insert(loc.add(Direction::East), 0); },
+              "where it is not");
+        check(&|world| { world.my_unit_mut(factory).unwrap().unload_unit(); },
+              "is not in the garrison of its structure");
+        check(&|world| { world.get_team_mut(Team::Red).rocket_landings
+                              .add_landing(400, RocketLanding::new(knight, loc)); },
+              "scheduled to land");
+        check(&|world| { world.get_team_mut(Team::Red).stats.units_value += 1; },
+              "stats value its units");
+        check(&|world| { world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+                         world.team_states.remove(&Team::Blue); },
+              "its team has no state");
+    }
+
+    #[test]
+    fn test_rocket_off_map() {
+        let mut world = GameWorld::test_world();
+        let takeoff_loc = MapLocation::new(Planet::Earth, 10, 10);
+        let mars_loc_off_map = MapLocation::new(Planet::Mars, 10000, 10000);
+        let rocket = world.create_unit(Team::Red, takeoff_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let boarder = world.create_unit(Team::Red, takeoff_loc.add(Direction::North), UnitType::Knight).unwrap();
+        let bystander = world.create_unit(Team::Blue, takeoff_loc.add(Direction::South), UnitType::Knight).unwrap();
+        assert![world.load(rocket, boarder).is_ok()];
+        assert![world.winner().is_none()];
+        let bystander_health = world.get_unit(bystander).unwrap().health();
+
+        // Launching off the map succeeds and still damages adjacent units.
+        assert![world.can_launch_rocket(rocket, mars_loc_off_map)];
+        assert![world.launch_rocket(rocket, mars_loc_off_map).is_ok()];
+        assert_lt![world.get_unit(bystander).unwrap().health(), bystander_health];
+
+        // The rocket and its garrison are gone for good.
+        assert_err![world.get_unit(rocket), GameError::NoSuchUnit];
+        assert_err![world.get_unit(boarder), GameError::NoSuchUnit];
+        assert![world.get_team(Team::Red).rocket_landings.all().is_empty()];
+        assert![world.is_occupiable(takeoff_loc).unwrap()];
+        for _ in 0..300 {
+            world.end_round();
+        }
+        assert_err![world.get_unit(rocket), GameError::NoSuchUnit];
+
+        // Red has no units left, so blue wins.
+        assert_eq![world.winner(), Some(Team::Blue)];
+    }
+
     #[test]
     fn test_rocket_load() {
         // Create the game world and the rocket for this test.
         let mut world = GameWorld::test_world();
         let takeoff_loc = MapLocation::new(Planet::Earth, 10, 10);        
         let rocket = world.create_unit(Team::Red, takeoff_loc, UnitType::Rocket).unwrap();
-        world.get_unit_mut(rocket).unwrap().be_built(1000);
+        world.finish_structure(rocket);
 
         // Correct loading.
         let valid_boarder = world.create_unit(Team::Red, takeoff_loc.add(Direction::North), UnitType::Knight).unwrap();
@@ -2935,7 +5676,7 @@ mod tests {
         let invalid_boarder_already_moved = invalid_boarder_too_far;
         assert![!world.is_move_ready(invalid_boarder_already_moved)];
         assert![!world.can_load(rocket, invalid_boarder_already_moved)];
-        assert_err![world.load(rocket, invalid_boarder_already_moved), GameError::Overheated];
+        assert_err![world.load(rocket, invalid_boarder_already_moved), GameError::Overheated { unit_id: invalid_boarder_already_moved }];
 
         // Factories and rockets cannot board rockets.
         let invalid_boarder_factory = world.create_unit(Team::Red, takeoff_loc.add(Direction::Southeast), UnitType::Factory).unwrap();
@@ -2965,19 +5706,101 @@ mod tests {
         assert_err![world.load(blue_rocket, invalid_boarder_wrong_team), GameError::TeamNotAllowed];
     }
 
+    #[test]
+    fn test_garrisoned_units_are_frozen() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Knight, Branch::Healer, Branch::Ranger]);
+
+        // Loading the knight takes it off the map, along with its vision.
+        let red = Player::new(Team::Red, Planet::Earth);
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let knight = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Knight).unwrap();
+        let count = world.vision[&red][17][11];
+        assert!(world.load(factory, knight).is_ok());
+        assert_eq!(world.vision[&red][17][11], count - 1);
+        assert_eq!(*world.vision[&red], world.vision_counts(red));
+        assert!(!world.units_by_loc().contains_key(&loc.add(Direction::East)));
+
+        let healer = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Healer).unwrap();
+        let rocket = world.create_unit(Team::Red, loc.add(Direction::Northeast), UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let mage = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 5), UnitType::Mage).unwrap();
+        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Ranger).unwrap();
+        let enemy = world.create_unit(Team::Blue, loc.add(Direction::South), UnitType::Knight).unwrap();
+
+        // The garrisoned knight can't act.
+        assert_err!(world.move_robot(knight, Direction::East), GameError::UnitNotOnMap);
+        assert_err!(world.attack(knight, enemy), GameError::UnitNotOnMap);
+        assert_err!(world.javelin(knight, enemy), GameError::UnitNotOnMap);
+        assert_err!(world.load(rocket, knight), GameError::UnitNotOnMap);
+
+        // Nor can either team act on it.
+        assert_err!(world.heal(healer, knight), GameError::UnitNotOnMap);
+        assert_err!(world.overcharge(healer, knight), GameError::UnitNotOnMap);
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert_err!(world.attack(enemy, knight), GameError::UnitNotOnMap);
+        assert_err!(world.javelin(enemy, knight), GameError::UnitNotOnMap);
+        world.player_to_move = red;
+
+        // Splash damage and snipes hit the factory, but not the knight inside.
+        let health = world.my_unit(knight).unwrap().health();
+        let factory_health = world.my_unit(factory).unwrap().health();
+        assert!(world.attack(mage, enemy).is_ok());
+        assert_lt!(world.my_unit(factory).unwrap().health(), factory_health);
+        let factory_health = world.my_unit(factory).unwrap().health();
+        assert!(world.begin_snipe(ranger, loc).is_ok());
+        let max_countdown = world.my_unit(ranger).unwrap().ranger_max_countdown().unwrap();
+        for _ in 0..max_countdown {
+            world.end_round();
+        }
+        assert_lt!(world.my_unit(factory).unwrap().health(), factory_health);
+        assert_eq!(world.my_unit(knight).unwrap().health(), health);
+
+        // A robot in a rocket is frozen the same way.
+        let rider = world.create_unit(Team::Red, loc.add(Direction::Northeast).add(Direction::East), UnitType::Knight).unwrap();
+        assert!(world.load(rocket, rider).is_ok());
+        assert_err!(world.move_robot(rider, Direction::East), GameError::UnitNotOnMap);
+        assert_err!(world.attack(rider, enemy), GameError::UnitNotOnMap);
+        assert_err!(world.load(factory, rider), GameError::UnitNotOnMap);
+        assert_err!(world.heal(healer, rider), GameError::UnitNotOnMap);
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert_err!(world.attack(enemy, rider), GameError::UnitNotOnMap);
+        world.player_to_move = red;
+
+        // Once the rocket is in space, neither it nor its rider can act, and
+        // it can't launch again.
+        assert!(world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 5, 5)).is_ok());
+        assert_eq!(world.my_unit(rocket).unwrap().location(), Location::InSpace);
+        assert!(!world.units_by_loc().contains_key(&loc.add(Direction::Northeast)));
+        assert_err!(world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 6, 6)), GameError::UnitNotOnMap);
+        assert_err!(world.unload(rocket, Direction::North), GameError::UnitNotOnMap);
+        assert_err!(world.load(rocket, healer), GameError::UnitNotOnMap);
+        assert_err!(world.move_robot(rider, Direction::East), GameError::UnitNotOnMap);
+        assert_err!(world.attack(rider, enemy), GameError::UnitNotOnMap);
+
+        // The other team can't even see the rocket in space.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert_err!(world.attack(enemy, rocket), GameError::NoSuchUnit);
+        world.player_to_move = red;
+    }
+
     #[test]
     fn test_rocket_unload() {
         // Create the game world and the rocket for this test.
         let mut world = GameWorld::test_world();
         let takeoff_loc = MapLocation::new(Planet::Earth, 10, 10);        
         let rocket = world.create_unit(Team::Red, takeoff_loc, UnitType::Rocket).unwrap();
-        world.get_unit_mut(rocket).unwrap().be_built(1000);
+        world.finish_structure(rocket);
         
         // Load the rocket with robots.
+        let mut robots = vec![];
         for _ in 0..2 {
             let robot = world.create_unit(Team::Red, takeoff_loc.add(Direction::North), UnitType::Knight).unwrap();
             assert![world.can_load(rocket, robot)];
             assert![world.load(rocket, robot).is_ok()];
+            robots.push(robot);
         }
 
         // Fly the rocket to Mars.
@@ -2987,11 +5810,11 @@ mod tests {
         // Go forward two turns so that we're on Mars.
         world.end_turn(FILLER_TIME);
         world.end_turn(FILLER_TIME);
-        world.land_rocket(rocket, landing_loc);
+        world.land_rocket(rocket, landing_loc).unwrap();
 
         // Cannot unload in the same round. But can after one turn.
         assert![!world.can_unload(rocket, Direction::North)];
-        assert_err![world.unload(rocket, Direction::North), GameError::Overheated];
+        assert_err![world.unload(rocket, Direction::North), GameError::Overheated { unit_id: robots[0] }];
         world.end_round();
 
         // Correct unloading.
@@ -3003,13 +5826,13 @@ mod tests {
         assert![world.unload(rocket, Direction::North).is_err()];
 
         // Cannot unload into an impassable square.
-        world.planet_maps.get_mut(&Planet::Mars).unwrap().is_passable_terrain[0][1] = false;
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Mars).unwrap()).is_passable_terrain[0][1] = false;
         assert![!world.can_unload(rocket, Direction::East)];
-        assert_err![world.unload(rocket, Direction::East), GameError::LocationNotEmpty];
+        assert_err![world.unload(rocket, Direction::East), GameError::LocationNotEmpty { location: MapLocation::new(Planet::Mars, 1, 0) }];
 
         // Error unloading off the map.
         assert![!world.can_unload(rocket, Direction::South)];
-        assert_err![world.unload(rocket, Direction::South), GameError::LocationOffMap];
+        assert_err![world.unload(rocket, Direction::South), GameError::LocationOffMap { location: MapLocation::new(Planet::Mars, 0, -1) }];
 
         // Error unloading not a rocket.
         let robot_loc = MapLocation::new(Planet::Mars, 10, 10);
@@ -3018,7 +5841,7 @@ mod tests {
         assert_err![world.unload(robot, Direction::East), GameError::InappropriateUnitType];
 
         // Correct unloading, again.
-        world.planet_maps.get_mut(&Planet::Mars).unwrap().is_passable_terrain[0][1] = true;
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Mars).unwrap()).is_passable_terrain[0][1] = true;
         assert![world.can_unload(rocket, Direction::East)];
         assert![world.unload(rocket, Direction::East).is_ok()];
 
@@ -3047,7 +5870,7 @@ mod tests {
             assert![!world.can_harvest(worker, Direction::South)];
             assert_eq![world.karbonite_at(deposit).unwrap(), expected_karbonite[i+1]];
             assert_eq![world.karbonite(), expected_team_karbonite[i+1]];
-            world.destroy_unit(worker);
+            world.destroy_unit(worker, Destruction::Combat).unwrap();
         }
 
         // The deposit has been mined out, so it cannot be harvested.
@@ -3063,6 +5886,35 @@ mod tests {
         assert![!world.can_harvest(worker, Direction::West)];
     }
 
+    #[test]
+    fn test_worker_harvest_once_per_round() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let harvest_amount = world.my_unit(worker).unwrap().worker_harvest_amount().unwrap();
+        let deposit = world.karbonite_at(loc).unwrap();
+
+        // The pool increases by exactly the amount taken from the deposit.
+        let karbonite = world.karbonite();
+        assert!(world.harvest(worker, Direction::Center).is_ok());
+        let mined = cmp::min(harvest_amount, deposit);
+        assert_eq!(world.karbonite(), karbonite + mined);
+        assert_eq!(world.karbonite_at(loc).unwrap(), deposit - mined);
+
+        // The worker can't harvest again, even from another deposit.
+        assert_err!(world.harvest(worker, Direction::North), GameError::Overheated { unit_id: worker });
+
+        // It can harvest again next round, until the deposit is empty.
+        world.end_round();
+        while world.karbonite_at(loc).unwrap() > 0 {
+            assert!(world.harvest(worker, Direction::Center).is_ok());
+            world.end_round();
+        }
+        assert!(!world.can_harvest(worker, Direction::Center));
+        assert_err!(world.harvest(worker, Direction::Center), GameError::KarboniteDepositEmpty);
+        assert_eq!(world.karbonite_at(loc).unwrap(), 0);
+    }
+
     #[test]
     fn test_worker_blueprint_and_build() {
         // Create the game world.
@@ -3091,7 +5943,7 @@ mod tests {
 
         // The factory cannot be built by the same worker, because it has already acted.
         assert![!world.can_build(worker_a, factory)];
-        world.destroy_unit(worker_a);
+        world.destroy_unit(worker_a, Destruction::Combat).unwrap();
 
         // It takes 45 build actions, with default research, to complete a factory.
         for i in 0..45 {
@@ -3111,14 +5963,14 @@ mod tests {
 
             // The worker has already acted, and cannot build again.
             assert![!world.can_build(worker_b, factory)];
-            world.destroy_unit(worker_b);
+            world.destroy_unit(worker_b, Destruction::Combat).unwrap();
         }
         assert![world.get_unit(factory).unwrap().structure_is_built().unwrap()];
 
         // Subsequent attempts to build the factory should fail.
         let worker_c = world.create_unit(Team::Red, factory_loc.add(Direction::North), UnitType::Worker).unwrap();
         assert![!world.can_build(worker_c, factory)];
-        world.destroy_unit(worker_c);
+        world.destroy_unit(worker_c, Destruction::Combat).unwrap();
 
         // It should not be possible to blueprint a rocket until researching Rocketry.
         let rocket_loc = MapLocation::new(Planet::Earth, 1, 0);
@@ -3143,12 +5995,168 @@ mod tests {
         assert![!world.can_blueprint(worker_e, UnitType::Factory, Direction::South)];
     }
 
+    #[test]
+    fn test_build_with_multiple_workers() {
+        let mut world = GameWorld::test_world();
+        let factory_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let workers = vec![
+            world.create_unit(Team::Red, factory_loc.add(Direction::North), UnitType::Worker).unwrap(),
+            world.create_unit(Team::Red, factory_loc.add(Direction::East), UnitType::Worker).unwrap(),
+            world.create_unit(Team::Red, factory_loc.add(Direction::South), UnitType::Worker).unwrap(),
+        ];
+        assert!(world.blueprint(workers[0], UnitType::Factory, Direction::South).is_ok());
+        let factory = world.my_planet().units_by_loc[&factory_loc];
+        world.end_round();
+
+        // Each worker contributes once per round, until the factory is done.
+        let max_health = world.my_unit(factory).unwrap().max_health();
+        let mut rounds = 0;
+        while !world.my_unit(factory).unwrap().structure_is_built().unwrap() {
+            for &worker in workers.iter() {
+                if world.my_unit(factory).unwrap().structure_is_built().unwrap() {
+                    assert!(!world.can_build(worker, factory));
+                    continue;
+                }
+                let old_health = world.my_unit(factory).unwrap().health();
+                let build_health = world.my_unit(worker).unwrap().worker_build_health().unwrap();
+                assert!(world.build(worker, factory).is_ok());
+                assert_err!(world.build(worker, factory), GameError::Overheated { unit_id: worker });
+
+                // The built flag flips exactly when the health hits the max.
+                let new_health = world.my_unit(factory).unwrap().health();
+                assert_eq!(new_health, cmp::min(old_health + build_health, max_health));
+                assert_eq!(world.my_unit(factory).unwrap().structure_is_built().unwrap(),
+                           new_health == max_health);
+            }
+            world.end_round();
+            rounds += 1;
+        }
+        assert_gt!(rounds, 1);
+
+        // A completed structure can no longer be built.
+        assert!(!world.can_build(workers[0], factory));
+        assert_err!(world.build(workers[0], factory), GameError::StructureAlreadyBuilt);
+    }
+
+    #[test]
+    fn test_blueprint_errors() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Knight).unwrap();
+
+        // Blueprinting fails without enough karbonite, or onto an occupied square.
+        let cost = UnitType::Factory.blueprint_cost().unwrap();
+        world.get_team_mut(Team::Red).karbonite = cost - 1;
+        assert_err!(world.blueprint(worker, UnitType::Factory, Direction::North), GameError::InsufficientKarbonite);
+        world.get_team_mut(Team::Red).karbonite = cost;
+        assert_err!(world.blueprint(worker, UnitType::Factory, Direction::East), GameError::LocationNotEmpty { location: loc.add(Direction::East) });
+        assert_err!(world.blueprint(worker, UnitType::Rocket, Direction::North),
+                    GameError::ResearchNotUnlocked { unit_type: UnitType::Rocket });
+
+        // A successful blueprint deducts the cost and starts out unbuilt.
+        assert!(world.blueprint(worker, UnitType::Factory, Direction::North).is_ok());
+        assert_eq!(world.karbonite(), 0);
+        let factory = world.my_planet().units_by_loc[&loc.add(Direction::North)];
+        let factory = world.my_unit(factory).unwrap();
+        assert!(!factory.structure_is_built().unwrap());
+        assert_lt!(factory.health(), factory.max_health());
+        assert_eq!(world.karbonite_at(loc.add(Direction::North)).unwrap(), 0);
+
+        // The worker has acted for this round.
+        world.get_team_mut(Team::Red).karbonite = cost;
+        assert_err!(world.blueprint(worker, UnitType::Factory, Direction::South), GameError::Overheated { unit_id: worker });
+
+        // Nothing can be blueprinted on Mars.
+        world.player_to_move = Player::new(Team::Red, Planet::Mars);
+        let mars_worker = world.create_unit(Team::Red, MapLocation::new(Planet::Mars, 5, 5), UnitType::Worker).unwrap();
+        assert_err!(world.blueprint(mars_worker, UnitType::Factory, Direction::North), GameError::CannotBuildOnMars);
+    }
+
+    #[test]
+    fn test_blueprint_rules() {
+        let mut world = GameWorld::test_world();
+        let red = Player::new(Team::Red, Planet::Earth);
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let healer = world.create_unit(Team::Red, loc.add(Direction::South), UnitType::Healer).unwrap();
+        assert!(world.blueprint(worker, UnitType::Factory, Direction::North).is_ok());
+        let factory = world.my_planet().units_by_loc[&loc.add(Direction::North)];
+
+        // The blueprint blocks movement, and can only be built, not repaired
+        // or healed.
+        assert!(!world.can_move(worker, Direction::North));
+        assert!(!world.can_move(healer, Direction::North));
+        world.end_round();
+        assert_err!(world.repair(worker, factory), GameError::StructureNotYetBuilt);
+        assert_err!(world.heal(healer, factory), GameError::InappropriateUnitType);
+
+        // The blueprint has no vision, but its team still knows where it is.
+        world.destroy_unit(worker, Destruction::Combat).unwrap();
+        world.destroy_unit(healer, Destruction::Combat).unwrap();
+        assert_eq!(*world.vision[&red], world.vision_counts(red));
+        let filtered = world.filter(red);
+        assert!(!filtered.can_sense_location(loc.add(Direction::North)));
+        assert!(filtered.can_sense_unit(factory));
+
+        // Once built, the factory can see around itself.
+        world.finish_structure(factory);
+        assert_eq!(*world.vision[&red], world.vision_counts(red));
+        assert!(world.filter(red).can_sense_location(loc.add(Direction::North)));
+    }
+
+    #[test]
+    fn test_enemy_destroys_blueprint() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let knight = world.create_unit(Team::Red, loc, UnitType::Knight).unwrap();
+        let factory = world.create_unit(Team::Blue, loc.add(Direction::East), UnitType::Factory).unwrap();
+
+        // The blueprint starts with little health, so two hits finish it off.
+        assert!(world.attack(knight, factory).is_ok());
+        assert!(world.can_sense_unit(factory));
+        while !world.is_attack_ready(knight) {
+            world.end_round();
+        }
+        assert!(world.attack(knight, factory).is_ok());
+        assert!(!world.can_sense_unit(factory));
+        assert!(!world.units_by_loc().contains_key(&loc.add(Direction::East)));
+    }
+
+    #[test]
+    fn test_unbuilt_structures_are_inert() {
+        let mut world = GameWorld::test_world();
+
+        // An unbuilt rocket can't be loaded or launched.
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+        let knight = world.create_unit(Team::Red, loc.add(Direction::West), UnitType::Knight).unwrap();
+        let mars = MapLocation::new(Planet::Mars, 5, 5);
+        assert_err!(world.load(rocket, knight), GameError::StructureNotYetBuilt);
+        assert_err!(world.launch_rocket(rocket, mars), GameError::StructureNotYetBuilt);
+        world.finish_structure(rocket);
+        assert!(world.launch_rocket(rocket, mars).is_ok());
+
+        // An unbuilt factory blocks a built factory from unloading onto it.
+        let loc = MapLocation::new(Planet::Earth, 0, 0);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let knight = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Knight).unwrap();
+        assert!(world.load(factory, knight).is_ok());
+        let blueprint = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Factory).unwrap();
+        world.end_round();
+        assert_err!(world.unload(factory, Direction::North),
+                    GameError::LocationNotEmpty { location: loc.add(Direction::North) });
+        assert_err!(world.produce_robot(blueprint, UnitType::Knight), GameError::StructureNotYetBuilt);
+        assert!(world.unload(factory, Direction::East).is_ok());
+    }
+
     #[test]
     fn test_factory_production() {
         let mut world = GameWorld::test_world();
         let loc = MapLocation::new(Planet::Earth, 10, 10);
         let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
-        world.get_unit_mut(factory).unwrap().be_built(1000);
+        world.finish_structure(factory);
         let mage_cost = UnitType::Mage.factory_cost().unwrap();
 
         // The factory can produce a robot only if it's not already busy.
@@ -3173,16 +6181,232 @@ mod tests {
     }
 
     #[test]
-    fn test_robot_attack_and_heal() {
+    fn test_factory_production_refunded_without_ids() {
         let mut world = GameWorld::test_world();
-        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Ranger).unwrap();
-        let worker_in_range = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 0), UnitType::Worker).unwrap();
-        let worker_out_of_range = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 0), UnitType::Worker).unwrap();
-
-        // The ranger can attack the adjacent worker, but not the non-adjacent worker.
-        assert![world.can_attack(ranger, worker_in_range)];
-        assert![!world.can_attack(ranger, worker_out_of_range)];
-        assert![world.attack(ranger, worker_in_range).is_ok()];
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let max_rounds = world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap();
+        let mage_cost = UnitType::Mage.factory_cost().unwrap();
+
+        // The game runs out of IDs while the mage is being produced.
+        assert!(world.produce_robot(factory, UnitType::Mage).is_ok());
+        while world.id_generator.next_id().is_ok() {}
+        for _ in 0..max_rounds - 1 {
+            world.end_round();
+        }
+        world.flush_viewer_changes();
+        world.my_team_mut().karbonite = 0;
+        world.end_round();
+
+        // The mage is never produced, and its cost is refunded.
+        assert_eq!(world.my_unit(factory).unwrap().structure_garrison().unwrap().len(), 0);
+        assert!(!world.my_unit(factory).unwrap().is_factory_producing().unwrap());
+        assert!(world.flush_viewer_changes().contains(&ViewerDelta::ProductionRefunded {
+            factory_id: factory,
+            unit_type: UnitType::Mage,
+            karbonite: mage_cost,
+        }));
+        assert_eq!(world.my_team().karbonite, KARBONITE_PER_ROUND + mage_cost);
+    }
+
+    #[test]
+    fn test_factory_production_stalls_when_full() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 10, 10);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        world.my_team_mut().karbonite = 1000;
+        let max_rounds = world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap();
+        let capacity = world.my_unit(factory).unwrap().structure_max_capacity().unwrap();
+
+        // Fill the factory's garrison with knights.
+        for _ in 0..capacity {
+            assert!(world.produce_robot(factory, UnitType::Knight).is_ok());
+            assert_eq!(world.my_unit(factory).unwrap().factory_unit_type_opt().unwrap(), Some(UnitType::Knight));
+            for _ in 0..max_rounds {
+                world.end_round();
+            }
+        }
+        assert_eq!(world.my_unit(factory).unwrap().structure_garrison().unwrap().len(), capacity);
+
+        // The next robot is paid for, but stalls rather than being dropped.
+        let karbonite = world.my_team().karbonite;
+        assert!(world.produce_robot(factory, UnitType::Mage).is_ok());
+        assert_eq!(world.my_team().karbonite, karbonite - UnitType::Mage.factory_cost().unwrap());
+        for _ in 0..max_rounds * 2 {
+            world.end_round();
+        }
+        assert_eq!(world.my_unit(factory).unwrap().structure_garrison().unwrap().len(), capacity);
+        assert_eq!(world.my_unit(factory).unwrap().factory_unit_type_opt().unwrap(), Some(UnitType::Mage));
+        assert_eq!(world.my_unit(factory).unwrap().factory_rounds_left().unwrap(), 1);
+        assert_err!(world.produce_robot(factory, UnitType::Mage), GameError::FactoryBusy);
+
+        // Unloading a knight makes room, and the mage is added next round.
+        let first_knight = world.my_unit(factory).unwrap().structure_garrison().unwrap()[0];
+        assert!(world.unload(factory, Direction::North).is_ok());
+        assert_eq!(world.my_unit(first_knight).unwrap().location(), OnMap(loc.add(Direction::North)));
+        world.end_round();
+        let garrison = world.my_unit(factory).unwrap().structure_garrison().unwrap();
+        assert_eq!(garrison.len(), capacity);
+        assert_eq!(world.my_unit(garrison[capacity - 1]).unwrap().unit_type(), UnitType::Mage);
+        assert!(!world.my_unit(factory).unwrap().is_factory_producing().unwrap());
+    }
+
+    #[test]
+    fn test_factory_unload() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 0, 0);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+
+        // An unbuilt factory can't produce or unload anything.
+        assert_err!(world.produce_robot(factory, UnitType::Knight), GameError::StructureNotYetBuilt);
+        assert_err!(world.unload(factory, Direction::North), GameError::StructureNotYetBuilt);
+
+        // Produce two knights.
+        world.finish_structure(factory);
+        assert_err!(world.unload(factory, Direction::North), GameError::GarrisonEmpty);
+        let max_rounds = world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap();
+        for _ in 0..2 {
+            assert!(world.produce_robot(factory, UnitType::Knight).is_ok());
+            for _ in 0..max_rounds {
+                world.end_round();
+            }
+        }
+        let garrison = world.my_unit(factory).unwrap().structure_garrison().unwrap();
+        assert_eq!(garrison.len(), 2);
+
+        // Squares off the map or occupied can't be unloaded into.
+        assert_err!(world.unload(factory, Direction::South), GameError::LocationOffMap { location: loc.add(Direction::South) });
+        world.create_unit(Team::Blue, loc.add(Direction::North), UnitType::Knight).unwrap();
+        assert!(!world.can_unload(factory, Direction::North));
+        assert_err!(world.unload(factory, Direction::North), GameError::LocationNotEmpty { location: loc.add(Direction::North) });
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Earth).unwrap()).is_passable_terrain[1][1] = false;
+        assert_err!(world.unload(factory, Direction::Northeast), GameError::LocationNotEmpty { location: loc.add(Direction::Northeast) });
+
+        // With every adjacent square blocked, nothing can be unloaded.
+        let worker = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Worker).unwrap();
+        for direction in Direction::all().iter() {
+            assert!(!world.can_unload(factory, *direction));
+        }
+
+        // Knights are unloaded in the order they were produced.
+        world.destroy_unit(worker, Destruction::Combat).unwrap();
+        assert!(world.unload(factory, Direction::East).is_ok());
+        assert_eq!(world.my_unit(garrison[0]).unwrap().location(), OnMap(loc.add(Direction::East)));
+        assert_eq!(world.my_unit(factory).unwrap().structure_garrison().unwrap(), vec![garrison[1]]);
+        assert!(!world.is_move_ready(garrison[0]));
+        assert!(world.move_robot(garrison[0], Direction::East).is_err());
+
+        // The second knight can't unload onto the first.
+        assert_err!(world.unload(factory, Direction::East), GameError::LocationNotEmpty { location: loc.add(Direction::East) });
+        world.end_round();
+        assert!(world.move_robot(garrison[0], Direction::East).is_ok());
+        assert!(world.unload(factory, Direction::East).is_ok());
+        assert_eq!(world.my_unit(garrison[1]).unwrap().location(), OnMap(loc.add(Direction::East)));
+        assert_err!(world.unload(factory, Direction::East), GameError::GarrisonEmpty);
+    }
+
+    #[test]
+    fn test_unloaded_robots_wait_a_round() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Healer, Branch::Knight]);
+
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let factory = world.create_unit(Team::Red, loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let max_rounds = world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap();
+        for _ in 0..2 {
+            assert!(world.produce_robot(factory, UnitType::Knight).is_ok());
+            for _ in 0..max_rounds {
+                world.end_round();
+            }
+        }
+        let knights = world.my_unit(factory).unwrap().structure_garrison().unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 4, 4), UnitType::Healer).unwrap();
+        let enemy = world.create_unit(Team::Blue, loc.add(Direction::Northeast), UnitType::Knight).unwrap();
+
+        // Neither produced knight can act in the round it is unloaded.
+        assert!(world.unload(factory, Direction::East).is_ok());
+        assert!(world.unload(factory, Direction::North).is_ok());
+        for &knight in knights.iter() {
+            assert!(!world.is_move_ready(knight));
+            assert!(!world.is_attack_ready(knight));
+            assert_err!(world.attack(knight, enemy), GameError::Overheated { unit_id: knight });
+        }
+
+        // Overcharging deliberately lifts the restriction.
+        assert!(world.overcharge(healer, knights[0]).is_ok());
+        assert!(world.attack(knights[0], enemy).is_ok());
+
+        // Otherwise, the knight acts in the next round.
+        world.end_round();
+        assert!(world.attack(knights[1], enemy).is_ok());
+    }
+
+    #[test]
+    fn test_structure_accessors() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let enemy_factory = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 15, 15), UnitType::Factory).unwrap();
+
+        // The garrison lists robots in the order they were loaded.
+        let mut robots = vec![];
+        for &direction in [Direction::East, Direction::North, Direction::West].iter() {
+            let robot = world.create_unit(Team::Red, loc.add(direction), UnitType::Ranger).unwrap();
+            assert!(world.load(rocket, robot).is_ok());
+            robots.push(robot);
+        }
+        assert_eq!(world.structure_garrison(rocket).unwrap(), robots);
+        assert_eq!(world.filter(world.player_to_move).structure_garrison(rocket).unwrap(), robots);
+
+        // The factory reports what it is producing until the robot is done.
+        assert_err!(world.factory_unit_type(factory), GameError::NullValue);
+        assert_err!(world.factory_rounds_left(factory), GameError::NullValue);
+        assert!(world.produce_robot(factory, UnitType::Mage).is_ok());
+        assert_eq!(world.factory_unit_type(factory).unwrap(), UnitType::Mage);
+        assert_eq!(world.factory_rounds_left(factory).unwrap(), FACTORY_PRODUCTION_ROUNDS);
+        let filtered = world.filter(world.player_to_move);
+        assert_eq!(filtered.factory_unit_type(factory).unwrap(), UnitType::Mage);
+        assert_eq!(filtered.factory_rounds_left(factory).unwrap(), FACTORY_PRODUCTION_ROUNDS);
+        for _ in 0..FACTORY_PRODUCTION_ROUNDS {
+            world.end_round();
+        }
+        assert_err!(world.factory_unit_type(factory), GameError::NullValue);
+        let mage = world.structure_garrison(factory).unwrap()[0];
+        assert_eq!(world.my_unit(mage).unwrap().unit_type(), UnitType::Mage);
+
+        // Robots are unloaded in the order the garrison lists them.
+        for (i, &direction) in [Direction::East, Direction::North, Direction::West].iter().enumerate() {
+            assert!(world.unload(rocket, direction).is_ok());
+            assert_eq!(world.my_unit(robots[i]).unwrap().location(), OnMap(loc.add(direction)));
+            assert_eq!(world.structure_garrison(rocket).unwrap(), robots[i + 1..].to_vec());
+        }
+
+        // Only your own structures can be inspected.
+        assert_err!(world.structure_garrison(enemy_factory), GameError::TeamNotAllowed);
+        assert_err!(world.factory_unit_type(enemy_factory), GameError::TeamNotAllowed);
+        assert_err!(world.factory_rounds_left(enemy_factory), GameError::TeamNotAllowed);
+        assert_err!(world.structure_garrison(robots[0]), GameError::InappropriateUnitType);
+        assert_err!(world.factory_unit_type(rocket), GameError::InappropriateUnitType);
+        assert_err!(world.factory_rounds_left(robots[0]), GameError::InappropriateUnitType);
+    }
+
+    #[test]
+    fn test_robot_attack_and_heal() {
+        let mut world = GameWorld::test_world();
+        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Ranger).unwrap();
+        let worker_in_range = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 5, 0), UnitType::Worker).unwrap();
+        let worker_out_of_range = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 10, 0), UnitType::Worker).unwrap();
+
+        // The ranger can attack the adjacent worker, but not the non-adjacent worker.
+        assert![world.can_attack(ranger, worker_in_range)];
+        assert![!world.can_attack(ranger, worker_out_of_range)];
+        assert![world.attack(ranger, worker_in_range).is_ok()];
 
         // The worker should have taken some damage.
         assert_eq![world.get_unit(worker_in_range).unwrap().health(), 60];
@@ -3190,7 +6414,7 @@ mod tests {
 
         // The ranger cannot attack again.
         assert![!world.is_attack_ready(ranger)];
-        assert_err![world.attack(ranger, worker_in_range), GameError::Overheated];
+        assert_err![world.attack(ranger, worker_in_range), GameError::Overheated { unit_id: ranger }];
         assert![!world.is_attack_ready(ranger)];
         assert![world.can_attack(ranger, worker_in_range)];
 
@@ -3206,12 +6430,89 @@ mod tests {
         assert_err![world.heal(healer, rocket), GameError::InappropriateUnitType];
         assert_err![world.heal(healer, blue), GameError::TeamNotAllowed];
 
-        // Use the healer to heal the worker.
-        assert![world.can_heal(healer, worker_in_range)];
-        assert![world.heal(healer, worker_in_range).is_ok()];
+        // Use the worker's own healer to heal it.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert![world.can_heal(blue, worker_in_range)];
+        assert![world.heal(blue, worker_in_range).is_ok()];
         assert_eq![world.get_unit(worker_in_range).unwrap().health(), 70];
     }
 
+    #[test]
+    fn test_healer_heal() {
+        let mut world = GameWorld::test_world();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Healer).unwrap();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 6, 5), UnitType::Knight).unwrap();
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 7), UnitType::Factory).unwrap();
+        let worker = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 8), UnitType::Worker).unwrap();
+        world.finish_structure(factory);
+
+        // Only healers can heal.
+        assert!(!world.can_heal(knight, healer));
+        assert_err!(world.heal(knight, healer), GameError::InappropriateUnitType);
+
+        // Healing never goes above the target's max health.
+        let max_health = world.my_unit(knight).unwrap().max_health();
+        world.damage_unit(knight, 6, Destruction::Combat).unwrap();
+        assert_eq!(world.my_unit(knight).unwrap().health(), max_health - 1);
+        assert!(world.heal(healer, knight).is_ok());
+        assert_eq!(world.my_unit(knight).unwrap().health(), max_health);
+
+        // Healing uses the healer's attack heat.
+        assert!(!world.is_heal_ready(healer));
+        assert_err!(world.heal(healer, knight), GameError::Overheated { unit_id: healer });
+        world.end_round();
+        assert!(world.is_heal_ready(healer));
+
+        // Structures and garrisoned robots cannot be healed.
+        assert!(!world.can_heal(healer, factory));
+        assert_err!(world.heal(healer, factory), GameError::InappropriateUnitType);
+        assert!(world.load(factory, worker).is_ok());
+        assert!(!world.can_heal(healer, worker));
+        assert_err!(world.heal(healer, worker), GameError::UnitNotOnMap);
+    }
+
+    #[test]
+    fn test_attack_and_heal_by_type() {
+        use self::GameError::*;
+        fn game_error(result: Result<(), Error>) -> Option<GameError> {
+            result.err().map(|e| e.downcast::<GameError>().expect("wrong error type"))
+        }
+
+        // (type, attacking an adjacent enemy, healing an adjacent friend,
+        //  is attack ready, is heal ready)
+        let table = [
+            (UnitType::Worker, Some(InappropriateUnitType), Some(InappropriateUnitType), false, false),
+            (UnitType::Knight, None, Some(InappropriateUnitType), true, false),
+            (UnitType::Ranger, Some(TooClose), Some(InappropriateUnitType), true, false),
+            (UnitType::Mage, None, Some(InappropriateUnitType), true, false),
+            (UnitType::Healer, Some(InappropriateUnitType), None, false, true),
+            (UnitType::Factory, Some(InappropriateUnitType), Some(InappropriateUnitType), false, false),
+            (UnitType::Rocket, Some(InappropriateUnitType), Some(InappropriateUnitType), false, false),
+        ];
+        for &(unit_type, ref attack, ref heal, attack_ready, heal_ready) in table.iter() {
+            let mut world = GameWorld::test_world();
+            let loc = MapLocation::new(Planet::Earth, 5, 5);
+            let unit = world.create_unit(Team::Red, loc, unit_type).unwrap();
+            let enemy = world.create_unit(Team::Blue, loc.add(Direction::East), UnitType::Knight).unwrap();
+            let friend = world.create_unit(Team::Red, loc.add(Direction::West), UnitType::Knight).unwrap();
+            world.damage_unit(friend, 20, Destruction::Combat).unwrap();
+
+            assert_eq!(world.is_attack_ready(unit), attack_ready, "{:?}", unit_type);
+            assert_eq!(world.is_heal_ready(unit), heal_ready, "{:?}", unit_type);
+            assert_eq!(world.can_attack(unit, enemy), attack.is_none(), "{:?}", unit_type);
+            assert_eq!(world.can_heal(unit, friend), heal.is_none(), "{:?}", unit_type);
+            assert_eq!(game_error(world.attack_status(unit, enemy)), *attack, "{:?}", unit_type);
+
+            // Attacking a friend through a delta can't be used to heal it.
+            let health = world.my_unit(friend).unwrap().health();
+            assert!(world.apply(&Delta::Attack { robot_id: unit, target_unit_id: friend }).is_err());
+            assert_eq!(world.my_unit(friend).unwrap().health(), health, "{:?}", unit_type);
+
+            assert_eq!(game_error(world.attack(unit, enemy)), *attack, "{:?}", unit_type);
+            assert_eq!(game_error(world.heal(unit, friend)), *heal, "{:?}", unit_type);
+        }
+    }
+
     #[test]
     fn test_replicate() {
         let mut world = GameWorld::test_world();
@@ -3220,20 +6521,24 @@ mod tests {
 
         // The worker cannot replicate to the west, because that space is off the map.
         assert![!world.can_replicate(worker, Direction::West)];
-        assert_err![world.replicate(worker, Direction::West), GameError::LocationOffMap];
+        assert_err![world.replicate(worker, Direction::West), GameError::LocationOffMap { location: MapLocation::new(Planet::Earth, -1, 0) }];
 
         // The worker cannot replicate to the east, because that space is obstructed.
         assert![!world.can_replicate(worker, Direction::East)];
-        assert_err![world.replicate(worker, Direction::East), GameError::LocationNotEmpty];
+        assert_err![world.replicate(worker, Direction::East), GameError::LocationNotEmpty { location: MapLocation::new(Planet::Earth, 1, 0) }];
 
         // The worker can replicate to the north.
         assert![world.can_replicate(worker, Direction::North)];
         assert![world.replicate(worker, Direction::North).is_ok()];
         assert_eq![world.karbonite(), 85];
 
-        // The child cannot replicate when there isn't enough Karbonite.
-        world.my_team_mut().karbonite = 0;
+        // The child cannot replicate in the round it was created.
         let child = world.sense_unit_at_location(MapLocation::new(Planet::Earth, 0, 1)).unwrap().unwrap().id();
+        assert_err![world.replicate(child, Direction::North), GameError::Overheated { unit_id: child }];
+        world.end_round();
+
+        // Nor when there isn't enough Karbonite.
+        world.my_team_mut().karbonite = 0;
         assert![!world.can_replicate(child, Direction::North)];
         assert_err![world.replicate(child, Direction::North), GameError::InsufficientKarbonite];
 
@@ -3244,12 +6549,202 @@ mod tests {
 
         // The child cannot replicate again this round.
         assert![!world.can_replicate(child, Direction::East)];
-        assert_err![world.replicate(child, Direction::East), GameError::Overheated];
+        assert_err![world.replicate(child, Direction::East), GameError::Overheated { unit_id: child }];
 
         // Even after ending the round, the child cannot replicate immediately again.
         world.end_round();
         assert![!world.can_replicate(child, Direction::East)];
-        assert_err![world.replicate(child, Direction::East), GameError::Overheated];
+        assert_err![world.replicate(child, Direction::East), GameError::Overheated { unit_id: child }];
+    }
+
+    #[test]
+    fn test_replicated_worker() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+
+        // Research workers, so the child is created at the team's level.
+        assert!(world.queue_research(Branch::Worker));
+        for _ in 0..cost_of(&Branch::Worker, 1).unwrap() {
+            world.end_round();
+        }
+        assert_eq!(world.research_info().get_level(&Branch::Worker), 1);
+
+        // The child's ID comes fresh from the ID generator.
+        let mut id_generator = world.id_generator.clone();
+        assert!(world.replicate(worker, Direction::North).is_ok());
+        let child = world.my_planet().units_by_loc[&loc.add(Direction::North)];
+        assert_eq!(child, id_generator.next_id().unwrap());
+        let unresearched = Unit::new(0, Team::Red, UnitType::Worker, 0, OnMap(loc)).unwrap();
+        assert_gt!(world.my_unit(child).unwrap().worker_harvest_amount().unwrap(),
+                   unresearched.worker_harvest_amount().unwrap());
+
+        // The child can't act until the next round.
+        assert_err!(world.harvest(child, Direction::Center), GameError::Overheated { unit_id: child });
+        assert!(!world.is_move_ready(child));
+        assert_err!(world.move_robot(child, Direction::North), GameError::Overheated { unit_id: child });
+        world.end_round();
+        assert!(world.harvest(child, Direction::Center).is_ok());
+        assert!(world.move_robot(child, Direction::North).is_ok());
+    }
+
+    #[test]
+    fn test_worker_acts_once_per_round() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let blueprint = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Factory).unwrap();
+        let factory = world.create_unit(Team::Red, loc.add(Direction::West), UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        world.get_unit_mut(factory).unwrap().take_damage(10);
+        world.my_team_mut().karbonite = 1000;
+
+        // Harvesting uses up the worker's action for the round.
+        assert!(!world.worker_has_acted(worker).unwrap());
+        assert!(world.harvest(worker, Direction::Center).is_ok());
+        assert!(world.worker_has_acted(worker).unwrap());
+        assert_err!(world.build(worker, blueprint), GameError::Overheated { unit_id: worker });
+        assert_err!(world.repair(worker, factory), GameError::Overheated { unit_id: worker });
+        assert_err!(world.blueprint(worker, UnitType::Factory, Direction::North),
+                    GameError::Overheated { unit_id: worker });
+
+        // But it can still replicate and move, which are not worker actions.
+        assert!(world.replicate(worker, Direction::South).is_ok());
+        assert!(world.move_robot(worker, Direction::North).is_ok());
+
+        // Next round it can act again. Replicating first does not use up its
+        // action either.
+        world.end_round();
+        assert!(!world.worker_has_acted(worker).unwrap());
+        while !world.can_replicate(worker, Direction::North) {
+            world.end_round();
+        }
+        assert!(world.replicate(worker, Direction::North).is_ok());
+        assert!(!world.worker_has_acted(worker).unwrap());
+        assert!(world.build(worker, blueprint).is_ok());
+        assert!(world.worker_has_acted(worker).unwrap());
+
+        // The flag can only be read for your own workers.
+        let enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 15, 15), UnitType::Worker).unwrap();
+        assert_err!(world.worker_has_acted(enemy), GameError::TeamNotAllowed);
+        assert_err!(world.worker_has_acted(factory), GameError::InappropriateUnitType);
+    }
+
+    #[test]
+    fn test_team_stats() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let knight = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Knight).unwrap();
+        let victim = world.create_unit(Team::Blue, loc.add(Direction::East).add(Direction::East), UnitType::Worker).unwrap();
+        let doomed = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 15, 15), UnitType::Ranger).unwrap();
+        let worker_value = UnitType::Worker.value();
+
+        let red = world.team_stats(Team::Red).unwrap();
+        assert_eq!(red.units_value, worker_value + UnitType::Knight.value());
+        assert_eq!(red.units_produced, 2);
+        assert_eq!(world.team_stats(Team::Blue).unwrap().units_value,
+                   worker_value + UnitType::Ranger.value());
+
+        // Harvesting over two rounds counts everything mined.
+        assert!(world.harvest(worker, Direction::Center).is_ok());
+        world.end_round();
+        assert!(world.harvest(worker, Direction::North).is_ok());
+        assert_eq!(world.team_stats(Team::Red).unwrap().karbonite_mined, 6);
+
+        // A kill in combat, and a unit lost some other way.
+        world.get_unit_mut(victim).unwrap().take_damage(90);
+        assert!(world.attack(knight, victim).is_ok());
+        assert!(world.get_unit(victim).is_err());
+        world.destroy_unit(doomed, Destruction::Rocket).unwrap();
+        world.disintegrate_unit(worker).unwrap();
+
+        let red = world.team_stats(Team::Red).unwrap();
+        assert_eq!(red, TeamStats {
+            units_value: UnitType::Knight.value(),
+            karbonite_mined: 6,
+            units_produced: 2,
+            units_destroyed_in_combat: 0,
+            units_destroyed_by_rockets: 0,
+            units_disintegrated: 1,
+            units_destroyed_by_flood: 0,
+        });
+        let blue = world.team_stats(Team::Blue).unwrap();
+        assert_eq!(blue, TeamStats {
+            units_value: 0,
+            karbonite_mined: 0,
+            units_produced: 2,
+            units_destroyed_in_combat: 1,
+            units_destroyed_by_rockets: 1,
+            units_disintegrated: 0,
+            units_destroyed_by_flood: 0,
+        });
+
+        // Red has more units left, so wins the tiebreak.
+        assert_eq!(world.winner(), Some(Team::Red));
+
+        // Players only see their own stats.
+        let red_world = world.filter(Player::new(Team::Red, Planet::Earth));
+        assert_eq!(red_world.team_stats(Team::Red).unwrap(), red);
+        assert_err!(red_world.team_stats(Team::Blue), GameError::TeamNotAllowed);
+        let blue_world = world.filter(Player::new(Team::Blue, Planet::Mars));
+        assert_eq!(blue_world.team_stats(Team::Blue).unwrap(), blue);
+        assert_err!(blue_world.team_stats(Team::Red), GameError::TeamNotAllowed);
+    }
+
+    #[test]
+    fn test_viewer_events() {
+        let mut world = GameWorld::test_world();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 4, 5), UnitType::Healer).unwrap();
+        let enemy_knight = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 6, 5), UnitType::Knight).unwrap();
+        let rocket_loc = MapLocation::new(Planet::Earth, 10, 10);
+        let rocket = world.create_unit(Team::Red, rocket_loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let passenger = world.create_unit(Team::Red, rocket_loc.add(Direction::North), UnitType::Worker).unwrap();
+        assert!(world.load(rocket, passenger).is_ok());
+        let victim_loc = rocket_loc.add(Direction::South);
+        let victim = world.create_unit(Team::Blue, victim_loc, UnitType::Worker).unwrap();
+        world.get_unit_mut(victim).unwrap().take_damage(99);
+        let defense = world.get_unit(knight).unwrap().knight_defense().unwrap();
+        world.get_unit_mut(knight).unwrap().take_damage(5 + defense as i32);
+        world.flush_viewer_changes();
+
+        // A scripted round of combat: an attack through the enemy's defense,
+        // a heal capped at max health, and a launch that kills a bystander.
+        let damage = world.get_unit(knight).unwrap().damage().unwrap() as u32
+            - world.get_unit(enemy_knight).unwrap().knight_defense().unwrap();
+        assert!(world.attack(knight, enemy_knight).is_ok());
+        assert!(world.heal(healer, knight).is_ok());
+        let destination = MapLocation::new(Planet::Mars, 1, 1);
+        assert!(world.launch_rocket(rocket, destination).is_ok());
+        assert_eq!(world.flush_viewer_changes(), vec![
+            ViewerDelta::UnitDamaged { unit_id: enemy_knight, damage },
+            ViewerDelta::UnitHealed { unit_id: knight, amount: 5 },
+            ViewerDelta::RocketLaunch { rocket_id: rocket, destination },
+            ViewerDelta::UnitDamaged { unit_id: victim, damage: 1 },
+            ViewerDelta::UnitDestroyed {
+                unit_id: victim, location: OnMap(victim_loc), cause: Destruction::Rocket,
+            },
+        ]);
+
+        // Destroying a structure reports its garrison too, and the log is
+        // emptied once flushed.
+        let factory_loc = MapLocation::new(Planet::Earth, 15, 15);
+        let factory = world.create_unit(Team::Red, factory_loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        let worker = world.create_unit(Team::Red, factory_loc.add(Direction::West), UnitType::Worker).unwrap();
+        assert!(world.load(factory, worker).is_ok());
+        assert!(world.disintegrate_unit(factory).is_ok());
+        assert_eq!(world.flush_viewer_changes(), vec![
+            ViewerDelta::UnitDestroyed {
+                unit_id: factory, location: OnMap(factory_loc), cause: Destruction::Disintegration,
+            },
+            ViewerDelta::UnitDestroyed {
+                unit_id: worker, location: InGarrison(factory), cause: Destruction::Disintegration,
+            },
+        ]);
+        assert_eq!(world.flush_viewer_changes(), vec![]);
     }
 
     #[test]
@@ -3262,9 +6757,19 @@ mod tests {
         assert![!world.can_repair(worker, factory)];
         assert_err![world.repair(worker, factory), GameError::StructureNotYetBuilt];
 
-        // After forcibly completing the structure, we damage it.
-        world.get_unit_mut(factory).unwrap().be_built(1000);
+        // After forcibly completing the structure, it doesn't need repairs.
+        world.finish_structure(factory);
         assert![world.get_unit(factory).unwrap().structure_is_built().unwrap()];
+        assert![!world.can_repair(worker, factory)];
+        assert_err![world.repair(worker, factory), GameError::StructureAtMaxHealth];
+
+        // Building and healing don't apply to completed structures.
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 1), UnitType::Healer).unwrap();
+        assert_err![world.build(worker, factory), GameError::StructureAlreadyBuilt];
+        assert_err![world.heal(healer, factory), GameError::InappropriateUnitType];
+        world.destroy_unit(healer, Destruction::Combat).unwrap();
+
+        // Damage the factory.
         world.get_unit_mut(factory).unwrap().take_damage(100);
         assert_eq![world.get_unit(factory).unwrap().health(), 200];
 
@@ -3275,29 +6780,99 @@ mod tests {
 
         // The worker cannot repair again this turn.
         assert![!world.can_repair(worker, factory)];
-        assert_err![world.repair(worker, factory), GameError::Overheated];
+        assert_err![world.repair(worker, factory), GameError::Overheated { unit_id: worker }];
 
         // After force-ending the round, the worker can repair again.
         world.end_round();
         assert![world.can_repair(worker, factory)];
 
+        // Building still doesn't apply to the damaged structure.
+        assert_err![world.build(worker, factory), GameError::StructureAlreadyBuilt];
+
         // If the worker moves away, it cannot repair the factory.
         assert![world.move_robot(worker, Direction::East).is_ok()];
         assert![!world.can_repair(worker, factory)];
         assert_err![world.repair(worker, factory), GameError::OutOfRange];
     }
 
+    #[test]
+    fn test_knight_kills_worker() {
+        let mut world = GameWorld::test_world();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let worker = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 6, 5), UnitType::Worker).unwrap();
+        let far_worker = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 8, 5), UnitType::Worker).unwrap();
+        assert_err!(world.attack(knight, far_worker), GameError::OutOfRange);
+
+        // Keep attacking the worker whenever the knight is ready.
+        let mut attacks = 0;
+        let mut rounds = 0;
+        while world.can_sense_unit(worker) {
+            if world.is_attack_ready(knight) {
+                assert!(world.attack(knight, worker).is_ok());
+                attacks += 1;
+            } else {
+                assert_err!(world.attack(knight, worker), GameError::Overheated { unit_id: knight });
+                world.end_round();
+                rounds += 1;
+            }
+        }
+
+        // It takes more than one attack, and the worker is gone for good.
+        assert_gt!(attacks, 1);
+        assert_gt!(rounds, 0);
+        assert!(!world.units_by_loc().contains_key(&MapLocation::new(Planet::Earth, 6, 5)));
+        assert_err!(world.attack(knight, worker), GameError::NoSuchUnit);
+    }
+
+    #[test]
+    fn test_attack_units_not_on_map() {
+        let mut world = GameWorld::test_world();
+        let factory_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let knight = world.create_unit(Team::Red, factory_loc.add(Direction::West), UnitType::Knight).unwrap();
+        let factory = world.create_unit(Team::Blue, factory_loc, UnitType::Factory).unwrap();
+        let blue_knight = world.create_unit(Team::Blue, factory_loc.add(Direction::East), UnitType::Knight).unwrap();
+        world.finish_structure(factory);
+
+        // Units in a garrison can't be attacked.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.load(factory, blue_knight).is_ok());
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert!(!world.can_attack(knight, blue_knight));
+        assert_err!(world.attack(knight, blue_knight), GameError::UnitNotOnMap);
+
+        // Nor can they attack anything themselves.
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert_err!(world.attack(blue_knight, knight), GameError::UnitNotOnMap);
+    }
+
     #[test]
     fn test_ranger_attack_range() {
         let mut world = GameWorld::test_world();
         let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Ranger).unwrap();
-        let too_close = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 1, 0), UnitType::Ranger).unwrap();
-        let too_far = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 10, 0), UnitType::Ranger).unwrap();
-        let just_right = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 5, 0), UnitType::Ranger).unwrap();
-
-        assert_err![world.attack(ranger, too_close), GameError::OutOfRange];
+        assert_eq!(UnitType::Ranger.ranger_cannot_attack_range().unwrap(), 10);
+        assert_eq!(UnitType::Ranger.attack_range().unwrap(), 50);
+
+        // Enemies at distance squared 10 and 53 are just inside the dead zone
+        // and just beyond the attack range. Those at 13 and 50 can be hit.
+        let too_close = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 3, 1), UnitType::Ranger).unwrap();
+        let just_outside = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 3, 2), UnitType::Ranger).unwrap();
+        let just_in_range = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 7, 1), UnitType::Ranger).unwrap();
+        let too_far = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 7, 2), UnitType::Ranger).unwrap();
+
+        assert![!world.can_attack(ranger, too_close)];
+        assert_err![world.attack_status(ranger, too_close), GameError::TooClose];
+        assert_err![world.attack(ranger, too_close), GameError::TooClose];
+        assert![!world.can_attack(ranger, too_far)];
+        assert_err![world.attack_status(ranger, too_far), GameError::OutOfRange];
         assert_err![world.attack(ranger, too_far), GameError::OutOfRange];
-        assert![world.attack(ranger, just_right).is_ok()];
+
+        for &target in [just_outside, just_in_range].iter() {
+            while !world.is_attack_ready(ranger) {
+                world.end_round();
+            }
+            assert![world.can_attack(ranger, target)];
+            assert![world.attack(ranger, target).is_ok()];
+        }
     }
   
     #[test]
@@ -3307,11 +6882,13 @@ mod tests {
         let mut victims = vec![];
         for x in 1..4 {
             for y in 1..4 {
-                victims.push(world.create_unit(Team::Red, MapLocation::new(Planet::Earth, x, y), UnitType::Factory).unwrap());
+                let team = if (x, y) == (2, 2) { Team::Blue } else { Team::Red };
+                victims.push(world.create_unit(team, MapLocation::new(Planet::Earth, x, y), UnitType::Factory).unwrap());
             }
         }
 
-        // After attacking the middle factory, all factories should be damaged.
+        // After attacking the middle enemy factory, all factories should be
+        // damaged, including the mage's own.
         for victim in victims.iter() {
             assert_eq![world.get_unit(*victim).unwrap().health(), 75];
         }
@@ -3321,6 +6898,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_friendly_fire() {
+        let mut world = GameWorld::test_world();
+        world.unlock_research(&[Branch::Knight, Branch::Ranger]);
+
+        // Direct attacks and javelins can't target your own units.
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Knight).unwrap();
+        let worker = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 6, 5), UnitType::Worker).unwrap();
+        assert!(!world.can_attack(knight, worker));
+        assert_err!(world.attack(knight, worker), GameError::TeamNotAllowed);
+        assert!(!world.can_javelin(knight, worker));
+        assert_err!(world.javelin(knight, worker), GameError::TeamNotAllowed);
+
+        // Splash damage hits your own units next to the target.
+        let mage = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Mage).unwrap();
+        let enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 12, 10), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 13, 10), UnitType::Healer).unwrap();
+        assert!(world.attack(mage, enemy).is_ok());
+        assert_lt!(world.get_unit(enemy).unwrap().health(), world.get_unit(enemy).unwrap().max_health());
+        assert_lt!(world.get_unit(healer).unwrap().health(), world.get_unit(healer).unwrap().max_health());
+
+        // So does a snipe that lands on your own factory.
+        let ranger = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Ranger).unwrap();
+        let factory_loc = MapLocation::new(Planet::Earth, 10, 15);
+        let factory = world.create_unit(Team::Red, factory_loc, UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        assert!(world.begin_snipe(ranger, factory_loc).is_ok());
+        let max_countdown = world.my_unit(ranger).unwrap().ranger_max_countdown().unwrap();
+        for _ in 0..max_countdown {
+            world.end_round();
+        }
+        assert_lt!(world.get_unit(factory).unwrap().health(), world.get_unit(factory).unwrap().max_health());
+    }
+
     #[test]
     fn test_karbonite_production() {
         let mut world = GameWorld::test_world();
@@ -3342,14 +6953,14 @@ mod tests {
     #[test]
     fn test_map_serialize() {
         use super::*;
-        let mut map = StructyMap::default();
+        let mut map = OccupancyGrid::new(&GameMap::test_map().earth_map);
         map.insert(MapLocation::new(Planet::Earth, 1,2), 1);
         map.insert(MapLocation::new(Planet::Earth, 1,3), 2);
         let p = PlanetInfo {
-            visible_locs: vec![],
+            visible_locs: Arc::new(vec![]),
             units: FnvHashMap::default(),
             units_by_loc: map,
-            karbonite: vec![]
+            karbonite: Arc::new(vec![])
         };
 
         use serde_json::{to_string, from_str};
@@ -3376,44 +6987,375 @@ mod tests {
         assert_eq![world.get_planet(Planet::Mars).units.len(), 1];
     }
 
+    #[test]
+    fn test_apocalypse_rockets() {
+        let mut world = GameWorld::test_world();
+        while world.round < APOCALYPSE_ROUND - 10 {
+            world.end_round();
+        }
+
+        // Red launches a rocket from Earth to Mars just before the flood, and
+        // leaves a factory with a garrison behind.
+        let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        let red_rocket = world.create_unit(Team::Red, earth_loc, UnitType::Rocket).unwrap();
+        let red_knight = world.create_unit(Team::Red, earth_loc.add(Direction::North), UnitType::Knight).unwrap();
+        world.finish_structure(red_rocket);
+        assert!(world.load(red_rocket, red_knight).is_ok());
+        assert!(world.launch_rocket(red_rocket, mars_loc).is_ok());
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Factory).unwrap();
+        world.finish_structure(factory);
+        assert!(world.produce_robot(factory, UnitType::Knight).is_ok());
+        for _ in 0..world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap() {
+            world.end_round();
+        }
+        assert_eq!(world.get_planet(Planet::Earth).units.len(), 2);
+
+        // Blue keeps everything on Earth, except a rocket flying from Mars
+        // back to Earth.
+        world.player_to_move = Player::new(Team::Blue, Planet::Mars);
+        let blue_rocket = world.create_unit(Team::Blue, mars_loc.add(Direction::East).add(Direction::East), UnitType::Rocket).unwrap();
+        world.finish_structure(blue_rocket);
+        assert!(world.launch_rocket(blue_rocket, earth_loc).is_ok());
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 0, 0), UnitType::Worker).unwrap();
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 19, 19), UnitType::Factory).unwrap();
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        assert_eq!(world.winner(), None);
+
+        // Everything on Earth is destroyed at the flood.
+        while world.round < APOCALYPSE_ROUND {
+            world.end_turn(FILLER_TIME);
+        }
+        assert_eq!(world.get_planet(Planet::Earth).units.len(), 0);
+        assert_eq!(world.get_planet(Planet::Earth).units_by_loc.len(), 0);
+
+        // Red's rocket survives and lands on Mars, but Blue's rocket is
+        // destroyed when it reaches Earth.
+        let last_landing = cmp::max(world.get_team(Team::Red).rocket_landings.all()[0].0,
+                                    world.get_team(Team::Blue).rocket_landings.all()[0].0);
+        assert_gt!(last_landing, APOCALYPSE_ROUND);
+        while world.round <= last_landing {
+            world.end_turn(FILLER_TIME);
+        }
+        assert_eq!(world.get_unit(red_rocket).unwrap().location(), OnMap(mars_loc));
+        assert_eq!(world.get_unit(red_knight).unwrap().location(), InGarrison(red_rocket));
+        assert!(world.get_unit(blue_rocket).is_err());
+        assert_eq!(world.get_planet(Planet::Earth).units.len(), 0);
+        assert_eq!(world.get_planet(Planet::Mars).units.len(), 2);
+        assert!(world.get_team(Team::Blue).units_in_space.is_empty());
+
+        // Blue had nothing left, so Red wins.
+        assert_eq!(world.winner(), Some(Team::Red));
+    }
+
+    #[test]
+    fn test_karbonite_per_round() {
+        let map = GameMap {
+            starting_karbonite: 50,
+            ..GameMap::test_map()
+        };
+        let mut world = GameWorld::new(map);
+        assert_eq!(world.get_team(Team::Red).karbonite, 50);
+        assert_eq!(world.get_team(Team::Blue).karbonite, 50);
+        world.get_team_mut(Team::Red).karbonite = KARBONITE_STARTING;
+
+        // Each round, both pools grow by KARBONITE_PER_ROUND, minus 1 for
+        // every KARBONITE_DECREASE_RATIO karbonite already in the pool.
+        let red = [108, 116, 124, 131, 138, 145, 152, 159, 166, 172];
+        let blue = [59, 68, 77, 86, 94, 102, 110, 118, 126, 133];
+        for round in 0..10 {
+            for _ in 0..4 {
+                world.end_turn(FILLER_TIME);
+            }
+            assert_eq!(world.karbonite(), red[round]);
+            assert_eq!(world.get_team(Team::Blue).karbonite, blue[round]);
+        }
+
+        // The next player sees the new pool at the start of their turn.
+        let stm = world.end_turn(FILLER_TIME);
+        assert_eq!(world.team(), Team::Blue);
+        assert_eq!(stm.karbonite, blue[9]);
+    }
+
+    #[test]
+    fn test_round_limit() {
+        let mut world = GameWorld::test_world();
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Mars, 15, 15), UnitType::Knight).unwrap();
+        while world.round < ROUND_LIMIT - 5 {
+            world.end_round();
+        }
+
+        // Red launches a rocket that would land after the last round.
+        world.player_to_move = Player::new(Team::Red, Planet::Mars);
+        let worker = world.create_unit(Team::Red, mars_loc, UnitType::Worker).unwrap();
+        let rocket = world.create_unit(Team::Red, mars_loc.add(Direction::East), UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        assert!(world.launch_rocket(rocket, MapLocation::new(Planet::Earth, 5, 5)).is_ok());
+        let landing_round = world.rocket_landings().all()[0].0;
+        assert_gt!(landing_round, ROUND_LIMIT);
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+
+        // Play out the rest of the game.
+        while world.round <= ROUND_LIMIT {
+            assert_eq!(world.winner(), None);
+            world.end_turn(FILLER_TIME);
+        }
+        assert_eq!(world.round(), ROUND_LIMIT + 1);
+        assert!(world.winner().is_some());
+
+        // Nothing can happen after the last round.
+        world.player_to_move = Player::new(Team::Red, Planet::Mars);
+        let karbonite = world.karbonite();
+        assert_err!(world.apply(&Delta::Move { robot_id: worker, direction: Direction::North }),
+                    GameError::GameOver);
+        assert_err!(world.apply(&Delta::Harvest { worker_id: worker, direction: Direction::North }),
+                    GameError::GameOver);
+        assert_err!(world.apply(&Delta::WriteTeamArray { index: 0, value: 1 }), GameError::GameOver);
+        assert_err!(world.apply(&Delta::QueueResearch { branch: Branch::Worker }), GameError::GameOver);
+        for _ in 0..4 * (landing_round - ROUND_LIMIT) {
+            world.end_turn(FILLER_TIME);
+        }
+        assert_eq!(world.round(), ROUND_LIMIT + 1);
+        assert_eq!(world.get_team(Team::Red).karbonite, karbonite);
+        assert_eq!(world.get_unit(rocket).unwrap().location(), InSpace);
+    }
+
     #[test]
     fn test_is_game_over() {
         let mut world = GameWorld::test_world();
 
         // Initially, neither player has units, so the game is over, but it's a tossup who won.
-        assert![world.is_game_over().is_some()];
+        assert![world.winner().is_some()];
 
         // If we give both red and blue units, the game is not over.
         world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Knight).unwrap();
-        world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 0, 0), UnitType::Knight).unwrap();
-        assert![world.is_game_over().is_none()];
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 0, 1), UnitType::Knight).unwrap();
+        assert![world.winner().is_none()];
 
         // If we advance 1000 rounds, the game should be over, and it's again a tossup.
         for _ in 0..1000 {
             world.end_round();
         }
-        assert![world.is_game_over().is_some()];
+        assert![world.winner().is_some()];
         // The apocalypse has now destroyed the preexisting units.
 
         // Giving red some extra Karbonite means a victory for red.
         world.get_team_mut(Team::Red).karbonite += 10;
-        assert![world.is_game_over().is_some()];
-        assert_eq![world.is_game_over().unwrap(), Team::Red];
+        assert![world.winner().is_some()];
+        assert_eq![world.winner().unwrap(), Team::Red];
 
         // Giving blue even more Karbonite lets blue win.
         world.get_team_mut(Team::Blue).karbonite += 20;
-        assert![world.is_game_over().is_some()];
-        assert_eq![world.is_game_over().unwrap(), Team::Blue];
+        assert![world.winner().is_some()];
+        assert_eq![world.winner().unwrap(), Team::Blue];
 
         // Giving red a unit lets red win.
         world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Knight).unwrap();
-        assert![world.is_game_over().is_some()];
-        assert_eq![world.is_game_over().unwrap(), Team::Red];
+        assert![world.winner().is_some()];
+        assert_eq![world.winner().unwrap(), Team::Red];
 
         // Giving blue a more expensive unit lets blue win.
         world.create_unit(Team::Blue, MapLocation::new(Planet::Mars, 0, 0), UnitType::Factory).unwrap();
-        assert![world.is_game_over().is_some()];
-        assert_eq![world.is_game_over().unwrap(), Team::Blue];
+        assert![world.winner().is_some()];
+        assert_eq![world.winner().unwrap(), Team::Blue];
+    }
+
+    #[test]
+    fn test_winner_by_unit_value() {
+        let mut world = GameWorld::test_world();
+        world.create_unit(Team::Red, MapLocation::new(Planet::Mars, 0, 0), UnitType::Worker).unwrap();
+        world.create_unit(Team::Red, MapLocation::new(Planet::Mars, 0, 1), UnitType::Factory).unwrap();
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Mars, 5, 0), UnitType::Worker).unwrap();
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Mars, 5, 1), UnitType::Knight).unwrap();
+        world.get_team_mut(Team::Blue).karbonite += 100;
+        assert!(!world.is_over());
+        assert_eq!(world.winner(), None);
+
+        // Past the round limit, both teams have two units, but Red's are
+        // worth more, which outweighs Blue's karbonite.
+        world.round = ROUND_LIMIT + 1;
+        assert!(world.is_over());
+        assert_eq!(world.winner(), Some(Team::Red));
+    }
+
+    #[test]
+    fn test_is_game_over_annihilation() {
+        let mut world = GameWorld::test_world();
+        world.create_unit(Team::Blue, MapLocation::new(Planet::Mars, 0, 0), UnitType::Worker).unwrap();
+
+        // Red's only units are in a rocket in space, which still count.
+        let rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Rocket).unwrap();
+        let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 6), UnitType::Knight).unwrap();
+        world.finish_structure(rocket);
+        assert!(world.load(rocket, knight).is_ok());
+        assert!(world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 5, 5)).is_ok());
+        assert_eq!(world.winner(), None);
+
+        // Once the rocket is destroyed along with its garrison, Blue wins.
+        world.destroy_unit(rocket, Destruction::Combat).unwrap();
+        assert_eq!(world.winner(), Some(Team::Blue));
+    }
+
+    #[test]
+    fn test_is_game_over_coin_flip() {
+        // With no units and no karbonite on either side, the winner is
+        // decided by the map seed, and is the same every time.
+        let world = GameWorld::test_world();
+        let winner = world.winner();
+        assert!(winner.is_some());
+        for _ in 0..10 {
+            assert_eq!(world.winner(), winner);
+            assert_eq!(GameWorld::test_world().winner(), winner);
+        }
+    }
+
+    #[test]
+    fn test_research_queue_state() {
+        let mut world = GameWorld::test_world();
+        assert_eq!(world.current_research(), None);
+        assert_eq!(world.research_rounds_left(), None);
+        assert!(world.research_queue().is_empty());
+
+        // Queue three branches.
+        let queue = vec![Branch::Worker, Branch::Knight, Branch::Worker];
+        for branch in queue.iter() {
+            assert!(world.queue_research(*branch));
+        }
+        assert_eq!(world.research_queue(), queue);
+
+        // Step through each upgrade. The next branch becomes current with its
+        // full duration as soon as the previous one completes.
+        let mut level = vec![0; 2];
+        for (i, branch) in queue.iter().enumerate() {
+            let branch_index = if *branch == Branch::Worker { 0 } else { 1 };
+            level[branch_index] += 1;
+            let cost = cost_of(branch, level[branch_index]).unwrap();
+            assert_eq!(world.current_research(), Some(*branch));
+            assert_eq!(world.research_queue(), queue[i..].to_vec());
+            for rounds_left in (1..cost + 1).rev() {
+                assert_eq!(world.research_rounds_left(), Some(rounds_left));
+                world.end_round();
+            }
+            assert_eq!(world.research_info().get_level(branch), level[branch_index]);
+        }
+
+        // All research is complete.
+        assert_eq!(world.current_research(), None);
+        assert_eq!(world.research_rounds_left(), None);
+        assert!(world.research_queue().is_empty());
+    }
+
+    #[test]
+    fn test_research_effects() {
+        let mut world = GameWorld::test_world();
+        let red_worker = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Worker).unwrap();
+        let blue_worker = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 5, 0), UnitType::Worker).unwrap();
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 0), UnitType::Factory).unwrap();
+        let red_rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 10), UnitType::Rocket).unwrap();
+        let blue_rocket = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 5, 10), UnitType::Rocket).unwrap();
+        for &id in [factory, red_rocket, blue_rocket].iter() {
+            world.finish_structure(id);
+        }
+        assert!(world.produce_robot(factory, UnitType::Knight).is_ok());
+        for _ in 0..world.my_unit(factory).unwrap().factory_max_rounds_left().unwrap() {
+            world.end_round();
+        }
+        let knight = world.my_unit(factory).unwrap().structure_garrison().unwrap()[0];
+        let knight_defense = world.get_unit(knight).unwrap().knight_defense().unwrap();
+        let capacity = world.get_unit(red_rocket).unwrap().structure_max_capacity().unwrap();
+
+        // Red researches workers and knights to level 2, and rockets to level
+        // 3. Blue only unlocks rockets.
+        let branches = [(Branch::Worker, 2), (Branch::Knight, 2), (Branch::Rocket, 3)];
+        for &(ref branch, max_level) in branches.iter() {
+            for level in 1..max_level + 1 {
+                assert!(world.queue_research(*branch));
+                for _ in 0..cost_of(branch, level).unwrap() {
+                    world.end_round();
+                }
+            }
+        }
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.queue_research(Branch::Rocket));
+        for _ in 0..cost_of(&Branch::Rocket, 1).unwrap() {
+            world.end_round();
+        }
+
+        // A level 2 worker harvests more than a level 0 worker.
+        let blue_karbonite = world.karbonite();
+        assert!(world.harvest(blue_worker, Direction::North).is_ok());
+        let blue_harvested = world.karbonite() - blue_karbonite;
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        let red_karbonite = world.karbonite();
+        assert!(world.harvest(red_worker, Direction::North).is_ok());
+        let red_harvested = world.karbonite() - red_karbonite;
+        assert_eq!(world.get_unit(red_worker).unwrap().research_level(), 2);
+        assert_eq!(world.get_unit(blue_worker).unwrap().research_level(), 0);
+        assert_gt!(red_harvested, blue_harvested);
+
+        // Units in a garrison are upgraded in place.
+        assert_eq!(world.get_unit(knight).unwrap().research_level(), 2);
+        assert_gt!(world.get_unit(knight).unwrap().knight_defense().unwrap(), knight_defense);
+
+        // Red's rocket carries more units, and lands sooner than Blue's.
+        assert_gt!(world.get_unit(red_rocket).unwrap().structure_max_capacity().unwrap(), capacity);
+        assert_eq!(world.get_unit(blue_rocket).unwrap().structure_max_capacity().unwrap(), capacity);
+        let duration = world.orbit.duration(world.round);
+        assert!(world.launch_rocket(red_rocket, MapLocation::new(Planet::Mars, 0, 0)).is_ok());
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.launch_rocket(blue_rocket, MapLocation::new(Planet::Mars, 5, 5)).is_ok());
+        let red_landing = world.get_team(Team::Red).rocket_landings.all()[0].0;
+        let blue_landing = world.get_team(Team::Blue).rocket_landings.all()[0].0;
+        assert_eq!(blue_landing, world.round + duration);
+        assert_lt!(red_landing, blue_landing);
+    }
+
+    #[test]
+    fn test_rocket_research_levels() {
+        let mut world = GameWorld::test_world();
+
+        // Red researches rockets to level 3, and Blue to level 1.
+        for level in 1..4 {
+            assert!(world.queue_research(Branch::Rocket));
+            for _ in 0..cost_of(&Branch::Rocket, level).unwrap() {
+                world.end_round();
+            }
+        }
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.queue_research(Branch::Rocket));
+        for _ in 0..cost_of(&Branch::Rocket, 1).unwrap() {
+            world.end_round();
+        }
+
+        // Rockets built after the research carry more robots.
+        let red_rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Rocket).unwrap();
+        let blue_rocket = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 0, 10), UnitType::Rocket).unwrap();
+        world.finish_structure(red_rocket);
+        world.finish_structure(blue_rocket);
+        let red_capacity = world.get_unit(red_rocket).unwrap().structure_max_capacity().unwrap();
+        let blue_capacity = world.get_unit(blue_rocket).unwrap().structure_max_capacity().unwrap();
+        assert_gt!(red_capacity, blue_capacity);
+
+        // Red fills its rocket past the capacity of Blue's.
+        world.player_to_move = Player::new(Team::Red, Planet::Earth);
+        let loading_loc = MapLocation::new(Planet::Earth, 11, 10);
+        for _ in 0..red_capacity {
+            let robot = world.create_unit(Team::Red, loading_loc, UnitType::Knight).unwrap();
+            assert!(world.load(red_rocket, robot).is_ok());
+        }
+        let robot = world.create_unit(Team::Red, loading_loc, UnitType::Knight).unwrap();
+        assert_err!(world.load(red_rocket, robot), GameError::GarrisonFull);
+
+        // Both teams launch on the same round, but Red lands sooner.
+        let takeoff = world.round;
+        assert!(world.launch_rocket(red_rocket, MapLocation::new(Planet::Mars, 0, 0)).is_ok());
+        world.player_to_move = Player::new(Team::Blue, Planet::Earth);
+        assert!(world.launch_rocket(blue_rocket, MapLocation::new(Planet::Mars, 5, 5)).is_ok());
+        let red_landing = world.get_team(Team::Red).rocket_landings.all()[0].0;
+        let blue_landing = world.get_team(Team::Blue).rocket_landings.all()[0].0;
+        assert_eq!(blue_landing, world.orbit.landing_round(takeoff));
+        assert_lt!(red_landing, blue_landing);
     }
 
     #[test]
@@ -3431,7 +7373,7 @@ mod tests {
         assert!(world.queue_research(UnitType::Knight));
 
         // Put some units in space.
-        world.get_unit_mut(space_rocket).unwrap().be_built(1000);
+        world.finish_structure(space_rocket);
         assert!(world.can_load(space_rocket, space_knight));
         assert!(world.load(space_rocket, space_knight).is_ok());
         assert!(world.can_launch_rocket(space_rocket, rocket_loc));
@@ -3478,12 +7420,650 @@ mod tests {
         assert_err!(world.blueprint(non_worker, UnitType::Factory, Direction::North), GameError::InappropriateUnitType);
         assert!(!world.can_build(non_worker, blueprint));
         assert_err!(world.build(non_worker, blueprint), GameError::InappropriateUnitType);
-        world.get_unit_mut(blueprint).unwrap().be_built(1000);
+        world.finish_structure(blueprint);
         world.get_unit_mut(blueprint).unwrap().take_damage(10);
         assert!(!world.can_repair(non_worker, blueprint));
         assert_err!(world.repair(non_worker, blueprint), GameError::InappropriateUnitType);
     }
 
+    #[test]
+    fn test_apply_other_players_units() {
+        let mut world = GameWorld::test_world();
+        let earth_loc = MapLocation::new(Planet::Earth, 5, 5);
+        let mars_loc = MapLocation::new(Planet::Mars, 5, 5);
+        let red_knight = world.create_unit(Team::Red, earth_loc, UnitType::Knight).unwrap();
+        let red_rocket = world.create_unit(Team::Red, earth_loc.add(Direction::West), UnitType::Rocket).unwrap();
+        let blue_knight = world.create_unit(Team::Blue, earth_loc.add(Direction::East), UnitType::Knight).unwrap();
+        let blue_worker = world.create_unit(Team::Blue, earth_loc.add(Direction::North), UnitType::Worker).unwrap();
+        let red_mars_knight = world.create_unit(Team::Red, mars_loc, UnitType::Knight).unwrap();
+        let red_mars_worker = world.create_unit(Team::Red, mars_loc.add(Direction::North), UnitType::Worker).unwrap();
+        world.finish_structure(red_rocket);
+
+        // On Red's Earth turn, none of these deltas are allowed, and none of
+        // them change the world.
+        let deltas = vec![
+            Delta::Move { robot_id: blue_knight, direction: Direction::East },
+            Delta::Attack { robot_id: blue_knight, target_unit_id: red_knight },
+            Delta::Harvest { worker_id: blue_worker, direction: Direction::North },
+            Delta::Disintegrate { unit_id: blue_worker },
+            Delta::Load { structure_id: red_rocket, robot_id: blue_worker },
+            Delta::Move { robot_id: red_mars_knight, direction: Direction::East },
+            Delta::Harvest { worker_id: red_mars_worker, direction: Direction::North },
+            Delta::Replicate { worker_id: red_mars_worker, direction: Direction::South },
+            Delta::Disintegrate { unit_id: red_mars_knight },
+        ];
+        let old_world = world.clone();
+        for delta in deltas.iter() {
+            assert_err!(world.apply(delta), GameError::TeamNotAllowed);
+            assert_eq!(world, old_world);
+        }
+
+        // Red's own Earth units can still act.
+        assert!(world.apply(&Delta::Move { robot_id: red_knight, direction: Direction::South }).is_ok());
+        assert_eq!(world.get_unit(red_knight).unwrap().location(), OnMap(earth_loc.add(Direction::South)));
+    }
+
+    #[test]
+    fn test_apply_many() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let enemy = world.create_unit(Team::Blue, loc.add(Direction::East), UnitType::Knight).unwrap();
+
+        // Invalid deltas fail without stopping the rest of the turn.
+        let deltas = vec![
+            Delta::Move { robot_id: worker, direction: Direction::East },
+            Delta::Move { robot_id: worker, direction: Direction::North },
+            Delta::Move { robot_id: worker, direction: Direction::North },
+            Delta::Move { robot_id: enemy, direction: Direction::East },
+            Delta::Harvest { worker_id: worker, direction: Direction::Center },
+            Delta::WriteTeamArray { index: COMMUNICATION_ARRAY_LENGTH, value: 1 },
+            Delta::WriteTeamArray { index: 0, value: 1 },
+        ];
+        let results = world.apply_many(&deltas);
+        assert_eq!(results.len(), deltas.len());
+        let errors: Vec<Option<GameError>> = results.into_iter().map(|result| {
+            result.err().map(|e| e.downcast::<GameError>().unwrap())
+        }).collect();
+        assert_eq!(errors, vec![
+            Some(GameError::LocationNotEmpty { location: loc.add(Direction::East) }),
+            None,
+            Some(GameError::Overheated { unit_id: worker }),
+            Some(GameError::TeamNotAllowed),
+            None,
+            Some(GameError::ArrayOutOfBounds),
+            None,
+        ]);
+        let message = errors[2].as_ref().unwrap().to_string();
+        assert!(message.contains(&worker.to_string()), "{}", message);
+        assert_eq!(world.my_unit(worker).unwrap().location(), OnMap(loc.add(Direction::North)));
+        assert_eq!(world.get_unit(enemy).unwrap().location(), OnMap(loc.add(Direction::East)));
+        assert_eq!(world.karbonite(), KARBONITE_STARTING + 3);
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 1);
+
+        // Deltas past the limit are dropped.
+        let deltas = vec![Delta::WriteTeamArray { index: 1, value: 1 }; MAX_DELTAS_PER_TURN + 1];
+        assert_eq!(world.apply_many(&deltas).len(), MAX_DELTAS_PER_TURN);
+    }
+
+    #[test]
+    fn test_action_log() {
+        let mut world = GameWorld::test_world();
+        let red = world.player_to_move;
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+
+        // Three bad deltas between good ones.
+        let deltas = vec![
+            Delta::WriteTeamArray { index: 0, value: 1 },
+            Delta::Disintegrate { unit_id: 9999 },
+            Delta::Move { robot_id: worker, direction: Direction::North },
+            Delta::Move { robot_id: worker, direction: Direction::North },
+            Delta::WriteTeamArray { index: COMMUNICATION_ARRAY_LENGTH, value: 1 },
+            Delta::WriteTeamArray { index: 1, value: 2 },
+        ];
+        world.apply_many(&deltas);
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 1);
+        assert_eq!(world.get_team_array(Planet::Earth)[1], 2);
+        assert_eq!(world.my_unit(worker).unwrap().location(), OnMap(loc.add(Direction::North)));
+
+        let log = world.take_action_log();
+        let failed = |index: usize, error: GameError| FailedDelta {
+            index, player: red, delta: deltas[index].clone(), error: error.to_string(),
+        };
+        assert_eq!(log, vec![
+            failed(1, GameError::NoSuchUnit),
+            failed(3, GameError::Overheated { unit_id: worker }),
+            failed(4, GameError::ArrayOutOfBounds),
+        ]);
+        assert_eq!(world.take_action_log(), vec![]);
+
+        // Each turn replaces the log, which is capped.
+        let deltas = vec![Delta::Disintegrate { unit_id: 9999 }; MAX_LOGGED_FAILURES_PER_TURN + 1];
+        world.apply_many(&deltas);
+        assert_eq!(world.action_log().len(), MAX_LOGGED_FAILURES_PER_TURN);
+        world.apply_many(&[Delta::Nothing]);
+        assert_eq!(world.action_log(), &[]);
+    }
+
+    #[test]
+    fn test_time_pool() {
+        let mut world = GameWorld::test_world();
+        let red = Player::new(Team::Red, Planet::Earth);
+        let blue = Player::new(Team::Blue, Planet::Earth);
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        world.create_unit(Team::Blue, loc.add(Direction::East), UnitType::Worker).unwrap();
+        assert_eq!(world.time_left(red), TIME_POOL_MS);
+
+        // Each turn's time comes out of the pool, and each turn adds to it.
+        world.apply_time_used(red, 100);
+        world.end_turn(FILLER_TIME);
+        assert_eq!(world.time_left(red), TIME_POOL_MS - 100 + TIME_PER_TURN_MS);
+        assert_eq!(world.time_used(red), 100);
+        assert_eq!(world.time_left(blue), TIME_POOL_MS);
+        assert_eq!(world.winner(), None);
+
+        // Red Earth runs out of time, so its turns are skipped.
+        while world.player_to_move() != red {
+            world.end_turn(FILLER_TIME);
+        }
+        world.apply_time_used(red, TIME_POOL_MS);
+        assert!(world.is_timed_out(red));
+        assert!(world.apply_many(&[Delta::WriteTeamArray { index: 0, value: 1 }]).is_empty());
+        assert_eq!(world.get_team_array(Planet::Earth)[0], 0);
+        world.end_turn(FILLER_TIME);
+        assert!(world.time_left(red) < 0);
+        assert_eq!(world.winner(), Some(Team::Blue));
+
+        // If both teams time out, the game goes to the tiebreakers.
+        world.apply_time_used(blue, TIME_POOL_MS * 2);
+        assert!(world.winner().is_some());
+    }
+
+    #[test]
+    fn test_time_pool_with_time() {
+        let mut world = GameWorld::with_time(GameMap::test_map(), 300, 20);
+        let red = Player::new(Team::Red, Planet::Earth);
+        let blue = Player::new(Team::Blue, Planet::Earth);
+        assert_eq!(world.time_pool_ms(), 300);
+        assert_eq!(world.time_per_turn_ms(), 20);
+        assert_eq!(world.time_left(red), 300);
+        assert_eq!(world.time_left(blue), 300);
+
+        // The pool and the increment are the world's, not the defaults.
+        world.apply_time_used(red, 100);
+        world.end_turn(FILLER_TIME);
+        assert_eq!(world.time_left(red), 220);
+        assert_eq!(world.cached_world(red).time_per_turn_ms(), 20);
+
+        // A player times out as soon as it spends more than its pool.
+        world.apply_time_used(blue, 301);
+        assert!(world.is_timed_out(blue));
+        assert!(!world.is_timed_out(red));
+    }
+
+    #[test]
+    fn test_checksum() {
+        let build = || {
+            let mut world = GameWorld::test_world();
+            let knight = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 1, 1), UnitType::Knight).unwrap();
+            world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 3, 3), UnitType::Mage).unwrap();
+            world.create_unit(Team::Red, MapLocation::new(Planet::Mars, 5, 5), UnitType::Worker).unwrap();
+            assert![world.move_robot(knight, Direction::North).is_ok()];
+            world
+        };
+
+        // Independently built worlds in the same state agree.
+        let world = build();
+        assert_eq!(world.checksum(), build().checksum());
+        assert_eq!(world.player_checksum(), build().player_checksum());
+
+        // The order units were inserted into the maps does not matter.
+        let mut reordered = world.clone();
+        for planet_info in reordered.planet_states.values_mut() {
+            let mut units: Vec<(UnitID, Unit)> = planet_info.units.drain().collect();
+            units.sort_by_key(|&(id, _)| cmp::Reverse(id));
+            planet_info.units = units.into_iter().collect();
+        }
+        assert_eq!(reordered.checksum(), world.checksum());
+
+        // Nor does a serialization round trip.
+        let round_trip: GameWorld = serde_json::from_str(&serde_json::to_string(&world).unwrap()).unwrap();
+        assert_eq!(round_trip.checksum(), world.checksum());
+
+        // Changing a single unit's health changes it.
+        let mut damaged = world.clone();
+        let id = *damaged.get_planet(Planet::Earth).units.keys().min().unwrap();
+        damaged.get_unit_mut(id).unwrap().take_damage(1);
+        assert!(damaged.checksum() != world.checksum());
+
+        // The player checksum only covers the player's own state, so it
+        // matches the player's filtered world, and ignores enemy units.
+        let player = Player::new(Team::Red, Planet::Earth);
+        assert_eq!(world.filter(player).player_checksum(), world.player_checksum());
+        let mut enemy_damaged = world.clone();
+        let mage = enemy_damaged.get_planet(Planet::Earth).units.values()
+            .find(|unit| unit.team() == Team::Blue).unwrap().id();
+        enemy_damaged.get_unit_mut(mage).unwrap().take_damage(1);
+        assert_eq!(enemy_damaged.player_checksum(), world.player_checksum());
+        assert!(enemy_damaged.checksum() != world.checksum());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut world = GameWorld::test_world();
+        for &(team, y) in [(Team::Red, 3), (Team::Blue, 16)].iter() {
+            for x in 0..5 {
+                let loc = MapLocation::new(Planet::Earth, 4 * x, y);
+                world.create_unit(team, loc, UnitType::Worker).unwrap();
+                world.create_unit(team, loc.add(Direction::East), UnitType::Knight).unwrap();
+            }
+        }
+        world.cache_filtered_worlds();
+
+        // Scripted deltas: every robot tries to move toward the enemy, attack
+        // every unit, and workers try to replicate and blueprint.
+        let deltas = |world: &GameWorld| -> Vec<Delta> {
+            let mut deltas = vec![];
+            let team = world.team();
+            let direction = if team == Team::Red { Direction::North } else { Direction::South };
+            let units: Vec<&Unit> = world.my_planet().units.values()
+                .filter(|unit| unit.team() == team).collect();
+            for unit in units.iter() {
+                deltas.push(Delta::Move { robot_id: unit.id(), direction });
+                for target in world.my_planet().units.keys() {
+                    deltas.push(Delta::Attack { robot_id: unit.id(), target_unit_id: *target });
+                }
+                deltas.push(Delta::Replicate { worker_id: unit.id(), direction: Direction::East });
+                deltas.push(Delta::Blueprint { worker_id: unit.id(), structure_type: UnitType::Factory, direction });
+            }
+            deltas
+        };
+
+        // Play partway into a turn, then save.
+        for _ in 0..8 {
+            let turn = deltas(&world);
+            world.apply_many(&turn);
+            world.end_turn(FILLER_TIME);
+        }
+        let turn = deltas(&world);
+        world.apply_many(&turn[..turn.len() / 2]);
+        let path = env::temp_dir().join(format!("bc-world-{}.json", process::id()));
+        world.to_file(&path).unwrap();
+        let mut loaded = GameWorld::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, world);
+
+        // Both copies play the rest of the game identically.
+        for _ in 0..40 {
+            let turn = deltas(&world);
+            let results: Vec<bool> = world.apply_many(&turn).iter().map(|r| r.is_ok()).collect();
+            let loaded_results: Vec<bool> = loaded.apply_many(&turn).iter().map(|r| r.is_ok()).collect();
+            assert_eq!(loaded_results, results);
+            assert_eq!(loaded.end_turn(FILLER_TIME), world.end_turn(FILLER_TIME));
+        }
+        assert_eq!(loaded, world);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_files() {
+        let world = GameWorld::test_world();
+        let path = env::temp_dir().join(format!("bc-invalid-world-{}.json", process::id()));
+
+        // Filtered worlds cannot be saved.
+        let filtered = world.filter(Player::new(Team::Red, Planet::Earth));
+        assert_err!(filtered.to_file(&path), GameError::InvalidSnapshot);
+
+        // Files from other versions, or that are corrupt, cannot be loaded.
+        world.to_file(&path).unwrap();
+        let mut contents = String::new();
+        File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+        let (header, body) = contents.split_at(contents.find('\n').unwrap());
+        let mut missing_map: serde_json::Value = serde_json::from_str(&body[1..]).unwrap();
+        missing_map[0]["planet_maps"].as_object_mut().unwrap().remove("Mars").unwrap();
+        for bad in [format!("battlecode-world 0{}", body),
+                     format!("{}\n{{}}", header),
+                     format!("{}\n{}", header, missing_map),
+                     body[1..].to_string(),
+                     String::new()].iter() {
+            File::create(&path).unwrap().write_all(bad.as_bytes()).unwrap();
+            assert_err!(GameWorld::from_file(&path), GameError::InvalidSnapshot);
+        }
+        fs::remove_file(&path).unwrap();
+        assert!(GameWorld::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_move_status() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let wall = world.create_unit(Team::Red, loc.add(Direction::West), UnitType::Knight).unwrap();
+        assert_eq!(world.move_status(worker, Direction::North).unwrap(), MoveStatus::Ready);
+        assert_eq!(world.move_status(worker, Direction::North).unwrap().cooldown_rounds().unwrap(), 0);
+
+        // After moving, the worker is on cooldown until its movement heat
+        // drops below MAX_HEAT_TO_ACT.
+        assert!(world.move_robot(worker, Direction::North).is_ok());
+        let status = world.move_status(worker, Direction::North).unwrap();
+        let rounds = status.cooldown_rounds().unwrap();
+        assert_eq!(status, MoveStatus::OnCooldown(rounds));
+        assert!(status.is_on_cooldown() && !status.is_ready() && !status.is_blocked());
+        assert_err!(status.blocked_location(), GameError::NullValue);
+        assert!(rounds > 0);
+        for i in 0..rounds {
+            assert!(!world.is_move_ready(worker));
+            assert_eq!(world.move_status(worker, Direction::North).unwrap(),
+                       MoveStatus::OnCooldown(rounds - i));
+            world.end_round();
+        }
+        assert!(world.is_move_ready(worker));
+        assert_eq!(world.move_status(worker, Direction::North).unwrap(), MoveStatus::Ready);
+
+        // A blocked destination is reported even while on cooldown.
+        assert!(world.move_robot(worker, Direction::South).is_ok());
+        let status = world.move_status(worker, Direction::West).unwrap();
+        assert_eq!(status, MoveStatus::Blocked(loc.add(Direction::West)));
+        assert!(status.is_blocked() && !status.is_ready() && !status.is_on_cooldown());
+        assert_eq!(status.blocked_location().unwrap(), world.my_unit(wall).unwrap().location().map_location().unwrap());
+        assert_err!(status.cooldown_rounds(), GameError::NullValue);
+        assert!(world.move_status(worker, Direction::East).unwrap().is_on_cooldown());
+
+        // So is a destination off the map.
+        let edge = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 0, 0), UnitType::Knight).unwrap();
+        let status = world.move_status(edge, Direction::Southwest).unwrap();
+        assert_eq!(status, MoveStatus::OffMap(MapLocation::new(Planet::Earth, -1, -1)));
+        assert!(status.is_blocked());
+
+        // Errors are kept for units that cannot move at all.
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Factory).unwrap();
+        assert_err!(world.move_status(factory, Direction::North), GameError::InappropriateUnitType);
+        assert_err!(world.move_status(0, Direction::North), GameError::NoSuchUnit);
+    }
+
+    #[test]
+    fn test_off_map_not_occupiable() {
+        let mut world = GameWorld::test_world();
+        let map = world.starting_map(Planet::Earth).clone();
+        let max = map.width as i32 - 1;
+        for &(x, y) in [(0, 0), (max, 0), (0, max), (max, max)].iter() {
+            let loc = MapLocation::new(Planet::Earth, x, y);
+            let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+            for direction in Direction::all() {
+                let next = loc.add(direction);
+                let on_map = map.on_map(next);
+                assert_eq!(world.is_occupiable(next).unwrap(), on_map);
+                assert_eq!(world.can_move(worker, direction), on_map);
+                assert_eq!(world.can_replicate(worker, direction), on_map);
+                assert_eq!(world.can_blueprint(worker, UnitType::Factory, direction), on_map);
+                if !on_map {
+                    assert_eq!(world.move_status(worker, direction).unwrap(), MoveStatus::OffMap(next));
+                    assert_err!(world.move_robot(worker, direction),
+                                GameError::LocationOffMap { location: next });
+                }
+            }
+        }
+        assert!(!world.is_occupiable(MapLocation::new(Planet::Mars, 1, 1)).unwrap());
+    }
+
+    #[test]
+    fn test_structures_are_not_robots() {
+        let mut world = GameWorld::test_world();
+        let factory = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Factory).unwrap();
+        let rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 6), UnitType::Rocket).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 6, 5), UnitType::Healer).unwrap();
+        let enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 4, 5), UnitType::Knight).unwrap();
+        world.finish_structure(factory);
+        world.finish_structure(rocket);
+
+        for &structure in [factory, rocket].iter() {
+            // Structures cannot move, even when a delta asks them to.
+            assert!(!world.can_move(structure, Direction::West));
+            assert_err!(world.move_status(structure, Direction::West), GameError::InappropriateUnitType);
+            assert_err!(world.move_robot(structure, Direction::West), GameError::InappropriateUnitType);
+            assert_err!(world.apply(&Delta::Move { robot_id: structure, direction: Direction::West }),
+                        GameError::InappropriateUnitType);
+
+            // Nor attack, be healed, or be garrisoned.
+            assert_err!(world.attack_status(structure, enemy), GameError::InappropriateUnitType);
+            assert_err!(world.attack(structure, enemy), GameError::InappropriateUnitType);
+            assert_err!(world.heal(healer, structure), GameError::InappropriateUnitType);
+        }
+        assert_err!(world.load(rocket, factory), GameError::InappropriateUnitType);
+        assert_err!(world.produce_robot(factory, UnitType::Rocket), GameError::InappropriateUnitType);
+        assert_err!(world.produce_robot(factory, UnitType::Factory), GameError::InappropriateUnitType);
+
+        // Nothing moved.
+        assert_eq!(world.my_unit(factory).unwrap().location(), OnMap(MapLocation::new(Planet::Earth, 5, 5)));
+        assert_eq!(world.my_unit(rocket).unwrap().location(), OnMap(MapLocation::new(Planet::Earth, 5, 6)));
+    }
+
+    #[test]
+    fn test_action_status() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 0, 0);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let knight = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 10, 10), UnitType::Healer).unwrap();
+        let near_enemy = world.create_unit(Team::Blue, loc.add(Direction::North).add(Direction::North), UnitType::Knight).unwrap();
+        let far_enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 0, 12), UnitType::Knight).unwrap();
+        let rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 0), UnitType::Rocket).unwrap();
+        let built_rocket = world.create_unit(Team::Red, MapLocation::new(Planet::Earth, 5, 5), UnitType::Rocket).unwrap();
+        world.finish_structure(built_rocket);
+        let mars_loc = MapLocation::new(Planet::Mars, 1, 1);
+        let mars_wall = MapLocation::new(Planet::Mars, 2, 2);
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Mars).unwrap()).is_passable_terrain[2][2] = false;
+        world.my_planet_mut().karbonite_mut()[0][1] = 0;
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Earth).unwrap()).is_passable_terrain[1][1] = false;
+
+        // The boolean and status versions agree, and the status explains why.
+        let check = |can: bool, status: Result<(), Error>, expected: Option<GameError>| {
+            assert_eq!(can, status.is_ok());
+            assert_eq!(status.err().map(|e| e.downcast::<GameError>().unwrap()), expected);
+        };
+        let cases = vec![
+            (worker, Direction::South, Ok(MoveStatus::OffMap(MapLocation::new(Planet::Earth, 0, -1)))),
+            (worker, Direction::North, Ok(MoveStatus::Blocked(loc.add(Direction::North)))),
+            (worker, Direction::Northeast, Ok(MoveStatus::Blocked(loc.add(Direction::Northeast)))),
+            (worker, Direction::East, Ok(MoveStatus::Ready)),
+            (near_enemy, Direction::East, Err(GameError::TeamNotAllowed)),
+            (0, Direction::East, Err(GameError::NoSuchUnit)),
+        ];
+        for (id, direction, expected) in cases {
+            let status = world.move_status(id, direction).map_err(|e| e.downcast::<GameError>().unwrap());
+            assert_eq!(world.can_move(id, direction), status.as_ref().map_or(false, |status| !status.is_blocked()));
+            assert_eq!(status, expected);
+        }
+        let cases = vec![
+            (knight, near_enemy, None),
+            (knight, far_enemy, Some(GameError::OutOfRange)),
+            (healer, near_enemy, Some(GameError::InappropriateUnitType)),
+            (rocket, near_enemy, Some(GameError::InappropriateUnitType)),
+        ];
+        for (id, target, expected) in cases {
+            check(world.can_attack(id, target), world.attack_status(id, target), expected);
+        }
+        let cases = vec![
+            (worker, Direction::Center, None),
+            (worker, Direction::East, Some(GameError::KarboniteDepositEmpty)),
+            (worker, Direction::West, Some(GameError::LocationOffMap { location: MapLocation::new(Planet::Earth, -1, 0) })),
+            (knight, Direction::Center, Some(GameError::InappropriateUnitType)),
+        ];
+        for (id, direction, expected) in cases {
+            check(world.can_harvest(id, direction), world.harvest_status(id, direction), expected);
+        }
+        let cases = vec![
+            (worker, UnitType::Factory, Direction::East, None),
+            (worker, UnitType::Factory, Direction::North, Some(GameError::LocationNotEmpty { location: loc.add(Direction::North) })),
+            (worker, UnitType::Rocket, Direction::East, Some(GameError::ResearchNotUnlocked { unit_type: UnitType::Rocket })),
+            (worker, UnitType::Knight, Direction::East, Some(GameError::InappropriateUnitType)),
+            (knight, UnitType::Factory, Direction::East, Some(GameError::InappropriateUnitType)),
+        ];
+        for (id, unit_type, direction, expected) in cases {
+            check(world.can_blueprint(id, unit_type, direction),
+                  world.blueprint_status(id, unit_type, direction), expected);
+        }
+        let cases = vec![
+            (built_rocket, mars_loc, None),
+            (built_rocket, mars_wall, Some(GameError::LocationNotEmpty { location: mars_wall })),
+            (built_rocket, MapLocation::new(Planet::Earth, 1, 1), Some(GameError::SamePlanet)),
+            (rocket, mars_loc, Some(GameError::StructureNotYetBuilt)),
+            (knight, mars_loc, Some(GameError::InappropriateUnitType)),
+        ];
+        for (id, destination, expected) in cases {
+            check(world.can_launch_rocket(id, destination),
+                  world.launch_rocket_status(id, destination), expected);
+        }
+
+        // Once the worker has acted, both versions report the heat.
+        world.harvest(worker, Direction::Center).unwrap();
+        check(world.can_harvest(worker, Direction::Center),
+              world.harvest_status(worker, Direction::Center),
+              Some(GameError::Overheated { unit_id: worker }));
+        check(world.can_blueprint(worker, UnitType::Factory, Direction::East),
+              world.blueprint_status(worker, UnitType::Factory, Direction::East),
+              Some(GameError::Overheated { unit_id: worker }));
+    }
+
+    #[test]
+    fn test_pathological_deltas() {
+        // Units on the map, in garrisons, in space, on the enemy team, and
+        // units that do not exist at all.
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let knight = world.create_unit(Team::Red, loc.add(Direction::Northeast), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, loc.add(Direction::Southeast), UnitType::Healer).unwrap();
+        let mage = world.create_unit(Team::Red, loc.add(Direction::Northwest), UnitType::Mage).unwrap();
+        let ranger = world.create_unit(Team::Red, loc.add(Direction::Southwest), UnitType::Ranger).unwrap();
+        let blueprint = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Factory).unwrap();
+        let factory = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Factory).unwrap();
+        let rocket = world.create_unit(Team::Red, loc.add(Direction::South), UnitType::Rocket).unwrap();
+        let launched = world.create_unit(Team::Red, loc.add(Direction::West), UnitType::Rocket).unwrap();
+        for &id in [factory, rocket, launched].iter() {
+            world.finish_structure(id);
+        }
+        let in_factory = world.create_unit(Team::Red, loc.add(Direction::North).add(Direction::North), UnitType::Knight).unwrap();
+        let in_rocket = world.create_unit(Team::Red, loc.add(Direction::South).add(Direction::South), UnitType::Worker).unwrap();
+        let in_space = world.create_unit(Team::Red, loc.add(Direction::West).add(Direction::West), UnitType::Worker).unwrap();
+        let enemy = world.create_unit(Team::Blue, MapLocation::new(Planet::Earth, 15, 15), UnitType::Knight).unwrap();
+
+        let ids = vec![worker, knight, healer, mage, ranger, blueprint, factory,
+                       rocket, launched, in_factory, in_rocket, in_space, enemy,
+                       0, u16::max_value()];
+        let locations = vec![loc, loc.add(Direction::East), MapLocation::new(Planet::Earth, -1, -1),
+                             MapLocation::new(Planet::Earth, 20, 20),
+                             MapLocation::new(Planet::Mars, 5, 5),
+                             MapLocation::new(Planet::Mars, -1, 20)];
+        let mut deltas = vec![];
+        for &a in ids.iter() {
+            for &b in ids.iter() {
+                deltas.push(Delta::Attack { robot_id: a, target_unit_id: b });
+                deltas.push(Delta::Build { worker_id: a, blueprint_id: b });
+                deltas.push(Delta::Heal { healer_id: a, target_robot_id: b });
+                deltas.push(Delta::Javelin { knight_id: a, target_unit_id: b });
+                deltas.push(Delta::Load { structure_id: a, robot_id: b });
+                deltas.push(Delta::Overcharge { healer_id: a, target_robot_id: b });
+                deltas.push(Delta::Repair { worker_id: a, structure_id: b });
+            }
+            for direction in Direction::all().into_iter().chain(Some(Direction::Center)) {
+                deltas.push(Delta::Blueprint { worker_id: a, structure_type: UnitType::Rocket, direction });
+                deltas.push(Delta::Harvest { worker_id: a, direction });
+                deltas.push(Delta::Move { robot_id: a, direction });
+                deltas.push(Delta::Replicate { worker_id: a, direction });
+                deltas.push(Delta::Unload { structure_id: a, direction });
+            }
+            for &location in locations.iter() {
+                deltas.push(Delta::BeginSnipe { ranger_id: a, location });
+                deltas.push(Delta::Blink { mage_id: a, location });
+            }
+            for &unit_type in UnitType::all().iter() {
+                deltas.push(Delta::ProduceRobot { factory_id: a, robot_type: unit_type });
+            }
+        }
+        for &a in ids.iter() {
+            for &location in locations.iter() {
+                deltas.push(Delta::LaunchRocket { rocket_id: a, location });
+            }
+        }
+        for &a in ids.iter() {
+            deltas.push(Delta::Disintegrate { unit_id: a });
+        }
+
+        // Load the garrisons and launch a rocket before the deltas arrive.
+        world.load(factory, in_factory).unwrap();
+        world.load(rocket, in_rocket).unwrap();
+        world.load(launched, in_space).unwrap();
+        world.launch_rocket(launched, MapLocation::new(Planet::Mars, 5, 5)).unwrap();
+
+        // Every player sends every delta, turn after turn. None of them may
+        // take the engine down, however little sense they make.
+        for _ in 0..8 {
+            for delta in deltas.iter() {
+                let _ = world.apply(delta);
+            }
+            world.end_turn(FILLER_TIME);
+        }
+    }
+
+    #[test]
+    fn test_internal_errors() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let rocket = world.create_unit(Team::Red, loc, UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        let worker = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Worker).unwrap();
+        world.load(rocket, worker).unwrap();
+
+        // Indexing a unit that is not on the map is an error, not a panic.
+        assert_err!(world.place_unit(worker), GameError::InternalEngineError);
+        assert_err!(world.remove_unit(worker), GameError::InternalEngineError);
+        assert_err!(world.place_unit(0), GameError::NoSuchUnit);
+        assert_err!(world.destroy_unit(worker, Destruction::Combat), GameError::InternalEngineError);
+        assert_err!(world.disintegrate_unit(worker), GameError::UnitNotOnMap);
+        assert_eq!(world.my_unit(worker).unwrap().location(), InGarrison(rocket));
+
+        // A rocket whose garrison has gone missing stays put.
+        world.get_planet_mut(Planet::Earth).units.remove(&worker);
+        assert_err!(world.move_to_space(rocket), GameError::InternalEngineError);
+        assert_eq!(world.my_unit(rocket).unwrap().location(), OnMap(loc));
+        world.filter(world.player_to_move);
+
+        // A landing off the map leaves the rocket in space.
+        let rocket = world.create_unit(Team::Red, loc.add(Direction::South), UnitType::Rocket).unwrap();
+        world.finish_structure(rocket);
+        world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 5, 5)).unwrap();
+        let off_map = MapLocation::new(Planet::Mars, -1, 5);
+        assert_err!(world.land_rocket(rocket, off_map), GameError::InternalEngineError);
+        assert_eq!(world.my_unit(rocket).unwrap().location(), InSpace);
+    }
+
+    #[test]
+    fn test_failed_actions_leave_world_unchanged() {
+        let mut world = GameWorld::test_world();
+        let loc = MapLocation::new(Planet::Earth, 5, 5);
+        let worker = world.create_unit(Team::Red, loc, UnitType::Worker).unwrap();
+        let rocket = world.create_unit(Team::Red, loc.add(Direction::East), UnitType::Rocket).unwrap();
+        let knight = world.create_unit(Team::Red, loc.add(Direction::Northeast), UnitType::Knight).unwrap();
+        let healer = world.create_unit(Team::Red, loc.add(Direction::North), UnitType::Healer).unwrap();
+        world.finish_structure(rocket);
+        assert!(world.load(rocket, knight).is_ok());
+        Arc::make_mut(world.planet_maps.get_mut(&Planet::Mars).unwrap()).is_passable_terrain[0][0] = false;
+
+        // Each of these actions fails on the last check before acting.
+        let serialized = serde_json::to_string(&world).unwrap();
+        world.my_team_mut().karbonite = 0;
+        assert_err!(world.replicate(worker, Direction::South), GameError::InsufficientKarbonite);
+        assert_err!(world.blueprint(worker, UnitType::Factory, Direction::South), GameError::InsufficientKarbonite);
+        world.my_team_mut().karbonite = KARBONITE_STARTING;
+        assert_err!(world.unload(rocket, Direction::East), GameError::Overheated { unit_id: knight });
+        assert_err!(world.launch_rocket(rocket, MapLocation::new(Planet::Mars, 0, 0)), GameError::LocationNotEmpty { location: MapLocation::new(Planet::Mars, 0, 0) });
+        assert_err!(world.overcharge(healer, worker), GameError::ResearchNotUnlocked { unit_type: UnitType::Healer });
+        assert_err!(world.apply(&Delta::Move { robot_id: worker, direction: Direction::North }), GameError::LocationNotEmpty { location: loc.add(Direction::North) });
+        assert_eq!(serde_json::to_string(&world).unwrap(), serialized);
+    }
+
     #[test]
     fn test_player_methods_on_mars_shouldnt_use_get_unit() {
         let mut world = GameWorld::test_world();
@@ -3492,8 +8072,8 @@ mod tests {
         let mars_enemy = world.create_unit(Team::Blue, mars_loc.add(Direction::East), UnitType::Healer).unwrap();
 
         // go to red mars turn
-        world.end_turn();
-        world.end_turn();
+        world.end_turn(FILLER_TIME);
+        world.end_turn(FILLER_TIME);
 
         // sense that unit
         let player = Player::new(Team::Red, Planet::Mars);
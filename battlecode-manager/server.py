@@ -88,7 +88,7 @@ class Game(object): # pylint: disable=too-many-instance-attributes
 
         self.map = game_map
 
-        self.manager = bc.GameController.new_manager(self.map)
+        self.manager = bc.GameController.new_manager_with_time(self.map, int(time_pool), int(time_additional))
         for player in self.players:
             player['start_message'] = self.manager.start_game(player['player']).to_json()
         self.viewer_messages = []
@@ -262,7 +262,10 @@ class Game(object): # pylint: disable=too-many-instance-attributes
         projected_time_ms = int(1000 * (self.times[next_client_id] + self.time_additional))
 
         # interact with the engine
+        self.manager.apply_time_used(int(1000 * diff_time))
         application = self.manager.apply_turn(turn_message, projected_time_ms)
+        if application.desynced:
+            print("{}'s world has diverged from the manager's".format(client_id))
         self.last_message = application.start_turn.to_json()
         self.viewer_messages.append(application.viewer.to_json())
         self.manager_viewer_messages.append(self.manager.manager_viewer_message())
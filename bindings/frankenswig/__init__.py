@@ -59,16 +59,18 @@ from collections import namedtuple
 
 from .helpers import *
 from .type import *
-from .function import FunctionWrapper
+from .function import FunctionWrapper, Method
 from .struct import StructWrapper
-from .enums import CEnumWrapper
+from .enums import CEnumWrapper, ErrorEnum
 
 RUST_HEADER = '''/// GENERATED RUST, DO NOT EDIT
 extern crate {crate};
 extern crate serde_json;
+extern crate failure;
 
 use {crate} as {module};
 
+use std::any::Any;
 use std::os::raw::c_char;
 use std::cell::RefCell;
 use std::ffi::{{CStr, CString}};
@@ -110,14 +112,18 @@ enum SwigError {{
 thread_local! {{
     // this can be replaced with UnsafeCell / pointers and flags
     // if we're really hurting for performance
-    static ERROR: RefCell<Option<(SwigError, String)>> = {{
+    static ERROR: RefCell<Option<(SwigError, Option<&'static str>, String)>> = {{
         RefCell::new(None)
     }};
 }}
 // only usable from rust
 fn set_error(code: SwigError, err: String) {{
+    set_error_kind(code, None, err);
+}}
+// the kind is the variant of the program's error enum, if any, that caused the error
+fn set_error_kind(code: SwigError, kind: Option<&'static str>, err: String) {{
     ERROR.with(move |e| {{
-        *e.borrow_mut() = Some((code, err));
+        *e.borrow_mut() = Some((code, kind, err));
     }});
 }}
 
@@ -139,7 +145,7 @@ pub unsafe extern "C" fn {module}_get_last_err(result: *mut *mut c_char) -> i8 {
     ERROR.with(|e| {{
         let mut data = None;
         mem::swap(&mut data, &mut *e.borrow_mut());
-        if let Some((code, err)) = data {{
+        if let Some((code, _, err)) = data {{
             result_code = code as i8;
             *result = CString::new(err)
                 .map(|r| r.into_raw())
@@ -148,6 +154,18 @@ pub unsafe extern "C" fn {module}_get_last_err(result: *mut *mut c_char) -> i8 {
     }});
     result_code
 }}
+// called from c, before get_last_err, which clears the error
+#[no_mangle]
+pub unsafe extern "C" fn {module}_get_last_err_kind(result: *mut *mut c_char) -> u8 {{
+    let mut has_kind = 0;
+    ERROR.with(|e| {{
+        if let Some((_, Some(kind), _)) = *e.borrow() {{
+            has_kind = 1;
+            *result = CString::new(kind).unwrap().into_raw();
+        }}
+    }});
+    has_kind
+}}
 // called from c
 #[no_mangle]
 pub unsafe extern "C" fn {module}_free_string(err: *mut c_char) {{
@@ -190,7 +208,7 @@ macro_rules! check_result {{
     ($result:expr, $default:expr) => {{
         match $result {{
             Err(err) => {{
-                set_error(SwigError::Runtime, format!("{{}}", err));
+                set_error_kind(SwigError::Runtime, error_kind(&err), format!("{{}}", err));
                 $default
             }},
             Ok(result) => {{
@@ -212,6 +230,7 @@ extern "C" {{
 #include <stdint.h>
 uint8_t {module}_has_err();
 int8_t {module}_get_last_err(char** result);
+uint8_t {module}_get_last_err_kind(char** result);
 int8_t {module}_free_string(char* err);
 '''
 
@@ -357,11 +376,26 @@ import enum
 # might be cheaper to just allocate new strings, TODO benchmark.
 def _check_errors():
     if _lib.{module}_has_err():
+        kind = _last_error_kind()
         _lasterror = _ffi.new('char**')
         err = _lib.{module}_get_last_err(_lasterror)
         errtext = _ffi.string(_lasterror[0])
         _lib.{module}_free_string(_lasterror[0])
-        raise Exception(errtext)
+        raise _error_class(kind)(errtext.decode())
+
+# exception classes for the variants of the program's error enum, by variant name
+_error_classes = {{}}
+
+def _last_error_kind():
+    _kind = _ffi.new('char**')
+    if not _lib.{module}_get_last_err_kind(_kind):
+        return None
+    kind = _ffi.string(_kind[0]).decode()
+    _lib.{module}_free_string(_kind[0])
+    return kind
+
+def _error_class(kind):
+    return _error_classes.get(kind, Exception)
 
 def game_turns():
     """Usage:
@@ -390,6 +424,7 @@ class Program(object):
         self.crate = crate
         self.docs = docs
         self.elements = []
+        self.error_enum_ = None
 
         # maintaining the "thing.type" idiom
         self.string = namedtuple('String', ['type'])(StringType(self.module))
@@ -402,12 +437,36 @@ class Program(object):
         vec.method(usize.type, "len", [], pyname="__len__", docs="The length of the vector.")
         # TODO impl option and use .get() instead
         vec.method(type.ref(), "index", [Var(usize.type, "index")], pyname="__getitem__", docs="Copy an element out of the vector.")
-        vec.pyextra(s('''\
+        # copies every element out in a single call, rather than one call per element
+        out = BuiltinType(f'*mut {type.rust}', f'{type.to_c()}*', 'INVALID', '0 as *mut _')
+        pre, this, _ = vec.type.mut_ref().wrap_c_value('this')
+        body = s(f'''\
+            if result == ptr::null_mut() {{
+                set_error(SwigError::NullReference, "result is null".into());
+                return _default;
+            }}
+            let maybe_panic = panic::catch_unwind(move || {{
+                {pre}
+                for (i, element) in {this}.iter().enumerate() {{
+                    unsafe {{ *result.offset(i as isize) = {type.ref().unwrap_rust_value('element')}; }}
+                }}
+            }});
+            check_panic!(maybe_panic, _default)
+        ''')
+        elements = Method(void.type, vec.c_name, "elements", [Var(vec.type, 'this'), Var(out, 'result')], body,
+            docs="Copy every element out of the vector, into an array at least as long as the vector.")
+        # only used by __iter__
+        elements.to_python = elements.to_swig = lambda: ''
+        vec.methods.append(elements)
+        vec.pyextra(s(f'''\
         def __iter__(self):
             l = len(self)
+            elements = _ffi.new('{type.to_c()}[]', l)
+            _lib.{elements.name}(self._ptr, elements)
+            _check_errors()
             for i in range(l):
-                yield self[i]
-        '''))
+                result = elements[i]
+        ''') + s(type.python_postfix(), indent=8) + '        yield result\n')
         return vec
 
     def add(self, elem):
@@ -417,7 +476,14 @@ class Program(object):
         return header.format(crate=self.crate, module=self.module, docs=self.docs)
 
     def to_rust(self):
+        # errors are only given a kind if the program has an error enum
+        fallback = '' if self.error_enum_ else s('''\
+        fn error_kind<E: 'static>(_: &E) -> Option<&'static str> {
+            None
+        }
+        ''')
         return self.format(RUST_HEADER)\
+            + fallback\
             + ''.join(elem.to_rust() for elem in self.elements)\
             + self.format(RUST_FOOTER)
 
@@ -451,6 +517,12 @@ class Program(object):
         self.elements.append(result)
         return result
 
+    def error_enum(self, *args, **kwargs):
+        result = ErrorEnum(self, *args, **kwargs)
+        self.error_enum_ = result
+        self.elements.append(result)
+        return result
+
     def c_enum(self, *args, **kwargs):
         result = CEnumWrapper(self, *args, **kwargs)
         self.elements.append(result)
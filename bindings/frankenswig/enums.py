@@ -160,3 +160,51 @@ class CEnumWrapper(CEnum, DeriveMixins):
     def to_python(self):
         methods = '\n'.join(m.to_python() for m in self.methods)
         return super().to_python() + s(methods, indent=4)
+
+class ErrorEnum(object):
+    '''A rust error enum, whose variants are raised as typed exceptions in python.
+    Errors that aren't one of its variants are still raised as plain Exceptions.'''
+
+    def __init__(self, program, name, docs=''):
+        self.program = program
+        self.name = name
+        self.gen_name = sanitize_rust_name(name)
+        self.docs = docs
+        self.variants = []
+
+    def variant(self, name, docs=''):
+        self.variants.append((name, docs))
+        return self
+
+    def to_rust(self):
+        # the match is exhaustive, so the bindings stop compiling if a variant is missing
+        start = s(f'''\
+        fn error_kind<E: 'static>(err: &E) -> Option<&'static str> {{
+            let err = (err as &Any).downcast_ref::<failure::Error>()?;
+            // look through any context, such as why a map is invalid
+            let err = err.find_root_cause().downcast_ref::<{self.program.module}::{self.name}>()?;
+            Some(match *err {{
+        ''')
+        body = ''.join(f'{self.program.module}::{self.name}::{name} {{ .. }} => "{name}",\n'
+                       for (name, _) in self.variants)
+        end = s('''\
+            })
+        }
+        ''')
+        return start + s(body, indent=8) + end
+
+    def to_c(self):
+        return ''
+
+    def to_swig(self):
+        return ''
+
+    def to_python(self):
+        result = f'class {self.gen_name}(Exception):\n'
+        result += s(f"'''{self.docs}'''\n", indent=4)
+        for name, docs in self.variants:
+            result += f'\nclass {name}({self.gen_name}):\n'
+            result += s(f"'''{docs}'''\n", indent=4)
+        result += '\n'
+        result += ''.join(f'_error_classes["{name}"] = {name}\n' for (name, _) in self.variants)
+        return result
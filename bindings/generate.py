@@ -4,6 +4,40 @@ p = Program(module='bc', crate='battlecode_engine', docs='''Battlecode engine.
 
 Woo.''')
 
+GameError = p.error_enum('error::GameError', docs='An error raised by the engine when an action or query is invalid.')
+GameError.variant('ArrayOutOfBounds', docs='You cannot read outside of the bounds of the communication array.')
+GameError.variant('CannotBuildOnMars', docs='You cannot build structures on Mars.')
+GameError.variant('DifferentPlanet', docs='The locations are on different planets.')
+GameError.variant('FactoryBusy', docs='The factory is already producing a unit.')
+GameError.variant('GameOver', docs='The game is over, and no more actions can be taken.')
+GameError.variant('GarrisonEmpty', docs="The structure's garrison is empty.")
+GameError.variant('GarrisonFull', docs="The structure's garrison is full.")
+GameError.variant('InappropriateUnitType', docs='The given unit does not have a type appropriate for the given action.')
+GameError.variant('InvalidMapObject', docs='The map-related object is invalid.')
+GameError.variant('InvalidSnapshot', docs='The saved game world is invalid, or was saved by a different version of the engine.')
+GameError.variant('InsufficientKarbonite', docs='Your team does not have enough Karbonite to perform the requested action.')
+GameError.variant('InternalEngineError', docs="The engine's internal state is inconsistent. This is a bug in the engine, not in your code.")
+GameError.variant('KarboniteDepositEmpty', docs='The Karbonite deposit is empty and cannot be harvested further.')
+GameError.variant('LocationNotEmpty', docs='The location corresponding to the requested action is not empty.')
+GameError.variant('LocationNotVisible', docs='The location is outside your vision range.')
+GameError.variant('LocationOffMap', docs='The location is off the map of the current planet.')
+GameError.variant('NoMoreUnitIDs', docs='The game has run out of unit IDs, and no more units can be created.')
+GameError.variant('NoSuchUnit', docs='The specified unit does not exist, at least within your vision range.')
+GameError.variant('NullValue', docs='No object returned, check whether it exists first.')
+GameError.variant('OutOfRange', docs='The unit is too far away to perform an action.')
+GameError.variant('Overheated', docs="The unit's heat is not low enough to perform the requested action.")
+GameError.variant('ResearchLevelInvalid', docs='The level of research does not exist for this branch.')
+GameError.variant('ResearchNotUnlocked', docs='The level of research has not been unlocked by your team.')
+GameError.variant('RocketUsed', docs='The rocket has already been used.')
+GameError.variant('SamePlanet', docs='Rockets cannot be flown to other locations on the same planet.')
+GameError.variant('StructureAlreadyBuilt', docs='The structure has already been completed, and cannot be built further.')
+GameError.variant('StructureAtMaxHealth', docs='The structure is already at full health, and cannot be repaired.')
+GameError.variant('StructureNotYetBuilt', docs='The structure has not yet been completed, and cannot perform actions yet.')
+GameError.variant('TeamNotAllowed', docs='You are not allowed to control units on the other team.')
+GameError.variant('TooClose', docs='The unit is too close to perform an action.')
+GameError.variant('UnitNotOnMap', docs="The unit is in a structure's garrison or flying through space.")
+GameError.variant('UnitNotInGarrison', docs="The unit is not in a structure's garrison.")
+
 Planet = p.c_enum('location::Planet', docs='The planets in the Battlecode world.')
 Planet.variant('Earth', 0)
 Planet.variant('Mars', 1)
@@ -141,6 +175,41 @@ UnitType.method(u32.type.result(), 'blueprint_cost', [], docs='''The cost to blu
 UnitType.method(u32.type.result(), 'replicate_cost', [], docs='''The cost to replicate the unit.
 
  * InappropriateUnitType - the unit type is not a worker.''')
+UnitType.method(Rounds.type.result(), 'factory_rounds', [], docs='''The number of rounds it takes a factory to produce the unit.
+
+ * InappropriateUnitType - the unit type cannot be produced in a factory.''')
+UnitType.method(u32.type.result(), 'max_health', [Var(Level.type, 'level')], docs='''The maximum health of the unit at the given research level.
+
+ * ResearchNotUnlocked - the research level does not exist for this
+   unit type.''')
+UnitType.method(i32.type.result(), 'damage', [Var(Level.type, 'level')], docs='''The damage inflicted by a normal attack at the given research level.
+Healers inflict negative damage.
+
+ * InappropriateUnitType - the unit type is not a robot.
+ * ResearchNotUnlocked - the research level does not exist for this
+   unit type.''')
+UnitType.method(u32.type.result(), 'attack_range', [], docs='''The attack range, which research does not change.
+
+ * InappropriateUnitType - the unit type is not a robot.''')
+UnitType.method(u32.type.result(), 'ranger_cannot_attack_range', [], docs='''The range within which a ranger cannot attack, which research does not change.
+
+ * InappropriateUnitType - the unit type is not a ranger.''')
+UnitType.method(u32.type.result(), 'vision_range', [Var(Level.type, 'level')], docs='''The vision range of the unit at the given research level.
+
+ * ResearchNotUnlocked - the research level does not exist for this
+   unit type.''')
+UnitType.method(u32.type.result(), 'movement_cooldown', [Var(Level.type, 'level')], docs='''The movement cooldown at the given research level.
+
+ * InappropriateUnitType - the unit type is not a robot.
+ * ResearchNotUnlocked - the research level does not exist for this
+   unit type.''')
+UnitType.method(u32.type.result(), 'attack_cooldown', [], docs='''The attack cooldown, which research does not change.
+
+ * InappropriateUnitType - the unit type is not a robot.''')
+UnitType.method(u32.type.result(), 'ability_cooldown', [], docs='''The ability cooldown, which research does not change. For workers,
+this is the cooldown of replication.
+
+ * InappropriateUnitType - the unit type is not a robot.''')
 UnitType.method(u32.type, 'value', [], docs="The value of a unit, as relevant to tiebreakers.")
 UnitTypeVec = p.vec(UnitType.type)
 
@@ -272,6 +341,23 @@ LocationOffMap - the location is off the map.''')
 PlanetMap.method(u32.type.result(), 'initial_karbonite_at', [Var(MapLocation.type, 'location')], docs='''The amount of Karbonite initially deposited at the given location.
 
 LocationOffMap - the location is off the map.''')
+PlanetMap.constructor('new', [Var(Planet.type, 'planet'), Var(usize.type, 'width'), Var(usize.type, 'height')], result=True, docs='''An empty map of the planet with the given dimensions: every square is passable, with no karbonite or initial units.
+
+InvalidMapObject - the dimensions are out of range.''')
+PlanetMap.method(void.type.result(), 'set_passable', [Var(MapLocation.type, 'location'), Var(boolean.type, 'passable')], docs='''Sets whether the square contains passable terrain.
+
+LocationOffMap - the location is off the map.
+LocationNotEmpty - the square has an initial unit, so it cannot become impassable.''')
+PlanetMap.method(void.type.result(), 'set_karbonite', [Var(MapLocation.type, 'location'), Var(u32.type, 'amount')], docs='''Sets the initial karbonite deposit on the square.
+
+LocationOffMap - the location is off the map.
+InvalidMapObject - the amount is outside [MAP_KARBONITE_MIN, MAP_KARBONITE_MAX] on Earth, or is not 0 on Mars.''')
+PlanetMap.method(UnitID.type.result(), 'add_initial_unit', [Var(Team.type, 'team'), Var(UnitType.type, 'unit_type'), Var(MapLocation.type, 'location')], docs='''Adds an initial unit to the map, returning its ID.
+
+InvalidMapObject - the map is of Mars, or the team already has the most initial units it can have.
+InappropriateUnitType - the unit is not a worker.
+LocationOffMap - the location is off the map.
+LocationNotEmpty - the square is impassable or has another unit.''')
 PlanetMap.clone()
 PlanetMap.serialize()
 
@@ -312,6 +398,7 @@ SentMessage.member(TurnMessage.type, "turn_message")
 TurnApplication = p.struct("controller::TurnApplication")
 TurnApplication.member(StartTurnMessage.type, 'start_turn')
 TurnApplication.member(ViewerMessage.type, 'viewer')
+TurnApplication.member(boolean.type, 'desynced')
 
 InitialTurnApplication = p.struct("controller::InitialTurnApplication")
 InitialTurnApplication.member(StartTurnMessage.type, 'start_turn')
@@ -330,6 +417,9 @@ AsteroidPattern = p.struct("map::AsteroidPattern", docs="The asteroid pattern, d
 AsteroidPattern.constructor("random", [Var(u16.type, "seed"), Var(PlanetMap.type.ref(), "mars_map")], docs='''Constructs a pseudorandom asteroid pattern given a map of Mars.''')
 AsteroidPattern.method(void.type.result(), "validate", [], docs='''Validates the asteroid pattern.
 
+ * InvalidMapObject - the asteroid pattern is invalid.''')
+AsteroidPattern.method(void.type.result(), "validate_against", [Var(PlanetMap.type.ref(), "mars_map")], docs='''Validates the asteroid pattern, and that every strike lands on the given map of Mars.
+
  * InvalidMapObject - the asteroid pattern is invalid.''')
 AsteroidPattern.method(boolean.type, "has_asteroid", [Var(Rounds.type, "round")], docs='''Whether there is an asteroid strike at the given round.''')
 AsteroidPattern.method(AsteroidStrike.type.ref().result(), "asteroid", [Var(Rounds.type, "round")], docs='''Get the asteroid strike at the given round.
@@ -349,7 +439,10 @@ The amplitude, period, and center are measured in rounds.''')
 OrbitPattern.method(void.type.result(), 'validate', [], docs='''Validates the orbit pattern.
 
  * InvalidMapObject - the orbit pattern is invalid.''')
-OrbitPattern.method(Rounds.type, 'duration', [Var(Rounds.type, 'round')], "Get the duration of flight if the rocket were to take off from either planet on the given round.")
+OrbitPattern.method(Rounds.type, 'duration', [Var(Rounds.type, 'round')], "Get the duration of flight if the rocket were to take off from either planet on the given round. For a valid orbit pattern, the duration is always in [ORBIT_FLIGHT_MIN, ORBIT_FLIGHT_MAX], on any round.")
+OrbitPattern.method(Rounds.type, 'landing_round', [Var(Rounds.type, 'takeoff_round')], "Get the round a rocket would land on if it were to take off from either planet on the given round.")
+OrbitPattern.method(Rounds.type, 'next_minimum', [Var(Rounds.type, 'after_round')], "Get the first round at or after the given round on which the duration of flight is the shortest it can be.")
+OrbitPattern.method(Rounds.type, 'next_maximum', [Var(Rounds.type, 'after_round')], "Get the first round at or after the given round on which the duration of flight is the longest it can be.")
 OrbitPattern.serialize()
 
 GameMap = p.struct('map::GameMap', docs="The map defining the starting state for an entire game.")
@@ -358,6 +451,7 @@ GameMap.member(PlanetMap.type, 'earth_map', docs="Earth map.")
 GameMap.member(PlanetMap.type, 'mars_map', docs="Mars map.")
 GameMap.member(AsteroidPattern.type, 'asteroids', docs="The asteroid strike pattern on Mars.")
 GameMap.member(OrbitPattern.type, 'orbit', docs="The orbit pattern that determines a rocket's flight duration.")
+GameMap.member(u32.type, 'starting_karbonite', docs="The karbonite in each team's resource pool at the start of the game.")
 GameMap.method(void.type.result(), 'validate', [], docs='''Validate the game map.
 
  * InvalidMapObject - the game map is invalid.''')
@@ -399,8 +493,39 @@ RocketLandingInfo.debug()
 RocketLandingInfo.serialize()
 RocketLandingInfo.eq()
 
+TeamStats = p.struct("world::TeamStats")
+TeamStats.member(u32.type, "units_value", docs="The combined value of the team's living units, used in tiebreakers.")
+TeamStats.member(u32.type, "karbonite_mined", docs="The karbonite the team's workers have harvested.")
+TeamStats.member(u32.type, "units_produced", docs="The units the team has created during the game by blueprinting, replicating, or producing them in a factory.")
+TeamStats.member(u32.type, "units_destroyed_in_combat", docs="The team's units destroyed by attacks.")
+TeamStats.member(u32.type, "units_destroyed_by_rockets", docs="The team's units destroyed by rocket blasts or landings, including rockets lost on launch or landing.")
+TeamStats.member(u32.type, "units_disintegrated", docs="The team's units that were disintegrated.")
+TeamStats.member(u32.type, "units_destroyed_by_flood", docs="The team's units destroyed by the flood on Earth.")
+TeamStats.clone()
+TeamStats.debug()
+TeamStats.serialize()
+TeamStats.eq()
+
+MoveStatus = p.struct("world::MoveStatus", docs="Whether a robot can move in a direction now, after cooling down, or not at all.")
+MoveStatus.method(boolean.type, 'is_ready', [], docs="Whether the robot can move now.")
+MoveStatus.method(boolean.type, 'is_on_cooldown', [], docs="Whether the destination is clear, but the robot must first cool down.")
+MoveStatus.method(boolean.type, 'is_blocked', [], docs="Whether the destination is occupied or off the map.")
+MoveStatus.method(Rounds.type.result(), 'cooldown_rounds', [], docs='''The number of rounds until the robot can move, which is 0 if it is ready.
+
+* NullValue - the destination is occupied or off the map.''')
+MoveStatus.method(MapLocation.type.result(), 'blocked_location', [], docs='''The destination the robot cannot move to.
+
+* NullValue - the destination is clear.''')
+MoveStatus.clone()
+MoveStatus.debug()
+MoveStatus.serialize()
+MoveStatus.eq()
+
 GameController = p.struct('controller::GameController')
 GameController.constructor("new_player_env", [], docs="Use environment variables to connect to the manager.", result=True)
+GameController.method(GameController.type, "new_player", [Var(StartGameMessage.type, "game")], static=True, docs="Create a player's controller from the first message the manager sends them, for hosts that exchange messages with the manager themselves. Call start_turn with each start turn message, then end_turn for the turn message to send back.")
+GameController.method(void.type, "start_turn", [Var(StartTurnMessage.type.ref(), "turn")], docs="Start the current turn, applying the changes since the player's last turn. Only for controllers created with new_player.")
+GameController.method(TurnMessage.type, "end_turn", [], docs="End the current turn, returning the turn message to send to the manager. Only for controllers created with new_player.")
 GameController.method(void.type.result(), "next_turn", [], docs="Send the moves from the current turn and wait for the next turn.")
 GameController.method(i32.type, "get_time_left_ms", [], docs="Get the time left at the start of this player's turn, in milliseconds.")
 
@@ -408,13 +533,20 @@ GameController.method(Rounds.type, 'round', [], docs='''The current round, start
 GameController.method(Planet.type, 'planet', [], docs='''The current planet.''')
 GameController.method(Team.type, 'team', [], docs='''The team whose turn it is.''')
 GameController.method(PlanetMap.type.ref(), 'starting_map', [Var(Planet.type, 'planet')], docs='''The starting map of the given planet. Includes the map's planet, dimensions, impassable terrain, and initial units and karbonite.''')
+GameController.method(UnitVec.type, 'initial_units', [Var(Team.type, 'team')], docs='''The units the given team started the game with, on both planets, sorted by ID. The starting maps are public, so you can see the other team's starting positions too.''')
 GameController.method(u32.type, 'karbonite', [], docs='''The karbonite in the team's resource pool.''')
-GameController.method(Unit.type.result(), 'unit', [Var(UnitID.type, 'id')], docs='''The single unit with this ID. Use this method to get detailed statistics on a unit - heat, cooldowns, and properties of special abilities like units garrisoned in a rocket.
+GameController.method(TeamStats.type.result(), 'team_stats', [Var(Team.type, 'team')], docs='''The running totals of the team's units and karbonite. You can only see the stats of your own team.
 
-* NoSuchUnit - the unit does not exist (inside the vision range).''')
-GameController.method(UnitVec.type, 'units', [], docs='''All the units within the vision range, in no particular order. Does not include units in space.''')
-GameController.method(UnitVec.type, 'my_units', [], docs='''All the units on your team. Does not include units in space.''')
-GameController.method(UnitVec.type, 'units_in_space', [], docs='''All the units of this team that are in space. You cannot see units on the other team that are in space.''')
+* TeamNotAllowed - the team is not your team.''')
+GameController.method(Unit.type.result(), 'unit', [Var(UnitID.type, 'id')], docs='''The single unit with this ID. Use this method to get detailed statistics on a unit on your team - heat, cooldowns, and properties of special abilities like units garrisoned in a rocket.
+
+* NoSuchUnit - the unit does not exist (inside the vision range).
+* TeamNotAllowed - the unit is not on the current player's team.''')
+GameController.method(UnitVec.type, 'units', [], docs='''All the units within the vision range, sorted by ID. Does not include units in space.''')
+GameController.method(UnitVec.type, 'my_units', [], docs='''All the units on your team on your planet, sorted by ID. Includes units in garrisons, but not units in space.''')
+GameController.method(UnitVec.type, 'my_units_by_type', [Var(UnitType.type, 'unit_type')], docs='''All the units on your team on your planet with the given type, sorted by ID. Includes units in garrisons, but not units in space.''')
+GameController.method(UnitVec.type, 'units_on_planet', [Var(Planet.type, 'planet')], docs='''All the units on the given planet within the vision range, sorted by ID. Does not include units in space. You cannot see the other planet, so it has no units.''')
+GameController.method(UnitVec.type, 'units_in_space', [], docs='''All the units of this team that are in space, sorted by ID. Includes both the rockets and the units garrisoned inside them. You cannot see units on the other team that are in space.''')
 GameController.method(u32.type.result(), 'karbonite_at', [Var(MapLocation.type, 'location')], docs='''The karbonite at the given location.
 
 * LocationOffMap - the location is off the map.
@@ -435,24 +567,42 @@ GameController.method(Unit.type.result(), 'sense_unit_at_location', [Var(MapLoca
 GameController.method(AsteroidPattern.type, 'asteroid_pattern', [], docs='''The asteroid strike pattern on Mars.''')
 GameController.method(OrbitPattern.type, 'orbit_pattern', [], docs='''The orbit pattern that determines a rocket's flight duration.''')
 GameController.method(Rounds.type, 'current_duration_of_flight', [], docs='''The current duration of flight if a rocket were to be launched this round. Does not take into account any research done on rockets.''')
+GameController.method(Rounds.type, 'best_launch_round_within', [Var(Rounds.type, 'window')], docs='''The round within the next `window` rounds, starting with the current round, on which a rocket launched would have the shortest flight. The earliest such round is chosen in case of a tie. Research on rockets shortens every flight by the same amount, so it does not change the best round.''')
 GameController.method(TeamArray.type.ref(), 'get_team_array', [Var(Planet.type, 'planet')], docs='''Gets a read-only version of this planet's team array. If the given planet is different from the planet of the player, reads the version of the planet's team array from COMMUNICATION_DELAY rounds prior.''')
 GameController.method(void.type.result(), 'write_team_array', [Var(usize.type, 'index'), Var(i32.type, 'value')], docs='''Writes the value at the index of this planet's team array.
 
 * ArrayOutOfBounds - the index of the array is out of bounds. It must be within [0, COMMUNICATION_ARRAY_LENGTH).''')
+GameController.method(void.type, 'debug_dot', [Var(MapLocation.type, 'location'), Var(u8.type, 'r'), Var(u8.type, 'g'), Var(u8.type, 'b')], docs='''Draws a dot at the location in the viewer, in the given color. Indicators only appear in the viewer, and have no effect on the game.
+
+At most 1024 indicators are kept each turn, and any more are ignored.''')
+GameController.method(void.type, 'debug_line', [Var(MapLocation.type, 'start'), Var(MapLocation.type, 'end'), Var(u8.type, 'r'), Var(u8.type, 'g'), Var(u8.type, 'b')], docs='''Draws a line between the locations in the viewer, in the given color. Indicators only appear in the viewer, and have no effect on the game.
+
+At most 1024 indicators are kept each turn, and any more are ignored.''')
+GameController.method(void.type, 'debug_string', [Var(UnitID.type, 'unit_id'), Var(p.strref.type, 'text')], docs='''Attaches a string to the unit in the viewer. Indicators only appear in the viewer, and have no effect on the game.
+
+Strings longer than 64 bytes are truncated, and at most 1024 indicators are kept each turn.''')
 GameController.method(void.type.result(), 'disintegrate_unit', [Var(UnitID.type, 'unit_id')], docs='''Disintegrates the unit and removes it from the map. If the unit is a factory or a rocket, also disintegrates any units garrisoned inside it.
 
 * NoSuchUnit - the unit does not exist (inside the vision range).
-* TeamNotAllowed - the unit is not on the current player's team.''')
-GameController.method(boolean.type.result(), 'is_occupiable', [Var(MapLocation.type, 'location')], docs='''Whether the location is clear for a unit to occupy, either by movement or by construction.
+* TeamNotAllowed - the unit is not on the current player's team.
+* UnitNotOnMap - the unit is in a garrison.''')
+GameController.method(boolean.type.result(), 'is_occupiable', [Var(MapLocation.type, 'location')], docs='''Whether the location is clear for a unit to occupy, either by movement or by construction. Locations off the map, or on the other planet, are never clear.
 
-* LocationOffMap - the location is off the map.
 * LocationNotVisible - the location is outside the vision range.''')
 GameController.method(boolean.type, 'can_move', [Var(UnitID.type, 'robot_id'), Var(Direction.type, 'direction')], docs='''Whether the robot can move in the given direction, without taking into account the unit's movement heat. Takes into account only the map terrain, positions of other robots, and the edge of the game map.''')
-GameController.method(boolean.type, 'is_move_ready', [Var(UnitID.type, 'robot_id')], docs='''Whether the robot is ready to move. Tests whether the robot's attack heat is sufficiently low.''')
+GameController.method(MoveStatus.type.result(), 'move_status', [Var(UnitID.type, 'robot_id'), Var(Direction.type, 'direction')], docs='''Whether the robot can move in the given direction now, or after cooling down for some rounds, or is blocked by the edge of the map, terrain, or another unit. The robot can move now if and only if can_move() and is_move_ready() are both true.
+
+* NoSuchUnit - the robot does not exist (within the vision range).
+* TeamNotAllowed - the robot is not on the current player's team.
+* InappropriateUnitType - the unit is not a robot.
+* UnitNotOnMap - the robot is not on the map.
+* LocationNotVisible - the location is outside the vision range.''')
+GameController.method(boolean.type, 'is_move_ready', [Var(UnitID.type, 'robot_id')], docs='''Whether the robot is ready to move. Tests whether the robot's movement heat is sufficiently low.''')
 GameController.method(void.type.result(), 'move_robot', [Var(UnitID.type, 'robot_id'), Var(Direction.type, 'direction')], docs='''Moves the robot in the given direction.
 
 * NoSuchUnit - the robot does not exist (within the vision range).
 * TeamNotAllowed - the robot is not on the current player's team.
+* InappropriateUnitType - the unit is not a robot.
 * UnitNotOnMap - the robot is not on the map.
 * LocationNotVisible - the location is outside the vision range.
 * LocationOffMap - the location is off the map.
@@ -460,26 +610,50 @@ GameController.method(void.type.result(), 'move_robot', [Var(UnitID.type, 'robot
 * Overheated - the robot is not ready to move again.''')
 GameController.method(boolean.type, 'can_attack', [Var(UnitID.type, 'robot_id'), Var(UnitID.type, 'target_unit_id')], docs='''Whether the robot can attack the given unit, without taking into account the robot's attack heat. Takes into account only the robot's attack range, and the location of the robot and target.
 
-Healers cannot attack, and should use can_heal() instead.''')
+Workers and healers cannot attack. Healers should use can_heal() instead.''')
+GameController.method(void.type.result(), 'attack_status', [Var(UnitID.type, 'robot_id'), Var(UnitID.type, 'target_unit_id')], docs='''Ok if the robot can attack the given unit, and otherwise the reason it cannot. Agrees with can_attack(), so it does not take into account the robot's attack heat either.
+
+* NoSuchUnit - the unit does not exist (inside the vision range).
+* TeamNotAllowed - the unit is not on the current player's team, or the target is.
+* InappropriateUnitType - the unit is not a robot, or is a worker or healer.
+* UnitNotOnMap - the unit or target is not on the map.
+* OutOfRange - the target location is not in range.
+* TooClose - the target location is within a ranger's cannot attack range.''')
 GameController.method(boolean.type, 'is_attack_ready', [Var(UnitID.type, 'robot_id')], docs='''Whether the robot is ready to attack. Tests whether the robot's attack heat is sufficiently low.
 
-Healers cannot attack, and should use is_heal_ready() instead.''')
+Workers and healers cannot attack. Healers should use is_heal_ready() instead.''')
 GameController.method(void.type.result(), 'attack', [Var(UnitID.type, 'robot_id'), Var(UnitID.type, 'target_unit_id')], docs='''Commands a robot to attack a unit, dealing the robot's standard amount of damage.
 
-Healers cannot attack, and should use heal() instead.
+Workers and healers cannot attack. Healers should use heal() instead.
 
 * NoSuchUnit - the unit does not exist (inside the vision range).
-* TeamNotAllowed - the unit is not on the current player's team.
-* InappropriateUnitType - the unit is not a robot, or is a healer.
+* TeamNotAllowed - the unit is not on the current player's team, or the target is.
+* InappropriateUnitType - the unit is not a robot, or is a worker or healer.
 * UnitNotOnMap - the unit or target is not on the map.
 * OutOfRange - the target location is not in range.
+* TooClose - the target location is within a ranger's cannot attack range.
 * Overheated - the unit is not ready to attack.''')
 GameController.method(ResearchInfo.type.result(), 'research_info', [], docs='''The research info of the current team, including what branch is currently being researched, the number of rounds left.''')
 GameController.method(boolean.type.result(), 'reset_research', [], docs='''Resets the research queue to be empty. Returns true if the queue was not empty before, and false otherwise.''')
 GameController.method(boolean.type.result(), 'queue_research', [Var(UnitType.type, 'branch')], docs='''Adds a branch to the back of the queue, if it is a valid upgrade, and starts research if it is the first in the queue.
 
 Returns whether the branch was successfully added.''')
+GameController.method(boolean.type.result(), 'worker_has_acted', [Var(UnitID.type, 'worker_id')], docs='''Whether your worker has already acted (harvested, blueprinted, built, or repaired) this round. Moving and replicating are not worker actions.
+
+* NoSuchUnit - the worker does not exist (inside the vision range).
+* TeamNotAllowed - the worker is not on the current player's team.
+* InappropriateUnitType - the unit is not a worker.''')
 GameController.method(boolean.type, 'can_harvest', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Whether the worker is ready to harvest, and the given direction contains karbonite to harvest. The worker cannot already have performed an action this round.''')
+GameController.method(void.type.result(), 'harvest_status', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Ok if the worker can harvest in the given direction, and otherwise the reason it cannot. Agrees with can_harvest().
+
+* NoSuchUnit - the worker does not exist (within the vision range).
+* TeamNotAllowed - the worker is not on the current player's team.
+* InappropriateUnitType - the unit is not a worker.
+* Overheated - the worker has already performed an action this turn.
+* UnitNotOnMap - the worker is not on the map.
+* LocationOffMap - the location in the target direction is off the map.
+* LocationNotVisible - the location is not in the vision range.
+* KarboniteDepositEmpty - the location described contains no Karbonite.''')
 GameController.method(void.type.result(), 'harvest', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Harvests up to the worker's harvest amount of karbonite from the given location, adding it to the team's resource pool.
 
 * NoSuchUnit - the worker does not exist (within the vision range).
@@ -491,6 +665,19 @@ GameController.method(void.type.result(), 'harvest', [Var(UnitID.type, 'worker_i
 * LocationNotVisible - the location is not in the vision range.
 * KarboniteDepositEmpty - the location described contains no Karbonite.''')
 GameController.method(boolean.type, 'can_blueprint', [Var(UnitID.type, 'worker_id'), Var(UnitType.type, 'unit_type'), Var(Direction.type, 'direction')], docs='''Whether the worker can blueprint a unit of the given type. The worker can only blueprint factories, and rockets if Rocketry has been researched. The team must have sufficient karbonite in its resource pool. The worker cannot already have performed an action this round.''')
+GameController.method(void.type.result(), 'blueprint_status', [Var(UnitID.type, 'worker_id'), Var(UnitType.type, 'unit_type'), Var(Direction.type, 'direction')], docs='''Ok if the worker can blueprint a structure of the given type in the given direction, and otherwise the reason it cannot. Agrees with can_blueprint().
+
+* NoSuchUnit - the worker does not exist (within the vision range).
+* TeamNotAllowed - the worker is not on the current player's team.
+* InappropriateUnitType - the unit is not a worker, or the unit type is not a structure.
+* Overheated - the worker has already performed an action this turn.
+* UnitNotOnMap - the unit is not on the map.
+* LocationOffMap - the location in the target direction is off the map.
+* LocationNotVisible - the location is outside the vision range.
+* LocationNotEmpty - the location in the target direction is already occupied.
+* CannotBuildOnMars - you cannot blueprint a structure on Mars.
+* ResearchNotUnlocked - you do not have the needed research to blueprint rockets.
+* InsufficientKarbonite - your team does not have enough Karbonite to build the requested structure.''')
 GameController.method(void.type.result(), 'blueprint', [Var(UnitID.type, 'worker_id'), Var(UnitType.type, 'structure_type'), Var(Direction.type, 'direction')], docs='''Blueprints a unit of the given type in the given direction. Subtract cost of that unit from the team's resource pool.
 
 * NoSuchUnit - the worker does not exist (within the vision range).
@@ -523,9 +710,10 @@ GameController.method(void.type.result(), 'repair', [Var(UnitID.type, 'worker_id
 * InappropriateUnitType - the unit is not a worker, or the target is not a structure.
 * Overheated - the worker has already performed an action this turn.
 * OutOfRange - the worker is not adjacent to the structure.
-* StructureNotYetBuilt - the structure has not been completed.''')
+* StructureNotYetBuilt - the structure has not been completed.
+* StructureAtMaxHealth - the structure is not damaged.''')
 GameController.method(boolean.type, 'can_replicate', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Whether the worker is ready to replicate. Tests that the worker's ability heat is sufficiently low, that the team has sufficient karbonite in its resource pool, and that the square in the given direction is empty.''')
-GameController.method(void.type.result(), 'replicate', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Replicates a worker in the given direction. Subtracts the cost of the worker from the team's resource pool.
+GameController.method(void.type.result(), 'replicate', [Var(UnitID.type, 'worker_id'), Var(Direction.type, 'direction')], docs='''Replicates a worker in the given direction. Subtracts the cost of the worker from the team's resource pool. The new worker cannot act until the next round.
 
 * NoSuchUnit - the worker does not exist (within the vision range).
 * TeamNotAllowed - the worker is not on the current player's team.
@@ -541,8 +729,8 @@ GameController.method(boolean.type, 'is_javelin_ready', [Var(UnitID.type, 'knigh
 GameController.method(void.type.result(), 'javelin', [Var(UnitID.type, 'knight_id'), Var(UnitID.type, 'target_unit_id')], docs='''Javelins the robot, dealing the knight's standard damage.
 
 * NoSuchUnit - either unit does not exist (inside the vision range).
-* TeamNotAllowed - the knight is not on the current player's team.
-* UnitNotOnMap - the knight is not on the map.
+* TeamNotAllowed - the knight is not on the current player's team, or the target is.
+* UnitNotOnMap - the knight or target is not on the map.
 * InappropriateUnitType - the unit is not a knight.
 * ResearchNotUnlocked - you do not have the needed research to use javelin.
 * OutOfRange - the target does not lie within ability range of the knight.
@@ -578,20 +766,25 @@ GameController.method(void.type.result(), 'heal', [Var(UnitID.type, 'healer_id')
 * NoSuchUnit - either unit does not exist (inside the vision range).
 * InappropriateUnitType - the unit is not a healer, or the target is not a robot.
 * TeamNotAllowed - either robot is not on the current player's team.
-* UnitNotOnMap - the healer is not on the map.
+* UnitNotOnMap - either unit is not on the map.
 * OutOfRange - the target does not lie within "attack" range of the healer.
 * Overheated - the healer is not ready to heal again.''')
 GameController.method(boolean.type, 'can_overcharge', [Var(UnitID.type, 'healer_id'), Var(UnitID.type, 'target_robot_id')], docs='''Whether the healer can overcharge the given robot, without taking into account the healer's ability heat. Takes into account only the healer's ability range, and the location of the robot.''')
 GameController.method(boolean.type, 'is_overcharge_ready', [Var(UnitID.type, 'healer_id')], docs='''Whether the healer is ready to overcharge. Tests whether the healer's ability heat is sufficiently low.''')
-GameController.method(void.type.result(), 'overcharge', [Var(UnitID.type, 'healer_id'), Var(UnitID.type, 'target_robot_id')], docs='''Overcharges the robot, resetting the robot's cooldowns. The robot must be on the same team as you.
+GameController.method(void.type.result(), 'overcharge', [Var(UnitID.type, 'healer_id'), Var(UnitID.type, 'target_robot_id')], docs='''Overcharges the robot, resetting the robot's cooldowns. The robot must be on the same team as you. Overcharging a sniping ranger cancels its snipe.
 
 * NoSuchUnit - either unit does not exist (inside the vision range).
 * TeamNotAllowed - either robot is not on the current player's team.
-* UnitNotOnMap - the healer is not on the map.
+* UnitNotOnMap - either unit is not on the map.
 * InappropriateUnitType - the unit is not a healer, or the target is not a robot.
 * ResearchNotUnlocked - you do not have the needed research to use overcharge.
 * OutOfRange - the target does not lie within ability range of the healer.
 * Overheated - the healer is not ready to use overcharge again.''')
+GameController.method(UnitIDVec.type.result(), 'structure_garrison', [Var(UnitID.type, 'structure_id')], docs='''The robots in the garrison of your structure, in the order they will be unloaded.
+
+* NoSuchUnit - the structure does not exist (inside the vision range).
+* TeamNotAllowed - the structure is not on the current player's team.
+* InappropriateUnitType - the unit is not a structure.''')
 GameController.method(boolean.type, 'can_load', [Var(UnitID.type, 'structure_id'), Var(UnitID.type, 'robot_id')], docs='''Whether the robot can be loaded into the given structure's garrison. The robot must be ready to move and must be adjacent to the structure. The structure and the robot must be on the same team, and the structure must have space.''')
 GameController.method(void.type.result(), 'load', [Var(UnitID.type, 'structure_id'), Var(UnitID.type, 'robot_id')], docs='''Loads the robot into the garrison of the structure.
 
@@ -604,7 +797,7 @@ GameController.method(void.type.result(), 'load', [Var(UnitID.type, 'structure_i
 * GarrisonFull - the structure's garrison is already full.
 * OutOfRange - the robot is not adjacent to the structure.''')
 GameController.method(boolean.type, 'can_unload', [Var(UnitID.type, 'structure_id'), Var(Direction.type, 'direction')], docs='''Tests whether the given structure is able to unload a unit in the given direction. There must be space in that direction, and the unit must be ready to move.''')
-GameController.method(void.type.result(), 'unload', [Var(UnitID.type, 'structure_id'), Var(Direction.type, 'direction')], docs='''Unloads a robot from the garrison of the specified structure into an adjacent space. Robots are unloaded in the order they were loaded.
+GameController.method(void.type.result(), 'unload', [Var(UnitID.type, 'structure_id'), Var(Direction.type, 'direction')], docs='''Unloads a robot from the garrison of the specified structure into an adjacent space. Robots are unloaded in the order they were loaded, and cannot act again until the next round.
 
 * NoSuchUnit - the unit does not exist (inside the vision range).
 * TeamNotAllowed - either unit is not on the current player's team.
@@ -615,6 +808,18 @@ GameController.method(void.type.result(), 'unload', [Var(UnitID.type, 'structure
 * LocationOffMap - the location in the target direction is off the map.
 * LocationNotEmpty - the location in the target direction is already occupied.
 * Overheated - the robot inside the structure is not ready to move again.''')
+GameController.method(UnitType.type.result(), 'factory_unit_type', [Var(UnitID.type, 'factory_id')], docs='''The type of robot your factory is producing.
+
+* NoSuchUnit - the factory does not exist (inside the vision range).
+* TeamNotAllowed - the factory is not on the current player's team.
+* InappropriateUnitType - the unit is not a factory.
+* NullValue - the factory is not producing.''')
+GameController.method(Rounds.type.result(), 'factory_rounds_left', [Var(UnitID.type, 'factory_id')], docs='''The number of rounds left until your factory produces its robot.
+
+* NoSuchUnit - the factory does not exist (inside the vision range).
+* TeamNotAllowed - the factory is not on the current player's team.
+* InappropriateUnitType - the unit is not a factory.
+* NullValue - the factory is not producing.''')
 GameController.method(boolean.type, 'can_produce_robot', [Var(UnitID.type, 'factory_id'), Var(UnitType.type, 'robot_type')], docs='''Whether the factory can produce a robot of the given type. The factory must not currently be producing a robot, and the team must have sufficient resources in its resource pool.''')
 GameController.method(void.type.result(), 'produce_robot', [Var(UnitID.type, 'factory_id'), Var(UnitType.type, 'robot_type')], docs='''Starts producing the robot of the given type.
 
@@ -625,27 +830,41 @@ GameController.method(void.type.result(), 'produce_robot', [Var(UnitID.type, 'fa
 * FactoryBusy - the factory is already producing a unit.
 * InsufficientKarbonite - your team does not have enough Karbonite to produce the given robot.''')
 GameController.method(RocketLandingInfo.type, 'rocket_landings', [], docs='''The landing rounds and locations of rockets in space that belong to the current team.''')
-GameController.method(boolean.type, 'can_launch_rocket', [Var(UnitID.type, 'rocket_id'), Var(MapLocation.type, 'destination')], docs='''Whether the rocket can launch into space to the given destination. The rocket can launch if the it has never been used before. The destination is valid if it contains passable terrain on the other planet.''')
-GameController.method(void.type.result(), 'launch_rocket', [Var(UnitID.type, 'rocket_id'), Var(MapLocation.type, 'location')], docs='''Launches the rocket into space, damaging the units adjacent to the takeoff location.
+GameController.method(boolean.type, 'can_launch_rocket', [Var(UnitID.type, 'rocket_id'), Var(MapLocation.type, 'destination')], docs='''Whether the rocket can launch into space to the given destination. The rocket can launch if the it has never been used before. The destination is valid if it contains passable terrain on the other planet, or if it is off the other planet's map.''')
+GameController.method(void.type.result(), 'launch_rocket_status', [Var(UnitID.type, 'rocket_id'), Var(MapLocation.type, 'destination')], docs='''Ok if the rocket can launch to the given destination, and otherwise the reason it cannot. Agrees with can_launch_rocket().
+
+* NoSuchUnit - the rocket does not exist (inside the vision range).
+* TeamNotAllowed - the rocket is not on the current player's team.
+* UnitNotOnMap - the rocket is not on the map.
+* SamePlanet - the rocket cannot fly to a location on the same planet.
+* InappropriateUnitType - the unit is not a rocket.
+* StructureNotYetBuilt - the rocket has not yet been completed.
+* RocketUsed - the rocket has already been used.
+* LocationNotEmpty - the given location contains impassable terrain.''')
+GameController.method(void.type.result(), 'launch_rocket', [Var(UnitID.type, 'rocket_id'), Var(MapLocation.type, 'location')], docs='''Launches the rocket into space, damaging the units adjacent to the takeoff location. If the destination is not on the map of the other planet, the rocket flies off, never to be seen again: the rocket and its garrison are destroyed.
 
 * NoSuchUnit - the rocket does not exist (inside the vision range).
 * TeamNotAllowed - the rocket is not on the current player's team.
+* UnitNotOnMap - the rocket is not on the map.
 * SamePlanet - the rocket cannot fly to a location on the same planet.
 * InappropriateUnitType - the unit is not a rocket.
 * StructureNotYetBuilt - the rocket has not yet been completed.
 * RocketUsed - the rocket has already been used.
-* LocationOffMap - the given location is off the map.
 * LocationNotEmpty - the given location contains impassable terrain.''')
 
 GameController.method(GameController.type, 'new_manager', [Var(GameMap.type, 'map')], static=True)
+GameController.method(GameController.type, 'new_manager_with_time', [Var(GameMap.type, 'map'), Var(i32.type, 'time_pool_ms'), Var(i32.type, 'time_per_turn_ms')], static=True)
 GameController.method(StartGameMessage.type, 'start_game', [Var(Player.type, 'player')])
 GameController.method(TurnApplication.type, 'apply_turn', [Var(TurnMessage.type.ref(), 'turn'), Var(i32.type, 'time_left_ms')])
 GameController.method(InitialTurnApplication.type, 'initial_start_turn_message', [Var(i32.type, 'time_left_ms')])
-GameController.method(boolean.type, "is_over", [])
-GameController.method(Team.type.result(), "winning_team", [])
+GameController.method(boolean.type, "is_over", [], docs="Whether the game has ended.")
+GameController.method(Team.type.result(), "winning_team", [], docs="The winning team. Errors if the game has not ended.")
 GameController.method(p.string.type, "manager_viewer_message", [])
 GameController.method(void.type, "print_game_ansi", [])
 GameController.method(u32.type, "manager_karbonite", [Var(Team.type, 'team')])
+GameController.method(void.type, "apply_time_used", [Var(i32.type, 'time_used_ms')])
+GameController.method(i32.type, "time_left", [Var(Player.type, 'player')])
+GameController.method(boolean.type, "is_timed_out", [Var(Player.type, 'player')])
 
 print('Generating...')
 with open("src/bindings.rs", "w+") as f:
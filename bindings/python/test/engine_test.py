@@ -20,3 +20,66 @@ def test_direction():
 def test_controller():
     c = bc.GameController.new_manager(bc.GameMap.test_map())
     print(c.start_game(bc.Player(bc.Team.Red, bc.Planet.Earth)).to_json())
+
+def _new_game():
+    manager = bc.GameController.new_manager(bc.GameMap.test_map())
+    players = [bc.Player(team, planet)
+               for planet in (bc.Planet.Earth, bc.Planet.Mars)
+               for team in (bc.Team.Red, bc.Team.Blue)]
+    controllers = [bc.GameController.new_player(manager.start_game(player)) for player in players]
+    return manager, controllers
+
+def test_turns():
+    manager, controllers = _new_game()
+    start_turn = manager.initial_start_turn_message(1000).start_turn
+    for turn in range(12):
+        controller = controllers[turn % 4]
+        controller.start_turn(start_turn)
+        assert controller.round() == turn // 4 + 1
+        # each worker on earth walks in any direction it can
+        for unit in controller.my_units():
+            for direction in list(bc.Direction)[:8]:
+                if controller.is_move_ready(unit.id) and controller.can_move(unit.id, direction):
+                    controller.move_robot(unit.id, direction)
+        start_turn = manager.apply_turn(controller.end_turn(), 1000).start_turn
+    assert manager.round() == 4
+
+def test_sense_nearby_units():
+    manager, controllers = _new_game()
+    red = controllers[0]
+    red.start_turn(manager.initial_start_turn_message(1000).start_turn)
+    units = red.my_units()
+    location = list(units)[0].location.map_location()
+    nearby = list(red.sense_nearby_units(location, 2))
+    assert len(nearby) == len(units)
+    assert all(type(unit) is bc.Unit for unit in nearby)
+    assert nearby[0].location.map_location() == location
+
+def test_errors():
+    manager, controllers = _new_game()
+    red = controllers[0]
+    red.start_turn(manager.initial_start_turn_message(1000).start_turn)
+    try:
+        red.move_robot(9999, bc.Direction.North)
+        assert False, "moved a unit that does not exist"
+    except bc.NoSuchUnit as e:
+        assert isinstance(e, bc.GameError)
+        assert 'does not exist' in str(e)
+
+    try:
+        red.write_team_array(1000, 1)
+        assert False, "wrote outside the team array"
+    except bc.ArrayOutOfBounds:
+        pass
+
+    # the controller is still usable after an error
+    assert len(red.my_units()) == 1
+
+    # errors that aren't game errors are still raised
+    try:
+        bc.GameMap.from_json('not json')
+        assert False, "parsed a bad map"
+    except bc.GameError:
+        assert False, "not a game error"
+    except Exception:
+        pass